@@ -358,3 +358,34 @@ fn fill_fail() {
 
     assert_eq!(Screen::fill(&mut buffer, color), Err(ErrorCode::Fail));
 }
+
+#[cfg_attr(miri, ignore)] // Miri does not support file system access.
+#[test]
+// Draws a red frame around a blue-to-green gradient and compares the result
+// against a stored snapshot.
+fn draw_snapshot() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Screen::new_with_resolution(16, 8);
+    kernel.add_driver(&driver);
+    assert_eq!(Screen::set_pixel_format(2), Ok(())); // RGB_565
+
+    let mut color = [0; 2];
+    assert_eq!(Screen::set_write_frame(0, 0, 16, 8), Ok(()));
+    assert_eq!(Screen::fill(&mut color, 0xf800), Ok(()));
+
+    let mut gradient = [0; 2 * 14 * 6];
+    for (i, pixel) in gradient.chunks_exact_mut(2).enumerate() {
+        let x = (i % 14) as u16;
+        let value = (31 - x * 2) | ((x * 4) << 5);
+        pixel.copy_from_slice(&value.to_be_bytes());
+    }
+    assert_eq!(Screen::set_write_frame(1, 1, 14, 6), Ok(()));
+    assert_eq!(Screen::write(&gradient), Ok(()));
+
+    assert_eq!(driver.pixel(0, 0), [255, 0, 0]);
+    assert_eq!(driver.pixel(1, 1), [0, 0, 255]);
+    driver.assert_snapshot(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/snapshots/draw_snapshot.ppm"
+    ));
+}
@@ -0,0 +1,129 @@
+//! Dispatching button events to handlers for individual buttons.
+
+use crate::{ButtonState, Buttons, DRIVER_NUM};
+use core::cell::Cell;
+use core::marker::PhantomData;
+use libtock_platform::share::Handle;
+use libtock_platform::subscribe::OneId;
+use libtock_platform::{DefaultConfig, ErrorCode, Subscribe, Syscalls, Upcall};
+
+// A registered handler: the button, the state it is called for (`None` for
+// both), and the handler itself.
+type Slot<'a> = Option<(u32, Option<ButtonState>, &'a dyn Fn(ButtonState))>;
+
+/// Calls handlers registered for individual buttons when button events occur,
+/// so that several components can react to their own buttons while only one
+/// upcall is subscribed. Holds up to `N` handlers.
+///
+/// # Example
+/// ```ignore
+/// use libtock::buttons::{ButtonDispatcher, ButtonState, Buttons};
+/// use libtock_platform::share;
+///
+/// let on_press = |_| { /* ... */ };
+/// let dispatcher = ButtonDispatcher::<4>::new();
+/// share::scope(|subscribe| {
+///     Buttons::register_dispatcher(&dispatcher, subscribe).unwrap();
+///     // Enables the button's interrupt until `_handler` is dropped.
+///     let _handler = dispatcher
+///         .register(0, Some(ButtonState::Pressed), &on_press)
+///         .unwrap();
+///     loop {
+///         TockSyscalls::yield_wait();
+///     }
+/// });
+/// ```
+pub struct ButtonDispatcher<'a, S: Syscalls, const N: usize> {
+    slots: [Cell<Slot<'a>>; N],
+    _syscalls: PhantomData<S>,
+}
+
+/// A handler registered with a `ButtonDispatcher`. Dropping it unregisters the
+/// handler, and disables the button's interrupt if no other handler is
+/// registered for the button.
+pub struct ButtonHandler<'d, 'a, S: Syscalls, const N: usize> {
+    dispatcher: &'d ButtonDispatcher<'a, S, N>,
+    index: usize,
+}
+
+impl<S: Syscalls> Buttons<S> {
+    /// Subscribes `dispatcher` to button events. Like `register_listener`,
+    /// this replaces the previously registered listener.
+    pub fn register_dispatcher<'share, const N: usize>(
+        dispatcher: &'share ButtonDispatcher<'_, S, N>,
+        subscribe: Handle<Subscribe<'share, S, DRIVER_NUM, 0>>,
+    ) -> Result<(), ErrorCode> {
+        S::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, dispatcher)
+    }
+}
+
+impl<'a, S: Syscalls, const N: usize> ButtonDispatcher<'a, S, N> {
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { Cell::new(None) }; N],
+            _syscalls: PhantomData,
+        }
+    }
+
+    /// Registers `handler` to be called when `button` changes to `state`, or
+    /// on every change if `state` is `None`, and enables the button's
+    /// interrupt.
+    ///
+    /// Returns `NoMem` if `N` handlers are already registered.
+    pub fn register<'d>(
+        &'d self,
+        button: u32,
+        state: Option<ButtonState>,
+        handler: &'a dyn Fn(ButtonState),
+    ) -> Result<ButtonHandler<'d, 'a, S, N>, ErrorCode> {
+        let index = self
+            .slots
+            .iter()
+            .position(|slot| slot.get().is_none())
+            .ok_or(ErrorCode::NoMem)?;
+        Buttons::<S>::enable_interrupts(button)?;
+        self.slots[index].set(Some((button, state, handler)));
+        Ok(ButtonHandler {
+            dispatcher: self,
+            index,
+        })
+    }
+}
+
+impl<S: Syscalls, const N: usize> Default for ButtonDispatcher<'_, S, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Syscalls, const N: usize> Upcall<OneId<DRIVER_NUM, 0>> for ButtonDispatcher<'_, S, N> {
+    fn upcall(&self, button_index: u32, state: u32, _arg2: u32) {
+        let state = ButtonState::from(state);
+        // Handlers may register or drop handlers, so each slot is read just
+        // before it is used.
+        for slot in &self.slots {
+            let Some((button, filter, handler)) = slot.get() else {
+                continue;
+            };
+            if button == button_index && filter.is_none_or(|filter| filter == state) {
+                handler(state);
+            }
+        }
+    }
+}
+
+impl<S: Syscalls, const N: usize> Drop for ButtonHandler<'_, '_, S, N> {
+    fn drop(&mut self) {
+        let slots = &self.dispatcher.slots;
+        let Some((button, _, _)) = slots[self.index].take() else {
+            return;
+        };
+        let in_use = slots
+            .iter()
+            .filter_map(Cell::get)
+            .any(|(other, _, _)| other == button);
+        if !in_use {
+            let _ = Buttons::<S>::disable_interrupts(button);
+        }
+    }
+}
@@ -28,6 +28,7 @@ use libtock_platform::{
 pub struct Buttons<S: Syscalls>(S);
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
 pub enum ButtonState {
     Pressed,
     Released,
@@ -95,7 +96,8 @@ impl<S: Syscalls> Buttons<S> {
     ///
     /// There can be only one single listener registered at a time.
     /// Each time this function is used, it will replace the
-    /// previously registered listener.
+    /// previously registered listener. To handle events for different
+    /// buttons separately, use a `ButtonDispatcher`.
     pub fn register_listener<'share, F: Fn(u32, ButtonState)>(
         listener: &'share ButtonListener<F>,
         subscribe: Handle<Subscribe<'share, S, DRIVER_NUM, 0>>,
@@ -127,6 +129,10 @@ impl<F: Fn(u32, ButtonState)> Upcall<OneId<DRIVER_NUM, 0>> for ButtonListener<F>
         self.0(button_index, state.into())
     }
 }
+
+mod dispatch;
+pub use dispatch::{ButtonDispatcher, ButtonHandler};
+
 #[cfg(test)]
 mod tests;
 
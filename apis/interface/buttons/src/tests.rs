@@ -5,6 +5,8 @@ use libtock_unittest::fake;
 
 use crate::{ButtonListener, ButtonState};
 
+type ButtonDispatcher<'a, const N: usize> = super::ButtonDispatcher<'a, fake::Syscalls, N>;
+
 type Buttons = super::Buttons<fake::Syscalls>;
 
 #[test]
@@ -113,3 +115,64 @@ fn subscribe() {
     });
     assert!(!pressed_interrupt_count.get());
 }
+
+#[test]
+fn dispatcher() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Buttons::<10>::new();
+    kernel.add_driver(&driver);
+
+    let presses = Cell::new(0);
+    let changes = Cell::new(0);
+    let on_press = |state| {
+        assert_eq!(state, ButtonState::Pressed);
+        presses.set(presses.get() + 1);
+    };
+    let on_change = |_| changes.set(changes.get() + 1);
+    let dispatcher = ButtonDispatcher::<2>::new();
+    share::scope(|subscribe| {
+        assert_eq!(Buttons::register_dispatcher(&dispatcher, subscribe), Ok(()));
+        let press_handler = dispatcher
+            .register(0, Some(ButtonState::Pressed), &on_press)
+            .unwrap();
+        let change_handler = dispatcher.register(1, None, &on_change).unwrap();
+        assert!(driver.get_button_state(0).unwrap().interrupt_enabled);
+        assert!(driver.get_button_state(1).unwrap().interrupt_enabled);
+        assert_eq!(
+            dispatcher.register(2, None, &on_change).err(),
+            Some(ErrorCode::NoMem)
+        );
+        assert_eq!(
+            dispatcher.register(11, None, &on_change).err(),
+            Some(ErrorCode::NoMem)
+        );
+
+        assert_eq!(driver.set_pressed(0, true), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(driver.set_pressed(0, false), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(driver.set_pressed(1, true), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(driver.set_pressed(1, false), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!((presses.get(), changes.get()), (1, 2));
+
+        // Dropping a handler disables its button's interrupt and frees its
+        // slot.
+        drop(press_handler);
+        assert!(!driver.get_button_state(0).unwrap().interrupt_enabled);
+        assert_eq!(driver.set_pressed(0, true), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+        assert_eq!(
+            dispatcher.register(11, None, &on_change).err(),
+            Some(ErrorCode::Invalid)
+        );
+
+        // The interrupt stays enabled while another handler uses the button.
+        let second_handler = dispatcher.register(1, None, &on_change).unwrap();
+        drop(change_handler);
+        assert!(driver.get_button_state(1).unwrap().interrupt_enabled);
+        drop(second_handler);
+        assert!(!driver.get_button_state(1).unwrap().interrupt_enabled);
+    });
+}
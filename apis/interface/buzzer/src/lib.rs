@@ -80,6 +80,7 @@ const BUZZER_ON: u32 = 1;
 #[allow(unused)]
 #[repr(u32)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
 pub enum Note {
     B0 = 31,
     C1 = 33,
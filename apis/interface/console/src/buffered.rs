@@ -0,0 +1,216 @@
+//! Buffered console output that does not wait for the kernel.
+
+use crate::{allow_ro, command, subscribe, Config, DRIVER_NUM};
+use core::cell::{Cell, UnsafeCell};
+use core::fmt;
+use core::marker::PhantomData;
+use libtock_platform::share::Handle;
+use libtock_platform::subscribe::OneId;
+use libtock_platform::{AllowRo, DefaultConfig, ErrorCode, Subscribe, Syscalls, Upcall};
+
+/// The shares a `BufferedWriter` needs: the write buffer and the write upcall.
+pub type WriteShares<'share, S> = (
+    AllowRo<'share, S, DRIVER_NUM, { allow_ro::WRITE }>,
+    Subscribe<'share, S, DRIVER_NUM, { subscribe::WRITE }>,
+);
+
+/// Storage for a `BufferedWriter`: two buffers of `N` bytes each. While the
+/// kernel writes one buffer, output accumulates in the other.
+///
+/// # Example
+/// ```ignore
+/// use libtock::console::WriteBuffer;
+/// use libtock_platform::share;
+///
+/// let buffer = WriteBuffer::<64>::new();
+/// share::scope(|handle| {
+///     let mut writer = buffer.writer(handle).unwrap();
+///     loop {
+///         // Returns without waiting for the kernel, unless both buffers are
+///         // full.
+///         writeln!(writer, "sample: {}", read_sensor()).unwrap();
+///         // ...
+///     }
+/// });
+/// ```
+pub struct WriteBuffer<S: Syscalls, const N: usize, C: Config = DefaultConfig> {
+    buffers: [UnsafeCell<[u8; N]>; 2],
+    // The index of the buffer being filled, and the number of bytes in it.
+    filling: Cell<usize>,
+    len: Cell<usize>,
+    // Whether the kernel is writing the other buffer.
+    writing: Cell<bool>,
+    // Whether the buffer being filled should be written as soon as the kernel
+    // is done with the other buffer.
+    flush_pending: Cell<bool>,
+    _syscalls: PhantomData<(S, C)>,
+}
+
+/// Writes to the console through a `WriteBuffer`, without waiting for the
+/// kernel to finish writing. Created by `WriteBuffer::writer`.
+///
+/// Output is handed to the kernel when a buffer fills up, when a newline is
+/// written, and on `flush`. If the kernel is still writing the previous
+/// buffer, the output is handed over by the next `write`, `poll` or `flush`
+/// call after the kernel's upcall arrives (upcalls are delivered when the
+/// process yields). `write` only waits for the kernel if both buffers are
+/// full.
+///
+/// Dropping a `BufferedWriter` flushes it. While a `BufferedWriter` exists,
+/// `Console::write` and `ConsoleWriter` must not be used, as they share the
+/// kernel's write buffer and upcall.
+pub struct BufferedWriter<'share, 'handle, S: Syscalls, const N: usize, C: Config = DefaultConfig> {
+    buffer: &'share WriteBuffer<S, N, C>,
+    allow_ro: Handle<'handle, AllowRo<'share, S, DRIVER_NUM, { allow_ro::WRITE }>>,
+}
+
+impl<S: Syscalls, const N: usize, C: Config> WriteBuffer<S, N, C> {
+    pub const fn new() -> Self {
+        WriteBuffer {
+            buffers: [UnsafeCell::new([0; N]), UnsafeCell::new([0; N])],
+            filling: Cell::new(0),
+            len: Cell::new(0),
+            writing: Cell::new(false),
+            flush_pending: Cell::new(false),
+            _syscalls: PhantomData,
+        }
+    }
+
+    /// Subscribes to the console's write upcall, and returns a writer that
+    /// uses this buffer. The writer can be used until `handle`'s scope ends.
+    pub fn writer<'share, 'handle>(
+        &'share self,
+        handle: Handle<'handle, WriteShares<'share, S>>,
+    ) -> Result<BufferedWriter<'share, 'handle, S, N, C>, ErrorCode> {
+        let (allow_ro, subscribe) = handle.split();
+        S::subscribe::<_, _, C, DRIVER_NUM, { subscribe::WRITE }>(subscribe, self)?;
+        Ok(BufferedWriter {
+            buffer: self,
+            allow_ro,
+        })
+    }
+}
+
+impl<S: Syscalls, const N: usize, C: Config> Default for WriteBuffer<S, N, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Syscalls, const N: usize, C: Config> Upcall<OneId<DRIVER_NUM, { subscribe::WRITE }>>
+    for WriteBuffer<S, N, C>
+{
+    fn upcall(&self, _bytes_written: u32, _: u32, _: u32) {
+        self.writing.set(false);
+    }
+}
+
+impl<S: Syscalls, const N: usize, C: Config> BufferedWriter<'_, '_, S, N, C> {
+    /// Buffers `bytes`, handing the buffer to the kernel if it fills up or
+    /// `bytes` contains a newline.
+    pub fn write(&mut self, mut bytes: &[u8]) -> Result<(), ErrorCode> {
+        let buffer = self.buffer;
+        let newline = bytes.contains(&b'\n');
+        while !bytes.is_empty() {
+            if buffer.len.get() == N {
+                self.send()?;
+            }
+            let len = buffer.len.get();
+            let count = bytes.len().min(N - len);
+            // SAFETY: The kernel only reads the buffer that is not being
+            // filled (see send), so nothing else accesses this buffer.
+            let filling = unsafe { &mut *buffer.buffers[buffer.filling.get()].get() };
+            filling[len..len + count].copy_from_slice(&bytes[..count]);
+            buffer.len.set(len + count);
+            bytes = &bytes[count..];
+        }
+        if newline {
+            buffer.flush_pending.set(true);
+        }
+        self.poll()
+    }
+
+    /// Hands buffered output to the kernel if a newline was written and the
+    /// kernel has finished writing the previous buffer. Does not wait.
+    pub fn poll(&mut self) -> Result<(), ErrorCode> {
+        let buffer = self.buffer;
+        if buffer.flush_pending.get() && !buffer.writing.get() && buffer.len.get() > 0 {
+            self.send()?;
+        }
+        Ok(())
+    }
+
+    /// Hands all buffered output to the kernel, and waits until the kernel has
+    /// written it.
+    pub fn flush(&mut self) -> Result<(), ErrorCode> {
+        if self.buffer.len.get() > 0 {
+            self.send()?;
+        }
+        self.wait();
+        Ok(())
+    }
+}
+
+impl<S: Syscalls, const N: usize, C: Config> fmt::Write for BufferedWriter<'_, '_, S, N, C> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<S: Syscalls, const N: usize, C: Config> ufmt_write::uWrite
+    for BufferedWriter<'_, '_, S, N, C>
+{
+    type Error = ErrorCode;
+
+    fn write_str(&mut self, s: &str) -> Result<(), ErrorCode> {
+        self.write(s.as_bytes())
+    }
+}
+
+impl<S: Syscalls, const N: usize, C: Config> Drop for BufferedWriter<'_, '_, S, N, C> {
+    fn drop(&mut self) {
+        // Output that could not be written is lost, but the kernel must finish
+        // with the buffer before the scope un-allows it.
+        if self.flush().is_err() {
+            self.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
+
+// -----------------------------------------------------------------------------
+// Implementation details below
+// -----------------------------------------------------------------------------
+
+impl<S: Syscalls, const N: usize, C: Config> BufferedWriter<'_, '_, S, N, C> {
+    // Waits until the kernel is not writing a buffer.
+    fn wait(&self) {
+        while self.buffer.writing.get() {
+            S::yield_wait();
+        }
+    }
+
+    // Hands the buffer being filled to the kernel, waiting for the kernel to
+    // finish writing the other buffer first, and starts filling the other
+    // buffer.
+    fn send(&mut self) -> Result<(), ErrorCode> {
+        self.wait();
+        let buffer = self.buffer;
+        let index = buffer.filling.get();
+        let len = buffer.len.get();
+        // SAFETY: The kernel is done with the other buffer, and this buffer
+        // is not written to until the kernel is done with it: it is only
+        // filled again after the next send, which waits for the kernel.
+        let shared = unsafe { &(&*buffer.buffers[index].get())[..len] };
+        S::allow_ro::<C, DRIVER_NUM, { allow_ro::WRITE }>(self.allow_ro, shared)?;
+        S::command(DRIVER_NUM, command::WRITE, len as u32, 0).to_result::<(), ErrorCode>()?;
+        buffer.writing.set(true);
+        buffer.filling.set(1 - index);
+        buffer.len.set(0);
+        buffer.flush_pending.set(false);
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+extern crate std;
+
+use crate::{command, DRIVER_NUM};
+use core::fmt::Write;
+use libtock_platform::{share, Syscalls};
+use libtock_unittest::{fake, SyscallLogEntry};
+use std::vec::Vec;
+
+type WriteBuffer<const N: usize> = super::WriteBuffer<fake::Syscalls, N>;
+
+// Returns the lengths of the writes in the syscall log.
+fn writes(kernel: &fake::Kernel) -> Vec<u32> {
+    kernel
+        .take_syscall_log()
+        .into_iter()
+        .filter_map(|entry| match entry {
+            SyscallLogEntry::Command {
+                driver_id: DRIVER_NUM,
+                command_id: command::WRITE,
+                argument0,
+                ..
+            } => Some(argument0),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn flushes_on_newline() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    kernel.add_driver(&driver);
+
+    let buffer = WriteBuffer::<32>::new();
+    share::scope(|handle| {
+        let mut writer = buffer.writer(handle).unwrap();
+        write!(writer, "x = {}, ", 1).unwrap();
+        write!(writer, "y = {}", 2).unwrap();
+        // Fragments are buffered rather than written one by one.
+        assert_eq!(driver.take_bytes(), b"");
+        writeln!(writer).unwrap();
+        assert_eq!(driver.take_bytes(), b"x = 1, y = 2\n");
+        assert_eq!(writes(&kernel), [13]);
+
+        // The kernel has not reported the first write as done, so the next
+        // line waits in the other buffer until the upcall is delivered.
+        writer.write(b"second\n").unwrap();
+        assert_eq!(driver.take_bytes(), b"");
+        fake::Syscalls::yield_no_wait();
+        writer.poll().unwrap();
+        assert_eq!(driver.take_bytes(), b"second\n");
+
+        // Output without a newline waits for a flush.
+        writer.write(b"partial").unwrap();
+        fake::Syscalls::yield_no_wait();
+        writer.poll().unwrap();
+        assert_eq!(driver.take_bytes(), b"");
+        writer.flush().unwrap();
+        assert_eq!(driver.take_bytes(), b"partial");
+    });
+    assert_eq!(writes(&kernel), [7, 7]);
+}
+
+// Long output is written a buffer at a time, waiting for the kernel only when
+// both buffers are full.
+#[test]
+fn double_buffering() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    kernel.add_driver(&driver);
+
+    let buffer = WriteBuffer::<4>::new();
+    share::scope(|handle| {
+        let mut writer = buffer.writer(handle).unwrap();
+        writer.write(b"abcdefghij").unwrap();
+        assert_eq!(driver.take_bytes(), b"abcdefgh");
+        assert_eq!(writes(&kernel), [4, 4]);
+        // Dropping the writer flushes it.
+    });
+    assert_eq!(driver.take_bytes(), b"ij");
+    assert_eq!(writes(&kernel), [2]);
+    // The buffer can be used again.
+    share::scope(|handle| {
+        let mut writer = buffer.writer(handle).unwrap();
+        writer.write(b"k\n").unwrap();
+        assert_eq!(driver.take_bytes(), b"k\n");
+    });
+}
+
+#[cfg(feature = "ufmt")]
+#[test]
+fn ufmt() {
+    use ufmt_write::uWrite;
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    kernel.add_driver(&driver);
+
+    let buffer = WriteBuffer::<8>::new();
+    share::scope(|handle| {
+        let mut writer = buffer.writer(handle).unwrap();
+        uWrite::write_str(&mut writer, "hi\n").unwrap();
+    });
+    assert_eq!(driver.take_bytes(), b"hi\n");
+}
@@ -0,0 +1,98 @@
+//! `embedded_io` support, so that protocol crates and serial-line parsers from
+//! the embedded ecosystem can run on the console.
+
+use crate::{Config, Console};
+use core::marker::PhantomData;
+use libtock_platform::{DefaultConfig, ErrorCode, Syscalls};
+
+/// A console handle that implements `embedded_io::Read`, `Write` and
+/// `ReadReady`. Created by `Console::io`.
+///
+/// The console driver only completes a read once the whole buffer is full, so
+/// `read` reads one byte at a time rather than waiting for `buf` to fill up.
+/// Bytes that arrive while no read is in progress may be dropped by the
+/// kernel.
+///
+/// # Example
+/// ```ignore
+/// use embedded_io::{Read, Write};
+/// use libtock::console::Console;
+///
+/// let mut io = Console::io();
+/// io.write_all(b"> ")?;
+/// let mut command = [0; 1];
+/// io.read_exact(&mut command)?;
+/// ```
+pub struct ConsoleIo<S: Syscalls, C: Config = DefaultConfig> {
+    // A byte read by `read_ready` that `read` has not returned yet.
+    lookahead: Option<u8>,
+    _syscalls: PhantomData<(S, C)>,
+}
+
+impl<S: Syscalls, C: Config> Console<S, C> {
+    pub fn io() -> ConsoleIo<S, C> {
+        ConsoleIo {
+            lookahead: None,
+            _syscalls: PhantomData,
+        }
+    }
+}
+
+impl<S: Syscalls, C: Config> embedded_io::ErrorType for ConsoleIo<S, C> {
+    type Error = ErrorCode;
+}
+
+impl<S: Syscalls, C: Config> embedded_io::Read for ConsoleIo<S, C> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, ErrorCode> {
+        let Some(first) = buf.first_mut() else {
+            return Ok(0);
+        };
+        if let Some(byte) = self.lookahead.take() {
+            *first = byte;
+            return Ok(1);
+        }
+        // Ok(0) would indicate the end of the input, which the console does
+        // not have, so this waits until a byte arrives.
+        loop {
+            match Console::<S, C>::read(core::slice::from_mut(first)) {
+                (0, Ok(())) => continue,
+                (0, Err(error)) => return Err(error),
+                (count, _) => return Ok(count),
+            }
+        }
+    }
+}
+
+impl<S: Syscalls, C: Config> embedded_io::ReadReady for ConsoleIo<S, C> {
+    fn read_ready(&mut self) -> Result<bool, ErrorCode> {
+        if self.lookahead.is_none() {
+            let mut byte = 0;
+            if Console::<S, C>::read_available(core::slice::from_mut(&mut byte))? == 1 {
+                self.lookahead = Some(byte);
+            }
+        }
+        Ok(self.lookahead.is_some())
+    }
+}
+
+impl<S: Syscalls, C: Config> embedded_io::Write for ConsoleIo<S, C> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, ErrorCode> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        Console::<S, C>::write(buf)?;
+        Ok(buf.len())
+    }
+
+    /// `write` waits for the kernel to finish writing, so there is nothing to
+    /// flush.
+    fn flush(&mut self) -> Result<(), ErrorCode> {
+        Ok(())
+    }
+}
+
+impl<S: Syscalls, C: Config> embedded_io::WriteReady for ConsoleIo<S, C> {
+    fn write_ready(&mut self) -> Result<bool, ErrorCode> {
+        Ok(true)
+    }
+}
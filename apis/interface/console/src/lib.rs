@@ -104,6 +104,58 @@ impl<S: Syscalls, C: Config> Console<S, C> {
         (bytes_received, r)
     }
 
+    /// Reads the bytes that have already arrived into `buf`, without waiting
+    /// for more. Returns the number of bytes read, which may be zero.
+    ///
+    /// The console driver only reports a read once the buffer is full, so
+    /// this starts a read, aborts it if it did not complete right away, and
+    /// returns the bytes received before the abort. If the read can be neither
+    /// completed nor aborted, returns the abort's error.
+    pub fn read_available(buf: &mut [u8]) -> Result<usize, ErrorCode> {
+        let called: Cell<Option<(u32, u32)>> = Cell::new(None);
+        share::scope::<
+            (
+                AllowRw<_, DRIVER_NUM, { allow_rw::READ }>,
+                Subscribe<_, DRIVER_NUM, { subscribe::READ }>,
+            ),
+            _,
+            _,
+        >(|handle| {
+            let (allow_rw, subscribe) = handle.split();
+            let len = buf.len();
+            S::allow_rw::<C, DRIVER_NUM, { allow_rw::READ }>(allow_rw, buf)?;
+            S::subscribe::<_, _, C, DRIVER_NUM, { subscribe::READ }>(subscribe, &called)?;
+            S::command(DRIVER_NUM, command::READ, len as u32, 0).to_result::<(), ErrorCode>()?;
+
+            S::yield_no_wait();
+            if called.get().is_none() {
+                let abort = S::command(DRIVER_NUM, command::ABORT, 0, 0);
+                if let Err(error) = abort.to_result::<(), ErrorCode>() {
+                    // The abort fails if the read completed between the yield
+                    // and the abort, in which case its upcall is queued. If it
+                    // failed for another reason, the read upcall may never
+                    // arrive, so report the error instead of waiting for it.
+                    S::yield_no_wait();
+                    if called.get().is_none() {
+                        return Err(error);
+                    }
+                }
+            }
+            loop {
+                if let Some((status, bytes_pushed_count)) = called.get() {
+                    return match status {
+                        0 => Ok(bytes_pushed_count as usize),
+                        status if status == ErrorCode::Cancel as u32 => {
+                            Ok(bytes_pushed_count as usize)
+                        }
+                        e_status => Err(e_status.try_into().unwrap_or(ErrorCode::Fail)),
+                    };
+                }
+                S::yield_wait();
+            }
+        })
+    }
+
     pub fn writer() -> ConsoleWriter<S> {
         ConsoleWriter {
             syscalls: Default::default(),
@@ -121,6 +173,24 @@ impl<S: Syscalls> fmt::Write for ConsoleWriter<S> {
     }
 }
 
+#[cfg(feature = "ufmt")]
+impl<S: Syscalls> ufmt_write::uWrite for ConsoleWriter<S> {
+    type Error = ErrorCode;
+
+    fn write_str(&mut self, s: &str) -> Result<(), ErrorCode> {
+        Console::<S>::write(s.as_bytes())
+    }
+}
+
+mod buffered;
+#[cfg(feature = "rust_embedded")]
+mod io;
+mod line_reader;
+pub use buffered::{BufferedWriter, WriteBuffer, WriteShares};
+#[cfg(feature = "rust_embedded")]
+pub use io::ConsoleIo;
+pub use line_reader::{Completer, LineReader};
+
 /// System call configuration trait for `Console`.
 pub trait Config:
     platform::allow_ro::Config + platform::allow_rw::Config + platform::subscribe::Config
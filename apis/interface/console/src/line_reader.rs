@@ -0,0 +1,384 @@
+//! A line editor for interactive console input, for use by command shells.
+
+use crate::{Config, Console};
+use core::marker::PhantomData;
+use libtock_platform::{DefaultConfig, ErrorCode, Syscalls};
+
+/// Reads lines from the console, one byte at a time, with basic line editing:
+///
+/// * Typed characters are echoed back (unless echo is disabled with
+///   `set_echo`).
+/// * Backspace (`0x08`) and Delete (`0x7F`, which many terminals send for the
+///   backspace key) remove the last character, and Ctrl-U clears the line.
+/// * A line ends with CR, LF, or CR LF.
+/// * The up and down arrow keys step through the last `HISTORY` lines.
+/// * Tab completes the last word of the line using the `Completer` set by
+///   `set_completer`.
+///
+/// Lines hold up to `LEN` bytes; input that does not fit rings the terminal's
+/// bell. Only printable ASCII characters are accepted, so lines are always
+/// valid `str`s.
+///
+/// # Example
+/// ```ignore
+/// use libtock::console::LineReader;
+///
+/// let mut reader = LineReader::<80, 4>::new();
+/// loop {
+///     let line = reader.read_line("> ")?;
+///     // ...
+/// }
+/// ```
+pub struct LineReader<
+    'a,
+    S: Syscalls,
+    const LEN: usize,
+    const HISTORY: usize,
+    C: Config = DefaultConfig,
+> {
+    echo: bool,
+    completer: Option<&'a dyn Completer>,
+    line: Line<LEN>,
+    // A ring of previous lines; `history_next` is the slot the next line is
+    // stored in.
+    history: [Line<LEN>; HISTORY],
+    history_len: usize,
+    history_next: usize,
+    // The history entry being shown: 0 for the line being typed, n for the
+    // n'th most recent line.
+    browsing: usize,
+    // Whether the last byte read was a CR, so that a following LF is not
+    // treated as a second line ending.
+    last_was_cr: bool,
+    escape: Escape,
+    _syscalls: PhantomData<(S, C)>,
+}
+
+/// Provides tab completions to a `LineReader`.
+pub trait Completer {
+    /// Calls `candidate` with each word that the last word of `line` (the text
+    /// after its last space) could be completed to. `line` is the input typed
+    /// so far. Candidates that do not start with the last word are ignored.
+    fn complete(&self, line: &str, candidate: &mut dyn FnMut(&str));
+}
+
+impl<F: Fn(&str, &mut dyn FnMut(&str))> Completer for F {
+    fn complete(&self, line: &str, candidate: &mut dyn FnMut(&str)) {
+        self(line, candidate)
+    }
+}
+
+impl<'a, S: Syscalls, const LEN: usize, const HISTORY: usize, C: Config>
+    LineReader<'a, S, LEN, HISTORY, C>
+{
+    pub fn new() -> Self {
+        LineReader {
+            echo: true,
+            completer: None,
+            line: Line::new(),
+            history: [Line::new(); HISTORY],
+            history_len: 0,
+            history_next: 0,
+            browsing: 0,
+            last_was_cr: false,
+            escape: Escape::None,
+            _syscalls: PhantomData,
+        }
+    }
+
+    /// Sets whether typed characters are echoed back. Echo is enabled by
+    /// default; disable it when the terminal echoes locally, or to read a
+    /// password.
+    pub fn set_echo(&mut self, echo: bool) {
+        self.echo = echo;
+    }
+
+    pub fn set_completer(&mut self, completer: &'a dyn Completer) {
+        self.completer = Some(completer);
+    }
+
+    /// Writes `prompt`, then reads and returns a line, without its line
+    /// ending. Non-empty lines are added to the history.
+    pub fn read_line(&mut self, prompt: &str) -> Result<&str, ErrorCode> {
+        self.line.clear();
+        self.browsing = 0;
+        self.escape = Escape::None;
+        Console::<S, C>::write(prompt.as_bytes())?;
+        while !self.handle(read_byte::<S, C>()?, prompt)? {}
+        self.add_history();
+        Ok(self.line.as_str())
+    }
+
+    /// Returns the lines in the history, most recent first.
+    pub fn history(&self) -> impl Iterator<Item = &str> {
+        (0..self.history_len).map(|n| self.history_entry(n))
+    }
+}
+
+impl<S: Syscalls, const LEN: usize, const HISTORY: usize, C: Config> Default
+    for LineReader<'_, S, LEN, HISTORY, C>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests;
+
+// -----------------------------------------------------------------------------
+// Implementation details below
+// -----------------------------------------------------------------------------
+
+const BELL: &[u8] = b"\x07";
+const NEWLINE: &[u8] = b"\r\n";
+// Moves to the start of the line and clears it.
+const CLEAR_LINE: &[u8] = b"\r\x1b[K";
+
+// Progress through an ANSI escape sequence, such as "ESC [ A" for the up
+// arrow.
+#[derive(Clone, Copy)]
+enum Escape {
+    None,
+    Started,
+    ControlSequence,
+}
+
+#[derive(Clone, Copy)]
+struct Line<const LEN: usize> {
+    bytes: [u8; LEN],
+    len: usize,
+}
+
+impl<const LEN: usize> Line<LEN> {
+    const fn new() -> Self {
+        Line {
+            bytes: [0; LEN],
+            len: 0,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        // Lines only hold printable ASCII and completions truncated at
+        // character boundaries, and lose whole characters, so this does not
+        // fail.
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap_or("")
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    // Appends `s` if it fits, returning whether it did.
+    fn push_str(&mut self, s: &str) -> bool {
+        let Some(dest) = self.bytes.get_mut(self.len..self.len + s.len()) else {
+            return false;
+        };
+        dest.copy_from_slice(s.as_bytes());
+        self.len += s.len();
+        true
+    }
+
+    // Removes the last character, which may be several bytes long.
+    fn pop(&mut self) -> bool {
+        let Some(last) = self.as_str().chars().next_back() else {
+            return false;
+        };
+        self.len -= last.len_utf8();
+        true
+    }
+}
+
+// Returns the largest character boundary of `s` that is not after `index`, like
+// the unstable `str::floor_char_boundary`.
+fn floor_char_boundary(s: &str, index: usize) -> usize {
+    let mut index = index.min(s.len());
+    while !s.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn read_byte<S: Syscalls, C: Config>() -> Result<u8, ErrorCode> {
+    let mut byte = 0;
+    loop {
+        match Console::<S, C>::read(core::slice::from_mut(&mut byte)) {
+            (1, _) => return Ok(byte),
+            (_, Err(error)) => return Err(error),
+            (_, Ok(())) => {}
+        }
+    }
+}
+
+impl<S: Syscalls, const LEN: usize, const HISTORY: usize, C: Config>
+    LineReader<'_, S, LEN, HISTORY, C>
+{
+    // Handles one byte of input. Returns true once the line is complete.
+    fn handle(&mut self, byte: u8, prompt: &str) -> Result<bool, ErrorCode> {
+        let was_cr = core::mem::replace(&mut self.last_was_cr, false);
+        match self.escape {
+            Escape::None => {}
+            Escape::Started => {
+                self.escape = match byte {
+                    b'[' => Escape::ControlSequence,
+                    _ => Escape::None,
+                };
+                return Ok(false);
+            }
+            // Parameter bytes (such as the 3 in "ESC [ 3 ~", the Delete key)
+            // are skipped until the final byte.
+            Escape::ControlSequence => {
+                if (0x40..=0x7e).contains(&byte) {
+                    self.escape = Escape::None;
+                    match byte {
+                        b'A' => self.browse(self.browsing + 1, prompt)?,
+                        b'B' => match self.browsing.checked_sub(1) {
+                            Some(browsing) => self.browse(browsing, prompt)?,
+                            None => self.echo(BELL)?,
+                        },
+                        _ => {}
+                    }
+                }
+                return Ok(false);
+            }
+        }
+        match byte {
+            b'\r' => {
+                self.last_was_cr = true;
+                self.echo(NEWLINE)?;
+                return Ok(true);
+            }
+            b'\n' if was_cr => {}
+            b'\n' => {
+                self.echo(NEWLINE)?;
+                return Ok(true);
+            }
+            0x08 | 0x7f if self.line.pop() => self.echo(b"\x08 \x08")?,
+            // Ctrl-U
+            0x15 => self.replace_line("", prompt)?,
+            b'\t' => self.complete(prompt)?,
+            0x1b => self.escape = Escape::Started,
+            0x20..=0x7e => {
+                let byte = [byte];
+                // Printable ASCII is valid UTF-8.
+                let s = core::str::from_utf8(&byte).unwrap_or("");
+                match self.line.push_str(s) {
+                    true => self.echo(&byte)?,
+                    false => self.echo(BELL)?,
+                }
+            }
+            // Other control characters are ignored.
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn echo(&self, bytes: &[u8]) -> Result<(), ErrorCode> {
+        match self.echo {
+            true => Console::<S, C>::write(bytes),
+            false => Ok(()),
+        }
+    }
+
+    // Replaces the line being edited, redrawing it.
+    fn replace_line(&mut self, s: &str, prompt: &str) -> Result<(), ErrorCode> {
+        self.line.clear();
+        self.line.push_str(s);
+        self.redraw(prompt)
+    }
+
+    fn redraw(&self, prompt: &str) -> Result<(), ErrorCode> {
+        self.echo(CLEAR_LINE)?;
+        self.echo(prompt.as_bytes())?;
+        self.echo(self.line.as_str().as_bytes())
+    }
+
+    fn history_entry(&self, n: usize) -> &str {
+        self.history[(self.history_next + HISTORY - 1 - n) % HISTORY].as_str()
+    }
+
+    // Shows history entry `browsing` (or the empty line for 0), if it exists.
+    fn browse(&mut self, browsing: usize, prompt: &str) -> Result<(), ErrorCode> {
+        if browsing > self.history_len {
+            return self.echo(BELL);
+        }
+        self.browsing = browsing;
+        let entry = match browsing {
+            0 => Line::new(),
+            n => self.history[(self.history_next + HISTORY - n) % HISTORY],
+        };
+        self.replace_line(entry.as_str(), prompt)
+    }
+
+    fn add_history(&mut self) {
+        let line = self.line.as_str();
+        if HISTORY == 0
+            || line.is_empty()
+            || (self.history_len > 0 && self.history_entry(0) == line)
+        {
+            return;
+        }
+        self.history[self.history_next] = self.line;
+        self.history_next = (self.history_next + 1) % HISTORY;
+        self.history_len = (self.history_len + 1).min(HISTORY);
+    }
+
+    fn complete(&mut self, prompt: &str) -> Result<(), ErrorCode> {
+        let Some(completer) = self.completer else {
+            return self.echo(BELL);
+        };
+        let line = self.line.as_str();
+        let word_len = line.len() - line.rfind(' ').map_or(0, |i| i + 1);
+        let word = &line[line.len() - word_len..];
+        // The longest common prefix of the candidates.
+        let mut prefix = Line::<LEN>::new();
+        let mut candidates = 0;
+        // Whether the first candidate is too long to fit in a line.
+        let mut too_long = false;
+        completer.complete(line, &mut |candidate| {
+            if !candidate.starts_with(word) {
+                return;
+            }
+            if candidates == 0 {
+                prefix.push_str(&candidate[..floor_char_boundary(candidate, LEN)]);
+                too_long = candidate.len() > LEN;
+            } else {
+                let len = prefix
+                    .as_str()
+                    .bytes()
+                    .zip(candidate.bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                prefix.len = floor_char_boundary(candidate, len);
+            }
+            candidates += 1;
+        });
+        if candidates == 0 || (candidates == 1 && too_long) {
+            return self.echo(BELL);
+        }
+        let extension = &prefix.as_str()[word_len..];
+        let suffix = if candidates == 1 { " " } else { "" };
+        if !extension.is_empty() || candidates == 1 {
+            let old_len = self.line.len;
+            if !(self.line.push_str(extension) && self.line.push_str(suffix)) {
+                self.line.len = old_len;
+                return self.echo(BELL);
+            }
+            self.echo(extension.as_bytes())?;
+            return self.echo(suffix.as_bytes());
+        }
+        // Several candidates and nothing to add: list them.
+        self.echo(NEWLINE)?;
+        let mut result = Ok(());
+        completer.complete(line, &mut |candidate| {
+            if candidate.starts_with(word) && result.is_ok() {
+                result = self
+                    .echo(candidate.as_bytes())
+                    .and_then(|()| self.echo(b"  "));
+            }
+        });
+        result?;
+        self.echo(NEWLINE)?;
+        self.redraw(prompt)
+    }
+}
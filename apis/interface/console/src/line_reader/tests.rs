@@ -0,0 +1,155 @@
+extern crate std;
+
+use libtock_unittest::fake;
+use std::vec::Vec;
+
+type LineReader<'a, const LEN: usize, const HISTORY: usize> =
+    super::LineReader<'a, fake::Syscalls, LEN, HISTORY>;
+
+// Reads `count` lines from `input` with `reader`, returning the lines and the
+// console output.
+fn read_lines<const LEN: usize, const HISTORY: usize>(
+    reader: &mut LineReader<LEN, HISTORY>,
+    input: &[u8],
+    count: usize,
+) -> (Vec<std::string::String>, Vec<u8>) {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new_with_input(input);
+    kernel.add_driver(&driver);
+    let lines = (0..count)
+        .map(|_| reader.read_line("> ").unwrap().into())
+        .collect();
+    assert_eq!(driver.pending_input(), 0);
+    (lines, driver.take_bytes())
+}
+
+#[test]
+fn line_endings() {
+    let mut reader = LineReader::<16, 0>::new();
+    let (lines, output) = read_lines(&mut reader, b"ab\r\ncd\ref\n\ngh\r", 5);
+    assert_eq!(lines, ["ab", "cd", "ef", "", "gh"]);
+    assert_eq!(output, b"> ab\r\n> cd\r\n> ef\r\n> \r\n> gh\r\n");
+}
+
+#[test]
+fn editing() {
+    let mut reader = LineReader::<16, 0>::new();
+    // Backspace, Delete, Ctrl-U, an ignored control character, and the Delete
+    // key's escape sequence.
+    let (lines, output) = read_lines(&mut reader, b"abc\x08d\x7f\x7f\x7fx\x15yz\x01\x1b[3~\r", 1);
+    assert_eq!(lines, ["yz"]);
+    assert_eq!(
+        output,
+        b"> abc\x08 \x08d\x08 \x08\x08 \x08\x08 \x08x\r\x1b[K> yz\r\n"
+    );
+}
+
+#[test]
+fn no_echo() {
+    let mut reader = LineReader::<16, 0>::new();
+    reader.set_echo(false);
+    let (lines, output) = read_lines(&mut reader, b"secret\x7fT\r", 1);
+    assert_eq!(lines, ["secreT"]);
+    assert_eq!(output, b"> ");
+}
+
+#[test]
+fn line_too_long() {
+    let mut reader = LineReader::<4, 0>::new();
+    let (lines, output) = read_lines(&mut reader, b"abcdef\r", 1);
+    assert_eq!(lines, ["abcd"]);
+    assert_eq!(output, b"> abcd\x07\x07\r\n");
+}
+
+#[test]
+fn history() {
+    let mut reader = LineReader::<16, 2>::new();
+    // Empty lines and repeats of the previous line are not recorded.
+    let (lines, _) = read_lines(&mut reader, b"one\rtwo\r\rtwo\rthree\r", 5);
+    assert_eq!(lines, ["one", "two", "", "two", "three"]);
+    assert_eq!(reader.history().collect::<Vec<_>>(), ["three", "two"]);
+
+    // Up goes back through the history, and rings the bell at the oldest
+    // entry; down comes forward to an empty line.
+    let (lines, output) = read_lines(
+        &mut reader,
+        b"\x1b[A\x1b[A\x1b[A\x1b[B!\r\x1b[A\x1b[B\x1b[Bx\r",
+        2,
+    );
+    assert_eq!(lines, ["three!", "x"]);
+    assert_eq!(
+        output,
+        [
+            &b"> \r\x1b[K> three\r\x1b[K> two\x07\r\x1b[K> three!\r\n"[..],
+            b"> \r\x1b[K> three!\r\x1b[K> \x07x\r\n",
+        ]
+        .concat()
+    );
+    assert_eq!(reader.history().collect::<Vec<_>>(), ["x", "three!"]);
+}
+
+#[test]
+fn completion() {
+    let complete = |line: &str, candidate: &mut dyn FnMut(&str)| {
+        // Completes command names, then file names.
+        let names: &[&str] = match line.contains(' ') {
+            false => &["help", "led", "list"],
+            true => &["file1", "file2"],
+        };
+        names.iter().for_each(|name| candidate(name));
+    };
+    let mut reader = LineReader::<16, 0>::new();
+    reader.set_completer(&complete);
+
+    // A unique completion is finished with a space.
+    let (lines, output) = read_lines(&mut reader, b"h\t\r", 1);
+    assert_eq!(lines, ["help "]);
+    assert_eq!(output, b"> help \r\n");
+
+    // Several completions are extended to their common prefix, then listed.
+    let (lines, output) = read_lines(&mut reader, b"led f\t\t2\r", 1);
+    assert_eq!(lines, ["led file2"]);
+    assert_eq!(
+        output,
+        b"> led file\r\nfile1  file2  \r\n\r\x1b[K> led file2\r\n"
+    );
+
+    // No completion rings the bell.
+    let (lines, output) = read_lines(&mut reader, b"x\t\r", 1);
+    assert_eq!(lines, ["x"]);
+    assert_eq!(output, b"> x\x07\r\n");
+}
+
+#[test]
+fn multibyte_completion() {
+    let complete = |line: &str, candidate: &mut dyn FnMut(&str)| {
+        let names: &[&str] = match line.starts_with('a') {
+            false => &["héllo"],
+            true => &["ab✓x", "ab✓y"],
+        };
+        names.iter().for_each(|name| candidate(name));
+    };
+    let mut reader = LineReader::<4, 0>::new();
+    reader.set_completer(&complete);
+
+    // Candidates longer than the line are cut at a character boundary when
+    // finding their common prefix.
+    let (lines, output) = read_lines(&mut reader, b"a\t\r", 1);
+    assert_eq!(lines, ["ab"]);
+    assert_eq!(output, b"> ab\r\n");
+
+    // A unique candidate that does not fit is not completed.
+    let (lines, output) = read_lines(&mut reader, b"h\t\r", 1);
+    assert_eq!(lines, ["h"]);
+    assert_eq!(output, b"> h\x07\r\n");
+
+    // Backspace removes a multibyte character as a whole.
+    let mut reader = LineReader::<16, 0>::new();
+    reader.set_completer(&complete);
+    let (lines, output) = read_lines(&mut reader, b"h\t\x7f\x7f\x7f\x7f\x7fi\r", 1);
+    assert_eq!(lines, ["hi"]);
+    assert_eq!(
+        output,
+        "> héllo \x08 \x08\x08 \x08\x08 \x08\x08 \x08\x08 \x08i\r\n".as_bytes()
+    );
+}
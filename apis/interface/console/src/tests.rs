@@ -1,7 +1,10 @@
+extern crate std;
+
 use super::*;
 use core::fmt::Write;
-use libtock_platform::ErrorCode;
-use libtock_unittest::{command_return, fake, ExpectedSyscall};
+use libtock_platform::{CommandReturn, ErrorCode};
+use libtock_unittest::{command_return, fake, DriverInfo, ExpectedSyscall, RwAllowBuffer};
+use std::rc::Rc;
 
 type Console = super::Console<fake::Syscalls>;
 
@@ -42,6 +45,18 @@ fn write_str() {
     assert_eq!(driver.take_bytes(), b"foo");
 }
 
+#[cfg(feature = "ufmt")]
+#[test]
+fn write_ufmt() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    kernel.add_driver(&driver);
+
+    let mut writer = Console::writer();
+    ufmt::uwrite!(&mut writer, "{} {:?}", 3u8, ErrorCode::NoMem).unwrap();
+    assert_eq!(driver.take_bytes(), b"3 NOMEM");
+}
+
 #[test]
 fn read_bytes_short() {
     let kernel = fake::Kernel::new();
@@ -129,3 +144,197 @@ fn failed_read() {
     assert_eq!(res, Err(ErrorCode::Fail));
     assert_eq!(count, 0);
 }
+
+#[test]
+fn read_waits_for_input() {
+    use core::time::Duration;
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    let alarm = fake::Alarm::new(1000);
+    kernel.add_driver(&driver);
+    kernel.add_driver(&alarm);
+
+    let timeline = fake::Timeline::new(&alarm);
+    let typist = driver.clone();
+    timeline.at(Duration::from_millis(10), move || typist.push_input(b"ok"));
+
+    let mut buf = [0; 8];
+    let (count, res) = Console::read(&mut buf);
+    res.unwrap();
+    assert_eq!(&buf[..count], b"ok");
+    assert_eq!(timeline.now(), Duration::from_millis(10));
+}
+
+#[test]
+fn prompt_loop() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    kernel.add_driver(&driver);
+    driver.respond_to(b"> ", b"add");
+    driver.respond_to(b"> ", b"quit");
+
+    let mut adds = 0;
+    loop {
+        Console::write(b"> ").unwrap();
+        let mut buf = [0; 8];
+        let (count, res) = Console::read(&mut buf);
+        res.unwrap();
+        match &buf[..count] {
+            b"add" => adds += 1,
+            b"quit" => break,
+            other => panic!("unexpected command {:?}", other),
+        }
+    }
+    assert_eq!(adds, 1);
+    assert_eq!(driver.remaining_script(), 0);
+}
+
+#[test]
+fn read_too_long() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new_with_input(b"abc");
+    kernel.add_driver(&driver);
+    driver.set_read_limit(2);
+
+    let mut buf = [0; 3];
+    assert_eq!(Console::read(&mut buf), (0, Err(ErrorCode::Size)));
+    let (count, res) = Console::read(&mut buf[..2]);
+    res.unwrap();
+    assert_eq!(&buf[..count], b"ab");
+}
+
+#[test]
+fn read_available() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new_with_input(b"ab");
+    kernel.add_driver(&driver);
+
+    let mut buf = [0; 4];
+    assert_eq!(Console::read_available(&mut buf), Ok(2));
+    assert_eq!(&buf[..2], b"ab");
+    // Without input, the read is aborted rather than waiting.
+    assert_eq!(Console::read_available(&mut buf), Ok(0));
+    assert!(!driver.is_reading());
+}
+
+#[test]
+fn read_available_abort_fails() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    kernel.add_driver(&driver);
+
+    // An abort that fails (e.g. because the read completed first) must not
+    // lose the read upcall.
+    kernel.add_expected_syscall(ExpectedSyscall::AllowRw {
+        driver_num: DRIVER_NUM,
+        buffer_num: allow_rw::READ,
+        return_error: None,
+    });
+    kernel.add_expected_syscall(ExpectedSyscall::Subscribe {
+        driver_num: DRIVER_NUM,
+        subscribe_num: subscribe::READ,
+        skip_with_error: None,
+    });
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: DRIVER_NUM,
+        command_id: command::READ,
+        argument0: 4,
+        argument1: 0,
+        override_return: None,
+    });
+    kernel.add_expected_syscall(ExpectedSyscall::YieldNoWait {
+        override_return: None,
+    });
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: DRIVER_NUM,
+        command_id: command::ABORT,
+        argument0: 0,
+        argument1: 0,
+        override_return: Some(command_return::failure(ErrorCode::Fail)),
+    });
+
+    let mut buf = [0; 4];
+    assert_eq!(Console::read_available(&mut buf), Ok(0));
+    assert!(!driver.is_reading());
+}
+
+// A console driver that does not support aborting a read, and so never sends
+// the read upcall unless input arrives.
+struct NoAbortConsole {
+    read_buffer: Cell<RwAllowBuffer>,
+}
+
+impl fake::SyscallDriver for NoAbortConsole {
+    fn info(&self) -> DriverInfo {
+        DriverInfo::new(DRIVER_NUM).upcall_count(3)
+    }
+
+    fn command(&self, command_id: u32, _: u32, _: u32) -> CommandReturn {
+        match command_id {
+            command::READ => command_return::success(),
+            _ => command_return::failure(ErrorCode::NoSupport),
+        }
+    }
+
+    fn allow_readwrite(
+        &self,
+        _: u32,
+        buffer: RwAllowBuffer,
+    ) -> Result<RwAllowBuffer, (RwAllowBuffer, ErrorCode)> {
+        Ok(self.read_buffer.replace(buffer))
+    }
+}
+
+#[test]
+fn read_available_abort_unsupported() {
+    let kernel = fake::Kernel::new();
+    kernel.add_driver(&Rc::new(NoAbortConsole {
+        read_buffer: Default::default(),
+    }));
+
+    // No read upcall will arrive, so the abort's error is returned rather than
+    // waiting forever.
+    let mut buf = [0; 4];
+    assert_eq!(Console::read_available(&mut buf), Err(ErrorCode::NoSupport));
+}
+
+#[cfg(feature = "rust_embedded")]
+#[test]
+fn embedded_io() {
+    use embedded_io::{Read, ReadReady, Write};
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    kernel.add_driver(&driver);
+    driver.respond_to(b"> ", b"hi");
+
+    let mut io = Console::io();
+    assert_eq!(io.read_ready(), Ok(false));
+    io.write_all(b"> ").unwrap();
+    io.flush().unwrap();
+    assert_eq!(driver.take_bytes(), b"> ");
+    assert_eq!(io.write(b""), Ok(0));
+    assert_eq!(io.read_ready(), Ok(true));
+    // read_ready does not consume input.
+    assert_eq!(io.read_ready(), Ok(true));
+
+    let mut buf = [0; 2];
+    io.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hi");
+    assert_eq!(io.read(&mut []), Ok(0));
+    assert_eq!(io.read_ready(), Ok(false));
+}
+
+#[cfg(feature = "rust_embedded")]
+#[test]
+fn embedded_io_errors() {
+    use embedded_io::{Error, ErrorKind, Read};
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new_with_input(b"abc");
+    kernel.add_driver(&driver);
+    driver.set_read_limit(0);
+
+    let mut buf = [0; 3];
+    let error = Console::io().read(&mut buf).unwrap_err();
+    assert_eq!(error, ErrorCode::Size);
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
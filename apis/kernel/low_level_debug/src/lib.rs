@@ -56,6 +56,7 @@ impl<S: Syscalls> LowLevelDebug<S> {
 }
 
 /// A predefined alert code, for use with [`LowLevelDebug::print_alert_code`].
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
 pub enum AlertCode {
     /// Application panic (e.g. `panic!()` called in Rust code).
     Panic = 0x01,
@@ -14,6 +14,23 @@ pub struct Frame {
     pub body: [u8; MAX_MTU],
 }
 
+// ufmt only implements uDebug for arrays of up to 32 elements, so format the
+// body as a slice.
+#[cfg(feature = "ufmt")]
+impl ufmt::uDebug for Frame {
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        f.debug_struct("Frame")?
+            .field("header_len", &self.header_len)?
+            .field("payload_len", &self.payload_len)?
+            .field("mic_len", &self.mic_len)?
+            .field("body", &&self.body[..])?
+            .finish()
+    }
+}
+
 const EMPTY_FRAME: Frame = Frame {
     header_len: 0,
     payload_len: 0,
@@ -58,6 +75,20 @@ impl<const N: usize> Default for RxRingBuffer<N> {
     }
 }
 
+#[cfg(feature = "ufmt")]
+impl<const N: usize> ufmt::uDebug for RxRingBuffer<N> {
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        f.debug_struct("RxRingBuffer")?
+            .field("read_index", &self.read_index)?
+            .field("write_index", &self.write_index)?
+            .field("frames", &&self.frames[..])?
+            .finish()
+    }
+}
+
 impl<const N: usize> RxRingBuffer<N> {
     /// Creates a new [RxRingBuffer] that can be used to receive frames into.
     pub const fn new() -> Self {
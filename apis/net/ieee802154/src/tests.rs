@@ -270,3 +270,196 @@ mod rx {
         });
     }
 }
+
+mod medium {
+    extern crate std;
+
+    use super::*;
+    use core::time::Duration;
+    use fake::ieee802154::{Medium, Peer};
+
+    // Builds a data frame addressed to short address `address` on PAN `pan`.
+    fn frame_to(pan: u16, address: u16, payload: &[u8]) -> std::vec::Vec<u8> {
+        let mut frame = std::vec![0x41, 0x88, 0];
+        frame.extend_from_slice(&pan.to_le_bytes());
+        frame.extend_from_slice(&address.to_le_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    fn payload(frame: &crate::Frame) -> &[u8] {
+        &frame.body[7..frame.payload_len as usize]
+    }
+
+    // Creates a kernel with a radio attached to `medium` and configured as
+    // node `address` on PAN 0x1234.
+    fn node(medium: &Medium, address: u16) -> (fake::Kernel, std::rc::Rc<Ieee802154Phy>) {
+        let kernel = fake::Kernel::new();
+        let radio = fake::Ieee802154Phy::new();
+        kernel.add_driver(&radio);
+        medium.attach(&radio);
+        Ieee802154::set_pan(0x1234);
+        Ieee802154::set_address_short(address);
+        Ieee802154::commit_config();
+        Ieee802154::radio_on().unwrap();
+        (kernel, radio)
+    }
+
+    fn peer(medium: &Medium, address: u16) -> Peer {
+        let peer = medium.add_peer();
+        peer.set_pan(0x1234);
+        peer.set_address_short(address);
+        peer
+    }
+
+    #[test]
+    fn exchange_with_peer() {
+        let medium = Medium::new();
+        let (_kernel, _radio) = node(&medium, 1);
+        let peer = peer(&medium, 2);
+
+        Ieee802154::transmit_frame_raw(&frame_to(0x1234, 2, b"ping")).unwrap();
+        Ieee802154::transmit_frame_raw(&frame_to(0x1234, 3, b"not for peer")).unwrap();
+        assert_eq!(peer.take_received(), [frame_to(0x1234, 2, b"ping")]);
+
+        let mut buf = RxRingBuffer::<2>::new();
+        let mut operator = RxSingleBufferOperator::new(&mut buf);
+        // The frame is sent while the process has no receive buffer allowed,
+        // so it is held until the process waits for a frame.
+        peer.transmit(&frame_to(0x1234, 1, b"pong"));
+        assert_eq!(payload(operator.receive_frame().unwrap()), b"pong");
+    }
+
+    #[test]
+    fn ring_overflow() {
+        let medium = Medium::new();
+        let (_kernel, radio) = node(&medium, 1);
+        let peer = peer(&medium, 2);
+
+        let mut buf = RxRingBuffer::<3>::new();
+        let mut operator = RxSingleBufferOperator::new(&mut buf);
+        // Queue a frame so the first receive_frame call allows the buffer and
+        // returns, leaving it allowed.
+        peer.transmit(&frame_to(0x1234, 1, b"0"));
+        assert_eq!(payload(operator.receive_frame().unwrap()), b"0");
+        assert_eq!(radio.rx_dropped(), 0);
+
+        // The buffer is no longer allowed, so these frames are held by the
+        // medium until the process waits again, then overflow the ring.
+        for frame in [b"1", b"2", b"3"] {
+            peer.transmit(&frame_to(0x1234, 1, frame));
+        }
+        assert_eq!(payload(operator.receive_frame().unwrap()), b"2");
+        assert_eq!(payload(operator.receive_frame().unwrap()), b"3");
+        assert_eq!(radio.rx_overwritten(), 1);
+    }
+
+    #[test]
+    fn latency() {
+        let medium = Medium::new();
+        medium.set_latency(Duration::from_millis(5));
+        let kernel = fake::Kernel::new();
+        let radio = fake::Ieee802154Phy::new();
+        let alarm = fake::Alarm::new(1000);
+        kernel.add_driver(&radio);
+        kernel.add_driver(&alarm);
+        medium.attach_with_clock(&radio, &alarm);
+        Ieee802154::radio_on().unwrap();
+        let peer = medium.add_peer();
+
+        let mut buf = RxRingBuffer::<2>::new();
+        let mut operator = RxSingleBufferOperator::new(&mut buf);
+        peer.transmit(&[0, 0, 0, b'x']);
+        assert_eq!(operator.receive_frame().unwrap().body[3], b'x');
+        assert_eq!(alarm.elapsed(), Duration::from_millis(5));
+    }
+
+    #[test]
+    fn across_threads() {
+        let medium = Medium::new();
+        let (_kernel, _radio) = node(&medium, 1);
+        let remote_medium = medium.clone();
+        let (ready_sender, ready) = std::sync::mpsc::channel();
+        let remote = std::thread::spawn(move || {
+            let (_kernel, _radio) = node(&remote_medium, 2);
+            ready_sender.send(()).unwrap();
+            let mut buf = RxRingBuffer::<2>::new();
+            let mut operator = RxSingleBufferOperator::new(&mut buf);
+            let request = operator.receive_frame().unwrap();
+            assert_eq!(payload(request), b"ping");
+            Ieee802154::transmit_frame_raw(&frame_to(0x1234, 1, b"pong")).unwrap();
+        });
+
+        let mut buf = RxRingBuffer::<2>::new();
+        let mut operator = RxSingleBufferOperator::new(&mut buf);
+        // Wait for the remote radio to be turned on.
+        ready.recv().unwrap();
+        Ieee802154::transmit_frame_raw(&frame_to(0x1234, 2, b"ping")).unwrap();
+        assert_eq!(payload(operator.receive_frame().unwrap()), b"pong");
+        remote.join().unwrap();
+    }
+
+    // Processes on different threads that both wait for a frame that never
+    // comes stop waiting, rather than hanging.
+    #[test]
+    fn across_threads_idle() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        let medium = Medium::new();
+        let (kernel, radio) = node(&medium, 1);
+        let remote_medium = medium.clone();
+        let (ready_sender, ready) = std::sync::mpsc::channel();
+        let remote = std::thread::spawn(move || {
+            let (_kernel, _radio) = node(&remote_medium, 2);
+            ready_sender.send(()).unwrap();
+            let mut buf = RxRingBuffer::<2>::new();
+            let mut operator = RxSingleBufferOperator::new(&mut buf);
+            catch_unwind(AssertUnwindSafe(|| {
+                let _ = operator.receive_frame();
+            }))
+            .is_err()
+        });
+
+        let mut buf = RxRingBuffer::<2>::new();
+        let mut operator = RxSingleBufferOperator::new(&mut buf);
+        ready.recv().unwrap();
+        let waited = catch_unwind(AssertUnwindSafe(|| {
+            let _ = operator.receive_frame();
+        }));
+        assert!(waited.is_err(), "receive_frame returned without a frame");
+        // The remote process may have stopped waiting before this one did,
+        // in which case it is still waiting for this radio to be dropped.
+        drop(kernel);
+        drop(radio);
+        assert!(remote.join().unwrap());
+    }
+}
+
+#[cfg(feature = "ufmt")]
+#[test]
+fn frame_udebug() {
+    extern crate std;
+    use std::string::String;
+
+    struct Collect(String);
+    impl ufmt::uWrite for Collect {
+        type Error = core::convert::Infallible;
+        fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+            self.0.push_str(s);
+            Ok(())
+        }
+    }
+
+    let mut frame = super::Frame {
+        header_len: 1,
+        payload_len: 2,
+        mic_len: 0,
+        body: [0; 127],
+    };
+    frame.body[126] = 9;
+    let mut out = Collect(String::new());
+    ufmt::uwrite!(&mut out, "{:?}", frame).unwrap();
+    assert!(out
+        .0
+        .starts_with("Frame { header_len: 1, payload_len: 2, mic_len: 0, body: [0, 0,"));
+    assert!(out.0.ends_with(", 0, 9] }"));
+}
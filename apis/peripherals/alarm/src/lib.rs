@@ -5,6 +5,11 @@ use libtock_platform as platform;
 use libtock_platform::share;
 use libtock_platform::{DefaultConfig, ErrorCode, Syscalls};
 
+mod time;
+mod timer;
+pub use time::{Clock, Duration, Instant};
+pub use timer::{Fired, TimerId, Timers};
+
 /// The alarm driver
 ///
 /// # Example
@@ -17,6 +22,7 @@ use libtock_platform::{DefaultConfig, ErrorCode, Syscalls};
 pub struct Alarm<S: Syscalls, C: platform::subscribe::Config = DefaultConfig>(S, C);
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
 pub struct Hz(pub u32);
 
 pub trait Convert {
@@ -25,6 +31,7 @@ pub trait Convert {
 }
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
 pub struct Ticks(pub u32);
 
 impl Convert for Ticks {
@@ -34,6 +41,7 @@ impl Convert for Ticks {
 }
 
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
 pub struct Milliseconds(pub u32);
 
 impl Convert for Milliseconds {
@@ -73,11 +81,16 @@ impl<S: Syscalls, C: platform::subscribe::Config> Alarm<S, C> {
         S::command(DRIVER_NUM, command::TIME, 0, 0).to_result()
     }
 
+    /// Returns the value of the tick counter in milliseconds. Like the tick
+    /// counter, this wraps around; use `Clock` for a monotonic time.
     pub fn get_milliseconds() -> Result<u64, ErrorCode> {
         let ticks = Self::get_ticks()? as u64;
         let freq = (Self::get_frequency()?).0 as u64;
+        if freq == 0 {
+            return Err(ErrorCode::Fail);
+        }
 
-        Ok(ticks.saturating_div(freq / 1000))
+        Ok(ticks * 1000 / freq)
     }
 
     pub fn sleep_for<T: Convert>(time: T) -> Result<(), ErrorCode> {
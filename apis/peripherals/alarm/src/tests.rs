@@ -22,3 +22,57 @@ fn sleep() {
     assert_eq!(Alarm::sleep_for(Ticks(1000)), Ok(()));
     assert_eq!(Alarm::sleep_for(Milliseconds(1000)), Ok(()));
 }
+
+#[test]
+fn get_ticks() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(1000);
+    kernel.add_driver(&driver);
+
+    assert_eq!(Alarm::get_ticks(), Ok(0));
+    driver.advance(1234);
+    assert_eq!(Alarm::get_ticks(), Ok(1234));
+}
+
+#[test]
+fn sleep_duration() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(32768);
+    kernel.add_driver(&driver);
+
+    assert_eq!(Alarm::sleep_for(Milliseconds(10)), Ok(()));
+    assert_eq!(Alarm::sleep_for(Ticks(5)), Ok(()));
+    // 10 ms at 32768 Hz is 327.68 ticks, which is rounded up.
+    assert_eq!(driver.take_sleeps(), [328, 5]);
+    assert_eq!(driver.now(), 333);
+}
+
+#[test]
+fn sleep_across_wraparound() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(1000);
+    kernel.add_driver(&driver);
+    driver.set_time(u32::MAX - 100);
+
+    assert_eq!(Alarm::sleep_for(Milliseconds(500)), Ok(()));
+    assert_eq!(driver.take_sleeps(), [500]);
+    assert_eq!(driver.now(), 399);
+    assert_eq!(driver.elapsed(), core::time::Duration::from_millis(500));
+}
+
+#[test]
+fn get_milliseconds() {
+    let kernel = fake::Kernel::new();
+    // Sub-kHz clocks used to divide by zero.
+    let driver = fake::Alarm::new(100);
+    kernel.add_driver(&driver);
+    driver.advance(250);
+    assert_eq!(Alarm::get_milliseconds(), Ok(2500));
+    drop(kernel);
+
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(32768);
+    kernel.add_driver(&driver);
+    driver.advance(32768 * 3 / 2);
+    assert_eq!(Alarm::get_milliseconds(), Ok(1500));
+}
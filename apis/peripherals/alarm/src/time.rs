@@ -0,0 +1,338 @@
+//! Monotonic time: `Instant` and `Duration`, measured by a `Clock` that extends
+//! the alarm's 32-bit tick counter to 64 bits.
+
+use crate::{Alarm, Convert, Hz, Ticks};
+use core::cell::Cell;
+use core::marker::PhantomData;
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+use libtock_platform as platform;
+use libtock_platform::{DefaultConfig, ErrorCode, Syscalls};
+
+/// A span of time, with microsecond resolution.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
+pub struct Duration {
+    micros: u64,
+}
+
+/// A point in time measured by a `Clock`. `Instant`s from different `Clock`s
+/// (or different boots) cannot be compared.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
+pub struct Instant {
+    // Microseconds since the Clock's tick counter was zero.
+    micros: u64,
+}
+
+/// Reads the current time from the alarm driver. The alarm's tick counter is
+/// only 32 bits wide, and wraps around every 2^32 ticks (about 36 hours at
+/// 32768 Hz, or 72 minutes at 1 MHz). `Clock` counts the wraparounds to extend
+/// it to 64 bits, which requires that the time is read (through `now`, or by
+/// sleeping with `sleep` or `sleep_until`) at least once per wraparound.
+///
+/// # Example
+/// ```ignore
+/// use libtock::alarm::{Clock, Duration};
+///
+/// let clock = Clock::new()?;
+/// let start = clock.now()?;
+/// clock.sleep_until(start + Duration::from_millis(250))?;
+/// let elapsed = start.elapsed(&clock)?;
+/// ```
+pub struct Clock<S: Syscalls, C: platform::subscribe::Config = DefaultConfig> {
+    frequency: Hz,
+    // The most recent reading of the tick counter.
+    last_ticks: Cell<u32>,
+    // The number of times the tick counter has wrapped around.
+    wraps: Cell<u32>,
+    _syscalls: PhantomData<(S, C)>,
+}
+
+impl Duration {
+    pub const ZERO: Duration = Duration { micros: 0 };
+    pub const MAX: Duration = Duration { micros: u64::MAX };
+
+    pub const fn from_micros(micros: u64) -> Duration {
+        Duration { micros }
+    }
+
+    pub const fn from_millis(millis: u64) -> Duration {
+        Duration {
+            micros: millis.saturating_mul(1_000),
+        }
+    }
+
+    pub const fn from_secs(secs: u64) -> Duration {
+        Duration {
+            micros: secs.saturating_mul(1_000_000),
+        }
+    }
+
+    /// Converts a number of nanoseconds into a `Duration`, rounding up.
+    pub const fn from_nanos(nanos: u64) -> Duration {
+        Duration {
+            micros: nanos.div_ceil(1_000),
+        }
+    }
+
+    pub const fn as_micros(self) -> u64 {
+        self.micros
+    }
+
+    /// Returns the number of whole milliseconds in this `Duration`.
+    pub const fn as_millis(self) -> u64 {
+        self.micros / 1_000
+    }
+
+    /// Returns the number of whole seconds in this `Duration`.
+    pub const fn as_secs(self) -> u64 {
+        self.micros / 1_000_000
+    }
+
+    pub fn checked_add(self, other: Duration) -> Option<Duration> {
+        self.micros
+            .checked_add(other.micros)
+            .map(Duration::from_micros)
+    }
+
+    pub fn checked_sub(self, other: Duration) -> Option<Duration> {
+        self.micros
+            .checked_sub(other.micros)
+            .map(Duration::from_micros)
+    }
+
+    pub fn saturating_sub(self, other: Duration) -> Duration {
+        Duration::from_micros(self.micros.saturating_sub(other.micros))
+    }
+
+    /// Converts a number of ticks of a clock running at `frequency` into a
+    /// `Duration`, rounding down.
+    pub fn from_ticks(ticks: u64, frequency: Hz) -> Duration {
+        let micros = ticks as u128 * 1_000_000 / frequency.0 as u128;
+        Duration::from_micros(micros.min(u64::MAX as u128) as u64)
+    }
+
+    /// Converts this `Duration` into a number of ticks of a clock running at
+    /// `frequency`, rounding up.
+    pub fn to_ticks_u64(self, frequency: Hz) -> u64 {
+        let ticks = (self.micros as u128 * frequency.0 as u128).div_ceil(1_000_000);
+        ticks.min(u64::MAX as u128) as u64
+    }
+}
+
+/// Converts into ticks by rounding up, saturating at `u32::MAX` ticks.
+impl Convert for Duration {
+    fn to_ticks(self, freq: Hz) -> Ticks {
+        Ticks(self.to_ticks_u64(freq).min(u32::MAX as u64) as u32)
+    }
+}
+
+/// Panics on overflow, like `core::time::Duration`.
+impl Add for Duration {
+    type Output = Duration;
+    fn add(self, other: Duration) -> Duration {
+        self.checked_add(other)
+            .expect("overflow when adding durations")
+    }
+}
+
+impl AddAssign for Duration {
+    fn add_assign(&mut self, other: Duration) {
+        *self = *self + other;
+    }
+}
+
+/// Panics if `other` is longer than `self`, like `core::time::Duration`.
+impl Sub for Duration {
+    type Output = Duration;
+    fn sub(self, other: Duration) -> Duration {
+        self.checked_sub(other)
+            .expect("overflow when subtracting durations")
+    }
+}
+
+impl SubAssign for Duration {
+    fn sub_assign(&mut self, other: Duration) {
+        *self = *self - other;
+    }
+}
+
+/// Rounds down to whole microseconds, and saturates at `Duration::MAX`.
+impl From<core::time::Duration> for Duration {
+    fn from(duration: core::time::Duration) -> Duration {
+        Duration::from_micros(duration.as_micros().min(u64::MAX as u128) as u64)
+    }
+}
+
+impl From<Duration> for core::time::Duration {
+    fn from(duration: Duration) -> core::time::Duration {
+        core::time::Duration::from_micros(duration.micros)
+    }
+}
+
+impl Instant {
+    /// Returns the time that passed between `earlier` and `self`, or zero if
+    /// `earlier` is later than `self`.
+    pub fn duration_since(self, earlier: Instant) -> Duration {
+        Duration::from_micros(self.micros.saturating_sub(earlier.micros))
+    }
+
+    /// Returns the time that has passed since `self`, according to `clock`.
+    pub fn elapsed<S: Syscalls, C: platform::subscribe::Config>(
+        self,
+        clock: &Clock<S, C>,
+    ) -> Result<Duration, ErrorCode> {
+        Ok(clock.now()?.duration_since(self))
+    }
+
+    pub fn checked_add(self, duration: Duration) -> Option<Instant> {
+        self.micros
+            .checked_add(duration.micros)
+            .map(|micros| Instant { micros })
+    }
+
+    pub fn checked_sub(self, duration: Duration) -> Option<Instant> {
+        self.micros
+            .checked_sub(duration.micros)
+            .map(|micros| Instant { micros })
+    }
+
+    /// Returns the time since the `Clock`'s tick counter was zero.
+    pub fn since_epoch(self) -> Duration {
+        Duration::from_micros(self.micros)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+    fn add(self, duration: Duration) -> Instant {
+        self.checked_add(duration)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, duration: Duration) {
+        *self = *self + duration;
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+    fn sub(self, duration: Duration) -> Instant {
+        self.checked_sub(duration)
+            .expect("overflow when subtracting duration from instant")
+    }
+}
+
+impl SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, duration: Duration) {
+        *self = *self - duration;
+    }
+}
+
+/// Saturates to zero, like `Instant::duration_since`.
+impl Sub for Instant {
+    type Output = Duration;
+    fn sub(self, earlier: Instant) -> Duration {
+        self.duration_since(earlier)
+    }
+}
+
+impl<S: Syscalls, C: platform::subscribe::Config> Clock<S, C> {
+    /// Creates a `Clock`, reading the alarm's frequency.
+    pub fn new() -> Result<Self, ErrorCode> {
+        let frequency = Alarm::<S, C>::get_frequency()?;
+        if frequency.0 == 0 {
+            return Err(ErrorCode::Fail);
+        }
+        Ok(Clock {
+            frequency,
+            last_ticks: Cell::new(Alarm::<S, C>::get_ticks()?),
+            wraps: Cell::new(0),
+            _syscalls: PhantomData,
+        })
+    }
+
+    pub fn frequency(&self) -> Hz {
+        self.frequency
+    }
+
+    /// Returns the current value of the 64-bit tick counter.
+    pub fn ticks(&self) -> Result<u64, ErrorCode> {
+        let ticks = Alarm::<S, C>::get_ticks()?;
+        if ticks < self.last_ticks.get() {
+            self.wraps.set(self.wraps.get().wrapping_add(1));
+        }
+        self.last_ticks.set(ticks);
+        Ok((self.wraps.get() as u64) << 32 | ticks as u64)
+    }
+
+    pub fn now(&self) -> Result<Instant, ErrorCode> {
+        let micros = Duration::from_ticks(self.ticks()?, self.frequency).micros;
+        Ok(Instant { micros })
+    }
+
+    /// Sleeps until `deadline`. Returns immediately if `deadline` has already
+    /// passed.
+    pub fn sleep_until(&self, deadline: Instant) -> Result<(), ErrorCode> {
+        let deadline = Duration::from_micros(deadline.micros).to_ticks_u64(self.frequency);
+        loop {
+            let now = self.ticks()?;
+            if now >= deadline {
+                return Ok(());
+            }
+            // Wakes up at least twice per wraparound so that no wraparound is
+            // missed.
+            let ticks = (deadline - now).min(u32::MAX as u64 / 2);
+            Alarm::<S, C>::sleep_for(Ticks(ticks as u32))?;
+        }
+    }
+
+    pub fn sleep(&self, duration: Duration) -> Result<(), ErrorCode> {
+        self.sleep_until(self.now()? + duration)
+    }
+}
+
+#[cfg(feature = "rust_embedded")]
+impl<S: Syscalls, C: platform::subscribe::Config> embedded_hal::delay::DelayNs for Clock<S, C> {
+    fn delay_ns(&mut self, ns: u32) {
+        // DelayNs cannot report errors. A failed sleep returns early.
+        let _ = self.sleep(Duration::from_nanos(ns as u64));
+    }
+}
+
+#[cfg(feature = "fugit")]
+impl From<Duration> for fugit::MicrosDurationU64 {
+    fn from(duration: Duration) -> fugit::MicrosDurationU64 {
+        fugit::MicrosDurationU64::from_ticks(duration.micros)
+    }
+}
+
+#[cfg(feature = "fugit")]
+impl<const NOM: u32, const DENOM: u32> From<fugit::Duration<u64, NOM, DENOM>> for Duration {
+    /// Rounds up to whole microseconds, and saturates at `Duration::MAX`.
+    fn from(duration: fugit::Duration<u64, NOM, DENOM>) -> Duration {
+        let micros = (duration.ticks() as u128 * NOM as u128 * 1_000_000).div_ceil(DENOM as u128);
+        Duration::from_micros(micros.min(u64::MAX as u128) as u64)
+    }
+}
+
+#[cfg(feature = "fugit")]
+impl From<Instant> for fugit::TimerInstantU64<1_000_000> {
+    fn from(instant: Instant) -> fugit::TimerInstantU64<1_000_000> {
+        fugit::TimerInstantU64::from_ticks(instant.micros)
+    }
+}
+
+#[cfg(feature = "fugit")]
+impl From<fugit::TimerInstantU64<1_000_000>> for Instant {
+    fn from(instant: fugit::TimerInstantU64<1_000_000>) -> Instant {
+        Instant {
+            micros: instant.ticks(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
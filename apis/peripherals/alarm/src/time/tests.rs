@@ -0,0 +1,163 @@
+extern crate std;
+
+use super::{Duration, Instant};
+use crate::{Convert, Hz, Ticks};
+use libtock_unittest::fake;
+
+type Clock = super::Clock<fake::Syscalls>;
+
+#[test]
+fn duration_conversions() {
+    assert_eq!(Duration::from_millis(1500).as_micros(), 1_500_000);
+    assert_eq!(Duration::from_secs(2).as_millis(), 2000);
+    assert_eq!(Duration::from_micros(2_999_999).as_secs(), 2);
+    assert_eq!(Duration::from_nanos(1001), Duration::from_micros(2));
+    assert_eq!(Duration::from_secs(u64::MAX), Duration::MAX);
+    assert_eq!(
+        Duration::from(core::time::Duration::from_nanos(1_999)),
+        Duration::from_micros(1)
+    );
+    assert_eq!(
+        core::time::Duration::from(Duration::from_millis(3)),
+        core::time::Duration::from_millis(3)
+    );
+
+    // Conversions to ticks round up, and conversions from ticks round down.
+    assert_eq!(Duration::from_micros(1).to_ticks(Hz(100)).0, 1);
+    assert_eq!(Duration::from_millis(10).to_ticks_u64(Hz(100)), 1);
+    assert_eq!(
+        Duration::from_ticks(1, Hz(32768)),
+        Duration::from_micros(30)
+    );
+    assert_eq!(Duration::from_ticks(3, Hz(16_000_000)), Duration::ZERO);
+    assert_eq!(
+        Duration::from_secs(3600).to_ticks(Hz(16_000_000)).0,
+        u32::MAX
+    );
+}
+
+#[test]
+fn instant_arithmetic() {
+    let start = Instant::default() + Duration::from_millis(5);
+    let end = start + Duration::from_millis(20);
+    assert_eq!(end - start, Duration::from_millis(20));
+    assert_eq!(start - end, Duration::ZERO);
+    assert_eq!(end - Duration::from_millis(20), start);
+    assert_eq!(start.checked_sub(Duration::from_millis(6)), None);
+    assert_eq!(end.since_epoch(), Duration::from_millis(25));
+}
+
+#[test]
+fn now_at_various_frequencies() {
+    for frequency in [100, 1000, 32768, 16_000_000] {
+        let kernel = fake::Kernel::new();
+        let driver = fake::Alarm::new(frequency);
+        kernel.add_driver(&driver);
+        let clock = Clock::new().unwrap();
+        assert_eq!(clock.frequency(), Hz(frequency));
+        let start = clock.now().unwrap();
+        driver.advance(frequency / 4);
+        let elapsed = start.elapsed(&clock).unwrap();
+        assert_eq!(elapsed.as_millis(), 250, "at {frequency} Hz");
+        drop(kernel);
+    }
+}
+
+#[test]
+fn wraparound() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(16_000_000);
+    kernel.add_driver(&driver);
+    driver.set_time(u32::MAX - 1000);
+    let clock = Clock::new().unwrap();
+    let start = clock.now().unwrap();
+    // Crosses two wraparounds, reading the time at least once per wraparound.
+    for _ in 0..4 {
+        driver.advance(u32::MAX / 2);
+        clock.now().unwrap();
+    }
+    assert_eq!(clock.ticks().unwrap(), (3 << 32) - 1005);
+    let elapsed = start.elapsed(&clock).unwrap();
+    // 2^33 - 4 ticks at 16 MHz, with each Instant rounded down to whole
+    // microseconds.
+    assert_eq!(elapsed.as_micros(), 536_870_912);
+}
+
+#[test]
+fn sleep_until() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(32768);
+    kernel.add_driver(&driver);
+    let clock = Clock::new().unwrap();
+    let deadline = clock.now().unwrap() + Duration::from_millis(100);
+    clock.sleep_until(deadline).unwrap();
+    assert!(clock.now().unwrap() >= deadline);
+    assert_eq!(driver.take_sleeps(), [3277]);
+
+    // A deadline in the past returns without sleeping.
+    clock.sleep_until(deadline).unwrap();
+    assert_eq!(driver.take_sleeps(), []);
+}
+
+// Sleeps longer than half the tick counter's range are split up so that the
+// clock sees every wraparound.
+#[test]
+fn long_sleep() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(1_000_000);
+    kernel.add_driver(&driver);
+    let clock = Clock::new().unwrap();
+    let start = clock.now().unwrap();
+    clock.sleep(Duration::from_secs(10_000)).unwrap();
+    assert_eq!(start.elapsed(&clock).unwrap(), Duration::from_secs(10_000));
+    let sleeps = driver.take_sleeps();
+    assert_eq!(sleeps.len(), 5);
+    assert!(sleeps.iter().all(|&ticks| ticks <= u32::MAX as u64 / 2));
+    assert_eq!(sleeps.iter().sum::<u64>(), 10_000_000_000);
+}
+
+#[test]
+fn convert() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(1000);
+    kernel.add_driver(&driver);
+    crate::Alarm::<fake::Syscalls>::sleep_for(Duration::from_micros(2500)).unwrap();
+    assert_eq!(driver.take_sleeps(), [3]);
+    assert_eq!(Ticks(5).to_ticks(Hz(1000)).0, 5);
+}
+
+#[cfg(feature = "rust_embedded")]
+#[test]
+fn delay_ns() {
+    use embedded_hal::delay::DelayNs;
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(32768);
+    kernel.add_driver(&driver);
+    let mut clock = Clock::new().unwrap();
+    clock.delay_ms(10);
+    clock.delay_us(100);
+    clock.delay_ns(0);
+    assert_eq!(driver.take_sleeps(), [328, 4]);
+}
+
+#[cfg(feature = "fugit")]
+#[test]
+fn fugit_conversions() {
+    assert_eq!(
+        Duration::from(fugit::MillisDurationU64::millis(3)),
+        Duration::from_millis(3)
+    );
+    // Rounds up to whole microseconds.
+    assert_eq!(
+        Duration::from(fugit::NanosDurationU64::nanos(1500)),
+        Duration::from_micros(2)
+    );
+    assert_eq!(
+        fugit::MicrosDurationU64::from(Duration::from_millis(3)),
+        fugit::MicrosDurationU64::micros(3000)
+    );
+    let instant = Instant::default() + Duration::from_micros(42);
+    let fugit_instant = fugit::TimerInstantU64::<1_000_000>::from(instant);
+    assert_eq!(fugit_instant.ticks(), 42);
+    assert_eq!(Instant::from(fugit_instant), instant);
+}
@@ -0,0 +1,359 @@
+//! Software timers multiplexed over the kernel's single alarm.
+
+use crate::{command, subscribe, Alarm, Convert, Hz, Ticks, DRIVER_NUM};
+use core::cell::Cell;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use libtock_platform as platform;
+use libtock_platform::share::Handle;
+use libtock_platform::subscribe::OneId;
+use libtock_platform::{DefaultConfig, ErrorCode, Subscribe, Syscalls, Upcall};
+
+/// Runs up to `N` software timers at once on top of the kernel's single alarm.
+/// Timers may be one-shot (`after`, `at`) or periodic (`every`), and call a
+/// callback each time they fire. Futures returned by `fired` and `sleep`
+/// complete when a timer fires, for use with an async executor.
+///
+/// `Timers` must be registered with `register` to receive the alarm's upcall;
+/// callbacks run during `yield_wait` or `yield_no_wait` calls, like other
+/// upcalls. While it is registered, nothing else may use the alarm driver
+/// (including `Alarm::sleep_for`).
+///
+/// Deadlines are tracked relative to the time a timer was started, so they
+/// remain correct when the 32-bit tick counter wraps around, as long as no
+/// timer is more than `u32::MAX` ticks overdue when `Timers` handles it.
+///
+/// # Example
+/// ```ignore
+/// use libtock::alarm::{Milliseconds, Timers};
+///
+/// let timers = Timers::<4>::new();
+/// let blink = |_| Leds::toggle(0).unwrap();
+/// share::scope(|subscribe| {
+///     timers.register(subscribe).unwrap();
+///     timers.every(Milliseconds(500), &blink).unwrap();
+///     loop {
+///         TockSyscalls::yield_wait();
+///     }
+/// });
+/// ```
+pub struct Timers<'a, S: Syscalls, const N: usize, C: platform::subscribe::Config = DefaultConfig> {
+    slots: [Slot<'a>; N],
+    frequency: Cell<Option<Hz>>,
+    // The (reference, dt) pair the kernel alarm is set to, if any.
+    kernel_alarm: Cell<Option<(u32, u32)>>,
+    _syscalls: PhantomData<(S, C)>,
+}
+
+/// Identifies a timer started by `Timers`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
+pub struct TimerId {
+    index: usize,
+    generation: u32,
+}
+
+impl<'a, S: Syscalls, const N: usize, C: platform::subscribe::Config> Timers<'a, S, N, C> {
+    pub fn new() -> Self {
+        Timers {
+            slots: core::array::from_fn(|_| Slot::default()),
+            frequency: Cell::new(None),
+            kernel_alarm: Cell::new(None),
+            _syscalls: PhantomData,
+        }
+    }
+
+    /// Subscribes to the alarm's upcall, which `Timers` needs to run timers.
+    /// Timers may be started before registering; they fire once registered.
+    pub fn register<'share>(
+        &'share self,
+        subscribe: Handle<Subscribe<'share, S, DRIVER_NUM, { subscribe::CALLBACK }>>,
+    ) -> Result<(), ErrorCode> {
+        S::subscribe::<_, _, C, DRIVER_NUM, { subscribe::CALLBACK }>(subscribe, self)?;
+        // A timer may have expired while unregistered.
+        self.service()
+    }
+
+    /// Starts a one-shot timer that calls `callback` once `delay` has passed.
+    pub fn after<T: Convert>(
+        &self,
+        delay: T,
+        callback: &'a dyn Fn(TimerId),
+    ) -> Result<TimerId, ErrorCode> {
+        let dt = delay.to_ticks(self.frequency()?).0;
+        self.start(Alarm::<S, C>::get_ticks()?, dt, None, callback)
+    }
+
+    /// Starts a one-shot timer that calls `callback` when the tick counter
+    /// reaches `deadline`. A deadline up to `u32::MAX / 2` ticks in the past
+    /// is considered to have passed, and fires right away; any other deadline
+    /// is in the future.
+    pub fn at(&self, deadline: Ticks, callback: &'a dyn Fn(TimerId)) -> Result<TimerId, ErrorCode> {
+        let now = Alarm::<S, C>::get_ticks()?;
+        let dt = match deadline.0.wrapping_sub(now) {
+            dt if dt > u32::MAX / 2 => 0,
+            dt => dt,
+        };
+        self.start(now, dt, None, callback)
+    }
+
+    /// Starts a periodic timer that calls `callback` every `period`, starting
+    /// one period from now. Each deadline is one period after the previous
+    /// deadline (not after the callback ran), so the timer does not drift. If
+    /// the process falls more than a whole period behind, the missed
+    /// deadlines are skipped and the callback runs once.
+    pub fn every<T: Convert>(
+        &self,
+        period: T,
+        callback: &'a dyn Fn(TimerId),
+    ) -> Result<TimerId, ErrorCode> {
+        let period = period.to_ticks(self.frequency()?).0;
+        if period == 0 {
+            return Err(ErrorCode::Invalid);
+        }
+        self.start(Alarm::<S, C>::get_ticks()?, period, Some(period), callback)
+    }
+
+    /// Stops a timer. Returns `Err(ErrorCode::Already)` if the timer already
+    /// fired (for one-shot timers) or was cancelled.
+    pub fn cancel(&self, id: TimerId) -> Result<(), ErrorCode> {
+        let slot = self.slot(id).ok_or(ErrorCode::Already)?;
+        if slot.timer.take().is_none() {
+            return Err(ErrorCode::Already);
+        }
+        slot.wake();
+        self.rearm()
+    }
+
+    /// Returns true if the timer has not yet fired (for one-shot timers) or
+    /// been cancelled.
+    pub fn is_active(&self, id: TimerId) -> bool {
+        self.slot(id).is_some_and(|slot| slot.is_armed())
+    }
+
+    /// Returns a future that completes the next time the timer fires, or when
+    /// it is cancelled.
+    pub fn fired(&self, id: TimerId) -> Fired<'_, 'a> {
+        let fires = self.slots[id.index].fires.get();
+        Fired {
+            slot: &self.slots[id.index],
+            id,
+            fires,
+        }
+    }
+
+    /// Starts a one-shot timer without a callback, and returns a future that
+    /// completes when it fires.
+    pub fn sleep<T: Convert>(&self, delay: T) -> Result<Fired<'_, 'a>, ErrorCode> {
+        let id = self.after(delay, &|_| {})?;
+        Ok(self.fired(id))
+    }
+}
+
+impl<S: Syscalls, const N: usize, C: platform::subscribe::Config> Default for Timers<'_, S, N, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Syscalls, const N: usize, C: platform::subscribe::Config>
+    Upcall<OneId<DRIVER_NUM, { subscribe::CALLBACK }>> for Timers<'_, S, N, C>
+{
+    fn upcall(&self, _now: u32, _expiration: u32, _: u32) {
+        self.kernel_alarm.set(None);
+        // There is no way to report an error from an upcall. If the alarm
+        // driver failed, the next call that starts or cancels a timer will
+        // report it.
+        let _ = self.service();
+    }
+}
+
+/// A future that completes when a timer fires or is cancelled. Returned by
+/// `Timers::fired` and `Timers::sleep`.
+pub struct Fired<'t, 'a> {
+    slot: &'t Slot<'a>,
+    id: TimerId,
+    // The value of the slot's fire count when the future was created.
+    fires: u32,
+}
+
+impl Future for Fired<'_, '_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let slot = self.slot;
+        if slot.generation.get() != self.id.generation
+            || slot.fires.get() != self.fires
+            || !slot.is_armed()
+        {
+            return Poll::Ready(());
+        }
+        slot.waker.set(Some(cx.waker().clone()));
+        Poll::Pending
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Implementation details below
+// -----------------------------------------------------------------------------
+
+#[derive(Default)]
+struct Slot<'a> {
+    timer: Cell<Option<Timer<'a>>>,
+    // Incremented each time the slot is reused, so that stale TimerIds can be
+    // detected.
+    generation: Cell<u32>,
+    // Incremented each time the timer fires.
+    fires: Cell<u32>,
+    waker: Cell<Option<Waker>>,
+}
+
+#[derive(Clone, Copy)]
+struct Timer<'a> {
+    // The timer expires once `dt` ticks have passed since `reference`, like
+    // the kernel alarm's SET_ABSOLUTE command.
+    reference: u32,
+    dt: u32,
+    period: Option<u32>,
+    callback: &'a dyn Fn(TimerId),
+}
+
+impl Timer<'_> {
+    // Returns the number of ticks until the timer expires.
+    fn remaining(&self, now: u32) -> u32 {
+        self.dt.saturating_sub(now.wrapping_sub(self.reference))
+    }
+}
+
+impl Slot<'_> {
+    fn is_armed(&self) -> bool {
+        self.timer.get().is_some()
+    }
+
+    fn wake(&self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<'a, S: Syscalls, const N: usize, C: platform::subscribe::Config> Timers<'a, S, N, C> {
+    fn frequency(&self) -> Result<Hz, ErrorCode> {
+        if let Some(frequency) = self.frequency.get() {
+            return Ok(frequency);
+        }
+        let frequency = Alarm::<S, C>::get_frequency()?;
+        self.frequency.set(Some(frequency));
+        Ok(frequency)
+    }
+
+    fn slot(&self, id: TimerId) -> Option<&Slot<'a>> {
+        self.slots
+            .get(id.index)
+            .filter(|slot| slot.generation.get() == id.generation)
+    }
+
+    fn start(
+        &self,
+        reference: u32,
+        dt: u32,
+        period: Option<u32>,
+        callback: &'a dyn Fn(TimerId),
+    ) -> Result<TimerId, ErrorCode> {
+        let index = self
+            .slots
+            .iter()
+            .position(|slot| !slot.is_armed())
+            .ok_or(ErrorCode::NoMem)?;
+        let slot = &self.slots[index];
+        slot.generation.set(slot.generation.get().wrapping_add(1));
+        slot.fires.set(0);
+        // A future for the slot's previous timer is done.
+        slot.wake();
+        slot.timer.set(Some(Timer {
+            reference,
+            dt,
+            period,
+            callback,
+        }));
+        self.rearm()?;
+        Ok(TimerId {
+            index,
+            generation: slot.generation.get(),
+        })
+    }
+
+    // Fires every expired timer, most overdue first, then re-arms the kernel
+    // alarm for the next deadline. The time is read again after each callback,
+    // as callbacks take time to run and may start timers, whose reference must
+    // not be later than the time they are checked against.
+    fn service(&self) -> Result<(), ErrorCode> {
+        loop {
+            let now = Alarm::<S, C>::get_ticks()?;
+            let Some((index, mut timer)) = self.most_overdue(now) else {
+                break;
+            };
+            let slot = &self.slots[index];
+            match timer.period {
+                None => slot.timer.set(None),
+                Some(period) => {
+                    // Skips to the first deadline after now.
+                    let passed = now.wrapping_sub(timer.reference);
+                    timer.reference = timer.reference.wrapping_add(passed / period * period);
+                    slot.timer.set(Some(timer));
+                }
+            }
+            slot.fires.set(slot.fires.get().wrapping_add(1));
+            slot.wake();
+            (timer.callback)(TimerId {
+                index,
+                generation: slot.generation.get(),
+            });
+        }
+        self.rearm()
+    }
+
+    // Returns the expired timer that expired the longest ago, and its index.
+    fn most_overdue(&self, now: u32) -> Option<(usize, Timer<'a>)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| Some((index, slot.timer.get()?)))
+            .filter(|(_, timer)| timer.remaining(now) == 0)
+            .max_by_key(|(_, timer)| now.wrapping_sub(timer.reference) - timer.dt)
+    }
+
+    // Sets the kernel alarm to the earliest deadline, or stops it if no timer
+    // is running.
+    fn rearm(&self) -> Result<(), ErrorCode> {
+        let now = Alarm::<S, C>::get_ticks()?;
+        let next = self
+            .slots
+            .iter()
+            .filter_map(|slot| slot.timer.get())
+            .min_by_key(|timer| timer.remaining(now))
+            .map(|timer| (timer.reference, timer.dt));
+        if next == self.kernel_alarm.get() {
+            return Ok(());
+        }
+        match next {
+            Some((reference, dt)) => {
+                S::command(DRIVER_NUM, command::SET_ABSOLUTE, reference, dt)
+                    .to_result()
+                    .map(|_when: u32| ())?;
+            }
+            // STOP fails with ALREADY if the alarm already fired, which is
+            // fine.
+            None => {
+                let _ = S::command(DRIVER_NUM, command::STOP, 0, 0);
+            }
+        }
+        self.kernel_alarm.set(next);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
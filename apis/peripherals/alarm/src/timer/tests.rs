@@ -0,0 +1,224 @@
+extern crate std;
+
+use crate::{Milliseconds, Ticks};
+use core::cell::{OnceCell, RefCell};
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+use libtock_platform::{share, ErrorCode, Syscalls};
+use libtock_unittest::fake;
+use std::vec::Vec;
+
+type Timers<'a, const N: usize> = super::Timers<'a, fake::Syscalls, N>;
+
+// Records which callbacks ran, and when.
+#[derive(Default)]
+struct Log(RefCell<Vec<(&'static str, u32)>>);
+
+impl Log {
+    fn callback<'a>(
+        &'a self,
+        driver: &'a fake::Alarm,
+        name: &'static str,
+    ) -> impl Fn(super::TimerId) + 'a {
+        move |_| self.0.borrow_mut().push((name, driver.now()))
+    }
+
+    fn take(&self) -> Vec<(&'static str, u32)> {
+        self.0.take()
+    }
+}
+
+#[test]
+fn one_shots_fire_in_order() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(1000);
+    kernel.add_driver(&driver);
+    let log = Log::default();
+    let (late, early, middle) = (
+        log.callback(&driver, "late"),
+        log.callback(&driver, "early"),
+        log.callback(&driver, "middle"),
+    );
+    let timers = Timers::<4>::new();
+    share::scope(|subscribe| {
+        timers.register(subscribe).unwrap();
+        let late = timers.after(Milliseconds(30), &late).unwrap();
+        assert_eq!(driver.expiration(), Some(30));
+        timers.after(Ticks(10), &early).unwrap();
+        // The kernel alarm is set to the earliest deadline.
+        assert_eq!(driver.expiration(), Some(10));
+        timers.at(Ticks(20), &middle).unwrap();
+        assert!(timers.is_active(late));
+        for _ in 0..3 {
+            fake::Syscalls::yield_wait();
+        }
+        assert!(!timers.is_active(late));
+    });
+    assert_eq!(log.take(), [("early", 10), ("middle", 20), ("late", 30)]);
+    assert_eq!(driver.expiration(), None);
+}
+
+// Timers that expire together fire in a single upcall, most overdue first.
+#[test]
+fn simultaneous_expiration() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(1000);
+    kernel.add_driver(&driver);
+    let log = Log::default();
+    let (a, b) = (log.callback(&driver, "a"), log.callback(&driver, "b"));
+    let timers = Timers::<2>::new();
+    share::scope(|subscribe| {
+        timers.register(subscribe).unwrap();
+        timers.after(Ticks(50), &a).unwrap();
+        timers.after(Ticks(40), &b).unwrap();
+        driver.advance(60);
+        fake::Syscalls::yield_no_wait();
+    });
+    assert_eq!(log.take(), [("b", 60), ("a", 60)]);
+}
+
+#[test]
+fn periodic_without_drift() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(1000);
+    kernel.add_driver(&driver);
+    let log = Log::default();
+    let tick = log.callback(&driver, "tick");
+    let timers = Timers::<1>::new();
+    share::scope(|subscribe| {
+        timers.register(subscribe).unwrap();
+        let id = timers.every(Milliseconds(100), &tick).unwrap();
+        fake::Syscalls::yield_wait();
+        // The process takes a while to handle the upcall, but the next
+        // deadline is still one period after the previous deadline.
+        driver.advance(30);
+        assert_eq!(driver.expiration(), Some(200));
+        fake::Syscalls::yield_wait();
+        // Missed deadlines are skipped.
+        driver.advance(250);
+        fake::Syscalls::yield_no_wait();
+        assert_eq!(driver.expiration(), Some(500));
+        assert!(timers.is_active(id));
+        assert_eq!(timers.cancel(id), Ok(()));
+        assert_eq!(timers.cancel(id), Err(ErrorCode::Already));
+    });
+    assert_eq!(log.take(), [("tick", 100), ("tick", 200), ("tick", 450)]);
+    assert_eq!(driver.expiration(), None);
+    assert_eq!(timers.every(Ticks(0), &|_| {}), Err(ErrorCode::Invalid));
+}
+
+#[test]
+fn cancel_and_capacity() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(1000);
+    kernel.add_driver(&driver);
+    let log = Log::default();
+    let (a, b, c) = (
+        log.callback(&driver, "a"),
+        log.callback(&driver, "b"),
+        log.callback(&driver, "c"),
+    );
+    let timers = Timers::<2>::new();
+    share::scope(|subscribe| {
+        timers.register(subscribe).unwrap();
+        let first = timers.after(Ticks(10), &a).unwrap();
+        timers.after(Ticks(20), &b).unwrap();
+        assert_eq!(timers.after(Ticks(30), &c), Err(ErrorCode::NoMem));
+        assert_eq!(timers.cancel(first), Ok(()));
+        assert_eq!(driver.expiration(), Some(20));
+        // The cancelled timer's slot is reused, and its old ID is stale.
+        let second = timers.after(Ticks(30), &c).unwrap();
+        assert_ne!(first, second);
+        assert!(!timers.is_active(first));
+        assert_eq!(timers.cancel(first), Err(ErrorCode::Already));
+        fake::Syscalls::yield_wait();
+        fake::Syscalls::yield_wait();
+    });
+    assert_eq!(log.take(), [("b", 20), ("c", 30)]);
+}
+
+#[test]
+fn wraparound() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(1000);
+    kernel.add_driver(&driver);
+    driver.set_time(u32::MAX - 50);
+    let log = Log::default();
+    let (a, b, past) = (
+        log.callback(&driver, "a"),
+        log.callback(&driver, "b"),
+        log.callback(&driver, "past"),
+    );
+    let timers = Timers::<3>::new();
+    share::scope(|subscribe| {
+        timers.register(subscribe).unwrap();
+        timers.after(Ticks(100), &a).unwrap();
+        timers.at(Ticks(u32::MAX - 10), &b).unwrap();
+        fake::Syscalls::yield_wait();
+        fake::Syscalls::yield_wait();
+        // A deadline shortly before now has passed, and fires right away.
+        timers.at(Ticks(10), &past).unwrap();
+        fake::Syscalls::yield_no_wait();
+    });
+    assert_eq!(log.take(), [("b", u32::MAX - 10), ("a", 49), ("past", 49)]);
+}
+
+// A timer started by a callback that took some time to run measures its delay
+// from when it was started, rather than from when the upcall arrived.
+#[test]
+fn start_from_slow_callback() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(1000);
+    kernel.add_driver(&driver);
+    let log = Log::default();
+    let restarted = log.callback(&driver, "restarted");
+    let timers_ref: OnceCell<&Timers<2>> = OnceCell::new();
+    // Starts another timer after taking 5 ticks.
+    let restart = |_| {
+        driver.advance(5);
+        timers_ref
+            .get()
+            .unwrap()
+            .after(Ticks(10), &restarted)
+            .unwrap();
+    };
+    let timers = Timers::<2>::new();
+    let _ = timers_ref.set(&timers);
+    share::scope(|subscribe| {
+        timers.register(subscribe).unwrap();
+        timers.after(Ticks(10), &restart).unwrap();
+        fake::Syscalls::yield_wait();
+        assert!(log.take().is_empty());
+        assert_eq!(driver.expiration(), Some(25));
+        fake::Syscalls::yield_wait();
+    });
+    assert_eq!(log.take(), [("restarted", 25)]);
+}
+
+#[test]
+fn futures() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Alarm::new(1000);
+    kernel.add_driver(&driver);
+    let timers = Timers::<2>::new();
+    let mut context = Context::from_waker(Waker::noop());
+    share::scope(|subscribe| {
+        timers.register(subscribe).unwrap();
+        let mut sleep = pin!(timers.sleep(Ticks(10)).unwrap());
+        assert_eq!(sleep.as_mut().poll(&mut context), Poll::Pending);
+        fake::Syscalls::yield_wait();
+        assert_eq!(sleep.as_mut().poll(&mut context), Poll::Ready(()));
+
+        let id = timers.every(Ticks(10), &|_| {}).unwrap();
+        let mut fired = pin!(timers.fired(id));
+        assert_eq!(fired.as_mut().poll(&mut context), Poll::Pending);
+        fake::Syscalls::yield_wait();
+        assert_eq!(fired.as_mut().poll(&mut context), Poll::Ready(()));
+        // Cancelling a timer completes its futures.
+        let mut fired = pin!(timers.fired(id));
+        assert_eq!(fired.as_mut().poll(&mut context), Poll::Pending);
+        timers.cancel(id).unwrap();
+        assert_eq!(fired.as_mut().poll(&mut context), Poll::Ready(()));
+    });
+}
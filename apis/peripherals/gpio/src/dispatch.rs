@@ -0,0 +1,212 @@
+//! Dispatching GPIO interrupts to handlers for individual pins.
+
+use crate::{Gpio, GpioState, InputPin, PinInterruptEdge, Pull, DRIVER_NUM};
+use core::cell::Cell;
+use core::marker::PhantomData;
+use core::task::Waker;
+use libtock_platform::share::Handle;
+use libtock_platform::subscribe::OneId;
+use libtock_platform::{DefaultConfig, ErrorCode, Subscribe, Syscalls, Upcall};
+
+// A registered handler or waiting future: the pin number, the edge it is for,
+// and what to do when an interrupt on that edge occurs.
+type Slot<'a> = Option<(u32, PinInterruptEdge, Action<'a>)>;
+
+#[derive(Clone, Copy)]
+enum Action<'a> {
+    Call(&'a dyn Fn(GpioState)),
+    // A future (see `AsyncInputPin`), which is woken through the slot's waker.
+    // `fired` is set when the interrupt occurs.
+    Wake { fired: bool },
+}
+
+/// Calls handlers registered for individual pins when GPIO interrupts occur,
+/// so that several components can react to their own pins while only one
+/// upcall is subscribed. Also wakes the futures of `AsyncInputPin`s created by
+/// `async_pin` (with the `rust_embedded` feature). Holds up to `N` handlers and
+/// waiting futures.
+///
+/// # Example
+/// ```ignore
+/// use libtock::gpio::{Gpio, PinDispatcher, PinInterruptEdge, PullUp};
+/// use libtock_platform::share;
+///
+/// let button_pin = Gpio::get_pin(0).unwrap();
+/// let button = button_pin.make_input::<PullUp>().unwrap();
+/// let on_press = |_| { /* ... */ };
+/// let dispatcher = PinDispatcher::<4>::new();
+/// share::scope(|subscribe| {
+///     Gpio::register_dispatcher(&dispatcher, subscribe).unwrap();
+///     // Enables the pin's interrupt until `_handler` is dropped.
+///     let _handler = dispatcher
+///         .register(&button, PinInterruptEdge::Falling, &on_press)
+///         .unwrap();
+///     loop {
+///         TockSyscalls::yield_wait();
+///     }
+/// });
+/// ```
+pub struct PinDispatcher<'a, S: Syscalls, const N: usize> {
+    slots: [Cell<Slot<'a>>; N],
+    wakers: [Cell<Option<Waker>>; N],
+    _syscalls: PhantomData<S>,
+}
+
+/// A handler registered with a `PinDispatcher`. Dropping it unregisters the
+/// handler, and disables the pin's interrupt if no other handler is registered
+/// for the pin.
+pub struct PinHandler<'d, 'p, 'a, S: Syscalls, P: Pull, const N: usize> {
+    dispatcher: &'d PinDispatcher<'a, S, N>,
+    pin: &'p InputPin<'p, S, P>,
+    index: usize,
+}
+
+impl<S: Syscalls> Gpio<S> {
+    /// Subscribes `dispatcher` to GPIO interrupts. Like `register_listener`,
+    /// this replaces the previously registered listener.
+    pub fn register_dispatcher<'share, const N: usize>(
+        dispatcher: &'share PinDispatcher<'_, S, N>,
+        subscribe: Handle<Subscribe<'share, S, DRIVER_NUM, 0>>,
+    ) -> Result<(), ErrorCode> {
+        S::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, dispatcher)
+    }
+}
+
+impl<'a, S: Syscalls, const N: usize> PinDispatcher<'a, S, N> {
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { Cell::new(None) }; N],
+            wakers: [const { Cell::new(None) }; N],
+            _syscalls: PhantomData,
+        }
+    }
+
+    /// Registers `handler` to be called with the pin's state when an
+    /// interrupt on `edge` occurs on `pin`, and enables the pin's interrupt.
+    ///
+    /// Several handlers may be registered for the same pin. If they are for
+    /// different edges, the interrupt is enabled on either edge, and each
+    /// handler is only called for its own edge.
+    ///
+    /// Returns `NoMem` if `N` handlers are already registered.
+    pub fn register<'d, 'p, P: Pull>(
+        &'d self,
+        pin: &'p InputPin<'p, S, P>,
+        edge: PinInterruptEdge,
+        handler: &'a dyn Fn(GpioState),
+    ) -> Result<PinHandler<'d, 'p, 'a, S, P, N>, ErrorCode> {
+        let index = self.add(pin, edge, Action::Call(handler))?;
+        Ok(PinHandler {
+            dispatcher: self,
+            pin,
+            index,
+        })
+    }
+
+    // Adds a future waiting for an interrupt on `edge`, and enables the pin's
+    // interrupt. Returns the future's slot.
+    #[cfg_attr(not(feature = "rust_embedded"), allow(dead_code))]
+    pub(crate) fn add_waiter<P: Pull>(
+        &self,
+        pin: &InputPin<'_, S, P>,
+        edge: PinInterruptEdge,
+    ) -> Result<usize, ErrorCode> {
+        self.add(pin, edge, Action::Wake { fired: false })
+    }
+
+    // Returns whether the interrupt the future in slot `index` waits for has
+    // occurred. If not, `waker` is woken when it does.
+    #[cfg_attr(not(feature = "rust_embedded"), allow(dead_code))]
+    pub(crate) fn poll_waiter(&self, index: usize, waker: &Waker) -> bool {
+        if let Some((_, _, Action::Wake { fired: true })) = self.slots[index].get() {
+            return true;
+        }
+        self.wakers[index].set(Some(waker.clone()));
+        false
+    }
+
+    // Frees slot `index`, and disables the pin's interrupt if no other handler
+    // or future is registered for the pin.
+    pub(crate) fn remove<P: Pull>(&self, pin: &InputPin<'_, S, P>, index: usize) {
+        self.slots[index].set(None);
+        self.wakers[index].set(None);
+        let _ = self.update(pin);
+    }
+
+    fn add<P: Pull>(
+        &self,
+        pin: &InputPin<'_, S, P>,
+        edge: PinInterruptEdge,
+        action: Action<'a>,
+    ) -> Result<usize, ErrorCode> {
+        let index = self
+            .slots
+            .iter()
+            .position(|slot| slot.get().is_none())
+            .ok_or(ErrorCode::NoMem)?;
+        self.slots[index].set(Some((pin.pin.pin_number, edge, action)));
+        if let Err(error) = self.update(pin) {
+            self.slots[index].set(None);
+            return Err(error);
+        }
+        Ok(index)
+    }
+
+    // Enables the pin's interrupt on the edges its handlers are registered
+    // for, or disables it if it has no handlers.
+    fn update<P: Pull>(&self, pin: &InputPin<'_, S, P>) -> Result<(), ErrorCode> {
+        let edge = self
+            .slots
+            .iter()
+            .filter_map(Cell::get)
+            .filter(|&(pin_number, _, _)| pin_number == pin.pin.pin_number)
+            .map(|(_, edge, _)| edge)
+            .reduce(|a, b| if a == b { a } else { PinInterruptEdge::Either });
+        match edge {
+            Some(edge) => pin.enable_interrupts(edge),
+            None => pin.disable_interrupts(),
+        }
+    }
+}
+
+impl<S: Syscalls, const N: usize> Default for PinDispatcher<'_, S, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Syscalls, const N: usize> Upcall<OneId<DRIVER_NUM, 0>> for PinDispatcher<'_, S, N> {
+    fn upcall(&self, gpio_index: u32, value: u32, _arg2: u32) {
+        let state = GpioState::from(value);
+        // Handlers may register or drop handlers, so each slot is read just
+        // before it is used.
+        for (slot, waker) in self.slots.iter().zip(&self.wakers) {
+            let Some((pin_number, edge, action)) = slot.get() else {
+                continue;
+            };
+            let matches = match edge {
+                PinInterruptEdge::Either => true,
+                PinInterruptEdge::Rising => state == GpioState::High,
+                PinInterruptEdge::Falling => state == GpioState::Low,
+            };
+            if pin_number != gpio_index || !matches {
+                continue;
+            }
+            match action {
+                Action::Call(handler) => handler(state),
+                Action::Wake { .. } => {
+                    slot.set(Some((pin_number, edge, Action::Wake { fired: true })));
+                    if let Some(waker) = waker.take() {
+                        waker.wake();
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<S: Syscalls, P: Pull, const N: usize> Drop for PinHandler<'_, '_, '_, S, P, N> {
+    fn drop(&mut self) {
+        self.dispatcher.remove(self.pin, self.index);
+    }
+}
@@ -0,0 +1,200 @@
+//! `embedded_hal` digital traits, so that driver crates for sensors and
+//! displays can use Tock pins.
+
+use crate::{GpioState, InputPin, OutputPin, PinDispatcher, PinInterruptEdge, Pull};
+use core::future::Future;
+use core::task::{Context, Poll};
+use libtock_platform::{ErrorCode, Syscalls};
+
+impl<S: Syscalls> embedded_hal::digital::ErrorType for OutputPin<'_, S> {
+    type Error = ErrorCode;
+}
+
+impl<S: Syscalls> embedded_hal::digital::OutputPin for OutputPin<'_, S> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.clear()
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set()
+    }
+}
+
+impl<S: Syscalls> embedded_hal::digital::StatefulOutputPin for OutputPin<'_, S> {
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.state()? == GpioState::High)
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.state()? == GpioState::Low)
+    }
+
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        OutputPin::toggle(self)
+    }
+}
+
+impl<S: Syscalls, P: Pull> embedded_hal::digital::ErrorType for InputPin<'_, S, P> {
+    type Error = ErrorCode;
+}
+
+impl<S: Syscalls, P: Pull> embedded_hal::digital::InputPin for InputPin<'_, S, P> {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.read()? == GpioState::High)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.read()? == GpioState::Low)
+    }
+}
+
+/// An input pin that implements `embedded_hal_async::digital::Wait`, created by
+/// `PinDispatcher::async_pin`.
+///
+/// Each wait registers with the dispatcher, which must be subscribed with
+/// `Gpio::register_dispatcher`, and enables the pin's interrupt. Its future
+/// returns `Pending` until the interrupt occurs, at which point the
+/// dispatcher's upcall wakes the future's waker. libtock-rs does not include an
+/// executor; the executor should call yield-wait when no future is ready, so
+/// that upcalls are delivered. Handlers and other pins registered with the same
+/// dispatcher keep working while a future waits.
+pub struct AsyncInputPin<'d, 'p, 'a, S: Syscalls, P: Pull, const N: usize> {
+    dispatcher: &'d PinDispatcher<'a, S, N>,
+    pin: &'p InputPin<'p, S, P>,
+}
+
+impl<'a, S: Syscalls, const N: usize> PinDispatcher<'a, S, N> {
+    /// Returns `pin` as an `AsyncInputPin` whose waits are woken by this
+    /// dispatcher.
+    pub fn async_pin<'d, 'p, P: Pull>(
+        &'d self,
+        pin: &'p InputPin<'p, S, P>,
+    ) -> AsyncInputPin<'d, 'p, 'a, S, P, N> {
+        AsyncInputPin {
+            dispatcher: self,
+            pin,
+        }
+    }
+}
+
+impl<S: Syscalls, P: Pull, const N: usize> embedded_hal::digital::ErrorType
+    for AsyncInputPin<'_, '_, '_, S, P, N>
+{
+    type Error = ErrorCode;
+}
+
+impl<S: Syscalls, P: Pull, const N: usize> embedded_hal::digital::InputPin
+    for AsyncInputPin<'_, '_, '_, S, P, N>
+{
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.pin.read()? == GpioState::High)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.pin.read()? == GpioState::Low)
+    }
+}
+
+impl<S: Syscalls, P: Pull, const N: usize> embedded_hal_async::digital::Wait
+    for AsyncInputPin<'_, '_, '_, S, P, N>
+{
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.wait(PinInterruptEdge::Rising, Some(GpioState::High))
+            .await
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.wait(PinInterruptEdge::Falling, Some(GpioState::Low))
+            .await
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait(PinInterruptEdge::Rising, None).await
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait(PinInterruptEdge::Falling, None).await
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait(PinInterruptEdge::Either, None).await
+    }
+}
+
+impl<'d, 'p, 'a, S: Syscalls, P: Pull, const N: usize> AsyncInputPin<'d, 'p, 'a, S, P, N> {
+    // Waits for an interrupt on `edge`. If `level` is given, completes
+    // immediately if the pin is already at that level.
+    fn wait(
+        &self,
+        edge: PinInterruptEdge,
+        level: Option<GpioState>,
+    ) -> Interrupt<'d, 'p, 'a, S, P, N> {
+        Interrupt {
+            dispatcher: self.dispatcher,
+            pin: self.pin,
+            edge,
+            level,
+            index: None,
+        }
+    }
+}
+
+// The future returned by `AsyncInputPin::wait`. It registers with the
+// dispatcher when it is first polled, and unregisters when it completes or is
+// dropped.
+struct Interrupt<'d, 'p, 'a, S: Syscalls, P: Pull, const N: usize> {
+    dispatcher: &'d PinDispatcher<'a, S, N>,
+    pin: &'p InputPin<'p, S, P>,
+    edge: PinInterruptEdge,
+    level: Option<GpioState>,
+    // The dispatcher slot, once registered.
+    index: Option<usize>,
+}
+
+impl<S: Syscalls, P: Pull, const N: usize> Future for Interrupt<'_, '_, '_, S, P, N> {
+    type Output = Result<(), ErrorCode>;
+
+    fn poll(mut self: core::pin::Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let index = match self.index {
+            Some(index) => index,
+            None => {
+                let index = match self.dispatcher.add_waiter(self.pin, self.edge) {
+                    Ok(index) => index,
+                    Err(error) => return Poll::Ready(Err(error)),
+                };
+                self.index = Some(index);
+                // The level is read after the interrupt is enabled, so a
+                // change in between is not missed.
+                if let Some(level) = self.level {
+                    match self.pin.read() {
+                        Ok(state) if state != level => {}
+                        result => {
+                            self.finish();
+                            return Poll::Ready(result.map(|_| ()));
+                        }
+                    }
+                }
+                index
+            }
+        };
+        if !self.dispatcher.poll_waiter(index, context.waker()) {
+            return Poll::Pending;
+        }
+        self.finish();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: Syscalls, P: Pull, const N: usize> Interrupt<'_, '_, '_, S, P, N> {
+    fn finish(&mut self) {
+        if let Some(index) = self.index.take() {
+            self.dispatcher.remove(self.pin, index);
+        }
+    }
+}
+
+impl<S: Syscalls, P: Pull, const N: usize> Drop for Interrupt<'_, '_, '_, S, P, N> {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
@@ -17,22 +17,41 @@ use libtock_platform::{
 /// let _ = pin.set();
 /// ```
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
 pub enum GpioState {
     Low = 0,
     High = 1,
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
 pub enum PinInterruptEdge {
     Either = 0,
     Rising = 1,
     Falling = 2,
 }
 
+#[deprecated(note = "GPIO operations return `ErrorCode`")]
 pub enum Error {
     Invalid,
     Failed,
 }
 
+// Derived impls would use the deprecated variants without allowing it.
+#[cfg(feature = "ufmt")]
+#[allow(deprecated)]
+impl ufmt::uDebug for Error {
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        f.write_str(match self {
+            Error::Invalid => "Invalid",
+            Error::Failed => "Failed",
+        })
+    }
+}
+
 pub trait Pull {
     const MODE: u32;
 }
@@ -78,7 +97,8 @@ impl<S: Syscalls> Gpio<S> {
     ///
     /// There can be only one single listener registered at a time.
     /// Each time this function is used, it will replace the
-    /// previously registered listener.
+    /// previously registered listener. To handle interrupts for different
+    /// pins separately, use a `PinDispatcher`.
     pub fn register_listener<'share, F: Fn(u32, GpioState)>(
         listener: &'share GpioInterruptListener<F>,
         subscribe: Handle<Subscribe<'share, S, DRIVER_NUM, 0>>,
@@ -128,7 +148,10 @@ pub struct Pin<S: Syscalls> {
 impl<S: Syscalls> Pin<S> {
     pub fn make_output(&mut self) -> Result<OutputPin<'_, S>, ErrorCode> {
         Gpio::<S>::enable_gpio_output(self.pin_number)?;
-        Ok(OutputPin { pin: self })
+        Ok(OutputPin {
+            pin: self,
+            state: None,
+        })
     }
 
     pub fn make_input<P: Pull>(&self) -> Result<InputPin<'_, S, P>, ErrorCode> {
@@ -142,17 +165,37 @@ impl<S: Syscalls> Pin<S> {
 
 pub struct OutputPin<'a, S: Syscalls> {
     pin: &'a Pin<S>,
+    // The level last written to the pin, if known.
+    state: Option<GpioState>,
 }
 
 impl<S: Syscalls> OutputPin<'_, S> {
     pub fn toggle(&mut self) -> Result<(), ErrorCode> {
-        Gpio::<S>::toggle(self.pin.pin_number)
+        Gpio::<S>::toggle(self.pin.pin_number)?;
+        self.state = self.state.map(|state| match state {
+            GpioState::Low => GpioState::High,
+            GpioState::High => GpioState::Low,
+        });
+        Ok(())
     }
     pub fn set(&mut self) -> Result<(), ErrorCode> {
-        Gpio::<S>::write(self.pin.pin_number, GpioState::High)
+        Gpio::<S>::write(self.pin.pin_number, GpioState::High)?;
+        self.state = Some(GpioState::High);
+        Ok(())
     }
     pub fn clear(&mut self) -> Result<(), ErrorCode> {
-        Gpio::<S>::write(self.pin.pin_number, GpioState::Low)
+        Gpio::<S>::write(self.pin.pin_number, GpioState::Low)?;
+        self.state = Some(GpioState::Low);
+        Ok(())
+    }
+
+    /// Returns the level the pin is driven to. If the pin has not been set or
+    /// cleared since it was made an output, this reads the pin's level.
+    pub fn state(&self) -> Result<GpioState, ErrorCode> {
+        match self.state {
+            Some(state) => Ok(state),
+            None => Gpio::<S>::read(self.pin.pin_number),
+        }
     }
 }
 
@@ -230,21 +273,12 @@ impl<S: Syscalls> Gpio<S> {
     }
 }
 
+mod dispatch;
 #[cfg(feature = "rust_embedded")]
-impl<S: Syscalls> embedded_hal::digital::ErrorType for OutputPin<'_, S> {
-    type Error = ErrorCode;
-}
-
+mod hal;
+pub use dispatch::{PinDispatcher, PinHandler};
 #[cfg(feature = "rust_embedded")]
-impl<S: Syscalls> embedded_hal::digital::OutputPin for OutputPin<'_, S> {
-    fn set_low(&mut self) -> Result<(), Self::Error> {
-        self.clear()
-    }
-
-    fn set_high(&mut self) -> Result<(), Self::Error> {
-        self.set()
-    }
-}
+pub use hal::AsyncInputPin;
 
 #[cfg(test)]
 mod tests;
@@ -6,6 +6,7 @@ use libtock_unittest::fake::{self, GpioMode, InterruptEdge, PullMode};
 use crate::{GpioInterruptListener, GpioState, PinInterruptEdge, PullDown, PullNone, PullUp};
 
 type Gpio = super::Gpio<fake::Syscalls>;
+type PinDispatcher<'a, const N: usize> = super::PinDispatcher<'a, fake::Syscalls, N>;
 
 #[test]
 fn no_driver() {
@@ -294,3 +295,281 @@ fn subscribe() {
     assert_eq!(driver.set_value(0, false), Ok(()));
     assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
 }
+
+// Drives an input pin from an output pin wired to it, with an interrupt on the
+// input.
+#[test]
+fn loopback() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Gpio::<4>::new();
+    kernel.add_driver(&driver);
+    driver.connect(0, 3);
+
+    let mut output_pin = Gpio::get_pin(0).unwrap();
+    let input_pin = Gpio::get_pin(3).unwrap();
+    let mut output = output_pin.make_output().unwrap();
+    let input = input_pin.make_input::<PullDown>().unwrap();
+    input.enable_interrupts(PinInterruptEdge::Rising).unwrap();
+
+    let gpio_state = Cell::<Option<GpioState>>::new(None);
+    let listener = GpioInterruptListener(|gpio, state| {
+        assert_eq!(gpio, 3);
+        gpio_state.set(Some(state));
+    });
+    share::scope(|subscribe| {
+        Gpio::register_listener(&listener, subscribe).unwrap();
+        output.set().unwrap();
+        assert_eq!(input.read(), Ok(GpioState::High));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(gpio_state.get(), Some(GpioState::High));
+
+        output.clear().unwrap();
+        assert_eq!(input.read(), Ok(GpioState::Low));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+    });
+}
+
+// A button with a pull-up, read through an input pin.
+#[test]
+fn button() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Gpio::<1>::new();
+    kernel.add_driver(&driver);
+    let button = driver.add_button(0, false);
+
+    let pin = Gpio::get_pin(0).unwrap();
+    let input = pin.make_input::<PullUp>().unwrap();
+    assert_eq!(input.read(), Ok(GpioState::High));
+    button.press();
+    assert_eq!(input.read(), Ok(GpioState::Low));
+    button.release();
+    assert_eq!(input.read(), Ok(GpioState::High));
+}
+
+// Handlers for different pins and edges, dispatched from one upcall.
+#[test]
+fn dispatcher() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Gpio::<3>::new();
+    kernel.add_driver(&driver);
+
+    let pin_0 = Gpio::get_pin(0).unwrap();
+    let pin_1 = Gpio::get_pin(1).unwrap();
+    let input_0 = pin_0.make_input::<PullDown>().unwrap();
+    let input_1 = pin_1.make_input::<PullDown>().unwrap();
+
+    let rising = Cell::new(0);
+    let falling = Cell::new(0);
+    let other = Cell::new(0);
+    let on_rising = |state| {
+        assert_eq!(state, GpioState::High);
+        rising.set(rising.get() + 1);
+    };
+    let on_falling = |state| {
+        assert_eq!(state, GpioState::Low);
+        falling.set(falling.get() + 1);
+    };
+    let on_other = |_| other.set(other.get() + 1);
+    let dispatcher = PinDispatcher::<3>::new();
+    share::scope(|subscribe| {
+        assert_eq!(Gpio::register_dispatcher(&dispatcher, subscribe), Ok(()));
+        let rising_handler = dispatcher
+            .register(&input_0, PinInterruptEdge::Rising, &on_rising)
+            .unwrap();
+        assert_eq!(
+            driver.get_gpio_state(0).unwrap().interrupt_enabled,
+            Some(InterruptEdge::Rising)
+        );
+        let falling_handler = dispatcher
+            .register(&input_0, PinInterruptEdge::Falling, &on_falling)
+            .unwrap();
+        assert_eq!(
+            driver.get_gpio_state(0).unwrap().interrupt_enabled,
+            Some(InterruptEdge::Either)
+        );
+        let _other_handler = dispatcher
+            .register(&input_1, PinInterruptEdge::Either, &on_other)
+            .unwrap();
+        assert_eq!(
+            dispatcher
+                .register(&input_1, PinInterruptEdge::Either, &on_other)
+                .err(),
+            Some(ErrorCode::NoMem)
+        );
+
+        assert_eq!(driver.set_value(0, true), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(driver.set_value(0, false), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(driver.set_value(1, true), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!((rising.get(), falling.get(), other.get()), (1, 1, 1));
+
+        // Dropping a handler narrows the pin's interrupt to the remaining
+        // handlers, and dropping the last one disables it.
+        drop(rising_handler);
+        assert_eq!(
+            driver.get_gpio_state(0).unwrap().interrupt_enabled,
+            Some(InterruptEdge::Falling)
+        );
+        drop(falling_handler);
+        assert_eq!(driver.get_gpio_state(0).unwrap().interrupt_enabled, None);
+        assert_eq!(driver.set_value(0, true), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+    });
+}
+
+#[cfg(feature = "rust_embedded")]
+#[test]
+fn embedded_hal() {
+    use embedded_hal::digital::{InputPin, OutputPin, StatefulOutputPin};
+    let kernel = fake::Kernel::new();
+    let driver = fake::Gpio::<2>::new();
+    kernel.add_driver(&driver);
+    driver.connect(0, 1);
+
+    let mut output_pin = Gpio::get_pin(0).unwrap();
+    let input_pin = Gpio::get_pin(1).unwrap();
+    let mut output = output_pin.make_output().unwrap();
+    let mut input = input_pin.make_input::<PullNone>().unwrap();
+
+    // Before the first write, the state is read from the pin.
+    assert_eq!(output.is_set_low(), Ok(true));
+    output.set_high().unwrap();
+    assert_eq!(output.is_set_high(), Ok(true));
+    assert_eq!(input.is_high(), Ok(true));
+    StatefulOutputPin::toggle(&mut output).unwrap();
+    assert_eq!(output.is_set_low(), Ok(true));
+    assert_eq!(input.is_low(), Ok(true));
+    output.set_low().unwrap();
+    assert_eq!(output.is_set_high(), Ok(false));
+    assert_eq!(input.is_high(), Ok(false));
+}
+
+// Runs `future` to completion like a single-task executor: while the future is
+// pending, yields until its waker is called. Returns the future's output and
+// the number of times it was woken.
+#[cfg(feature = "rust_embedded")]
+fn block_on<F: core::future::Future>(future: F) -> (F::Output, usize) {
+    extern crate std;
+    use core::task::{Context, Poll, Waker};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct WakeCounter(AtomicUsize);
+    impl std::task::Wake for WakeCounter {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let counter = Arc::new(WakeCounter(AtomicUsize::new(0)));
+    let waker = Waker::from(counter.clone());
+    let mut context = Context::from_waker(&waker);
+    let mut future = core::pin::pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return (output, counter.0.load(Ordering::Relaxed));
+        }
+        let wakes = counter.0.load(Ordering::Relaxed);
+        while counter.0.load(Ordering::Relaxed) == wakes {
+            fake::Syscalls::yield_wait();
+        }
+    }
+}
+
+#[cfg(feature = "rust_embedded")]
+#[test]
+fn wait() {
+    use core::future::Future;
+    use core::task::{Context, Waker};
+    use core::time::Duration;
+    use embedded_hal_async::digital::Wait;
+    let kernel = fake::Kernel::new();
+    let driver = fake::Gpio::<2>::new();
+    let alarm = fake::Alarm::new(1000);
+    kernel.add_driver(&driver);
+    kernel.add_driver(&alarm);
+    let timeline = fake::Timeline::new(&alarm);
+
+    let pin = Gpio::get_pin(0).unwrap();
+    let input = pin.make_input::<PullDown>().unwrap();
+    let other_pin = Gpio::get_pin(1).unwrap();
+    let other = other_pin.make_input::<PullNone>().unwrap();
+    let other_changes = Cell::new(0);
+    let on_other_change = |_| other_changes.set(other_changes.get() + 1);
+    let dispatcher = PinDispatcher::<2>::new();
+    share::scope(|subscribe| {
+        Gpio::register_dispatcher(&dispatcher, subscribe).unwrap();
+        let _handler = dispatcher
+            .register(&other, PinInterruptEdge::Either, &on_other_change)
+            .unwrap();
+        let mut input = dispatcher.async_pin(&input);
+
+        // A level that is already reached does not wait.
+        assert_eq!(block_on(input.wait_for_low()), (Ok(()), 0));
+        assert_eq!(timeline.now(), Duration::ZERO);
+
+        let gpio = driver.clone();
+        timeline.at(Duration::from_millis(10), move || {
+            gpio.set_value(0, true).unwrap();
+        });
+        assert_eq!(block_on(input.wait_for_high()), (Ok(()), 1));
+        assert_eq!(timeline.now(), Duration::from_millis(10));
+
+        // Interrupts on other pins go to their handlers, and do not wake the
+        // future.
+        let gpio = driver.clone();
+        timeline.at(Duration::from_millis(20), move || {
+            gpio.set_value(1, true).unwrap();
+        });
+        let gpio = driver.clone();
+        timeline.at(Duration::from_millis(30), move || {
+            gpio.set_value(0, false).unwrap();
+        });
+        assert_eq!(block_on(input.wait_for_any_edge()), (Ok(()), 1));
+        assert_eq!(timeline.now(), Duration::from_millis(30));
+        assert_eq!(other_changes.get(), 1);
+
+        let gpio = driver.clone();
+        timeline.at(Duration::from_millis(40), move || {
+            gpio.set_value(0, true).unwrap();
+        });
+        let gpio = driver.clone();
+        timeline.at(Duration::from_millis(50), move || {
+            gpio.set_value(0, false).unwrap();
+        });
+        assert_eq!(block_on(input.wait_for_falling_edge()), (Ok(()), 1));
+        assert_eq!(timeline.now(), Duration::from_millis(50));
+
+        // The interrupt is disabled after waiting, and the other pin's handler
+        // is still registered.
+        assert_eq!(driver.get_gpio_state(0).unwrap().interrupt_enabled, None);
+        assert_eq!(
+            driver.get_gpio_state(1).unwrap().interrupt_enabled,
+            Some(InterruptEdge::Either)
+        );
+
+        // A pending future keeps the interrupt enabled until it is dropped.
+        {
+            let mut context = Context::from_waker(Waker::noop());
+            let mut future = core::pin::pin!(input.wait_for_rising_edge());
+            assert!(future.as_mut().poll(&mut context).is_pending());
+            assert!(future.as_mut().poll(&mut context).is_pending());
+            assert_eq!(
+                driver.get_gpio_state(0).unwrap().interrupt_enabled,
+                Some(InterruptEdge::Rising)
+            );
+        }
+        assert_eq!(driver.get_gpio_state(0).unwrap().interrupt_enabled, None);
+
+        // Waits share the dispatcher's slots.
+        let _full = dispatcher
+            .register(&other, PinInterruptEdge::Rising, &on_other_change)
+            .unwrap();
+        assert_eq!(
+            block_on(input.wait_for_any_edge()),
+            (Err(ErrorCode::NoMem), 0)
+        );
+    });
+}
@@ -57,7 +57,7 @@ impl<S: Syscalls, C: Config> I2CMaster<S, C> {
 
             S::command(
                 DRIVER_NUM,
-                i2c_master_cmd::MASTER_WRITE,
+                i2c_master_cmd::MASTER_WRITE_READ,
                 cmd_arg0,
                 r_len.into(),
             )
@@ -189,6 +189,9 @@ impl<T: platform::allow_ro::Config + platform::allow_rw::Config + platform::subs
 {
 }
 
+#[cfg(test)]
+mod tests;
+
 // -----------------------------------------------------------------------------
 // Driver number and command IDs
 // -----------------------------------------------------------------------------
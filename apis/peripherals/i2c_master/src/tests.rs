@@ -0,0 +1,104 @@
+use libtock_platform::ErrorCode;
+use libtock_unittest::{fake, SyscallLogEntry};
+
+type I2CMaster = super::I2CMaster<fake::Syscalls>;
+
+#[test]
+fn no_driver() {
+    let _kernel = fake::Kernel::new();
+    assert_eq!(I2CMaster::exists(), Err(ErrorCode::NoDevice));
+}
+
+#[test]
+fn exists() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::I2cMaster::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(I2CMaster::exists(), Ok(()));
+}
+
+#[test]
+fn write_sync() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::I2cMaster::new();
+    kernel.add_driver(&driver);
+    let device = fake::I2cRegisterMap::new(4);
+    driver.bus().add_device(0x40, &device);
+
+    let mut buf = [1, 0x11, 0x22, 0x33];
+    assert_eq!(I2CMaster::i2c_master_write_sync(0x40, &mut buf, 3), Ok(()));
+    assert_eq!(device.registers(), [0, 0x11, 0x22, 0]);
+
+    assert_eq!(
+        I2CMaster::i2c_master_write_sync(0x41, &mut buf, 3),
+        Err(ErrorCode::NoAck)
+    );
+    assert_eq!(
+        I2CMaster::i2c_master_write_sync(0x40, &mut buf, 5),
+        Err(ErrorCode::Size)
+    );
+}
+
+#[test]
+fn read_sync() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::I2cMaster::new();
+    kernel.add_driver(&driver);
+    let device = fake::I2cRegisterMap::new(4);
+    driver.bus().add_device(0x40, &device);
+    device.set_registers(0, &[5, 6, 7, 8]);
+
+    let mut buf = [0; 4];
+    assert_eq!(I2CMaster::i2c_master_read_sync(0x40, &mut buf, 2), Ok(()));
+    assert_eq!(buf, [5, 6, 0, 0]);
+}
+
+#[test]
+fn write_read_sync() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::I2cMaster::new();
+    kernel.add_driver(&driver);
+    let device = fake::I2cRegisterMap::new(4);
+    driver.bus().add_device(0x40, &device);
+    device.set_registers(0, &[5, 6, 7, 8]);
+
+    let mut buf = [2, 0, 0];
+    assert_eq!(
+        I2CMaster::i2c_master_write_read_sync(0x40, &mut buf, 1, 3),
+        Ok(())
+    );
+    assert_eq!(buf, [7, 8, 5]);
+
+    assert_eq!(
+        I2CMaster::i2c_master_write_read_sync(0x40, &mut buf, 1, 4),
+        Err(ErrorCode::NoMem)
+    );
+}
+
+// i2c_master_write_read_sync used to start the transfer with MASTER_WRITE, so
+// the device was written to but never read from.
+#[test]
+fn write_read_sync_uses_write_read_command() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::I2cMaster::new();
+    kernel.add_driver(&driver);
+    let device = fake::I2cRegisterMap::new(4);
+    driver.bus().add_device(0x40, &device);
+    device.set_registers(0, &[5, 6, 7, 8]);
+
+    let mut buf = [1, 0];
+    assert_eq!(
+        I2CMaster::i2c_master_write_read_sync(0x40, &mut buf, 1, 2),
+        Ok(())
+    );
+    assert_eq!(buf, [6, 7]);
+    assert!(kernel
+        .take_syscall_log()
+        .contains(&SyscallLogEntry::Command {
+            driver_id: super::DRIVER_NUM,
+            command_id: super::i2c_master_cmd::MASTER_WRITE_READ,
+            argument0: 1 << 8 | 0x40,
+            argument1: 2,
+        }));
+}
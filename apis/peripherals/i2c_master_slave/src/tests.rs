@@ -0,0 +1,141 @@
+use libtock_platform::ErrorCode;
+use libtock_unittest::fake;
+
+type I2CMasterSlave = super::I2CMasterSlave<fake::Syscalls>;
+
+#[test]
+fn no_driver() {
+    let _kernel = fake::Kernel::new();
+    assert_eq!(I2CMasterSlave::exists(), Err(ErrorCode::NoDevice));
+}
+
+#[test]
+fn exists() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::I2cMasterSlave::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(I2CMasterSlave::exists(), Ok(()));
+}
+
+#[test]
+fn master_write_sync() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::I2cMasterSlave::new();
+    kernel.add_driver(&driver);
+    let device = fake::I2cRegisterMap::new(4);
+    driver.bus().add_device(0x40, &device);
+
+    assert_eq!(
+        I2CMasterSlave::i2c_master_slave_write_sync(0x40, &[3, 0x99], 2),
+        Ok(())
+    );
+    assert_eq!(device.registers(), [0, 0, 0, 0x99]);
+
+    assert_eq!(
+        I2CMasterSlave::i2c_master_slave_write_sync(0x41, &[3, 0x99], 2),
+        Err(ErrorCode::NoAck)
+    );
+    assert_eq!(
+        I2CMasterSlave::i2c_master_slave_write_sync(0x40, &[3, 0x99], 3),
+        Err(ErrorCode::NoMem)
+    );
+}
+
+#[test]
+fn master_read_sync() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::I2cMasterSlave::new();
+    kernel.add_driver(&driver);
+    let device = fake::I2cRegisterMap::new(4);
+    driver.bus().add_device(0x40, &device);
+    device.set_registers(0, &[1, 2, 3, 4]);
+
+    let mut buf = [0; 4];
+    assert_eq!(
+        I2CMasterSlave::i2c_master_slave_read_sync(0x40, &mut buf, 3),
+        (3, Ok(()))
+    );
+    assert_eq!(buf, [1, 2, 3, 0]);
+
+    assert_eq!(
+        I2CMasterSlave::i2c_master_slave_read_sync(0x41, &mut buf, 3),
+        (0, Err(ErrorCode::NoAck))
+    );
+}
+
+#[test]
+fn master_write_read_sync() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::I2cMasterSlave::new();
+    kernel.add_driver(&driver);
+    let device = fake::I2cRegisterMap::new(4);
+    driver.bus().add_device(0x40, &device);
+    device.set_registers(0, &[1, 2, 3, 4]);
+
+    let mut w_buf = [2, 0];
+    let mut r_buf = [0; 2];
+    assert_eq!(
+        I2CMasterSlave::i2c_master_slave_write_read_sync(0x40, &mut w_buf, &mut r_buf, 1, 2),
+        (2, Ok(()))
+    );
+    assert_eq!(r_buf, [3, 4]);
+}
+
+#[test]
+fn set_slave_address() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::I2cMasterSlave::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(
+        I2CMasterSlave::i2c_master_slave_set_slave_address(0x80),
+        Err(ErrorCode::Invalid)
+    );
+    assert_eq!(
+        I2CMasterSlave::i2c_master_slave_set_slave_address(0x32),
+        Ok(())
+    );
+    assert_eq!(driver.slave_address(), Some(0x32));
+}
+
+#[test]
+fn slave_write_recv_sync() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::I2cMasterSlave::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(
+        I2CMasterSlave::i2c_master_slave_set_slave_address(0x32),
+        Ok(())
+    );
+    driver.add_master_write(0x32, b"ping");
+    let mut buf = [0; 8];
+    assert_eq!(
+        I2CMasterSlave::i2c_master_slave_write_recv_sync(&mut buf),
+        (4, Ok(()))
+    );
+    assert_eq!(&buf[..4], b"ping");
+}
+
+#[test]
+fn slave_read_send_sync() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::I2cMasterSlave::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(
+        I2CMasterSlave::i2c_master_slave_set_slave_address(0x32),
+        Ok(())
+    );
+    driver.add_master_read(0x32, 3);
+    assert_eq!(
+        I2CMasterSlave::i2c_master_slave_read_send_sync(b"pong", 4),
+        (3, Ok(()))
+    );
+    assert_eq!(driver.take_sent(), [b"pon".to_vec()]);
+    assert_eq!(
+        I2CMasterSlave::i2c_master_slave_read_send_sync(b"pong", 5),
+        (0, Err(ErrorCode::Invalid))
+    );
+}
@@ -0,0 +1,71 @@
+use crate::DRIVER_NUM;
+use core::cell::Cell;
+use libtock_platform::{share, AllowRw, ErrorCode, Subscribe, Syscalls, YieldNoWaitReturn};
+use libtock_unittest::fake;
+
+type Rng = super::Rng<fake::Syscalls>;
+
+#[test]
+fn no_driver() {
+    let _kernel = fake::Kernel::new();
+    assert_eq!(Rng::exists(), Err(ErrorCode::NoDevice));
+}
+
+#[test]
+fn exists() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Rng::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(Rng::exists(), Ok(()));
+}
+
+#[test]
+fn get_bytes_sync() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Rng::new();
+    kernel.add_driver(&driver);
+
+    driver.add_bytes(&[0xde, 0xad]);
+    let mut buf = [0; 4];
+    assert_eq!(Rng::get_bytes_sync(&mut buf, 3), Ok(()));
+    assert_eq!(buf, [0xde, 0xad, 0, 0]);
+
+    // Requests larger than the buffer fill the whole buffer.
+    assert_eq!(Rng::get_bytes_sync(&mut buf, 10), Ok(()));
+    assert_eq!(buf, [1, 2, 3, 4]);
+}
+
+#[test]
+fn get_bytes_async() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Rng::new_with_source(|| 7);
+    kernel.add_driver(&driver);
+
+    let filled: Cell<Option<u32>> = Cell::new(None);
+    let listener = crate::RngListener(|count| filled.set(Some(count)));
+    let mut buf = [0; 8];
+    share::scope::<
+        (
+            AllowRw<fake::Syscalls, DRIVER_NUM, 0>,
+            Subscribe<fake::Syscalls, DRIVER_NUM, 0>,
+        ),
+        _,
+        _,
+    >(|handle| {
+        let (allow_rw, subscribe) = handle.split();
+        assert_eq!(Rng::allow_buffer(&mut buf, allow_rw), Ok(()));
+        assert_eq!(Rng::register_listener(&listener, subscribe), Ok(()));
+
+        driver.limit_next_request(2);
+        assert_eq!(Rng::get_bytes_async(5), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(filled.take(), Some(2));
+
+        Rng::unregister_listener();
+        assert_eq!(Rng::get_bytes_async(5), Ok(()));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+        Rng::unallow_buffer();
+    });
+    assert_eq!(buf, [7, 7, 7, 7, 7, 0, 0, 0]);
+}
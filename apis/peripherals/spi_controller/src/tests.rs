@@ -0,0 +1,96 @@
+extern crate std;
+
+use libtock_platform::ErrorCode;
+use libtock_unittest::fake;
+use std::rc::Rc;
+
+type SpiController = super::SpiController<fake::Syscalls>;
+
+#[test]
+fn no_driver() {
+    let _kernel = fake::Kernel::new();
+    assert_eq!(SpiController::exists(), Err(ErrorCode::NoDevice));
+}
+
+#[test]
+fn exists() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::SpiController::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(SpiController::exists(), Ok(()));
+}
+
+#[test]
+fn write_read_sync() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::SpiController::new();
+    kernel.add_driver(&driver);
+    driver.set_device(&fake::SpiLoopback::new());
+
+    let mut r_buf = [0; 4];
+    assert_eq!(
+        SpiController::spi_controller_write_read_sync(b"spi!", &mut r_buf, 3),
+        Ok(())
+    );
+    assert_eq!(&r_buf, b"spi\0");
+    assert_eq!(driver.take_bytes(), b"spi");
+
+    assert_eq!(
+        SpiController::spi_controller_write_read_sync(b"spi!", &mut r_buf, 5),
+        Err(ErrorCode::NoMem)
+    );
+}
+
+#[test]
+fn write_sync() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::SpiController::new();
+    kernel.add_driver(&driver);
+
+    assert_eq!(SpiController::spi_controller_write_sync(b"abc", 2), Ok(()));
+    assert_eq!(driver.take_bytes(), b"ab");
+}
+
+#[test]
+fn read_sync() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::SpiController::new();
+    kernel.add_driver(&driver);
+
+    let mut r_buf = [0xff; 3];
+    assert_eq!(
+        SpiController::spi_controller_read_sync(&mut r_buf, 2),
+        Ok(())
+    );
+    assert_eq!(r_buf, [0, 0, 0xff]);
+    assert_eq!(driver.take_bytes(), [0, 0]);
+}
+
+// A peripheral that shifts back the complement of each byte it receives, so
+// the data read differs from the data written.
+struct Inverter;
+
+impl fake::SpiDevice for Inverter {
+    fn transfer(&self, write: &[u8], read: &mut [u8]) {
+        for (read, write) in read.iter_mut().zip(write) {
+            *read = !write;
+        }
+    }
+}
+
+#[test]
+fn inplace_write_read_sync() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::SpiController::new();
+    kernel.add_driver(&driver);
+    driver.set_device(&Rc::new(Inverter));
+
+    let mut buf = [0x00, 0x0f, 0xaa, 0x55];
+    assert_eq!(
+        SpiController::spi_controller_inplace_write_read_sync(&mut buf, 3),
+        Ok(())
+    );
+    assert_eq!(buf, [0xff, 0xf0, 0x55, 0x55]);
+    assert_eq!(driver.take_bytes(), [0x00, 0x0f, 0xaa]);
+}
@@ -8,6 +8,7 @@ use libtock_platform::{
 pub struct NineDof<S: Syscalls>(S);
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
 pub struct NineDofData {
     pub x: i32,
     pub y: i32,
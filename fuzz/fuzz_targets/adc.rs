@@ -0,0 +1,19 @@
+//! Fuzzes `libtock_adc` against an adversarial ADC driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type Adc = libtock_adc::Adc<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x5, 1, input));
+        let _ = Adc::exists();
+        let _ = Adc::get_resolution_bits();
+        let _ = Adc::get_reference_voltage_mv();
+        let _ = Adc::read_single_sample_sync();
+    });
+});
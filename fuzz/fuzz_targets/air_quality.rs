@@ -0,0 +1,19 @@
+//! Fuzzes `libtock_air_quality` against an adversarial air quality driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type AirQuality = libtock_air_quality::AirQuality<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x60007, 1, input));
+        let _ = AirQuality::exists();
+        let _ = AirQuality::read_co2_sync();
+        let _ = AirQuality::read_tvoc_sync();
+        let _ = AirQuality::read_sync();
+    });
+});
@@ -0,0 +1,21 @@
+//! Fuzzes `libtock_alarm` against an adversarial alarm driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_alarm::Milliseconds;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type Alarm = libtock_alarm::Alarm<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x0, 1, input));
+        let _ = Alarm::exists();
+        let _ = Alarm::get_frequency();
+        let _ = Alarm::get_ticks();
+        let _ = Alarm::get_milliseconds();
+        let _ = Alarm::sleep_for(Milliseconds(input.u32()));
+    });
+});
@@ -0,0 +1,17 @@
+//! Fuzzes `libtock_ambient_light` against an adversarial ambient light driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type AmbientLight = libtock_ambient_light::AmbientLight<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x60002, 1, input));
+        let _ = AmbientLight::exists();
+        let _ = AmbientLight::read_intensity_sync();
+    });
+});
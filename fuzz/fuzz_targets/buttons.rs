@@ -0,0 +1,28 @@
+//! Fuzzes `libtock_buttons` against an adversarial buttons driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_buttons::ButtonListener;
+use libtock_platform::{share, Syscalls};
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type Buttons = libtock_buttons::Buttons<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x3, 1, input));
+        let _ = Buttons::count();
+        let button = input.u32();
+        let _ = Buttons::read(button);
+        let _ = Buttons::is_pressed(button);
+        let _ = Buttons::enable_interrupts(button);
+        let listener = ButtonListener(|_, _| {});
+        share::scope(|subscribe| {
+            if Buttons::register_listener(&listener, subscribe).is_ok() {
+                fake::Syscalls::yield_wait();
+            }
+        });
+    });
+});
@@ -0,0 +1,18 @@
+//! Fuzzes `libtock_buzzer` against an adversarial buzzer driver.
+
+#![no_main]
+
+use core::time::Duration;
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type Buzzer = libtock_buzzer::Buzzer<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x90000, 1, input));
+        let _ = Buzzer::exists();
+        let _ = Buzzer::tone_sync(input.u32(), Duration::from_millis(input.u32().into()));
+    });
+});
@@ -0,0 +1,20 @@
+//! Fuzzes `libtock_console` against an adversarial console driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type Console = libtock_console::Console<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x1, 3, input));
+        let _ = Console::exists();
+        let _ = Console::write(b"prompt> ");
+        let mut buffer = [0; 16];
+        let (count, _) = Console::read(&mut buffer);
+        assert!(count <= buffer.len());
+    });
+});
@@ -0,0 +1,37 @@
+//! Fuzzes `libtock_gpio` against an adversarial GPIO driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_gpio::{GpioInterruptListener, PinInterruptEdge, PullUp};
+use libtock_platform::{share, Syscalls};
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type Gpio = libtock_gpio::Gpio<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x4, 1, input));
+        let _ = Gpio::exists();
+        let _ = Gpio::count();
+        let Ok(mut pin) = Gpio::get_pin(input.u32()) else {
+            return;
+        };
+        if let Ok(mut output) = pin.make_output() {
+            let _ = output.set();
+            let _ = output.toggle();
+            let _ = output.clear();
+        }
+        if let Ok(input_pin) = pin.make_input::<PullUp>() {
+            let _ = input_pin.read();
+            let _ = input_pin.enable_interrupts(PinInterruptEdge::Either);
+        }
+        let listener = GpioInterruptListener(|_, _| {});
+        share::scope(|subscribe| {
+            if Gpio::register_listener(&listener, subscribe).is_ok() {
+                fake::Syscalls::yield_wait();
+            }
+        });
+    });
+});
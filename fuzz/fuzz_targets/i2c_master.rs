@@ -0,0 +1,21 @@
+//! Fuzzes `libtock_i2c_master` against an adversarial I2C master driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type I2cMaster = libtock_i2c_master::I2CMaster<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x20003, 1, input));
+        let _ = I2cMaster::exists();
+        let addr = input.u32() as u16;
+        let mut buffer = [0; 8];
+        let _ = I2cMaster::i2c_master_write_sync(addr, &mut buffer, 4);
+        let _ = I2cMaster::i2c_master_read_sync(addr, &mut buffer, 8);
+        let _ = I2cMaster::i2c_master_write_read_sync(addr, &mut buffer, 2, 6);
+    });
+});
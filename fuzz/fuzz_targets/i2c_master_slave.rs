@@ -0,0 +1,36 @@
+//! Fuzzes `libtock_i2c_master_slave` against an adversarial I2C master/slave
+//! driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type I2cMasterSlave = libtock_i2c_master_slave::I2CMasterSlave<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x20006, 1, input));
+        let _ = I2cMasterSlave::exists();
+        let addr = input.u32() as u16;
+        let mut write_buffer = [0; 8];
+        let mut read_buffer = [0; 8];
+        let _ = I2cMasterSlave::i2c_master_slave_write_sync(addr, &write_buffer, 4);
+        let (count, _) = I2cMasterSlave::i2c_master_slave_read_sync(addr, &mut read_buffer, 8);
+        assert!(count <= read_buffer.len());
+        let (count, _) = I2cMasterSlave::i2c_master_slave_write_read_sync(
+            addr,
+            &mut write_buffer,
+            &mut read_buffer,
+            2,
+            6,
+        );
+        assert!(count <= read_buffer.len());
+        let _ = I2cMasterSlave::i2c_master_slave_set_slave_address(addr as u8);
+        let (count, _) = I2cMasterSlave::i2c_master_slave_write_recv_sync(&mut read_buffer);
+        assert!(count <= read_buffer.len());
+        let (count, _) = I2cMasterSlave::i2c_master_slave_read_send_sync(&write_buffer, 8);
+        assert!(count <= write_buffer.len());
+    });
+});
@@ -0,0 +1,34 @@
+//! Fuzzes `libtock_ieee802154` against an adversarial IEEE 802.15.4 driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_ieee802154::{RxOperator, RxRingBuffer, RxSingleBufferOperator};
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type Ieee802154 = libtock_ieee802154::Ieee802154<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x30001, 2, input));
+        let _ = Ieee802154::exists();
+        let _ = Ieee802154::is_on();
+        let _ = Ieee802154::radio_on();
+        Ieee802154::set_pan(input.u32() as u16);
+        Ieee802154::commit_config();
+        let _ = Ieee802154::get_address_short();
+        let _ = Ieee802154::get_address_long();
+        let _ = Ieee802154::get_pan();
+        let _ = Ieee802154::get_channel();
+        let _ = Ieee802154::get_tx_power();
+        let _ = Ieee802154::transmit_frame_raw(b"frame");
+        let mut buffer = RxRingBuffer::<3>::new();
+        let mut operator = RxSingleBufferOperator::<3, fake::Syscalls>::new(&mut buffer);
+        for _ in 0..3 {
+            if let Ok(frame) = operator.receive_frame() {
+                let _ = frame.body.get(..frame.payload_len as usize);
+            }
+        }
+    });
+});
@@ -0,0 +1,24 @@
+//! Fuzzes `libtock_key_value` against an adversarial key-value driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type KeyValue = libtock_key_value::KeyValue<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x50003, 1, input));
+        let _ = KeyValue::exists();
+        let mut value = [0; 16];
+        if let Ok(len) = KeyValue::get(b"key", &mut value) {
+            let _ = value.get(..len as usize);
+        }
+        let _ = KeyValue::set(b"key", b"value");
+        let _ = KeyValue::add(b"key", b"value");
+        let _ = KeyValue::update(b"key", b"value");
+        let _ = KeyValue::delete(b"key");
+    });
+});
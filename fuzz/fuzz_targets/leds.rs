@@ -0,0 +1,22 @@
+//! Fuzzes `libtock_leds` against an adversarial LED driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type Leds = libtock_leds::Leds<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x2, 0, input));
+        if let Ok(count) = Leds::count() {
+            for led in 0..count.min(8) {
+                let _ = Leds::on(led);
+                let _ = Leds::toggle(led);
+                let _ = Leds::off(led);
+            }
+        }
+    });
+});
@@ -0,0 +1,21 @@
+//! Fuzzes `libtock_low_level_debug` against an adversarial low-level debug
+//! driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_low_level_debug::AlertCode;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type LowLevelDebug = libtock_low_level_debug::LowLevelDebug<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x8, 0, input));
+        let _ = LowLevelDebug::exists();
+        LowLevelDebug::print_alert_code(AlertCode::Panic);
+        LowLevelDebug::print_1(input.u32());
+        LowLevelDebug::print_2(input.u32(), input.u32());
+    });
+});
@@ -0,0 +1,21 @@
+//! Fuzzes `libtock_ninedof` against an adversarial nine-degrees-of-freedom
+//! sensor driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type NineDof = libtock_ninedof::NineDof<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x60004, 1, input));
+        let _ = NineDof::exists();
+        let _ = NineDof::read_accelerometer_sync();
+        let _ = NineDof::read_magnetometer_sync();
+        let _ = NineDof::read_gyroscope_sync();
+        let _ = NineDof::read_accelerometer_mag();
+    });
+});
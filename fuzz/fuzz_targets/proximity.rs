@@ -0,0 +1,19 @@
+//! Fuzzes `libtock_proximity` against an adversarial proximity sensor driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type Proximity = libtock_proximity::Proximity<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x60005, 1, input));
+        let _ = Proximity::exists();
+        let _ = Proximity::read_sync();
+        let lower = input.u8();
+        let _ = Proximity::wait_for_value_between(lower, lower.saturating_add(input.u8()));
+    });
+});
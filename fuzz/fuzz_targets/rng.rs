@@ -0,0 +1,18 @@
+//! Fuzzes `libtock_rng` against an adversarial random number generator driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type Rng = libtock_rng::Rng<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x40001, 1, input));
+        let _ = Rng::exists();
+        let mut buffer = [0; 16];
+        let _ = Rng::get_bytes_sync(&mut buffer, input.u32() % 32);
+    });
+});
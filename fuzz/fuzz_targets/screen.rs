@@ -0,0 +1,38 @@
+//! Fuzzes `libtock_screen` against an adversarial screen driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type Screen = libtock_screen::Screen<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x90001, 3, input));
+        let _ = Screen::exists();
+        let _ = Screen::screen_setup();
+        let _ = Screen::set_power(1);
+        let _ = Screen::set_brightness(input.u32() as usize);
+        let _ = Screen::set_invert(input.u32() as usize);
+        if let Ok(count) = Screen::get_resolution_modes_count() {
+            for index in 0..count.min(4) {
+                let _ = Screen::get_resolution_width_height(index as usize);
+            }
+        }
+        if let Ok(count) = Screen::pixel_modes_count() {
+            for index in 0..count.min(4) {
+                let _ = Screen::pixel_format(index as usize);
+            }
+        }
+        let _ = Screen::get_rotation();
+        let _ = Screen::set_rotation(input.u32() as usize);
+        let _ = Screen::get_resolution();
+        let _ = Screen::get_pixel_format();
+        let _ = Screen::set_write_frame(input.u32(), input.u32(), input.u32(), input.u32());
+        let _ = Screen::write(&[0xff; 8]);
+        let mut buffer = [0; 8];
+        let _ = Screen::fill(&mut buffer, 0x1234);
+    });
+});
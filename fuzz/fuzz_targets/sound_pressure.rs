@@ -0,0 +1,20 @@
+//! Fuzzes `libtock_sound_pressure` against an adversarial sound pressure
+//! sensor driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type SoundPressure = libtock_sound_pressure::SoundPressure<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x60006, 1, input));
+        let _ = SoundPressure::exists();
+        let _ = SoundPressure::enable();
+        let _ = SoundPressure::read_sync();
+        let _ = SoundPressure::disable();
+    });
+});
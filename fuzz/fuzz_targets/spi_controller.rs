@@ -0,0 +1,23 @@
+//! Fuzzes `libtock_spi_controller` against an adversarial SPI controller driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type SpiController = libtock_spi_controller::SpiController<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x20001, 1, input));
+        let _ = SpiController::exists();
+        let write_buffer = [0x55; 8];
+        let mut read_buffer = [0; 8];
+        let len = input.u32() % 10;
+        let _ = SpiController::spi_controller_write_read_sync(&write_buffer, &mut read_buffer, len);
+        let _ = SpiController::spi_controller_write_sync(&write_buffer, len);
+        let _ = SpiController::spi_controller_read_sync(&mut read_buffer, len);
+        let _ = SpiController::spi_controller_inplace_write_read_sync(&mut read_buffer, len);
+    });
+});
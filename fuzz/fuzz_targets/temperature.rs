@@ -0,0 +1,17 @@
+//! Fuzzes `libtock_temperature` against an adversarial temperature sensor driver.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libtock_unittest::fake;
+use libtock_unittest::fuzz::{self, Adversary};
+
+type Temperature = libtock_temperature::Temperature<fake::Syscalls>;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::run(data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(0x60000, 1, input));
+        let _ = Temperature::exists();
+        let _ = Temperature::read_temperature_sync();
+    });
+});
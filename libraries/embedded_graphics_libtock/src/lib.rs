@@ -6,14 +6,15 @@
 //!
 //! ## Example Usage
 //!
-//! Using Embedded Graphics to draw a circle on the screen might look like:DrawTarget
+//! Using Embedded Graphics to draw a circle on the screen might look like:
 //!
-//! ```rust
+//! ```ignore
 //! use embedded_graphics_libtock::tock_screen::TockMonochrome8BitPage128x64Screen;
 //!
 //! use embedded_graphics::pixelcolor::BinaryColor;
-//! use embedded_graphics::prelude::Point;
+//! use embedded_graphics::prelude::{Point, Primitive};
 //! use embedded_graphics::primitives::{Circle, PrimitiveStyle};
+//! use embedded_graphics::Drawable;
 //!
 //! let mut screen = TockMonochrome8BitPage128x64Screen::new();
 //!
@@ -23,10 +24,16 @@
 //! let _ = Circle::new(Point::new(x as i32, y as i32), diameter)
 //!     .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
 //!     .draw(&mut screen);
-//! }
 //! let _ = screen.flush();
 //! ```
+//!
+//! `TockMonochrome8BitPage128x64Screen` is only available when building for
+//! Tock. In unit tests, use `Monochrome8BitPage128x64Screen` with
+//! `libtock_unittest::fake::Syscalls`.
 
 #![no_std]
 
 pub mod tock_screen;
+
+#[cfg(test)]
+mod tests;
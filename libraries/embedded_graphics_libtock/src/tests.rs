@@ -0,0 +1,141 @@
+//! Visual regression tests, which draw the same scenes as the crate example and
+//! the embedded_graphics demos and compare them against stored snapshots.
+
+extern crate std;
+
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::{Point, Primitive};
+use embedded_graphics::primitives::{Circle, Line, PrimitiveStyle};
+use embedded_graphics::Drawable;
+use libtock_unittest::fake;
+use std::rc::Rc;
+
+type Screen = crate::tock_screen::Monochrome8BitPage128x64Screen<fake::Syscalls>;
+
+fn setup() -> (fake::Kernel, Rc<fake::Screen>) {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Screen::new_with_resolution(128, 64);
+    kernel.add_driver(&driver);
+    (kernel, driver)
+}
+
+fn assert_snapshot(driver: &fake::Screen, name: &str) {
+    driver.assert_snapshot(
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("snapshots")
+            .join(name),
+    );
+}
+
+#[test]
+fn dimensions() {
+    let (_kernel, _driver) = setup();
+    let screen = Screen::new();
+    assert_eq!((screen.get_width(), screen.get_height()), (128, 64));
+}
+
+#[test]
+fn pixels() {
+    let (_kernel, driver) = setup();
+    let mut screen = Screen::new();
+    let pixels = [(0, 0), (127, 0), (5, 9), (0, 63), (127, 63)];
+    let _ = screen.draw_iter(
+        pixels
+            .iter()
+            .map(|&(x, y)| embedded_graphics::Pixel(Point::new(x, y), BinaryColor::On)),
+    );
+    // Pixels outside the screen are ignored.
+    let _ = screen.draw_iter([embedded_graphics::Pixel(
+        Point::new(128, 0),
+        BinaryColor::On,
+    )]);
+    assert_eq!(screen.flush(), Ok(()));
+    for y in 0..64 {
+        for x in 0..128 {
+            let on = pixels.contains(&(x as i32, y as i32));
+            assert_eq!(driver.pixel(x, y) != [0, 0, 0], on, "pixel ({x}, {y})");
+        }
+    }
+}
+
+// The circle from the crate-level example, which is clipped by the bottom of
+// the screen.
+#[cfg_attr(miri, ignore)] // Miri does not support file system access.
+#[test]
+fn example_circle() {
+    let (_kernel, driver) = setup();
+    let mut screen = Screen::new();
+    let _ = Circle::new(Point::new(50, 50), 40)
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+        .draw(&mut screen);
+    assert_eq!(screen.flush(), Ok(()));
+    assert_snapshot(&driver, "example_circle.ppm");
+}
+
+// Draws frame `rot` of the spin demo.
+fn draw_spin(screen: &mut Screen, rot: usize) {
+    let center_x = screen.get_width() as i32 / 2;
+    let center_y = screen.get_height() as i32 / 2;
+    let radius = center_x.min(center_y) - 1;
+    let angle = (rot as f32 / 100.0) * (2.0 * core::f32::consts::PI);
+    let x = (center_x as f32 + (radius as f32 * angle.cos())) as i32;
+    let y = (center_y as f32 + (radius as f32 * angle.sin())) as i32;
+
+    let _ = screen.clear(BinaryColor::Off);
+    let _ = Line::new(Point::new(center_x, center_y), Point::new(x, y))
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+        .draw(screen);
+}
+
+#[cfg_attr(miri, ignore)] // Miri does not support file system access.
+#[test]
+fn spin_demo() {
+    let (_kernel, driver) = setup();
+    let mut screen = Screen::new();
+    draw_spin(&mut screen, 0);
+    assert_eq!(screen.flush(), Ok(()));
+    assert_snapshot(&driver, "spin_demo_0.ppm");
+
+    // Clearing between frames removes the previous line.
+    draw_spin(&mut screen, 30);
+    assert_eq!(screen.flush(), Ok(()));
+    assert_snapshot(&driver, "spin_demo_30.ppm");
+}
+
+// Draws the buttons demo with `pressed` indicating which buttons are pressed.
+fn draw_buttons(screen: &mut Screen, pressed: &[bool]) {
+    let (width, height) = (screen.get_width(), screen.get_height());
+    let button_count = pressed.len() as u32;
+    let button_padding_px = (button_count - 1) * 2;
+    let max_x = (width - button_padding_px) / button_count;
+    let max_y = height - 2;
+    let diameter = core::cmp::min(max_x, max_y);
+    let buttons_width = (diameter * button_count) + button_padding_px;
+    let padding_left_px = (width - buttons_width) / 2;
+    let y = (height / 2) - (diameter / 2);
+
+    for (i, &pressed) in pressed.iter().enumerate() {
+        let x = padding_left_px + ((diameter + 2) * i as u32);
+        let _ = Circle::new(Point::new(x as i32, y as i32), diameter)
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(screen);
+        let fill = match pressed {
+            true => BinaryColor::On,
+            false => BinaryColor::Off,
+        };
+        let _ = Circle::new(Point::new(x as i32 + 1, y as i32 + 1), diameter - 2)
+            .into_styled(PrimitiveStyle::with_fill(fill))
+            .draw(screen);
+    }
+}
+
+#[cfg_attr(miri, ignore)] // Miri does not support file system access.
+#[test]
+fn buttons_demo() {
+    let (_kernel, driver) = setup();
+    let mut screen = Screen::new();
+    draw_buttons(&mut screen, &[false, true, false, false]);
+    assert_eq!(screen.flush(), Ok(()));
+    assert_snapshot(&driver, "buttons_demo.ppm");
+}
@@ -1,40 +1,48 @@
 //! Implementations of `DrawTarget` using the screen system call.
 
-use libtock::display::Screen;
-use libtock_platform::ErrorCode;
+use core::marker::PhantomData;
+use libtock_platform::{ErrorCode, Syscalls};
+use libtock_screen::Screen;
+
+/// `Monochrome8BitPage128x64Screen` using the Tock system calls.
+#[cfg(target_os = "none")]
+pub type TockMonochrome8BitPage128x64Screen =
+    Monochrome8BitPage128x64Screen<libtock_runtime::TockSyscalls>;
 
 /// An implementation of a `DrawTarget` for monochromatic, 128x64 pixel screens
 /// where the pixels in each byte are vertical on the screen.
 ///
 /// This corresponds to the `Mono_8BitPage` pixel format documented
 /// [here](https://github.com/tock/tock/blob/master/doc/syscalls/90001_screen.md#command-number-25).
-pub struct TockMonochrome8BitPage128x64Screen {
+pub struct Monochrome8BitPage128x64Screen<S: Syscalls> {
     /// The framebuffer for the max supported screen size (128x64). Each pixel
     /// is a bit.
     framebuffer: [u8; (128 * 64) / 8],
     width: u32,
     height: u32,
+    _syscalls: PhantomData<S>,
 }
 
-impl Default for TockMonochrome8BitPage128x64Screen {
+impl<S: Syscalls> Default for Monochrome8BitPage128x64Screen<S> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl TockMonochrome8BitPage128x64Screen {
+impl<S: Syscalls> Monochrome8BitPage128x64Screen<S> {
     pub fn new() -> Self {
-        let (width, height) = Screen::get_resolution().unwrap_or((0, 0));
+        let (width, height) = Screen::<S>::get_resolution().unwrap_or((0, 0));
 
         // Because this is a specific type of screen with a specific pixel
         // format, we tell the kernel that is the pixel format we expect.
         let mono_8_bit_page = 6;
-        let _ = Screen::set_pixel_format(mono_8_bit_page);
+        let _ = Screen::<S>::set_pixel_format(mono_8_bit_page);
 
         Self {
             framebuffer: [0; 1024],
             width,
             height,
+            _syscalls: PhantomData,
         }
     }
 
@@ -48,13 +56,13 @@ impl TockMonochrome8BitPage128x64Screen {
 
     /// Updates the screen from the framebuffer.
     pub fn flush(&self) -> Result<(), ErrorCode> {
-        Screen::set_write_frame(0, 0, self.width, self.height)?;
-        Screen::write(&self.framebuffer)?;
+        Screen::<S>::set_write_frame(0, 0, self.width, self.height)?;
+        Screen::<S>::write(&self.framebuffer)?;
         Ok(())
     }
 }
 
-impl embedded_graphics::draw_target::DrawTarget for TockMonochrome8BitPage128x64Screen {
+impl<S: Syscalls> embedded_graphics::draw_target::DrawTarget for Monochrome8BitPage128x64Screen<S> {
     type Color = embedded_graphics::pixelcolor::BinaryColor;
     type Error = core::convert::Infallible;
 
@@ -90,7 +98,9 @@ impl embedded_graphics::draw_target::DrawTarget for TockMonochrome8BitPage128x64
     }
 }
 
-impl embedded_graphics::geometry::OriginDimensions for TockMonochrome8BitPage128x64Screen {
+impl<S: Syscalls> embedded_graphics::geometry::OriginDimensions
+    for Monochrome8BitPage128x64Screen<S>
+{
     fn size(&self) -> embedded_graphics::geometry::Size {
         embedded_graphics::geometry::Size::new(128, 64)
     }
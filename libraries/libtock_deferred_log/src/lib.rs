@@ -0,0 +1,189 @@
+//! Deferred-format logging: log records are sent over the console as compact
+//! binary frames, and formatted on the host by the runner.
+//!
+//! Each logging macro interns its level, target and format string into the
+//! `.libtock_log_strings` section of the process binary. `libtock_layout.ld`
+//! keeps that section in the ELF file but does not load it onto the device, so
+//! format strings take no flash. At run time, only the string's address and the
+//! arguments are sent, in the binary format described in `format`. The runner
+//! finds the strings in the ELF file it deploys, and prints each frame as a
+//! line of text, e.g. `INFO app::sensors: 3 samples`.
+//!
+//! Arguments are formatted by the runner, so they must implement `Encode`
+//! (integers, `bool`, `char`, `f32`, `str` and byte slices). Format strings
+//! support `{}`, `{:?}`, `{:x}`, `{:#x}`, `{:X}`, and `{:b}`.
+//!
+//! Records are filtered by the same settings as `libtock_log`: the
+//! `max_level_*` features of `libtock_log` at compile time, and
+//! `set_max_level` and `set_target_filters` at run time.
+//!
+//! ```ignore
+//! use libtock::runtime::TockSyscalls;
+//! use libtock_deferred_log::{info, DeferredLogger, LevelFilter};
+//!
+//! DeferredLogger::<TockSyscalls>::init(LevelFilter::Info);
+//! info!("{} samples, first {:#x}", samples.len(), samples[0]);
+//! ```
+
+#![no_std]
+
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use format::{Arg, MAX_FRAME_LEN};
+use libtock_console::{Config, Console};
+use libtock_platform::{DefaultConfig, Syscalls};
+
+pub use libtock_deferred_log_format as format;
+pub use libtock_log::{
+    enabled, max_level, set_max_level, set_target_filters, Level, LevelFilter, STATIC_MAX_LEVEL,
+};
+
+mod macros;
+
+/// Sends frames to the console. Frames are dropped until `DeferredLogger::init`
+/// is called.
+pub struct DeferredLogger<S: Syscalls, C: Config = DefaultConfig>(PhantomData<(S, C)>);
+
+impl<S: Syscalls, C: Config> DeferredLogger<S, C> {
+    /// Starts sending records at or below `level`.
+    pub fn init(level: LevelFilter) {
+        SEND_FN.store(send::<S, C> as SendFn as *mut (), Ordering::Relaxed);
+        set_max_level(level);
+    }
+}
+
+/// A value that can be sent as a logging macro argument.
+pub trait Encode {
+    fn to_arg(&self) -> Arg<'_>;
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn to_arg(&self) -> Arg<'_> {
+        (**self).to_arg()
+    }
+}
+
+macro_rules! impl_encode {
+    ($variant:ident($as:ty): $($type:ty),*) => {$(
+        impl Encode for $type {
+            fn to_arg(&self) -> Arg<'_> {
+                Arg::$variant(*self as $as)
+            }
+        }
+    )*};
+}
+
+impl_encode!(Unsigned(u64): u8, u16, u32, u64, usize);
+impl_encode!(Signed(i64): i8, i16, i32, i64, isize);
+impl_encode!(Bool(bool): bool);
+impl_encode!(Char(char): char);
+impl_encode!(F32(f32): f32);
+
+impl Encode for str {
+    fn to_arg(&self) -> Arg<'_> {
+        Arg::Str(self)
+    }
+}
+
+impl Encode for [u8] {
+    fn to_arg(&self) -> Arg<'_> {
+        Arg::Bytes(self)
+    }
+}
+
+impl<const N: usize> Encode for [u8; N] {
+    fn to_arg(&self) -> Arg<'_> {
+        Arg::Bytes(self)
+    }
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use super::*;
+
+    /// Copies `s` into an array, for interning.
+    pub const fn to_array<const N: usize>(s: &str) -> [u8; N] {
+        let mut array = [0; N];
+        let mut i = 0;
+        while i < s.len() {
+            array[i] = s.as_bytes()[i];
+            i += 1;
+        }
+        array
+    }
+
+    /// A frame being encoded by a logging macro.
+    pub struct FrameBuffer {
+        bytes: [u8; MAX_FRAME_LEN],
+        len: usize,
+        // The position of the argument count, which is updated as arguments
+        // are added.
+        count_pos: usize,
+        full: bool,
+    }
+
+    impl FrameBuffer {
+        pub fn new(interned: &'static [u8]) -> Self {
+            let mut frame = FrameBuffer {
+                bytes: [0; MAX_FRAME_LEN],
+                len: 1,
+                count_pos: 0,
+                full: false,
+            };
+            frame.bytes[0] = format::MARKER;
+            // A varint of a u64 takes at most 10 bytes, which always fits.
+            let address = interned.as_ptr() as u64;
+            frame.len += format::encode_varint(address, &mut frame.bytes[1..]).unwrap_or(0);
+            frame.count_pos = frame.len;
+            // The count is less than 128 (each argument takes at least 2
+            // bytes), so it is always encoded as a single byte.
+            frame.len += 1;
+            frame
+        }
+
+        /// Adds an argument. Once an argument does not fit, it and the
+        /// following arguments are dropped.
+        pub fn push<T: Encode + ?Sized>(&mut self, value: &T) {
+            if self.full {
+                return;
+            }
+            match format::encode_arg(value.to_arg(), &mut self.bytes[self.len..]) {
+                Some(len) => {
+                    self.len += len;
+                    self.bytes[self.count_pos] += 1;
+                }
+                None => self.full = true,
+            }
+        }
+
+        pub fn send(&self) {
+            let send_fn = SEND_FN.load(Ordering::Relaxed);
+            if send_fn.is_null() {
+                return;
+            }
+            // Safety: SEND_FN is only set (by DeferredLogger::init) to SendFn
+            // values.
+            let send_fn: SendFn = unsafe { core::mem::transmute(send_fn) };
+            send_fn(&self.bytes[..self.len]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
+
+// -----------------------------------------------------------------------------
+// Implementation details below
+// -----------------------------------------------------------------------------
+
+// The send instance for the Syscalls implementation passed to
+// DeferredLogger::init, or null before init. An atomic (with only loads and
+// stores, which every Tock target supports) is used so this can be a plain
+// static.
+static SEND_FN: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+type SendFn = fn(&[u8]);
+
+fn send<S: Syscalls, C: Config>(frame: &[u8]) {
+    let _ = Console::<S, C>::write(frame);
+}
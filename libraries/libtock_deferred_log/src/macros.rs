@@ -0,0 +1,58 @@
+// Interns the record's string and sends a frame, if the level is enabled.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log {
+    ($level:ident, $name:literal, $fmt:literal $(, $arg:expr)* $(,)?) => {{
+        const LEVEL: $crate::Level = $crate::Level::$level;
+        // STATIC_MAX_LEVEL is a constant, so disabled records are compiled out.
+        if LEVEL <= $crate::STATIC_MAX_LEVEL && $crate::enabled(LEVEL, module_path!()) {
+            const STRING: &str = concat!($name, " ", module_path!(), ": ", $fmt, "\0");
+            #[cfg_attr(target_os = "none", link_section = ".libtock_log_strings")]
+            static INTERNED: [u8; STRING.len()] = $crate::__private::to_array(STRING);
+            #[allow(unused_mut)]
+            let mut frame = $crate::__private::FrameBuffer::new(&INTERNED);
+            $(frame.push(&$arg);)*
+            frame.send();
+        }
+    }};
+}
+
+/// Logs a record at the `Error` level.
+#[macro_export]
+macro_rules! error {
+    ($($tt:tt)+) => {
+        $crate::__log!(Error, "ERROR", $($tt)+)
+    };
+}
+
+/// Logs a record at the `Warn` level.
+#[macro_export]
+macro_rules! warn {
+    ($($tt:tt)+) => {
+        $crate::__log!(Warn, "WARN", $($tt)+)
+    };
+}
+
+/// Logs a record at the `Info` level.
+#[macro_export]
+macro_rules! info {
+    ($($tt:tt)+) => {
+        $crate::__log!(Info, "INFO", $($tt)+)
+    };
+}
+
+/// Logs a record at the `Debug` level.
+#[macro_export]
+macro_rules! debug {
+    ($($tt:tt)+) => {
+        $crate::__log!(Debug, "DEBUG", $($tt)+)
+    };
+}
+
+/// Logs a record at the `Trace` level.
+#[macro_export]
+macro_rules! trace {
+    ($($tt:tt)+) => {
+        $crate::__log!(Trace, "TRACE", $($tt)+)
+    };
+}
@@ -0,0 +1,74 @@
+extern crate std;
+
+use super::format::{decode_frame, Arg};
+use super::*;
+use libtock_unittest::fake;
+use std::ffi::CStr;
+use std::sync::{Mutex, MutexGuard};
+use std::vec::Vec;
+
+type DeferredLogger = super::DeferredLogger<fake::Syscalls>;
+
+// The logger's settings are global, so tests that use them must not run in
+// parallel.
+static SETTINGS: Mutex<()> = Mutex::new(());
+
+fn lock() -> MutexGuard<'static, ()> {
+    SETTINGS.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+// Returns the interned string at `address`. In unit tests, interned strings
+// are ordinary statics, so the address is a pointer to the string.
+fn interned(address: u64) -> &'static str {
+    // Safety: The address came from a frame sent by a logging macro, so it
+    // points to a NUL-terminated static.
+    let string = unsafe { CStr::from_ptr(address as usize as *const core::ffi::c_char) };
+    string.to_str().unwrap()
+}
+
+#[test]
+fn macros() {
+    let _guard = lock();
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    kernel.add_driver(&console);
+
+    DeferredLogger::init(LevelFilter::Info);
+    crate::info!("{} {} {}", 7u8, -2i32, "str");
+    crate::debug!("filtered");
+    crate::error!("no args");
+
+    let bytes = console.take_bytes();
+    let (frame, len) = decode_frame(&bytes).unwrap();
+    assert_eq!(
+        interned(frame.string),
+        "INFO libtock_deferred_log::tests: {} {} {}"
+    );
+    assert_eq!(
+        frame.args().collect::<Vec<_>>(),
+        [Arg::Unsigned(7), Arg::Signed(-2), Arg::Str("str")]
+    );
+    let (frame, rest) = decode_frame(&bytes[len..]).unwrap();
+    assert_eq!(
+        interned(frame.string),
+        "ERROR libtock_deferred_log::tests: no args"
+    );
+    assert_eq!(frame.args().count(), 0);
+    assert_eq!(len + rest, bytes.len());
+}
+
+#[test]
+fn full_frame() {
+    let _guard = lock();
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    kernel.add_driver(&console);
+
+    DeferredLogger::init(LevelFilter::Trace);
+    let big = [0u8; MAX_FRAME_LEN - 4];
+    crate::trace!("{} {:?} {}", 1u8, big, 2u8);
+    let bytes = console.take_bytes();
+    let (frame, _) = decode_frame(&bytes).unwrap();
+    // The array does not fit, so it and the following argument are dropped.
+    assert_eq!(frame.args().collect::<Vec<_>>(), [Arg::Unsigned(1)]);
+}
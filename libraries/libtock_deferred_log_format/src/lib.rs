@@ -0,0 +1,278 @@
+//! The binary format of deferred log frames.
+//!
+//! A frame is `MARKER`, followed by the address of the record's interned
+//! string, the number of arguments, and the arguments. The address and
+//! argument count are unsigned LEB128 integers. Each argument is a one-byte tag
+//! followed by its value:
+//!
+//! * `UNSIGNED`: an unsigned LEB128 integer.
+//! * `SIGNED`: a zigzag-encoded LEB128 integer.
+//! * `BOOL`: one byte, 0 or 1.
+//! * `CHAR`: the code point, as an unsigned LEB128 integer.
+//! * `STR` and `BYTES`: the length as an unsigned LEB128 integer, followed by
+//!   the bytes.
+//! * `F32`: four bytes, little-endian.
+//!
+//! `MARKER` never appears in UTF-8 text, so frames can be mixed with text
+//! written to the console.
+//!
+//! The interned string is the record's level, target and format string, in the
+//! form `"INFO app::module: {} bytes"`, followed by a NUL byte. Interned
+//! strings are stored in the `SECTION` section of the process binary, which is
+//! not loaded onto the device; its address is the string's offset into the
+//! section. The section starts with a padding byte, so no string has address
+//! 0.
+//!
+//! This crate has no dependencies, so that host tools can decode frames
+//! without building the logger.
+
+#![no_std]
+
+/// The byte that starts each frame.
+pub const MARKER: u8 = 0xff;
+
+/// The name of the ELF section interned strings are stored in.
+pub const SECTION: &str = ".libtock_log_strings";
+
+/// The maximum length of a frame. Arguments that do not fit are dropped.
+pub const MAX_FRAME_LEN: usize = 128;
+
+pub const UNSIGNED: u8 = 0;
+pub const SIGNED: u8 = 1;
+pub const BOOL: u8 = 2;
+pub const CHAR: u8 = 3;
+pub const STR: u8 = 4;
+pub const BYTES: u8 = 5;
+pub const F32: u8 = 6;
+
+/// A logged value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arg<'a> {
+    Unsigned(u64),
+    Signed(i64),
+    Bool(bool),
+    Char(char),
+    Str(&'a str),
+    Bytes(&'a [u8]),
+    F32(f32),
+}
+
+/// A decoded frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame<'a> {
+    /// The address of the interned string.
+    pub string: u64,
+    arg_count: usize,
+    // The encoded arguments, which have been checked to be valid.
+    args: &'a [u8],
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    /// The frame is cut off; more bytes are needed to decode it.
+    Incomplete,
+
+    /// The bytes are not a valid frame.
+    Invalid,
+}
+
+/// Encodes `arg` into the start of `buffer`, returning the number of bytes
+/// written, or `None` if it does not fit.
+pub fn encode_arg(arg: Arg<'_>, buffer: &mut [u8]) -> Option<usize> {
+    let mut writer = Writer { buffer, len: 0 };
+    match arg {
+        Arg::Unsigned(value) => {
+            writer.push(UNSIGNED)?;
+            writer.push_varint(value)?;
+        }
+        Arg::Signed(value) => {
+            writer.push(SIGNED)?;
+            writer.push_varint(((value << 1) ^ (value >> 63)) as u64)?;
+        }
+        Arg::Bool(value) => {
+            writer.push(BOOL)?;
+            writer.push(value as u8)?;
+        }
+        Arg::Char(value) => {
+            writer.push(CHAR)?;
+            writer.push_varint(value as u64)?;
+        }
+        Arg::Str(value) => {
+            writer.push(STR)?;
+            writer.push_bytes(value.as_bytes())?;
+        }
+        Arg::Bytes(value) => {
+            writer.push(BYTES)?;
+            writer.push_bytes(value)?;
+        }
+        Arg::F32(value) => {
+            writer.push(F32)?;
+            for byte in value.to_le_bytes() {
+                writer.push(byte)?;
+            }
+        }
+    }
+    Some(writer.len)
+}
+
+/// Encodes an unsigned LEB128 integer into the start of `buffer`, returning the
+/// number of bytes written, or `None` if it does not fit.
+pub fn encode_varint(value: u64, buffer: &mut [u8]) -> Option<usize> {
+    let mut writer = Writer { buffer, len: 0 };
+    writer.push_varint(value)?;
+    Some(writer.len)
+}
+
+/// Decodes the unsigned LEB128 integer at the start of `bytes`, returning the
+/// value and its length.
+pub fn decode_varint(bytes: &[u8]) -> Result<(u64, usize), DecodeError> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let value = reader.varint()?;
+    Ok((value, reader.pos))
+}
+
+/// Decodes the frame at the start of `bytes`, which must start with `MARKER`.
+/// Returns the frame and its length.
+pub fn decode_frame(bytes: &[u8]) -> Result<(Frame<'_>, usize), DecodeError> {
+    let mut reader = Reader { bytes, pos: 0 };
+    if reader.byte()? != MARKER {
+        return Err(DecodeError::Invalid);
+    }
+    let string = reader.varint()?;
+    let arg_count = reader
+        .varint()?
+        .try_into()
+        .map_err(|_| DecodeError::Invalid)?;
+    let args_start = reader.pos;
+    for _ in 0..arg_count {
+        reader.arg()?;
+    }
+    let frame = Frame {
+        string,
+        arg_count,
+        args: &bytes[args_start..reader.pos],
+    };
+    Ok((frame, reader.pos))
+}
+
+impl<'a> Frame<'a> {
+    /// Returns the frame's arguments.
+    pub fn args(&self) -> impl Iterator<Item = Arg<'a>> {
+        let mut reader = Reader {
+            bytes: self.args,
+            pos: 0,
+        };
+        // The arguments were checked by decode_frame, so this does not fail.
+        (0..self.arg_count).map_while(move |_| reader.arg().ok())
+    }
+}
+
+#[cfg(test)]
+mod tests;
+
+// -----------------------------------------------------------------------------
+// Implementation details below
+// -----------------------------------------------------------------------------
+
+struct Writer<'b> {
+    buffer: &'b mut [u8],
+    len: usize,
+}
+
+impl Writer<'_> {
+    fn push(&mut self, byte: u8) -> Option<()> {
+        *self.buffer.get_mut(self.len)? = byte;
+        self.len += 1;
+        Some(())
+    }
+
+    fn push_varint(&mut self, mut value: u64) -> Option<()> {
+        while value >= 0x80 {
+            self.push(value as u8 | 0x80)?;
+            value >>= 7;
+        }
+        self.push(value as u8)
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) -> Option<()> {
+        self.push_varint(bytes.len() as u64)?;
+        let dest = self.buffer.get_mut(self.len..self.len + bytes.len())?;
+        dest.copy_from_slice(bytes);
+        self.len += bytes.len();
+        Some(())
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let byte = *self.bytes.get(self.pos).ok_or(DecodeError::Incomplete)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::Invalid)
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], DecodeError> {
+        let len: usize = self
+            .varint()?
+            .try_into()
+            .map_err(|_| DecodeError::Invalid)?;
+        let end = self.pos.checked_add(len).ok_or(DecodeError::Invalid)?;
+        let bytes = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(DecodeError::Incomplete)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn arg(&mut self) -> Result<Arg<'a>, DecodeError> {
+        Ok(match self.byte()? {
+            UNSIGNED => Arg::Unsigned(self.varint()?),
+            SIGNED => {
+                let value = self.varint()?;
+                Arg::Signed((value >> 1) as i64 ^ -((value & 1) as i64))
+            }
+            BOOL => match self.byte()? {
+                0 => Arg::Bool(false),
+                1 => Arg::Bool(true),
+                _ => return Err(DecodeError::Invalid),
+            },
+            CHAR => {
+                let value = self
+                    .varint()?
+                    .try_into()
+                    .map_err(|_| DecodeError::Invalid)?;
+                Arg::Char(char::from_u32(value).ok_or(DecodeError::Invalid)?)
+            }
+            STR => {
+                let bytes = self.bytes()?;
+                Arg::Str(core::str::from_utf8(bytes).map_err(|_| DecodeError::Invalid)?)
+            }
+            BYTES => Arg::Bytes(self.bytes()?),
+            F32 => {
+                let mut bytes = [0; 4];
+                for byte in &mut bytes {
+                    *byte = self.byte()?;
+                }
+                Arg::F32(f32::from_le_bytes(bytes))
+            }
+            _ => return Err(DecodeError::Invalid),
+        })
+    }
+}
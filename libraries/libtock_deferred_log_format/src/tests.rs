@@ -0,0 +1,68 @@
+extern crate std;
+
+use super::*;
+use std::vec;
+use std::vec::Vec;
+
+#[test]
+fn args_round_trip() {
+    let args = [
+        Arg::Unsigned(0),
+        Arg::Unsigned(u64::MAX),
+        Arg::Signed(-1),
+        Arg::Signed(i64::MIN),
+        Arg::Signed(i64::MAX),
+        Arg::Bool(true),
+        Arg::Char('é'),
+        Arg::Str("hello"),
+        Arg::Bytes(&[1, 2, 3]),
+        Arg::F32(1.5),
+    ];
+    let mut frame = vec![MARKER, 0x80, 0x01, args.len() as u8];
+    for arg in args {
+        let mut buffer = [0; 16];
+        let len = encode_arg(arg, &mut buffer).unwrap();
+        frame.extend_from_slice(&buffer[..len]);
+    }
+    let (decoded, len) = decode_frame(&frame).unwrap();
+    assert_eq!(len, frame.len());
+    assert_eq!(decoded.string, 128);
+    assert_eq!(decoded.args().collect::<Vec<_>>(), args);
+
+    // Every prefix of a frame is incomplete.
+    for len in 0..frame.len() {
+        assert_eq!(decode_frame(&frame[..len]), Err(DecodeError::Incomplete));
+    }
+}
+
+#[test]
+fn invalid_frames() {
+    assert_eq!(decode_frame(b"x"), Err(DecodeError::Invalid));
+    // Unknown tag.
+    assert_eq!(decode_frame(&[MARKER, 0, 1, 99]), Err(DecodeError::Invalid));
+    // Invalid bool.
+    assert_eq!(
+        decode_frame(&[MARKER, 0, 1, 2, 2]),
+        Err(DecodeError::Invalid)
+    );
+    // Invalid UTF-8.
+    assert_eq!(
+        decode_frame(&[MARKER, 0, 1, 4, 1, 0xc0]),
+        Err(DecodeError::Invalid)
+    );
+    assert_eq!(encode_arg(Arg::Str("hello"), &mut [0; 4]), None);
+}
+
+#[test]
+fn varints() {
+    let mut buffer = [0; 10];
+    for value in [0, 127, 128, 300, u64::MAX] {
+        let len = encode_varint(value, &mut buffer).unwrap();
+        assert_eq!(decode_varint(&buffer[..len]), Ok((value, len)));
+        assert_eq!(
+            decode_varint(&buffer[..len - 1]),
+            Err(DecodeError::Incomplete)
+        );
+    }
+    assert_eq!(decode_varint(&[0x80; 10]), Err(DecodeError::Invalid));
+}
@@ -0,0 +1,406 @@
+//! Leveled logging for libtock-rs apps, without `core::fmt`.
+//!
+//! The `error!`, `warn!`, `info!`, `debug!` and `trace!` macros format their
+//! message with `ufmt` (see the `ufmt` crate for the supported syntax), and
+//! pass it to the logger registered by `Logger::init`. Each record has a
+//! target, which is the module that logged it unless the macro is given a
+//! `target:` argument.
+//!
+//! Records are filtered twice:
+//!
+//! * At compile time, by the `max_level_*` cargo features. Records above the
+//!   selected level are compiled out, along with their formatting code.
+//! * At run time, by `set_max_level`, and per target by `set_target_filters`.
+//!
+//! ```ignore
+//! use libtock::runtime::TockSyscalls;
+//! use libtock_log::{info, warn, LevelFilter, Logger, Output};
+//!
+//! Logger::<TockSyscalls>::init(Output::Console, LevelFilter::Info);
+//! libtock_log::set_target_filters(&[("app::radio", LevelFilter::Warn)]);
+//!
+//! info!("started, {} sensors", sensor_count);
+//! warn!(target: "app::radio", "dropped {} frames", dropped);
+//! ```
+//!
+//! On the console, records look like `[12.345] INFO app::sensors: message`;
+//! the timestamp (in seconds since the alarm counter started, wrapping around
+//! with it) is omitted if the alarm driver is missing or timestamps are
+//! disabled with `set_timestamps`.
+//!
+//! With the `log` feature, `Logger::init` also installs the logger as the
+//! `log` crate's logger, so records from third-party crates that use `log`
+//! are output the same way. Those records are formatted with `core::fmt`.
+
+#![no_std]
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU8, AtomicUsize, Ordering};
+use libtock_alarm::Alarm;
+use libtock_console::{Config, Console};
+use libtock_low_level_debug::LowLevelDebug;
+use libtock_platform::{DefaultConfig, Syscalls};
+
+mod macros;
+
+/// The importance of a record. `Error` is the most important.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[repr(u8)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+/// The least important level that is logged, or `Off` to log nothing.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[repr(u8)]
+pub enum LevelFilter {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+/// The maximum level that is compiled in, selected by the `max_level_*` cargo
+/// features.
+pub const STATIC_MAX_LEVEL: LevelFilter = if cfg!(feature = "max_level_off") {
+    LevelFilter::Off
+} else if cfg!(feature = "max_level_error") {
+    LevelFilter::Error
+} else if cfg!(feature = "max_level_warn") {
+    LevelFilter::Warn
+} else if cfg!(feature = "max_level_info") {
+    LevelFilter::Info
+} else if cfg!(feature = "max_level_debug") {
+    LevelFilter::Debug
+} else {
+    LevelFilter::Trace
+};
+
+/// The maximum length of a message, in bytes. Longer messages are truncated,
+/// and end with `...` in the output.
+pub const MAX_MESSAGE_LEN: usize = 128;
+
+/// Where the logger writes records.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Output {
+    /// Records are written to the console as text, one line per record.
+    Console,
+
+    /// Records are printed with the LowLevelDebug driver, for apps that do not
+    /// use the console. LowLevelDebug can only print numbers, so each record
+    /// is printed as its level (1 for `Error` through 5 for `Trace`) and the
+    /// line number of the statement that logged it; the message is dropped.
+    LowLevelDebug,
+}
+
+/// The logger. Records are only output after `Logger::init` is called.
+// The PhantomData holds a function pointer so that the logger is Send and Sync,
+// which the log crate requires.
+pub struct Logger<S: Syscalls, C: Config = DefaultConfig>(PhantomData<fn() -> (S, C)>);
+
+impl<S: Syscalls + 'static, C: Config + 'static> Logger<S, C> {
+    /// Starts writing records at or below `level` to `output`.
+    pub fn init(output: Output, level: LevelFilter) {
+        OUTPUT.store(output as u8, Ordering::Relaxed);
+        LOG_FN.store(log_record::<S, C> as LogFn as *mut (), Ordering::Relaxed);
+        set_max_level(level);
+        #[cfg(feature = "log")]
+        {
+            // Safety: Tock processes are single-threaded, so these cannot race
+            // with other calls to the log crate.
+            unsafe {
+                // Filtering is done by Logger::enabled.
+                log::set_max_level_racy(log::LevelFilter::Trace);
+                let _ = log::set_logger_racy(&Logger::<S, C>(PhantomData));
+            }
+        }
+    }
+}
+
+/// Sets the maximum level that is logged, for targets without a filter set by
+/// `set_target_filters`. Levels above `STATIC_MAX_LEVEL` are never logged.
+pub fn set_max_level(level: LevelFilter) {
+    MAX_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn max_level() -> LevelFilter {
+    LevelFilter::from_u8(MAX_LEVEL.load(Ordering::Relaxed))
+}
+
+/// Sets per-target maximum levels, replacing any previous filters. A filter
+/// applies to its target and the modules inside it: `("app::radio", _)`
+/// applies to `app::radio` and `app::radio::mac`, but not `app::radios`. If
+/// several filters apply, the longest target wins.
+pub fn set_target_filters(filters: &'static [(&'static str, LevelFilter)]) {
+    FILTERS_LEN.store(0, Ordering::Relaxed);
+    FILTERS.store(filters.as_ptr() as *mut _, Ordering::Relaxed);
+    FILTERS_LEN.store(filters.len(), Ordering::Relaxed);
+}
+
+/// Sets whether console records start with a timestamp. Timestamps are
+/// enabled by default.
+pub fn set_timestamps(enabled: bool) {
+    TIMESTAMPS.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether a record at `level` for `target` would be logged.
+pub fn enabled(level: Level, target: &str) -> bool {
+    if level > STATIC_MAX_LEVEL {
+        return false;
+    }
+    let mut max = max_level();
+    let mut matched_len = None;
+    for &(filter_target, filter_level) in filters() {
+        let applies = target
+            .strip_prefix(filter_target)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"));
+        if applies && matched_len < Some(filter_target.len()) {
+            max = filter_level;
+            matched_len = Some(filter_target.len());
+        }
+    }
+    level <= max
+}
+
+impl Level {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+impl PartialEq<LevelFilter> for Level {
+    fn eq(&self, other: &LevelFilter) -> bool {
+        *self as u8 == *other as u8
+    }
+}
+
+impl PartialOrd<LevelFilter> for Level {
+    fn partial_cmp(&self, other: &LevelFilter) -> Option<core::cmp::Ordering> {
+        Some((*self as u8).cmp(&(*other as u8)))
+    }
+}
+
+impl From<Level> for LevelFilter {
+    fn from(level: Level) -> LevelFilter {
+        LevelFilter::from_u8(level as u8)
+    }
+}
+
+impl ufmt::uDisplay for Level {
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "log")]
+impl From<log::Level> for Level {
+    fn from(level: log::Level) -> Level {
+        match level {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Warn,
+            log::Level::Info => Level::Info,
+            log::Level::Debug => Level::Debug,
+            log::Level::Trace => Level::Trace,
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+impl<S: Syscalls, C: Config> log::Log for Logger<S, C> {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        enabled(metadata.level().into(), metadata.target())
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        if !log::Log::enabled(self, record.metadata()) {
+            return;
+        }
+        let mut message = Message::new();
+        let _ = fmt::write(&mut message, *record.args());
+        log_record::<S, C>(
+            record.level().into(),
+            record.target(),
+            record.line().unwrap_or(0),
+            &message,
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::Message;
+    pub use ufmt;
+
+    // Passes a record to the logger, if one is registered. Called by the
+    // logging macros after checking `enabled`.
+    pub fn log(level: crate::Level, target: &str, line: u32, message: &Message) {
+        let log_fn = crate::LOG_FN.load(core::sync::atomic::Ordering::Relaxed);
+        if log_fn.is_null() {
+            return;
+        }
+        // Safety: LOG_FN is only set (by Logger::init) to LogFn values.
+        let log_fn: crate::LogFn = unsafe { core::mem::transmute(log_fn) };
+        log_fn(level, target, line, message);
+    }
+}
+
+#[cfg(test)]
+mod tests;
+
+// -----------------------------------------------------------------------------
+// Implementation details below
+// -----------------------------------------------------------------------------
+
+// The logger's settings. Atomics (with only loads and stores, which every Tock
+// target supports) are used so the settings can be plain statics.
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(LevelFilter::Off as u8);
+static OUTPUT: AtomicU8 = AtomicU8::new(Output::Console as u8);
+static TIMESTAMPS: AtomicBool = AtomicBool::new(true);
+// The slice passed to set_target_filters.
+static FILTERS: AtomicPtr<(&str, LevelFilter)> = AtomicPtr::new(core::ptr::null_mut());
+static FILTERS_LEN: AtomicUsize = AtomicUsize::new(0);
+// The log_record instance for the Syscalls implementation passed to
+// Logger::init, or null before init.
+static LOG_FN: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+type LogFn = fn(Level, &str, u32, &Message);
+
+// The maximum length of a console line: the message plus room for the
+// timestamp, level, and target.
+const MAX_LINE_LEN: usize = MAX_MESSAGE_LEN + 96;
+
+impl LevelFilter {
+    fn from_u8(value: u8) -> LevelFilter {
+        match value {
+            1 => LevelFilter::Error,
+            2 => LevelFilter::Warn,
+            3 => LevelFilter::Info,
+            4 => LevelFilter::Debug,
+            5 => LevelFilter::Trace,
+            _ => LevelFilter::Off,
+        }
+    }
+}
+
+fn filters() -> &'static [(&'static str, LevelFilter)] {
+    let len = FILTERS_LEN.load(Ordering::Relaxed);
+    if len == 0 {
+        return &[];
+    }
+    // Safety: FILTERS and FILTERS_LEN are only set (by set_target_filters)
+    // from a &'static slice. FILTERS_LEN is cleared while FILTERS is changed.
+    unsafe { core::slice::from_raw_parts(FILTERS.load(Ordering::Relaxed), len) }
+}
+
+/// A message buffer, which truncates text that does not fit. Used by the
+/// logging macros.
+#[doc(hidden)]
+pub struct Message<const N: usize = MAX_MESSAGE_LEN> {
+    bytes: [u8; N],
+    len: usize,
+    truncated: bool,
+}
+
+impl<const N: usize> Message<N> {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Message {
+            bytes: [0; N],
+            len: 0,
+            truncated: false,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        // Only whole characters are written, so this does not fail.
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap_or("")
+    }
+
+    // Appends as much of `s` as fits. Once something does not fit, the rest
+    // of the message is dropped.
+    fn push_str(&mut self, s: &str) {
+        if self.truncated {
+            return;
+        }
+        let mut count = s.len().min(N - self.len);
+        while !s.is_char_boundary(count) {
+            count -= 1;
+        }
+        self.bytes[self.len..self.len + count].copy_from_slice(&s.as_bytes()[..count]);
+        self.len += count;
+        self.truncated = count < s.len();
+    }
+
+    // Ends the line with "...\n" if it was truncated, or "\n" if not,
+    // dropping text to make room if necessary.
+    fn end_line(&mut self, truncated: bool) {
+        let ending = match truncated || self.truncated {
+            true => "...\n",
+            false => "\n",
+        };
+        let mut len = self.len.min(N.saturating_sub(ending.len()));
+        while !self.as_str().is_char_boundary(len) {
+            len -= 1;
+        }
+        self.len = len;
+        self.truncated = false;
+        self.push_str(ending);
+    }
+}
+
+impl<const N: usize> ufmt::uWrite for Message<N> {
+    type Error = core::convert::Infallible;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+impl<const N: usize> fmt::Write for Message<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+// Writes a record to the output selected by Logger::init.
+fn log_record<S: Syscalls, C: Config>(level: Level, target: &str, line: u32, message: &Message) {
+    if OUTPUT.load(Ordering::Relaxed) == Output::LowLevelDebug as u8 {
+        LowLevelDebug::<S>::print_2(level as u32, line);
+        return;
+    }
+    let mut text = Message::<MAX_LINE_LEN>::new();
+    if TIMESTAMPS.load(Ordering::Relaxed) {
+        if let Ok(ms) = Alarm::<S, C>::get_milliseconds() {
+            let millis = ms % 1000;
+            let padding = match millis {
+                0..=9 => "00",
+                10..=99 => "0",
+                _ => "",
+            };
+            let _ = ufmt::uwrite!(text, "[{}.{}{}] ", ms / 1000, padding, millis);
+        }
+    }
+    let _ = ufmt::uwrite!(text, "{} {}: {}", level, target, message.as_str());
+    text.end_line(message.truncated);
+    let _ = Console::<S, C>::write(text.as_str().as_bytes());
+}
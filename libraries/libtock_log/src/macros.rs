@@ -0,0 +1,77 @@
+/// Logs a record at the given level. The message is formatted with `ufmt`.
+///
+/// ```ignore
+/// log!(Level::Info, "{} bytes", len);
+/// log!(target: "app::radio", Level::Warn, "timeout");
+/// ```
+#[macro_export]
+macro_rules! log {
+    (target: $target:expr, $level:expr, $($tt:tt)+) => {{
+        let level: $crate::Level = $level;
+        // STATIC_MAX_LEVEL is a constant, so disabled records are compiled out.
+        if level <= $crate::STATIC_MAX_LEVEL && $crate::enabled(level, $target) {
+            use $crate::__private::ufmt;
+            let mut message = $crate::__private::Message::new();
+            let _ = ufmt::uwrite!(&mut message, $($tt)+);
+            $crate::__private::log(level, $target, line!(), &message);
+        }
+    }};
+    ($level:expr, $($tt:tt)+) => {
+        $crate::log!(target: module_path!(), $level, $($tt)+)
+    };
+}
+
+/// Logs a record at the `Error` level.
+#[macro_export]
+macro_rules! error {
+    (target: $target:expr, $($tt:tt)+) => {
+        $crate::log!(target: $target, $crate::Level::Error, $($tt)+)
+    };
+    ($($tt:tt)+) => {
+        $crate::log!($crate::Level::Error, $($tt)+)
+    };
+}
+
+/// Logs a record at the `Warn` level.
+#[macro_export]
+macro_rules! warn {
+    (target: $target:expr, $($tt:tt)+) => {
+        $crate::log!(target: $target, $crate::Level::Warn, $($tt)+)
+    };
+    ($($tt:tt)+) => {
+        $crate::log!($crate::Level::Warn, $($tt)+)
+    };
+}
+
+/// Logs a record at the `Info` level.
+#[macro_export]
+macro_rules! info {
+    (target: $target:expr, $($tt:tt)+) => {
+        $crate::log!(target: $target, $crate::Level::Info, $($tt)+)
+    };
+    ($($tt:tt)+) => {
+        $crate::log!($crate::Level::Info, $($tt)+)
+    };
+}
+
+/// Logs a record at the `Debug` level.
+#[macro_export]
+macro_rules! debug {
+    (target: $target:expr, $($tt:tt)+) => {
+        $crate::log!(target: $target, $crate::Level::Debug, $($tt)+)
+    };
+    ($($tt:tt)+) => {
+        $crate::log!($crate::Level::Debug, $($tt)+)
+    };
+}
+
+/// Logs a record at the `Trace` level.
+#[macro_export]
+macro_rules! trace {
+    (target: $target:expr, $($tt:tt)+) => {
+        $crate::log!(target: $target, $crate::Level::Trace, $($tt)+)
+    };
+    ($($tt:tt)+) => {
+        $crate::log!($crate::Level::Trace, $($tt)+)
+    };
+}
@@ -0,0 +1,205 @@
+extern crate std;
+
+use super::*;
+use libtock_unittest::fake;
+use std::sync::{Mutex, MutexGuard};
+use std::vec;
+use std::vec::Vec;
+
+type Logger = super::Logger<fake::Syscalls>;
+
+// The logger's settings are global, so tests that use them must not run in
+// parallel.
+static SETTINGS: Mutex<()> = Mutex::new(());
+
+fn lock() -> MutexGuard<'static, ()> {
+    let guard = SETTINGS.lock().unwrap_or_else(|error| error.into_inner());
+    set_target_filters(&[]);
+    set_timestamps(true);
+    guard
+}
+
+// The max_level_* features compile records out, so expected output only
+// includes the lines whose level is compiled in.
+fn compiled_in(level: Level) -> bool {
+    level <= STATIC_MAX_LEVEL
+}
+
+fn expected_lines(lines: &[(Level, &[u8])]) -> Vec<u8> {
+    lines
+        .iter()
+        .filter(|(level, _)| compiled_in(*level))
+        .flat_map(|(_, line)| line.iter().copied())
+        .collect()
+}
+
+#[test]
+fn levels() {
+    assert!(Level::Error < Level::Trace);
+    assert!(Level::Error <= LevelFilter::Error);
+    assert!(Level::Warn > LevelFilter::Error);
+    assert!(Level::Error > LevelFilter::Off);
+    assert_eq!(LevelFilter::from(Level::Debug), LevelFilter::Debug);
+}
+
+#[test]
+fn not_initialized() {
+    let _guard = lock();
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    kernel.add_driver(&console);
+
+    // Records are dropped before init; the log function must not be called.
+    set_max_level(LevelFilter::Off);
+    crate::error!("lost");
+    assert_eq!(console.take_bytes(), b"");
+}
+
+#[test]
+fn console() {
+    let _guard = lock();
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    kernel.add_driver(&console);
+
+    Logger::init(Output::Console, LevelFilter::Info);
+    assert_eq!(max_level(), LevelFilter::Info);
+    crate::info!("{} + {} = {}", 1u8, 2u8, 3u8);
+    crate::warn!(target: "radio", "timeout");
+    crate::debug!("filtered");
+    assert_eq!(
+        console.take_bytes(),
+        expected_lines(&[
+            (Level::Info, b"INFO libtock_log::tests: 1 + 2 = 3\n"),
+            (Level::Warn, b"WARN radio: timeout\n"),
+        ])
+    );
+}
+
+#[test]
+fn timestamps() {
+    let _guard = lock();
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    let alarm = fake::Alarm::new(1000);
+    kernel.add_driver(&console);
+    kernel.add_driver(&alarm);
+
+    Logger::init(Output::Console, LevelFilter::Trace);
+    alarm.set_time(12_045);
+    crate::error!(target: "app", "failed");
+    set_timestamps(false);
+    crate::trace!(target: "app", "done");
+    assert_eq!(
+        console.take_bytes(),
+        expected_lines(&[
+            (Level::Error, b"[12.045] ERROR app: failed\n"),
+            (Level::Trace, b"TRACE app: done\n"),
+        ])
+    );
+}
+
+#[test]
+fn target_filters() {
+    let _guard = lock();
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    kernel.add_driver(&console);
+
+    Logger::init(Output::Console, LevelFilter::Info);
+    set_target_filters(&[
+        ("app", LevelFilter::Error),
+        ("app::radio", LevelFilter::Debug),
+    ]);
+    assert_eq!(enabled(Level::Error, "app"), compiled_in(Level::Error));
+    assert!(!enabled(Level::Warn, "app"));
+    assert!(!enabled(Level::Warn, "app::sensors"));
+    assert_eq!(
+        enabled(Level::Debug, "app::radio"),
+        compiled_in(Level::Debug)
+    );
+    assert_eq!(
+        enabled(Level::Debug, "app::radio::mac"),
+        compiled_in(Level::Debug)
+    );
+    assert!(!enabled(Level::Trace, "app::radio"));
+    // Filters match whole path segments.
+    assert!(!enabled(Level::Warn, "app::radios"));
+    assert_eq!(
+        enabled(Level::Info, "application"),
+        compiled_in(Level::Info)
+    );
+    assert!(!enabled(Level::Debug, "application"));
+
+    crate::debug!(target: "app::radio::mac", "ack");
+    crate::info!(target: "app::sensors", "filtered");
+    assert_eq!(
+        console.take_bytes(),
+        expected_lines(&[(Level::Debug, b"DEBUG app::radio::mac: ack\n")])
+    );
+}
+
+#[test]
+fn truncation() {
+    let _guard = lock();
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    kernel.add_driver(&console);
+
+    Logger::init(Output::Console, LevelFilter::Trace);
+    let long = "ab".repeat(MAX_MESSAGE_LEN);
+    crate::info!(target: "t", "{}", long.as_str());
+    let mut expected = b"INFO t: ".to_vec();
+    expected.extend_from_slice(&long.as_bytes()[..MAX_MESSAGE_LEN]);
+    expected.extend_from_slice(b"...\n");
+    assert_eq!(
+        console.take_bytes(),
+        expected_lines(&[(Level::Info, &expected)])
+    );
+
+    // Truncation does not split characters.
+    let mut message = Message::<4>::new();
+    let _ = ufmt::uWrite::write_str(&mut message, "abcé");
+    let _ = ufmt::uWrite::write_str(&mut message, "c");
+    assert_eq!(message.as_str(), "abc");
+    message.end_line(false);
+    assert_eq!(message.as_str(), "...\n");
+}
+
+#[test]
+fn low_level_debug() {
+    let _guard = lock();
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    let lldb = fake::LowLevelDebug::new();
+    kernel.add_driver(&console);
+    kernel.add_driver(&lldb);
+
+    Logger::init(Output::LowLevelDebug, LevelFilter::Warn);
+    let line = line!() + 1;
+    crate::warn!("{}", 5u8);
+    crate::info!("filtered");
+    let expected = match compiled_in(Level::Warn) {
+        true => vec![fake::Message::Print2(2, line)],
+        false => vec![],
+    };
+    assert_eq!(lldb.take_messages(), expected);
+    assert_eq!(console.take_bytes(), b"");
+}
+
+#[cfg(feature = "log")]
+#[test]
+fn log_crate() {
+    let _guard = lock();
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    kernel.add_driver(&console);
+
+    Logger::init(Output::Console, LevelFilter::Info);
+    log::info!(target: "dep", "{:>3}", 7);
+    log::debug!(target: "dep", "filtered");
+    assert_eq!(
+        console.take_bytes(),
+        expected_lines(&[(Level::Info, b"INFO dep:   7\n")])
+    );
+}
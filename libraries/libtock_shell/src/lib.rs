@@ -0,0 +1,186 @@
+//! A small command shell for libtock-rs apps. Apps register a handler for
+//! each command with `Shell::register`, then call `Shell::run`, which reads
+//! lines with `libtock_console::LineReader` and runs the matching handler.
+//!
+//! A line is split on whitespace; the first word selects the command, and the
+//! rest are passed to its handler as arguments. Handlers write their output to
+//! the `fmt::Write` they are given, which writes to the console. The built-in
+//! `help` command lists the registered commands, and tab completes command
+//! names.
+//!
+//! ```ignore
+//! use libtock::console::LineReader;
+//! use libtock::leds::Leds;
+//! use libtock::runtime::TockSyscalls;
+//! use libtock_shell::{Args, Shell};
+//!
+//! let led = |mut args: Args, _: &mut dyn fmt::Write| {
+//!     let index = args.next().and_then(|arg| arg.parse().ok());
+//!     Leds::toggle(index.ok_or(ErrorCode::Invalid)?)
+//! };
+//! let mut shell = Shell::<TockSyscalls, 4>::new();
+//! shell.register("led", "led <n>: toggles LED n", &led)?;
+//! shell.run(&mut LineReader::<64, 4>::new(), "> ")
+//! ```
+
+#![no_std]
+
+use core::fmt::{self, Write};
+use core::marker::PhantomData;
+use libtock_console::{Completer, Config, Console, LineReader};
+use libtock_platform::{DefaultConfig, ErrorCode, Syscalls};
+
+/// The arguments to a command: the words on the line after the command name.
+pub type Args<'l> = core::str::SplitWhitespace<'l>;
+
+/// Runs a command. Errors are reported on the console by the shell.
+pub type Handler<'a> = &'a dyn Fn(Args<'_>, &mut dyn fmt::Write) -> Result<(), ErrorCode>;
+
+/// A command shell with room for `COMMANDS` commands (not counting `help`).
+pub struct Shell<'a, S: Syscalls, const COMMANDS: usize, C: Config = DefaultConfig> {
+    commands: [Option<Command<'a>>; COMMANDS],
+    _syscalls: PhantomData<(S, C)>,
+}
+
+impl<'a, S: Syscalls, const COMMANDS: usize, C: Config> Shell<'a, S, COMMANDS, C> {
+    pub fn new() -> Self {
+        Shell {
+            commands: [None; COMMANDS],
+            _syscalls: PhantomData,
+        }
+    }
+
+    /// Registers a command. `help` is a one-line description, shown by the
+    /// `help` command.
+    ///
+    /// Returns `Err(ErrorCode::Invalid)` if `name` is empty, contains
+    /// whitespace, or is `help`; `Err(ErrorCode::Already)` if a command with
+    /// the same name is registered; and `Err(ErrorCode::NoMem)` if the shell
+    /// is full.
+    pub fn register(
+        &mut self,
+        name: &'a str,
+        help: &'a str,
+        handler: Handler<'a>,
+    ) -> Result<(), ErrorCode> {
+        if name.is_empty() || name.contains(char::is_whitespace) || name == HELP {
+            return Err(ErrorCode::Invalid);
+        }
+        if self.find(name).is_some() {
+            return Err(ErrorCode::Already);
+        }
+        let slot = self
+            .commands
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(ErrorCode::NoMem)?;
+        *slot = Some(Command {
+            name,
+            help,
+            handler,
+        });
+        Ok(())
+    }
+
+    /// Runs the command on `line`, and returns the command's result. Empty
+    /// lines are ignored. Unknown commands fail with `ErrorCode::NoSupport`.
+    /// Failures are also reported on the console.
+    pub fn dispatch(&self, line: &str) -> Result<(), ErrorCode> {
+        let mut writer = Console::<S, C>::writer();
+        let mut args = line.split_whitespace();
+        let Some(name) = args.next() else {
+            return Ok(());
+        };
+        let result = match (name, self.find(name)) {
+            (HELP, _) => self.help(&mut writer).map_err(|_| ErrorCode::Fail),
+            (_, Some(command)) => (command.handler)(args, &mut writer),
+            (_, None) => {
+                // The error is returned even if it cannot be reported.
+                let _ = writeln!(writer, "{name}: command not found");
+                return Err(ErrorCode::NoSupport);
+            }
+        };
+        if let Err(error) = result {
+            let _ = writeln!(writer, "{name}: {error:?}");
+        }
+        result
+    }
+
+    /// Reads one line with `reader` and runs its command. Returns an error if
+    /// reading the line or the command failed.
+    pub fn run_once<const LEN: usize, const HISTORY: usize>(
+        &self,
+        reader: &mut LineReader<'_, S, LEN, HISTORY, C>,
+        prompt: &str,
+    ) -> Result<(), ErrorCode> {
+        let line = reader.read_line(prompt)?;
+        self.dispatch(line)
+    }
+
+    /// Runs commands read by `reader` forever, completing command names with
+    /// the tab key.
+    pub fn run<const LEN: usize, const HISTORY: usize>(
+        &'a self,
+        reader: &mut LineReader<'a, S, LEN, HISTORY, C>,
+        prompt: &str,
+    ) -> ! {
+        reader.set_completer(self);
+        loop {
+            // Failed commands are reported by dispatch.
+            let _ = self.run_once(reader, prompt);
+        }
+    }
+}
+
+impl<S: Syscalls, const COMMANDS: usize, C: Config> Default for Shell<'_, S, COMMANDS, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Completes command names.
+impl<S: Syscalls, const COMMANDS: usize, C: Config> Completer for Shell<'_, S, COMMANDS, C> {
+    fn complete(&self, line: &str, candidate: &mut dyn FnMut(&str)) {
+        if line.contains(' ') {
+            return;
+        }
+        candidate(HELP);
+        self.commands().for_each(|command| candidate(command.name));
+    }
+}
+
+#[cfg(test)]
+mod tests;
+
+// -----------------------------------------------------------------------------
+// Implementation details below
+// -----------------------------------------------------------------------------
+
+const HELP: &str = "help";
+
+#[derive(Clone, Copy)]
+struct Command<'a> {
+    name: &'a str,
+    help: &'a str,
+    handler: Handler<'a>,
+}
+
+impl<'a, S: Syscalls, const COMMANDS: usize, C: Config> Shell<'a, S, COMMANDS, C> {
+    fn commands(&self) -> impl Iterator<Item = &Command<'a>> {
+        self.commands.iter().flatten()
+    }
+
+    fn find(&self, name: &str) -> Option<&Command<'a>> {
+        self.commands().find(|command| command.name == name)
+    }
+
+    fn help(&self, writer: &mut dyn Write) -> fmt::Result {
+        let width = self.commands().map(|command| command.name.len()).max();
+        let width = width.unwrap_or(0).max(HELP.len());
+        writeln!(writer, "{HELP:width$}  lists commands")?;
+        for command in self.commands() {
+            writeln!(writer, "{:width$}  {}", command.name, command.help)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+extern crate std;
+
+use super::Args;
+use core::cell::RefCell;
+use core::fmt;
+use libtock_console::Completer;
+use libtock_platform::ErrorCode;
+use libtock_unittest::fake;
+use std::string::String;
+use std::vec;
+use std::vec::Vec;
+
+type Shell<'a, const COMMANDS: usize> = super::Shell<'a, fake::Syscalls, COMMANDS>;
+type LineReader<'a> = libtock_console::LineReader<'a, fake::Syscalls, 32, 4>;
+
+#[test]
+fn register() {
+    let handler = |_: Args, _: &mut dyn fmt::Write| Ok(());
+    let mut shell = Shell::<2>::new();
+    assert_eq!(shell.register("", "", &handler), Err(ErrorCode::Invalid));
+    assert_eq!(shell.register("a b", "", &handler), Err(ErrorCode::Invalid));
+    assert_eq!(
+        shell.register("help", "", &handler),
+        Err(ErrorCode::Invalid)
+    );
+    assert_eq!(shell.register("one", "", &handler), Ok(()));
+    assert_eq!(shell.register("one", "", &handler), Err(ErrorCode::Already));
+    assert_eq!(shell.register("two", "", &handler), Ok(()));
+    assert_eq!(shell.register("three", "", &handler), Err(ErrorCode::NoMem));
+}
+
+#[test]
+fn dispatch() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    kernel.add_driver(&driver);
+
+    let calls = RefCell::new(Vec::<Vec<String>>::new());
+    let record = |args: Args, _: &mut dyn fmt::Write| {
+        calls.borrow_mut().push(args.map(String::from).collect());
+        Ok(())
+    };
+    let greet = |mut args: Args, out: &mut dyn fmt::Write| {
+        let name = args.next().ok_or(ErrorCode::Invalid)?;
+        writeln!(out, "hello, {name}").map_err(|_| ErrorCode::Fail)
+    };
+    let mut shell = Shell::<2>::new();
+    shell
+        .register("record", "records its arguments", &record)
+        .unwrap();
+    shell.register("greet", "greets someone", &greet).unwrap();
+
+    assert_eq!(shell.dispatch("  record  a b "), Ok(()));
+    assert_eq!(shell.dispatch("record"), Ok(()));
+    assert_eq!(shell.dispatch("   "), Ok(()));
+    assert_eq!(*calls.borrow(), [vec!["a", "b"], vec![]]);
+    assert_eq!(driver.take_bytes(), b"");
+
+    assert_eq!(shell.dispatch("greet tock"), Ok(()));
+    assert_eq!(driver.take_bytes(), b"hello, tock\n");
+    assert_eq!(shell.dispatch("greet"), Err(ErrorCode::Invalid));
+    assert_eq!(driver.take_bytes(), b"greet: INVALID\n");
+    assert_eq!(shell.dispatch("reboot now"), Err(ErrorCode::NoSupport));
+    assert_eq!(driver.take_bytes(), b"reboot: command not found\n");
+}
+
+#[test]
+fn help() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new();
+    kernel.add_driver(&driver);
+
+    let handler = |_: Args, _: &mut dyn fmt::Write| Ok(());
+    let mut shell = Shell::<2>::new();
+    shell.register("led", "toggles an LED", &handler).unwrap();
+    shell
+        .register("status", "prints the status", &handler)
+        .unwrap();
+    assert_eq!(shell.dispatch("help"), Ok(()));
+    assert_eq!(
+        driver.take_bytes(),
+        b"help    lists commands\nled     toggles an LED\nstatus  prints the status\n"
+    );
+}
+
+#[test]
+fn run_once() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::Console::new_with_input(b"le\t1\rsta\t\r");
+    kernel.add_driver(&driver);
+
+    let leds = RefCell::new(Vec::new());
+    let led = |mut args: Args, _: &mut dyn fmt::Write| {
+        let index: u32 = args
+            .next()
+            .and_then(|arg| arg.parse().ok())
+            .ok_or(ErrorCode::Invalid)?;
+        leds.borrow_mut().push(index);
+        Ok(())
+    };
+    let status =
+        |_: Args, out: &mut dyn fmt::Write| out.write_str("ok\n").map_err(|_| ErrorCode::Fail);
+    let mut shell = Shell::<2>::new();
+    shell.register("led", "toggles an LED", &led).unwrap();
+    shell
+        .register("status", "prints the status", &status)
+        .unwrap();
+
+    let mut reader = LineReader::new();
+    reader.set_completer(&shell);
+    shell.run_once(&mut reader, "$ ").unwrap();
+    shell.run_once(&mut reader, "$ ").unwrap();
+    assert_eq!(*leds.borrow(), [1]);
+    assert_eq!(driver.take_bytes(), b"$ led 1\r\n$ status \r\nok\n");
+}
+
+#[test]
+fn completion() {
+    let handler = |_: Args, _: &mut dyn fmt::Write| Ok(());
+    let mut shell = Shell::<2>::new();
+    shell.register("led", "", &handler).unwrap();
+    shell.register("list", "", &handler).unwrap();
+
+    let mut candidates = Vec::new();
+    shell.complete("l", &mut |candidate| {
+        candidates.push(String::from(candidate))
+    });
+    assert_eq!(candidates, ["help", "led", "list"]);
+    // Only command names are completed.
+    candidates.clear();
+    shell.complete("led ", &mut |candidate| {
+        candidates.push(String::from(candidate))
+    });
+    assert!(candidates.is_empty());
+}
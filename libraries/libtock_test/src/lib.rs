@@ -0,0 +1,258 @@
+//! A test harness for libtock-rs tests that run as a process on a real (or
+//! emulated) Tock system, exercising `TockSyscalls` against the kernel rather
+//! than `libtock_unittest`'s fake kernel.
+//!
+//! A test binary lists its test cases with `test_main!`. Each test case is a
+//! function returning `TestResult`, and uses `check!` and `check_eq!` to fail.
+//! The harness runs the tests in order, reports their results over the console,
+//! then calls `exit_terminate` with the number of failed tests (so 0 indicates
+//! success).
+//!
+//! ```ignore
+//! #![no_main]
+//! #![no_std]
+//! use libtock::console::Console;
+//! use libtock::runtime::stack_size;
+//! use libtock_test::{check, test_main, TestResult};
+//!
+//! stack_size! {0x800}
+//! test_main! {console_exists}
+//!
+//! fn console_exists() -> TestResult {
+//!     check!(Console::exists());
+//!     Ok(())
+//! }
+//! ```
+//!
+//! # Output format
+//!
+//! The harness reports results as lines that start with `libtock_test: `, so
+//! that `runner` can pick them out of the rest of the console output:
+//!
+//! ```text
+//! libtock_test: start 2
+//! libtock_test: run console_exists
+//! libtock_test: pass console_exists
+//! libtock_test: run alarm_frequency
+//! libtock_test: fail alarm_frequency at examples/tests.rs:20: check failed: frequency > 0 (was 0)
+//! libtock_test: done 1 passed, 1 failed
+//! ```
+//!
+//! A test that crashes the process leaves a `run` line without a matching
+//! `pass` or `fail` line.
+
+#![no_std]
+
+use core::fmt::{self, Write};
+use libtock_console::Console;
+use libtock_platform::Syscalls;
+
+/// A test case registered with `test_main!`.
+#[derive(Clone, Copy)]
+pub struct Test {
+    pub name: &'static str,
+    pub run: fn() -> TestResult,
+}
+
+pub type TestResult = Result<(), Failure>;
+
+/// Describes why a test case failed. Created by `check!` and `check_eq!`.
+pub struct Failure {
+    file: &'static str,
+    line: u32,
+    check: &'static str,
+    message: Message,
+}
+
+impl Failure {
+    /// Creates a `Failure` for the check `check` (the source text of the
+    /// condition) at the given location, with additional details in
+    /// `message`. The message is truncated if it is longer than `MESSAGE_LEN`
+    /// bytes.
+    pub fn new(
+        file: &'static str,
+        line: u32,
+        check: &'static str,
+        message: fmt::Arguments,
+    ) -> Failure {
+        let mut failure = Failure {
+            file,
+            line,
+            check,
+            message: Message {
+                buffer: [0; MESSAGE_LEN],
+                len: 0,
+            },
+        };
+        // Message's Write implementation truncates rather than failing.
+        let _ = failure.message.write_fmt(message);
+        failure
+    }
+
+    pub fn file(&self) -> &'static str {
+        self.file
+    }
+
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    pub fn check(&self) -> &'static str {
+        self.check
+    }
+
+    pub fn message(&self) -> &str {
+        self.message.as_str()
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: check failed: {}",
+            self.file, self.line, self.check
+        )?;
+        match self.message() {
+            "" => Ok(()),
+            message => write!(f, " ({message})"),
+        }
+    }
+}
+
+/// The maximum length of a `Failure`'s message, in bytes. This keeps
+/// `TestResult` small, as it is returned on the stack.
+pub const MESSAGE_LEN: usize = 64;
+
+/// The prefix of every line the harness prints.
+pub const PREFIX: &str = "libtock_test: ";
+
+/// Runs `tests` in order, reports their results over the console, then exits
+/// with the number of failed tests as the completion code. Usually called
+/// through `test_main!`.
+pub fn run<S: Syscalls>(tests: &[Test]) -> ! {
+    let mut writer = Console::<S>::writer();
+    // Console write failures are ignored: there is nowhere else to report
+    // them, and the completion code still reports the result.
+    let _ = writeln!(writer, "{PREFIX}start {}", tests.len());
+    let mut failed = 0;
+    for test in tests {
+        let _ = writeln!(writer, "{PREFIX}run {}", test.name);
+        match (test.run)() {
+            Ok(()) => {
+                let _ = writeln!(writer, "{PREFIX}pass {}", test.name);
+            }
+            Err(failure) => {
+                failed += 1;
+                let _ = writeln!(writer, "{PREFIX}fail {} at {failure}", test.name);
+            }
+        }
+    }
+    let passed = tests.len() as u32 - failed;
+    let _ = writeln!(writer, "{PREFIX}done {passed} passed, {failed} failed");
+    S::exit_terminate(failed)
+}
+
+/// Generates the process binary's `main`, which runs the listed test functions
+/// with `run`. Each function must have the signature `fn() -> TestResult`. The
+/// binary must still specify its stack size with `stack_size!`.
+///
+/// ```ignore
+/// libtock_test::test_main! {command_exists, yield_no_wait_without_upcall}
+/// ```
+#[cfg(target_os = "none")]
+#[macro_export]
+macro_rules! test_main {
+    {$($test:ident),* $(,)?} => {
+        fn libtock_test_main() -> ! {
+            $crate::run::<$crate::__private::TockSyscalls>(&[
+                $($crate::Test { name: stringify!($test), run: $test }),*
+            ])
+        }
+        $crate::__private::set_main! {libtock_test_main}
+    };
+}
+
+#[cfg(target_os = "none")]
+#[doc(hidden)]
+pub mod __private {
+    pub use libtock_runtime::{set_main, TockSyscalls};
+}
+
+/// Fails the current test case if `condition` is false. An optional format
+/// string and arguments add details to the failure message.
+#[macro_export]
+macro_rules! check {
+    ($condition:expr $(,)?) => {
+        if !$condition {
+            return Err($crate::Failure::new(
+                file!(),
+                line!(),
+                stringify!($condition),
+                format_args!(""),
+            ));
+        }
+    };
+    ($condition:expr, $($arg:tt)+) => {
+        if !$condition {
+            return Err($crate::Failure::new(
+                file!(),
+                line!(),
+                stringify!($condition),
+                format_args!($($arg)+),
+            ));
+        }
+    };
+}
+
+/// Fails the current test case if `left != right`, reporting both values.
+#[macro_export]
+macro_rules! check_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if !(*left == *right) {
+                    return Err($crate::Failure::new(
+                        file!(),
+                        line!(),
+                        concat!(stringify!($left), " == ", stringify!($right)),
+                        format_args!("left: {:?}, right: {:?}", left, right),
+                    ));
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests;
+
+// -----------------------------------------------------------------------------
+// Implementation details below
+// -----------------------------------------------------------------------------
+
+// A fixed-size string that truncates (at a character boundary) writes that do
+// not fit.
+struct Message {
+    buffer: [u8; MESSAGE_LEN],
+    len: usize,
+}
+
+impl Message {
+    fn as_str(&self) -> &str {
+        // write_str only copies whole characters, so this does not fail.
+        core::str::from_utf8(&self.buffer[..self.len]).unwrap_or("")
+    }
+}
+
+impl Write for Message {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut len = s.len().min(MESSAGE_LEN - self.len);
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.buffer[self.len..self.len + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.len += len;
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+extern crate std;
+
+use super::*;
+use libtock_unittest::{catch_exit, fake, ExitCall};
+use std::format;
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+fn passing() -> TestResult {
+    check!(u32::MAX.checked_add(1).is_none());
+    check_eq!(2 * 2, 4);
+    Ok(())
+}
+
+fn failing_check() -> TestResult {
+    let frequency = 0;
+    check!(frequency > 0, "was {}", frequency);
+    Ok(())
+}
+
+fn failing_check_eq() -> TestResult {
+    check_eq!(u32::from(b'a'), 98u32);
+    Ok(())
+}
+
+fn no_message() -> TestResult {
+    check!(u32::MAX.checked_add(1).is_some());
+    Ok(())
+}
+
+// Runs `tests` with a fake console, and returns the output and Exit call.
+fn run_tests(tests: &[Test]) -> (String, ExitCall) {
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    kernel.add_driver(&console);
+    let exit = catch_exit(|| run::<fake::Syscalls>(tests)).unwrap_err();
+    (String::from_utf8(console.take_bytes()).unwrap(), exit)
+}
+
+#[test]
+fn all_pass() {
+    let (output, exit) = run_tests(&[Test {
+        name: "passing",
+        run: passing,
+    }]);
+    assert_eq!(
+        output,
+        "libtock_test: start 1\n\
+         libtock_test: run passing\n\
+         libtock_test: pass passing\n\
+         libtock_test: done 1 passed, 0 failed\n"
+    );
+    assert_eq!(exit, ExitCall::Terminate(0));
+}
+
+#[test]
+fn failures() {
+    let (output, exit) = run_tests(&[
+        Test {
+            name: "failing_check",
+            run: failing_check,
+        },
+        Test {
+            name: "passing",
+            run: passing,
+        },
+        Test {
+            name: "failing_check_eq",
+            run: failing_check_eq,
+        },
+        Test {
+            name: "no_message",
+            run: no_message,
+        },
+    ]);
+    let file = file!();
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(lines.len(), 10);
+    assert!(lines[2].starts_with(&format!("libtock_test: fail failing_check at {file}:")));
+    assert!(lines[2].ends_with(": check failed: frequency > 0 (was 0)"));
+    assert!(lines[6].ends_with(": check failed: u32::from(b'a') == 98u32 (left: 97, right: 98)"));
+    assert!(lines[8].ends_with(": check failed: u32::MAX.checked_add(1).is_some()"));
+    assert_eq!(lines[9], "libtock_test: done 1 passed, 3 failed");
+    assert_eq!(exit, ExitCall::Terminate(3));
+}
+
+#[test]
+fn long_messages_are_truncated() {
+    let failure = Failure::new("file.rs", 7, "x", format_args!("{:é<200}", ""));
+    assert_eq!(failure.file(), "file.rs");
+    assert_eq!(failure.line(), 7);
+    assert_eq!(failure.check(), "x");
+    // 'é' is 2 bytes long, so 32 fit.
+    assert_eq!(failure.message(), "é".repeat(32));
+    assert_eq!(
+        failure.to_string(),
+        format!("file.rs:7: check failed: x ({})", "é".repeat(32))
+    );
+}
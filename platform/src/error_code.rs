@@ -1,6 +1,5 @@
 use core::{convert::TryFrom, fmt, mem::transmute};
 
-// TODO: Add a ufmt debug implementation for process binaries to use.
 /// An error code that libtock-rs APIs may return, as specified in
 /// [TRD 104][error-codes]. Note that while `BADRVAL` can never be produced by
 /// the kernel, it can be produced by userspace APIs.
@@ -272,6 +271,30 @@ impl fmt::Debug for ErrorCode {
     }
 }
 
+/// Formats like `Debug`, e.g. `NOMEM`.
+#[cfg(feature = "ufmt")]
+impl ufmt::uDebug for ErrorCode {
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        match self.as_str() {
+            Some(s) => f.write_str(s),
+            None => ufmt::uwrite!(f, "code {}", *self as u16),
+        }
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uDisplay for ErrorCode {
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        ufmt::uDebug::fmt(self, f)
+    }
+}
+
 impl TryFrom<u32> for ErrorCode {
     type Error = NotAnErrorCode;
 
@@ -299,3 +322,20 @@ impl embedded_hal::spi::Error for ErrorCode {
         ErrorKind::Other
     }
 }
+
+#[cfg(feature = "rust_embedded")]
+impl embedded_io::Error for ErrorCode {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        use embedded_io::ErrorKind;
+        match self {
+            ErrorCode::Invalid | ErrorCode::Size => ErrorKind::InvalidInput,
+            ErrorCode::Cancel => ErrorKind::Interrupted,
+            ErrorCode::NoMem => ErrorKind::OutOfMemory,
+            ErrorCode::NoSupport | ErrorCode::NoDevice | ErrorCode::Uninstalled => {
+                ErrorKind::Unsupported
+            }
+            ErrorCode::NoAck => ErrorKind::TimedOut,
+            _ => ErrorKind::Other,
+        }
+    }
+}
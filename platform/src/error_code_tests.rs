@@ -20,3 +20,23 @@ fn error_code_try_into() {
     }
     assert_eq!(TryInto::<ErrorCode>::try_into(1025u32), Err(NotAnErrorCode));
 }
+
+#[cfg(feature = "ufmt")]
+#[test]
+fn error_code_ufmt() {
+    struct Writer(String);
+    impl ufmt::uWrite for Writer {
+        type Error = core::convert::Infallible;
+        fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+            self.0.push_str(s);
+            Ok(())
+        }
+    }
+
+    let mut writer = Writer(String::new());
+    ufmt::uwrite!(writer, "{:?} {}", ErrorCode::NoMem, ErrorCode::BadRVal).unwrap();
+    assert_eq!(writer.0, "NOMEM BADRVAL");
+    writer.0.clear();
+    ufmt::uwrite!(writer, "{:?}", ErrorCode::try_from(1000u32).unwrap()).unwrap();
+    assert_eq!(writer.0, "code 1000");
+}
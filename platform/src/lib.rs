@@ -16,6 +16,8 @@ pub mod subscribe;
 mod syscalls;
 mod syscalls_impl;
 mod termination;
+#[cfg(feature = "trace")]
+pub mod trace;
 mod yield_types;
 
 pub use allow_ro::AllowRo;
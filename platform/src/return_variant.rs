@@ -4,8 +4,8 @@
 
 /// `ReturnVariant` describes what value type the kernel has returned.
 // ReturnVariant is not an enum so that it can be converted from a u32 for free.
-// TODO: Add a ufmt debug implementation for use by process binaries.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "ufmt", derive(ufmt::derive::uDebug))]
 pub struct ReturnVariant(u32);
 
 impl From<u32> for ReturnVariant {
@@ -162,6 +162,10 @@ impl<S: RawSyscalls> Syscalls for S {
     // Command
     // -------------------------------------------------------------------------
 
+    // Command and Allow track their caller when built for the host, so that
+    // libtock_unittest's strict mode can report the call that triggered a
+    // violation. Caller tracking costs code size, so it is disabled on Tock.
+    #[cfg_attr(not(target_os = "none"), track_caller)]
     fn command(driver_id: u32, command_id: u32, argument0: u32, argument1: u32) -> CommandReturn {
         unsafe {
             // syscall4's documentation indicates it can be used to call
@@ -185,6 +189,7 @@ impl<S: RawSyscalls> Syscalls for S {
     // Read-Write Allow
     // -------------------------------------------------------------------------
 
+    #[cfg_attr(not(target_os = "none"), track_caller)]
     fn allow_rw<'share, CONFIG: allow_rw::Config, const DRIVER_NUM: u32, const BUFFER_NUM: u32>(
         _allow_rw: share::Handle<AllowRw<'share, Self, DRIVER_NUM, BUFFER_NUM>>,
         buffer: &'share mut [u8],
@@ -194,6 +199,7 @@ impl<S: RawSyscalls> Syscalls for S {
         //
         // Safety: A share::Handle<AllowRw<'share, S, driver_num, buffer_num>>
         // must exist, and `buffer` must last for at least the 'share lifetime.
+        #[cfg_attr(not(target_os = "none"), track_caller)]
         unsafe fn inner<S: Syscalls, CONFIG: allow_rw::Config>(
             driver_num: u32,
             buffer_num: u32,
@@ -251,6 +257,7 @@ impl<S: RawSyscalls> Syscalls for S {
         unsafe { inner::<Self, CONFIG>(DRIVER_NUM, BUFFER_NUM, buffer) }
     }
 
+    #[cfg_attr(not(target_os = "none"), track_caller)]
     fn unallow_rw(driver_num: u32, buffer_num: u32) {
         unsafe {
             // syscall4's documentation indicates it can be used to call
@@ -269,6 +276,7 @@ impl<S: RawSyscalls> Syscalls for S {
     // Read-Only Allow
     // -------------------------------------------------------------------------
 
+    #[cfg_attr(not(target_os = "none"), track_caller)]
     fn allow_ro<'share, CONFIG: allow_ro::Config, const DRIVER_NUM: u32, const BUFFER_NUM: u32>(
         _allow_ro: share::Handle<AllowRo<'share, Self, DRIVER_NUM, BUFFER_NUM>>,
         buffer: &'share [u8],
@@ -281,6 +289,7 @@ impl<S: RawSyscalls> Syscalls for S {
         // Allow call. Therefore the caller must ensure the Allow ID is
         // unallowed or overwritten before `*buffer` is deallocated, to avoid
         // leaking newly-allocated information at the same address as `*buffer`.
+        #[cfg_attr(not(target_os = "none"), track_caller)]
         fn inner<S: Syscalls, CONFIG: allow_ro::Config>(
             driver_num: u32,
             buffer_num: u32,
@@ -338,6 +347,7 @@ impl<S: RawSyscalls> Syscalls for S {
         inner::<Self, CONFIG>(DRIVER_NUM, BUFFER_NUM, buffer)
     }
 
+    #[cfg_attr(not(target_os = "none"), track_caller)]
     fn unallow_ro(driver_num: u32, buffer_num: u32) {
         unsafe {
             // syscall4's documentation indicates it can be used to call
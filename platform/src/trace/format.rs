@@ -0,0 +1,366 @@
+//! The binary format of a recorded system call trace.
+//!
+//! A trace is a sequence of entries. Each entry is a one-byte tag followed by
+//! the entry's fields, each encoded as an unsigned LEB128 integer. Most fields
+//! are small (driver numbers, command IDs, lengths), so most fields take a
+//! single byte.
+
+use crate::{return_variant, ErrorCode, ReturnVariant};
+
+/// A single recorded event. Entries appear in the order they happened. An
+/// `Upcall` entry always immediately follows the `YieldNoWait` or `YieldWait`
+/// entry of the Yield call that invoked it, and is followed by any system calls
+/// the upcall made.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TraceEntry {
+    YieldNoWait,
+
+    YieldWait,
+
+    Upcall {
+        driver_num: u32,
+        subscribe_num: u32,
+        args: (u32, u32, u32),
+    },
+
+    Subscribe {
+        driver_num: u32,
+        subscribe_num: u32,
+        /// `None` if the Subscribe succeeded.
+        error: Option<ErrorCode>,
+    },
+
+    Command {
+        driver_id: u32,
+        command_id: u32,
+        argument0: u32,
+        argument1: u32,
+        return_variant: ReturnVariant,
+        r1: u32,
+        r2: u32,
+        r3: u32,
+    },
+
+    AllowRo {
+        driver_num: u32,
+        buffer_num: u32,
+        len: u32,
+        /// `None` if the Allow succeeded.
+        error: Option<ErrorCode>,
+    },
+
+    AllowRw {
+        driver_num: u32,
+        buffer_num: u32,
+        len: u32,
+        /// `None` if the Allow succeeded.
+        error: Option<ErrorCode>,
+    },
+
+    Memop {
+        memop_num: u32,
+        /// The low 32 bits of argument 0.
+        argument0: u32,
+        return_variant: ReturnVariant,
+        r1: u32,
+    },
+
+    Exit {
+        exit_num: u32,
+        completion_code: u32,
+    },
+}
+
+/// The longest encoding of a single entry: a tag followed by eight 5-byte
+/// fields.
+pub(crate) const MAX_ENTRY_LEN: usize = 1 + 8 * 5;
+
+/// Error returned by `Decoder` when a trace is malformed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    /// The entry at this byte offset has an unknown tag.
+    UnknownTag { offset: usize },
+
+    /// The trace ends partway through an entry.
+    UnexpectedEnd,
+
+    /// The entry at this byte offset contains an out-of-range value, such as an
+    /// invalid error code.
+    InvalidValue { offset: usize },
+}
+
+/// Iterates over the entries of an encoded trace.
+pub struct Decoder<'t> {
+    bytes: &'t [u8],
+    offset: usize,
+}
+
+impl<'t> Decoder<'t> {
+    pub fn new(bytes: &'t [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn field(&mut self) -> Result<u32, DecodeError> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let &byte = self
+                .bytes
+                .get(self.offset)
+                .ok_or(DecodeError::UnexpectedEnd)?;
+            self.offset += 1;
+            value |= u32::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::InvalidValue {
+            offset: self.offset,
+        })
+    }
+
+    // Decodes the next N fields.
+    fn fields<const N: usize>(&mut self) -> Result<[u32; N], DecodeError> {
+        let mut fields = [0; N];
+        for field in &mut fields {
+            *field = self.field()?;
+        }
+        Ok(fields)
+    }
+
+    fn decode_entry(&mut self) -> Result<TraceEntry, DecodeError> {
+        let start = self.offset;
+        let tag = self.bytes[start];
+        self.offset += 1;
+        let invalid = DecodeError::InvalidValue { offset: start };
+        let error = |value: u32| match value {
+            0 => Ok(None),
+            value => ErrorCode::try_from(value).map(Some).or(Err(invalid)),
+        };
+        Ok(match tag {
+            tag::YIELD_NO_WAIT => TraceEntry::YieldNoWait,
+            tag::YIELD_WAIT => TraceEntry::YieldWait,
+            tag::UPCALL => {
+                let [driver_num, subscribe_num, arg0, arg1, arg2] = self.fields()?;
+                TraceEntry::Upcall {
+                    driver_num,
+                    subscribe_num,
+                    args: (arg0, arg1, arg2),
+                }
+            }
+            tag::SUBSCRIBE => {
+                let [driver_num, subscribe_num, e] = self.fields()?;
+                TraceEntry::Subscribe {
+                    driver_num,
+                    subscribe_num,
+                    error: error(e)?,
+                }
+            }
+            tag::COMMAND => {
+                let [driver_id, command_id, argument0, argument1, return_variant, r1, r2, r3] =
+                    self.fields()?;
+                let return_variant = return_variant.into();
+                // A failure variant must carry a valid error code, as
+                // CommandReturn relies on that for soundness.
+                if is_failure(return_variant) && ErrorCode::try_from(r1).is_err() {
+                    return Err(invalid);
+                }
+                TraceEntry::Command {
+                    driver_id,
+                    command_id,
+                    argument0,
+                    argument1,
+                    return_variant,
+                    r1,
+                    r2,
+                    r3,
+                }
+            }
+            tag::ALLOW_RO => {
+                let [driver_num, buffer_num, len, e] = self.fields()?;
+                TraceEntry::AllowRo {
+                    driver_num,
+                    buffer_num,
+                    len,
+                    error: error(e)?,
+                }
+            }
+            tag::ALLOW_RW => {
+                let [driver_num, buffer_num, len, e] = self.fields()?;
+                TraceEntry::AllowRw {
+                    driver_num,
+                    buffer_num,
+                    len,
+                    error: error(e)?,
+                }
+            }
+            tag::MEMOP => {
+                let [memop_num, argument0, return_variant, r1] = self.fields()?;
+                TraceEntry::Memop {
+                    memop_num,
+                    argument0,
+                    return_variant: return_variant.into(),
+                    r1,
+                }
+            }
+            tag::EXIT => {
+                let [exit_num, completion_code] = self.fields()?;
+                TraceEntry::Exit {
+                    exit_num,
+                    completion_code,
+                }
+            }
+            _ => return Err(DecodeError::UnknownTag { offset: start }),
+        })
+    }
+}
+
+impl Iterator for Decoder<'_> {
+    type Item = Result<TraceEntry, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.bytes.len() {
+            return None;
+        }
+        let result = self.decode_entry();
+        if result.is_err() {
+            // Stop after the first error, as the remaining bytes cannot be
+            // reliably split into entries.
+            self.offset = self.bytes.len();
+        }
+        Some(result)
+    }
+}
+
+/// Encodes `entry` into `out`, returning the number of bytes written.
+pub(crate) fn encode(entry: &TraceEntry, out: &mut [u8; MAX_ENTRY_LEN]) -> usize {
+    let error = |error: &Option<ErrorCode>| error.map_or(0, |e| e as u32);
+    let mut encoder = Encoder { out, len: 0 };
+    match *entry {
+        TraceEntry::YieldNoWait => encoder.tag(tag::YIELD_NO_WAIT),
+        TraceEntry::YieldWait => encoder.tag(tag::YIELD_WAIT),
+        TraceEntry::Upcall {
+            driver_num,
+            subscribe_num,
+            args,
+        } => {
+            encoder.tag(tag::UPCALL);
+            encoder.fields(&[driver_num, subscribe_num, args.0, args.1, args.2]);
+        }
+        TraceEntry::Subscribe {
+            driver_num,
+            subscribe_num,
+            error: ref e,
+        } => {
+            encoder.tag(tag::SUBSCRIBE);
+            encoder.fields(&[driver_num, subscribe_num, error(e)]);
+        }
+        TraceEntry::Command {
+            driver_id,
+            command_id,
+            argument0,
+            argument1,
+            return_variant,
+            r1,
+            r2,
+            r3,
+        } => {
+            encoder.tag(tag::COMMAND);
+            encoder.fields(&[
+                driver_id,
+                command_id,
+                argument0,
+                argument1,
+                return_variant.into(),
+                r1,
+                r2,
+                r3,
+            ]);
+        }
+        TraceEntry::AllowRo {
+            driver_num,
+            buffer_num,
+            len,
+            error: ref e,
+        } => {
+            encoder.tag(tag::ALLOW_RO);
+            encoder.fields(&[driver_num, buffer_num, len, error(e)]);
+        }
+        TraceEntry::AllowRw {
+            driver_num,
+            buffer_num,
+            len,
+            error: ref e,
+        } => {
+            encoder.tag(tag::ALLOW_RW);
+            encoder.fields(&[driver_num, buffer_num, len, error(e)]);
+        }
+        TraceEntry::Memop {
+            memop_num,
+            argument0,
+            return_variant,
+            r1,
+        } => {
+            encoder.tag(tag::MEMOP);
+            encoder.fields(&[memop_num, argument0, return_variant.into(), r1]);
+        }
+        TraceEntry::Exit {
+            exit_num,
+            completion_code,
+        } => {
+            encoder.tag(tag::EXIT);
+            encoder.fields(&[exit_num, completion_code]);
+        }
+    }
+    encoder.len
+}
+
+// -----------------------------------------------------------------------------
+// Implementation details below.
+// -----------------------------------------------------------------------------
+
+struct Encoder<'o> {
+    out: &'o mut [u8; MAX_ENTRY_LEN],
+    len: usize,
+}
+
+impl Encoder<'_> {
+    fn tag(&mut self, tag: u8) {
+        self.out[self.len] = tag;
+        self.len += 1;
+    }
+
+    fn fields(&mut self, fields: &[u32]) {
+        for &field in fields {
+            let mut value = field;
+            while value >= 0x80 {
+                self.out[self.len] = (value as u8) | 0x80;
+                self.len += 1;
+                value >>= 7;
+            }
+            self.out[self.len] = value as u8;
+            self.len += 1;
+        }
+    }
+}
+
+fn is_failure(return_variant: ReturnVariant) -> bool {
+    matches!(
+        return_variant,
+        return_variant::FAILURE
+            | return_variant::FAILURE_U32
+            | return_variant::FAILURE_2_U32
+            | return_variant::FAILURE_U64
+    )
+}
+
+mod tag {
+    pub const YIELD_NO_WAIT: u8 = 0;
+    pub const YIELD_WAIT: u8 = 1;
+    pub const UPCALL: u8 = 2;
+    pub const SUBSCRIBE: u8 = 3;
+    pub const COMMAND: u8 = 4;
+    pub const ALLOW_RO: u8 = 5;
+    pub const ALLOW_RW: u8 = 6;
+    pub const MEMOP: u8 = 7;
+    pub const EXIT: u8 = 8;
+}
@@ -0,0 +1,21 @@
+//! System call tracing, for reproducing on-device behavior in unit tests.
+//!
+//! `TracingSyscalls` wraps a `RawSyscalls` implementation (typically
+//! `libtock_runtime::TockSyscalls`) and records each system call, its results,
+//! and each upcall into a compact in-memory trace. `dump` writes the trace out
+//! (e.g. over the console), and `libtock_unittest::fake::Replay` feeds a dumped
+//! trace back into `fake::Kernel`, turning a failure observed on hardware into
+//! a deterministic unit test.
+//!
+//! This module is only available when the `trace` feature is enabled.
+
+mod format;
+mod recorder;
+
+pub use format::{DecodeError, Decoder, TraceEntry};
+pub use recorder::{
+    clear, dump, is_truncated, TracingSyscalls, DUMP_PREFIX, MAX_TRACED_UPCALLS, TRACE_CAPACITY,
+};
+
+#[cfg(test)]
+mod tests;
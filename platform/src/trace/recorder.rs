@@ -0,0 +1,300 @@
+//! `TracingSyscalls`, a `RawSyscalls` wrapper that records every system call
+//! into a global trace buffer.
+
+use super::format::{encode, TraceEntry, MAX_ENTRY_LEN};
+use crate::{syscall_class, ErrorCode, RawSyscalls, Register, ReturnVariant};
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicU8, AtomicUsize, Ordering};
+
+/// The size of the trace buffer, in bytes. Once the buffer fills up, recording
+/// stops and the trace is marked as truncated.
+pub const TRACE_CAPACITY: usize = 1024;
+
+/// The maximum number of upcalls `TracingSyscalls` can track at once. If an
+/// app subscribes more upcalls than this, the additional upcalls are not
+/// recorded and the trace is marked as truncated.
+pub const MAX_TRACED_UPCALLS: usize = 16;
+
+/// The prefix of each line written by `dump`.
+pub const DUMP_PREFIX: &str = "libtock-trace:";
+
+/// A `RawSyscalls` implementation that forwards every system call to `S` and
+/// records it (along with its results and any upcalls it invokes) into the
+/// trace buffer. Use it in place of the app's `Syscalls` implementation, e.g.
+/// `Console<TracingSyscalls<TockSyscalls>>`.
+///
+/// The trace buffer is global, so there should only be one `TracingSyscalls`
+/// type in use at a time. Tock processes are single-threaded; in host tests,
+/// only one thread at a time should use `TracingSyscalls`.
+pub struct TracingSyscalls<S: RawSyscalls>(PhantomData<S>);
+
+/// Discards the recorded trace and restarts recording.
+pub fn clear() {
+    LEN.store(0, Ordering::Relaxed);
+    TRUNCATED.store(false, Ordering::Relaxed);
+}
+
+/// Returns true if the trace buffer filled up (or too many upcalls were
+/// subscribed) and part of the trace was lost.
+pub fn is_truncated() -> bool {
+    TRUNCATED.load(Ordering::Relaxed)
+}
+
+/// Writes the recorded trace to `writer` as hex-encoded lines starting with
+/// `DUMP_PREFIX`, followed by an `end` line (`end truncated` if the trace was
+/// truncated). System calls made by `writer` are not recorded. This is
+/// typically called with a `ConsoleWriter`, and the resulting output is passed
+/// to `libtock_unittest::fake::Replay::from_dump`.
+pub fn dump<W: core::fmt::Write>(writer: &mut W) -> core::fmt::Result {
+    const BYTES_PER_LINE: usize = 32;
+    let was_paused = PAUSED.load(Ordering::Relaxed);
+    PAUSED.store(true, Ordering::Relaxed);
+    let len = LEN.load(Ordering::Relaxed);
+    let mut result = Ok(());
+    for line_start in (0..len).step_by(BYTES_PER_LINE) {
+        result = result.and_then(|()| writer.write_str(DUMP_PREFIX));
+        result = result.and_then(|()| writer.write_str(" "));
+        for byte in &BUFFER[line_start..len.min(line_start + BYTES_PER_LINE)] {
+            result = result.and_then(|()| write!(writer, "{:02x}", byte.load(Ordering::Relaxed)));
+        }
+        result = result.and_then(|()| writer.write_str("\n"));
+    }
+    result = result.and_then(|()| match is_truncated() {
+        false => writeln!(writer, "{DUMP_PREFIX} end"),
+        true => writeln!(writer, "{DUMP_PREFIX} end truncated"),
+    });
+    PAUSED.store(was_paused, Ordering::Relaxed);
+    result
+}
+
+unsafe impl<S: RawSyscalls> RawSyscalls for TracingSyscalls<S> {
+    unsafe fn yield1([r0]: [Register; 1]) {
+        // Record the yield first so that any upcalls it invokes (and system
+        // calls they make) appear after it in the trace.
+        record(&TraceEntry::YieldWait);
+        // Safety: the caller upholds yield1's requirements.
+        unsafe { S::yield1([r0]) }
+    }
+
+    unsafe fn yield2([r0, r1]: [Register; 2]) {
+        record(&TraceEntry::YieldNoWait);
+        // Safety: the caller upholds yield2's requirements.
+        unsafe { S::yield2([r0, r1]) }
+    }
+
+    unsafe fn syscall1<const CLASS: usize>([r0]: [Register; 1]) -> [Register; 2] {
+        // Safety: the caller upholds syscall1's requirements.
+        let [ret0, ret1] = unsafe { S::syscall1::<CLASS>([r0]) };
+        if CLASS == syscall_class::MEMOP {
+            record(&TraceEntry::Memop {
+                memop_num: r0.as_u32(),
+                argument0: 0,
+                return_variant: ret0.as_u32().into(),
+                r1: usize::from(ret1) as u32,
+            });
+        }
+        [ret0, ret1]
+    }
+
+    unsafe fn syscall2<const CLASS: usize>([r0, r1]: [Register; 2]) -> [Register; 2] {
+        if CLASS == syscall_class::EXIT {
+            // Exit does not return, so it must be recorded beforehand.
+            record(&TraceEntry::Exit {
+                exit_num: r0.as_u32(),
+                completion_code: r1.as_u32(),
+            });
+        }
+        // Safety: the caller upholds syscall2's requirements.
+        let [ret0, ret1] = unsafe { S::syscall2::<CLASS>([r0, r1]) };
+        if CLASS == syscall_class::MEMOP {
+            record(&TraceEntry::Memop {
+                memop_num: r0.as_u32(),
+                argument0: usize::from(r1) as u32,
+                return_variant: ret0.as_u32().into(),
+                r1: usize::from(ret1) as u32,
+            });
+        }
+        [ret0, ret1]
+    }
+
+    unsafe fn syscall4<const CLASS: usize>(args: [Register; 4]) -> [Register; 4] {
+        let [r0, r1, r2, r3] = args;
+        if CLASS == syscall_class::SUBSCRIBE {
+            // Safety: the caller upholds syscall4's requirements for Subscribe.
+            return unsafe { subscribe::<S>(r0.as_u32(), r1.as_u32(), r2, r3) };
+        }
+        // Safety: the caller upholds syscall4's requirements.
+        let ret = unsafe { S::syscall4::<CLASS>(args) };
+        let error = allow_error(ret[0], ret[1]);
+        match CLASS {
+            syscall_class::COMMAND => record(&TraceEntry::Command {
+                driver_id: r0.as_u32(),
+                command_id: r1.as_u32(),
+                argument0: r2.as_u32(),
+                argument1: r3.as_u32(),
+                return_variant: ret[0].as_u32().into(),
+                r1: ret[1].as_u32(),
+                r2: ret[2].as_u32(),
+                r3: ret[3].as_u32(),
+            }),
+            syscall_class::ALLOW_RO => record(&TraceEntry::AllowRo {
+                driver_num: r0.as_u32(),
+                buffer_num: r1.as_u32(),
+                len: usize::from(r3) as u32,
+                error,
+            }),
+            syscall_class::ALLOW_RW => record(&TraceEntry::AllowRw {
+                driver_num: r0.as_u32(),
+                buffer_num: r1.as_u32(),
+                len: usize::from(r3) as u32,
+                error,
+            }),
+            _ => {}
+        }
+        ret
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Implementation details below.
+// -----------------------------------------------------------------------------
+
+// The trace buffer. Atomics (with only loads and stores, which every Tock
+// target supports) are used so the buffer can be a plain static without any
+// unsafe code.
+static BUFFER: [AtomicU8; TRACE_CAPACITY] = [const { AtomicU8::new(0) }; TRACE_CAPACITY];
+static LEN: AtomicUsize = AtomicUsize::new(0);
+static TRUNCATED: AtomicBool = AtomicBool::new(false);
+// Set while `dump` is running, so the dump does not record itself.
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+// Appends an entry to the trace buffer. Once an entry does not fit, recording
+// stops entirely so the trace remains a prefix of what happened.
+fn record(entry: &TraceEntry) {
+    if PAUSED.load(Ordering::Relaxed) || is_truncated() {
+        return;
+    }
+    let mut encoded = [0; MAX_ENTRY_LEN];
+    let encoded_len = encode(entry, &mut encoded);
+    let len = LEN.load(Ordering::Relaxed);
+    let Some(dest) = BUFFER.get(len..len + encoded_len) else {
+        TRUNCATED.store(true, Ordering::Relaxed);
+        return;
+    };
+    for (dest, &byte) in dest.iter().zip(&encoded[..encoded_len]) {
+        dest.store(byte, Ordering::Relaxed);
+    }
+    LEN.store(len + encoded_len, Ordering::Relaxed);
+}
+
+// Subscribe, Allow Read-Only, and Allow Read-Write all return Failure with 2
+// u32 on failure with the error code in r1.
+fn allow_error(r0: Register, r1: Register) -> Option<ErrorCode> {
+    match ReturnVariant::from(r0.as_u32()) {
+        crate::return_variant::FAILURE_2_U32 => ErrorCode::try_from(r1.as_u32()).ok(),
+        _ => None,
+    }
+}
+
+// To record upcalls, TracingSyscalls passes `trampoline` to the kernel in place
+// of the app's upcall function, with the index of an UpcallSlot (which stores
+// the app's upcall function and data) as the upcall data.
+struct UpcallSlot {
+    in_use: AtomicBool,
+    driver_num: AtomicU32,
+    subscribe_num: AtomicU32,
+    fcn: AtomicPtr<()>,
+    data: AtomicPtr<()>,
+}
+
+static SLOTS: [UpcallSlot; MAX_TRACED_UPCALLS] = [const {
+    UpcallSlot {
+        in_use: AtomicBool::new(false),
+        driver_num: AtomicU32::new(0),
+        subscribe_num: AtomicU32::new(0),
+        fcn: AtomicPtr::new(core::ptr::null_mut()),
+        data: AtomicPtr::new(core::ptr::null_mut()),
+    }
+}; MAX_TRACED_UPCALLS];
+
+type UpcallFn = unsafe extern "C" fn(u32, u32, u32, Register);
+
+// Safety: data must be the index of an in-use UpcallSlot, whose fcn and data
+// fields must be valid to invoke as an upcall.
+unsafe extern "C" fn trampoline(arg0: u32, arg1: u32, arg2: u32, data: Register) {
+    let slot = &SLOTS[usize::from(data)];
+    record(&TraceEntry::Upcall {
+        driver_num: slot.driver_num.load(Ordering::Relaxed),
+        subscribe_num: slot.subscribe_num.load(Ordering::Relaxed),
+        args: (arg0, arg1, arg2),
+    });
+    // Safety: fcn was a non-null upcall function passed to Subscribe, which
+    // TRD 104 requires to have the UpcallFn signature.
+    let fcn: UpcallFn = unsafe { core::mem::transmute(slot.fcn.load(Ordering::Relaxed)) };
+    // Safety: the upcall is still subscribed (otherwise the kernel would not
+    // have invoked the trampoline), so it is valid to invoke.
+    unsafe { fcn(arg0, arg1, arg2, slot.data.load(Ordering::Relaxed).into()) }
+}
+
+// Performs a Subscribe call, substituting the trampoline for the upcall.
+//
+// Safety: the arguments must satisfy syscall4's requirements for Subscribe.
+unsafe fn subscribe<S: RawSyscalls>(
+    driver_num: u32,
+    subscribe_num: u32,
+    fcn: Register,
+    data: Register,
+) -> [Register; 4] {
+    let fcn_ptr: *mut () = fcn.into();
+    // Null upcalls are never invoked, so they do not need a slot. If every
+    // slot is in use, the upcall is passed through untraced.
+    let slot_index = match fcn_ptr.is_null() {
+        true => None,
+        false => SLOTS
+            .iter()
+            .position(|slot| !slot.in_use.load(Ordering::Relaxed)),
+    };
+    let args = match slot_index {
+        None => {
+            if !fcn_ptr.is_null() {
+                TRUNCATED.store(true, Ordering::Relaxed);
+            }
+            [driver_num.into(), subscribe_num.into(), fcn, data]
+        }
+        Some(index) => {
+            let slot = &SLOTS[index];
+            slot.in_use.store(true, Ordering::Relaxed);
+            slot.driver_num.store(driver_num, Ordering::Relaxed);
+            slot.subscribe_num.store(subscribe_num, Ordering::Relaxed);
+            slot.fcn.store(fcn_ptr, Ordering::Relaxed);
+            slot.data.store(data.into(), Ordering::Relaxed);
+            [
+                driver_num.into(),
+                subscribe_num.into(),
+                (trampoline as UpcallFn as *const ()).into(),
+                index.into(),
+            ]
+        }
+    };
+    // Safety: the trampoline has the required signature and the slot keeps the
+    // caller's upcall valid for as long as the caller required.
+    let ret = unsafe { S::syscall4::<{ syscall_class::SUBSCRIBE }>(args) };
+    let error = allow_error(ret[0], ret[1]);
+    // On success, the kernel dropped the previous upcall for this ID, so its
+    // slot can be reused. On failure, the kernel kept the previous upcall, and
+    // the new slot is unused.
+    for (index, slot) in SLOTS.iter().enumerate() {
+        let same_id = slot.driver_num.load(Ordering::Relaxed) == driver_num
+            && slot.subscribe_num.load(Ordering::Relaxed) == subscribe_num;
+        let is_new = Some(index) == slot_index;
+        if same_id && is_new == error.is_some() {
+            slot.in_use.store(false, Ordering::Relaxed);
+        }
+    }
+    record(&TraceEntry::Subscribe {
+        driver_num,
+        subscribe_num,
+        error,
+    });
+    ret
+}
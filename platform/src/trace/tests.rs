@@ -0,0 +1,119 @@
+use super::format::{encode, MAX_ENTRY_LEN};
+use super::*;
+use crate::{return_variant, ErrorCode};
+
+// Encodes each of `entries` and concatenates the results.
+fn encode_all(entries: &[TraceEntry]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for entry in entries {
+        let mut encoded = [0; MAX_ENTRY_LEN];
+        let len = encode(entry, &mut encoded);
+        bytes.extend_from_slice(&encoded[..len]);
+    }
+    bytes
+}
+
+#[test]
+fn round_trip() {
+    let entries = [
+        TraceEntry::Subscribe {
+            driver_num: 0x60000,
+            subscribe_num: 1,
+            error: None,
+        },
+        TraceEntry::Command {
+            driver_id: 0x60000,
+            command_id: 2,
+            argument0: u32::MAX,
+            argument1: 0,
+            return_variant: return_variant::FAILURE,
+            r1: ErrorCode::Busy as u32,
+            r2: 0,
+            r3: 0,
+        },
+        TraceEntry::YieldWait,
+        TraceEntry::Upcall {
+            driver_num: 0x60000,
+            subscribe_num: 1,
+            args: (1, 200, 70000),
+        },
+        TraceEntry::YieldNoWait,
+        TraceEntry::AllowRo {
+            driver_num: 1,
+            buffer_num: 1,
+            len: 300,
+            error: Some(ErrorCode::Invalid),
+        },
+        TraceEntry::AllowRw {
+            driver_num: 1,
+            buffer_num: 0,
+            len: 0,
+            error: None,
+        },
+        TraceEntry::Memop {
+            memop_num: 1,
+            argument0: 16,
+            return_variant: return_variant::SUCCESS_U32,
+            r1: 0x2000_1000,
+        },
+        TraceEntry::Exit {
+            exit_num: 0,
+            completion_code: 3,
+        },
+    ];
+    let bytes = encode_all(&entries);
+    let decoded: Result<Vec<_>, _> = Decoder::new(&bytes).collect();
+    assert_eq!(decoded.unwrap(), entries);
+}
+
+#[test]
+fn compact() {
+    // Small fields take a single byte each.
+    let bytes = encode_all(&[TraceEntry::Command {
+        driver_id: 2,
+        command_id: 1,
+        argument0: 0,
+        argument1: 0,
+        return_variant: return_variant::SUCCESS,
+        r1: 0,
+        r2: 0,
+        r3: 0,
+    }]);
+    assert_eq!(bytes.len(), 1 + 8 + 1);
+}
+
+#[test]
+fn decode_errors() {
+    assert_eq!(
+        Decoder::new(&[0xff]).collect::<Vec<_>>(),
+        [Err(DecodeError::UnknownTag { offset: 0 })]
+    );
+    let mut bytes = encode_all(&[TraceEntry::Exit {
+        exit_num: 0,
+        completion_code: 500,
+    }]);
+    bytes.pop();
+    assert_eq!(
+        Decoder::new(&bytes).collect::<Vec<_>>(),
+        [Err(DecodeError::UnexpectedEnd)]
+    );
+    // A Subscribe with an invalid error code, after a valid yield-wait entry.
+    let mut bytes = encode_all(&[
+        TraceEntry::YieldWait,
+        TraceEntry::Subscribe {
+            driver_num: 0,
+            subscribe_num: 0,
+            error: None,
+        },
+    ]);
+    // Replace the error code field (0) with 2000, which is out of range.
+    bytes.pop();
+    bytes.extend_from_slice(&[0xd0, 0x0f]);
+    assert_eq!(
+        Decoder::new(&bytes).collect::<Vec<_>>(),
+        [
+            Ok(TraceEntry::YieldWait),
+            Err(DecodeError::InvalidValue { offset: 1 })
+        ]
+    );
+}
@@ -0,0 +1,159 @@
+//! Decodes the frames `libtock_deferred_log` sends in a process binary's
+//! console output, using the format strings interned in the process binary's
+//! ELF file.
+
+use libtock_deferred_log_format::{decode_frame, decode_varint, Arg, DecodeError, Frame, MARKER};
+use libtock_deferred_log_format::{MAX_FRAME_LEN, SECTION};
+use std::path::Path;
+
+/// Replaces frames in console output with the text they encode. Output that is
+/// not part of a frame, including bytes that start with the frame marker but
+/// are not a valid frame, is passed through unchanged. A frame must name an
+/// interned string, so text after a stray marker is passed through as soon as
+/// its first byte arrives rather than held back.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    // The contents of the interned strings section, or None if the process
+    // binary has no such section (in which case frames are not decoded).
+    strings: Option<Vec<u8>>,
+    // The bytes of a frame that has not been received completely.
+    partial_frame: Vec<u8>,
+}
+
+impl Decoder {
+    /// Creates a decoder for the process binary at `elf`.
+    pub fn from_elf(elf: &Path) -> Decoder {
+        let file = elf::File::open_path(elf).expect("Unable to open ELF");
+        Decoder::new(
+            file.get_section(SECTION)
+                .map(|section| section.data.clone()),
+        )
+    }
+
+    /// Creates a decoder that looks up interned strings in `strings`, the
+    /// contents of the interned strings section.
+    pub fn new(strings: Option<Vec<u8>>) -> Decoder {
+        Decoder {
+            strings,
+            partial_frame: Vec::new(),
+        }
+    }
+
+    /// Processes bytes of console output, returning them with each complete
+    /// frame replaced by a line of text.
+    pub fn process(&mut self, bytes: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(bytes.len());
+        for &byte in bytes {
+            if self.strings.is_none() || (self.partial_frame.is_empty() && byte != MARKER) {
+                output.push(byte);
+                continue;
+            }
+            self.partial_frame.push(byte);
+            match self.decode_partial_frame() {
+                Err(DecodeError::Incomplete) if self.partial_frame.len() < MAX_FRAME_LEN => {
+                    continue
+                }
+                Err(_) => {
+                    // Not a frame, so pass the marker through unchanged, and
+                    // look for frames in the bytes after it.
+                    let rest = self.partial_frame.split_off(1);
+                    output.append(&mut self.partial_frame);
+                    output.extend(self.process(&rest));
+                    continue;
+                }
+                Ok((string, frame)) => {
+                    output.extend_from_slice(format(string, &frame).as_bytes());
+                    output.push(b'\n');
+                }
+            }
+            self.partial_frame.clear();
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests;
+
+// -----------------------------------------------------------------------------
+// Implementation details below
+// -----------------------------------------------------------------------------
+
+impl Decoder {
+    // Decodes the partial frame, returning its interned string and the frame.
+    // The frame is invalid as soon as its string address is known and is not
+    // the address of an interned string.
+    fn decode_partial_frame(&self) -> Result<(&str, Frame<'_>), DecodeError> {
+        let (address, _) = decode_varint(&self.partial_frame[1..])?;
+        let string = self.string(address).ok_or(DecodeError::Invalid)?;
+        let (frame, _) = decode_frame(&self.partial_frame)?;
+        Ok((string, frame))
+    }
+
+    // Returns the interned string at `address`, if a string starts there.
+    fn string(&self, address: u64) -> Option<&str> {
+        let strings = self.strings.as_deref()?;
+        let start = usize::try_from(address).ok()?;
+        if start > 0 && strings.get(start - 1) != Some(&0) {
+            return None;
+        }
+        let strings = strings.get(start..)?;
+        let len = strings.iter().position(|&byte| byte == 0)?;
+        std::str::from_utf8(&strings[..len]).ok()
+    }
+}
+
+// Formats a frame using its interned string.
+fn format(string: &str, frame: &Frame) -> String {
+    let mut args = frame.args();
+    let mut output = String::new();
+    let mut chars = string.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.next_if_eq(&'{').is_some() => output.push('{'),
+            '}' if chars.next_if_eq(&'}').is_some() => output.push('}'),
+            '{' => {
+                let spec: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                match args.next() {
+                    Some(arg) => output.push_str(&format_arg(arg, &spec)),
+                    None => output.push_str("{?}"),
+                }
+            }
+            c => output.push(c),
+        }
+    }
+    // Arguments without a placeholder are shown rather than dropped.
+    for arg in args {
+        output.push(' ');
+        output.push_str(&format_arg(arg, ":?"));
+    }
+    output
+}
+
+// Formats an argument according to the placeholder's format spec (e.g. ":x").
+// Unsupported specs format the argument as if the spec were empty.
+fn format_arg(arg: Arg, spec: &str) -> String {
+    macro_rules! integer {
+        ($value:expr) => {
+            match spec {
+                ":x" => format!("{:x}", $value),
+                ":#x" => format!("{:#x}", $value),
+                ":X" => format!("{:X}", $value),
+                ":b" => format!("{:b}", $value),
+                _ => $value.to_string(),
+            }
+        };
+    }
+    match arg {
+        Arg::Unsigned(value) => integer!(value),
+        Arg::Signed(value) => integer!(value),
+        Arg::Bool(value) => value.to_string(),
+        Arg::Char(value) if spec == ":?" => format!("{value:?}"),
+        Arg::Char(value) => value.to_string(),
+        Arg::Str(value) if spec == ":?" => format!("{value:?}"),
+        Arg::Str(value) => value.to_string(),
+        Arg::Bytes(value) if spec == ":x" => format!("{value:02x?}"),
+        Arg::Bytes(value) => format!("{value:?}"),
+        Arg::F32(value) => value.to_string(),
+    }
+}
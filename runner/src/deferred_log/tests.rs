@@ -0,0 +1,81 @@
+use super::Decoder;
+use libtock_deferred_log_format::{encode_arg, Arg, MARKER};
+
+const STRINGS: &[u8] = b"INFO app: {} + {:#x} = {:?}\0WARN app::radio: {{{}}}\0";
+
+fn frame(string: u8, args: &[Arg]) -> Vec<u8> {
+    let mut frame = vec![MARKER, string, args.len() as u8];
+    for &arg in args {
+        let mut buffer = [0; 32];
+        let len = encode_arg(arg, &mut buffer).unwrap();
+        frame.extend_from_slice(&buffer[..len]);
+    }
+    frame
+}
+
+#[test]
+fn decodes_frames() {
+    let mut decoder = Decoder::new(Some(STRINGS.to_vec()));
+    let mut input = b"text\n".to_vec();
+    input.extend(frame(
+        0,
+        &[Arg::Signed(-1), Arg::Unsigned(255), Arg::Str("x")],
+    ));
+    input.extend(frame(28, &[Arg::Bool(true)]));
+    input.extend(b"more text");
+    // Frames may be split across reads.
+    let mut output = decoder.process(&input[..10]);
+    output.extend(decoder.process(&input[10..]));
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "text\nINFO app: -1 + 0xff = \"x\"\nWARN app::radio: {true}\nmore text"
+    );
+}
+
+#[test]
+fn mismatched_args() {
+    let mut decoder = Decoder::new(Some(STRINGS.to_vec()));
+    let mut input = frame(28, &[]);
+    input.extend(frame(28, &[Arg::Unsigned(1), Arg::Bytes(&[10, 11])]));
+    assert_eq!(
+        String::from_utf8(decoder.process(&input)).unwrap(),
+        "WARN app::radio: {{?}}\nWARN app::radio: {1} [10, 11]\n"
+    );
+}
+
+#[test]
+fn bad_frames() {
+    let mut decoder = Decoder::new(Some(STRINGS.to_vec()));
+    // Addresses that are not the start of an interned string, and an unknown
+    // argument tag. Invalid frames are passed through, and frames after them
+    // are still decoded.
+    let mut input = frame(100, &[Arg::Char('c')]);
+    input.extend(frame(5, &[]));
+    input.extend([MARKER, 0, 1, 99]);
+    input.extend(frame(28, &[Arg::Unsigned(2)]));
+    input.extend(b"ok");
+    let mut expected = frame(100, &[Arg::Char('c')]);
+    expected.extend(frame(5, &[]));
+    expected.extend([MARKER, 0, 1, 99]);
+    expected.extend(b"WARN app::radio: {2}\nok");
+    assert_eq!(decoder.process(&input), expected);
+}
+
+#[test]
+fn stray_marker() {
+    let mut decoder = Decoder::new(Some(STRINGS.to_vec()));
+    // Text after a stray marker is not held back waiting for more input.
+    assert_eq!(decoder.process(b"a\xffbc"), b"a\xffbc");
+    // Only a marker at the end of the input is held, until the next byte
+    // shows whether it starts a frame.
+    assert_eq!(decoder.process(b"d\xff"), b"d");
+    assert_eq!(decoder.process(b"ef"), b"\xffef");
+}
+
+#[test]
+fn no_strings_section() {
+    // Without interned strings, output is passed through unchanged.
+    let mut decoder = Decoder::new(None);
+    let input = frame(0, &[Arg::Unsigned(1)]);
+    assert_eq!(decoder.process(&input), input);
+}
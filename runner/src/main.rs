@@ -1,6 +1,8 @@
+mod deferred_log;
 mod elf2tab;
 mod output_processor;
 mod qemu;
+mod test_results;
 mod tockloader;
 
 use clap::{Parser, ValueEnum};
@@ -20,6 +22,12 @@ pub struct Cli {
     #[clap(action)]
     elf: PathBuf,
 
+    /// Stops the process binary after this many seconds. Useful for
+    /// `libtock_test` binaries, which would otherwise hang if a test crashes
+    /// the process.
+    #[clap(action, long)]
+    timeout: Option<u64>,
+
     /// Whether to output verbose debugging information to the console.
     #[clap(long, short, action)]
     verbose: bool,
@@ -1,15 +1,31 @@
+use super::deferred_log::Decoder;
+use super::test_results::TestResults;
 use super::Cli;
 use libc::{kill, pid_t, SIGINT};
 use std::io::{stderr, stdin, stdout, BufRead, BufReader, ErrorKind, Stdout, Write};
-use std::process::Child;
-use std::thread::spawn;
+use std::process::{exit, Child};
+use std::thread::{sleep, spawn};
+use std::time::Duration;
 use termion::raw::{IntoRawMode, RawTerminal};
 
 /// Reads the console messages from `child`'s standard output, sending SIGTERM
 /// to the child when the process is terminated.
+///
+/// Log frames sent by `libtock_deferred_log` are decoded into text using the
+/// format strings in `cli.elf`.
+///
+/// If the process binary is a `libtock_test` harness, this stops the child
+/// once the tests are done, prints a summary of the results, and exits with a
+/// failure status if any test failed.
 pub fn process(cli: &Cli, mut child: Child) {
     let raw_mode = forward_stdin_if_piped(&mut child);
     forward_stderr_if_piped(&mut child, raw_mode.is_some());
+    let child_id = child.id();
+    if let Some(timeout) = cli.timeout {
+        interrupt_after(child_id, Duration::from_secs(timeout));
+    }
+    let mut decoder = Decoder::from_elf(&cli.elf);
+    let mut test_results = TestResults::default();
     let mut to_print = Vec::new();
     let mut reader = BufReader::new(child.stdout.as_mut().expect("Child's stdout not piped."));
     loop {
@@ -22,7 +38,8 @@ pub fn process(cli: &Cli, mut child: Child) {
         }
         // Print the bytes received over stdout. If the terminal is in raw mode,
         // translate '\n' into '\r\n'.
-        for &byte in buffer {
+        let decoded = decoder.process(buffer);
+        for &byte in &decoded {
             if raw_mode.is_some() && byte == b'\n' {
                 to_print.push(b'\r');
             }
@@ -36,6 +53,14 @@ pub fn process(cli: &Cli, mut child: Child) {
         drop(lock);
         to_print.clear();
 
+        // The Tock kernel keeps running after the process exits, so the child
+        // has to be stopped once the tests are done.
+        let was_done = test_results.is_done();
+        test_results.process(&decoded);
+        if !was_done && test_results.is_done() {
+            interrupt(child_id);
+        }
+
         let buffer_len = buffer.len();
         reader.consume(buffer_len);
     }
@@ -44,6 +69,13 @@ pub fn process(cli: &Cli, mut child: Child) {
     }
     let status = child.wait().expect("Unable to wait for child process");
     drop(raw_mode);
+    if test_results.found_tests() {
+        println!("{}", test_results.summary());
+        exit(match test_results.succeeded() {
+            true => 0,
+            false => 1,
+        });
+    }
     assert!(
         status.success(),
         "Child process did not exit successfully. {status}"
@@ -85,12 +117,7 @@ fn forward_stdin_if_piped(child: &mut Child) -> Option<RawTerminal<Stdout>> {
         }
         // Send SIGINT to the child, telling it to exit. After the child exits,
         // the main loop will detect the exit and we will shut down cleanly.
-        //
-        // Safety: Sending SIGINT to a process is a safe operation -- kill is
-        // marked unsafe because it is a FFI function.
-        unsafe {
-            kill(child_id as pid_t, SIGINT);
-        }
+        interrupt(child_id);
     });
     Some(
         stdout()
@@ -129,3 +156,20 @@ fn forward_stderr_if_piped(child: &mut Child, raw_mode: bool) {
         }
     });
 }
+
+// Sends SIGINT to the child process after `timeout` has elapsed.
+fn interrupt_after(child_id: u32, timeout: Duration) {
+    spawn(move || {
+        sleep(timeout);
+        interrupt(child_id);
+    });
+}
+
+// Sends SIGINT to the child process with ID `child_id`, telling it to exit.
+fn interrupt(child_id: u32) {
+    // Safety: Sending SIGINT to a process is a safe operation -- kill is marked
+    // unsafe because it is a FFI function.
+    unsafe {
+        kill(child_id as pid_t, SIGINT);
+    }
+}
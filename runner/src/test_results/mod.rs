@@ -0,0 +1,114 @@
+//! Parses the results reported by `libtock_test`'s harness out of a process
+//! binary's console output.
+
+/// The prefix `libtock_test` starts each of its lines with.
+const PREFIX: &str = "libtock_test: ";
+
+/// Collects test results from console output. Output that is not from the
+/// harness is ignored.
+#[derive(Debug, Default)]
+pub struct TestResults {
+    // Bytes of the current line that have been received so far.
+    partial_line: Vec<u8>,
+    // The number of tests the harness announced, if it has started.
+    expected: Option<usize>,
+    // The test that is currently running.
+    running: Option<String>,
+    passed: Vec<String>,
+    // Failed tests and the reason they failed.
+    failed: Vec<(String, String)>,
+    done: bool,
+}
+
+impl TestResults {
+    /// Processes bytes of console output.
+    pub fn process(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            if byte != b'\n' {
+                self.partial_line.push(byte);
+                continue;
+            }
+            let line = String::from_utf8_lossy(&self.partial_line).into_owned();
+            self.process_line(line.trim_end_matches('\r'));
+            self.partial_line.clear();
+        }
+    }
+
+    /// Returns true if the output came from a `libtock_test` harness.
+    pub fn found_tests(&self) -> bool {
+        self.expected.is_some()
+    }
+
+    /// Returns true once the harness has reported that every test ran.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Returns true if every test ran and passed.
+    pub fn succeeded(&self) -> bool {
+        self.done && self.failed.is_empty() && Some(self.passed.len()) == self.expected
+    }
+
+    /// Returns a human-readable summary of the results.
+    pub fn summary(&self) -> String {
+        let mut summary = String::new();
+        for (name, reason) in &self.failed {
+            summary.push_str(&format!("FAILED {name}: {reason}\n"));
+        }
+        if let Some(name) = &self.running {
+            summary.push_str(&format!(
+                "FAILED {name}: did not finish (the process crashed or timed out)\n"
+            ));
+        }
+        // A test that did not finish counts as failed.
+        let failed = self.failed.len() + usize::from(self.running.is_some());
+        let not_run = self
+            .expected
+            .unwrap_or(0)
+            .saturating_sub(self.passed.len() + failed);
+        summary.push_str(&format!(
+            "test result: {}. {} passed; {} failed; {} not run",
+            match self.succeeded() {
+                true => "ok",
+                false => "FAILED",
+            },
+            self.passed.len(),
+            failed,
+            not_run
+        ));
+        summary
+    }
+
+    fn process_line(&mut self, line: &str) {
+        // The harness' output may be preceded by other output on the same
+        // line, such as a kernel message that lacked a trailing newline.
+        let Some(position) = line.find(PREFIX) else {
+            return;
+        };
+        let message = &line[position + PREFIX.len()..];
+        let (kind, rest) = message.split_once(' ').unwrap_or((message, ""));
+        match kind {
+            "start" => {
+                *self = TestResults {
+                    expected: rest.parse().ok(),
+                    ..Default::default()
+                }
+            }
+            "run" => self.running = Some(rest.to_owned()),
+            "pass" => {
+                self.running = None;
+                self.passed.push(rest.to_owned());
+            }
+            "fail" => {
+                self.running = None;
+                let (name, reason) = rest.split_once(" at ").unwrap_or((rest, ""));
+                self.failed.push((name.to_owned(), reason.to_owned()));
+            }
+            "done" => self.done = true,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
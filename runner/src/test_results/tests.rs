@@ -0,0 +1,65 @@
+use super::TestResults;
+
+#[test]
+fn all_pass() {
+    let mut results = TestResults::default();
+    // Output arrives in arbitrary chunks, with CRLF line endings.
+    results
+        .process(b"Initialization complete.\r\nlibtock_test: start 2\r\nlibtock_test: run a\r\n");
+    results.process(b"libtock_test: pass a\r\nlibtock_test: run b\r\nlibtock_");
+    assert!(results.found_tests());
+    assert!(!results.is_done());
+    results.process(b"test: pass b\r\nlibtock_test: done 2 passed, 0 failed\r\n");
+    assert!(results.is_done());
+    assert!(results.succeeded());
+    assert_eq!(
+        results.summary(),
+        "test result: ok. 2 passed; 0 failed; 0 not run"
+    );
+}
+
+#[test]
+fn failure() {
+    let mut results = TestResults::default();
+    results.process(
+        b"libtock_test: start 2\n\
+          libtock_test: run a\n\
+          libtock_test: fail a at tests.rs:7: check failed: x == y (left: 1, right: 2)\n\
+          libtock_test: run b\n\
+          libtock_test: pass b\n\
+          libtock_test: done 1 passed, 1 failed\n",
+    );
+    assert!(results.is_done());
+    assert!(!results.succeeded());
+    assert_eq!(
+        results.summary(),
+        "FAILED a: tests.rs:7: check failed: x == y (left: 1, right: 2)\n\
+         test result: FAILED. 1 passed; 1 failed; 0 not run"
+    );
+}
+
+#[test]
+fn crash() {
+    let mut results = TestResults::default();
+    results.process(
+        b"libtock_test: start 3\n\
+          libtock_test: run a\n\
+          libtock_test: pass a\n\
+          libtock_test: run b\n\
+          panicked at tests.rs:12\n",
+    );
+    assert!(!results.is_done());
+    assert!(!results.succeeded());
+    assert_eq!(
+        results.summary(),
+        "FAILED b: did not finish (the process crashed or timed out)\n\
+         test result: FAILED. 1 passed; 1 failed; 1 not run"
+    );
+}
+
+#[test]
+fn no_tests() {
+    let mut results = TestResults::default();
+    results.process(b"Hello world!\n");
+    assert!(!results.found_tests());
+}
@@ -9,6 +9,15 @@ extern crate libtock_small_panic;
 pub use libtock_platform as platform;
 pub use libtock_runtime as runtime;
 
+#[cfg(feature = "ufmt")]
+mod print;
+// Used by the print macros.
+#[cfg(feature = "ufmt")]
+#[doc(hidden)]
+pub mod __private {
+    pub use ufmt;
+}
+
 pub mod adc {
     use libtock_adc as adc;
     pub type Adc = adc::Adc<super::runtime::TockSyscalls>;
@@ -27,7 +36,9 @@ pub mod air_quality {
 pub mod alarm {
     use libtock_alarm as alarm;
     pub type Alarm = alarm::Alarm<super::runtime::TockSyscalls>;
-    pub use alarm::{Convert, Hz, Milliseconds, Ticks};
+    pub type Clock = alarm::Clock<super::runtime::TockSyscalls>;
+    pub type Timers<'a, const N: usize> = alarm::Timers<'a, super::runtime::TockSyscalls, N>;
+    pub use alarm::{Convert, Duration, Fired, Hz, Instant, Milliseconds, Ticks, TimerId};
 }
 pub mod ambient_light {
     use libtock_ambient_light as ambient_light;
@@ -37,7 +48,9 @@ pub mod ambient_light {
 pub mod buttons {
     use libtock_buttons as buttons;
     pub type Buttons = buttons::Buttons<super::runtime::TockSyscalls>;
-    pub use buttons::{ButtonListener, ButtonState};
+    pub type ButtonDispatcher<'a, const N: usize> =
+        buttons::ButtonDispatcher<'a, super::runtime::TockSyscalls, N>;
+    pub use buttons::{ButtonHandler, ButtonListener, ButtonState};
 }
 pub mod buzzer {
     use libtock_buzzer as buzzer;
@@ -47,13 +60,24 @@ pub mod buzzer {
 pub mod console {
     use libtock_console as console;
     pub type Console = console::Console<super::runtime::TockSyscalls>;
-    pub use console::ConsoleWriter;
+    #[cfg(feature = "rust_embedded")]
+    pub type ConsoleIo = console::ConsoleIo<super::runtime::TockSyscalls>;
+    pub type LineReader<'a, const LEN: usize, const HISTORY: usize> =
+        console::LineReader<'a, super::runtime::TockSyscalls, LEN, HISTORY>;
+    pub type WriteBuffer<const N: usize> = console::WriteBuffer<super::runtime::TockSyscalls, N>;
+    pub use console::{BufferedWriter, Completer, ConsoleWriter};
 }
 pub mod gpio {
     use libtock_gpio as gpio;
     pub type Gpio = gpio::Gpio<super::runtime::TockSyscalls>;
+    pub type PinDispatcher<'a, const N: usize> =
+        gpio::PinDispatcher<'a, super::runtime::TockSyscalls, N>;
+    #[cfg(feature = "rust_embedded")]
+    pub use gpio::AsyncInputPin;
+    #[allow(deprecated)]
+    pub use gpio::Error;
     pub use gpio::{
-        Error, GpioInterruptListener, GpioState, InputPin, OutputPin, PinInterruptEdge, Pull,
+        GpioInterruptListener, GpioState, InputPin, OutputPin, PinHandler, PinInterruptEdge, Pull,
         PullDown, PullNone, PullUp,
     };
 }
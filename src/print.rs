@@ -0,0 +1,56 @@
+//! `print!`-style macros that format with `ufmt`, which produces much smaller
+//! binaries than `core::fmt`. Values are formatted with `uDebug` (`{:?}`) and
+//! `uDisplay` (`{}`); see the `ufmt` crate for the supported syntax.
+//!
+//! ```ignore
+//! use libtock::{uprintln, ueprintln};
+//!
+//! uprintln!("temperature: {}", temperature);
+//! if let Err(error) = result {
+//!     ueprintln!("read failed: {:?}", error);
+//! }
+//! ```
+//!
+//! Tock has a single console, so the `ueprint` macros write to the same
+//! console as the `uprint` macros. Errors writing to the console are ignored.
+
+/// Writes formatted text to the console.
+#[macro_export]
+macro_rules! uprint {
+    ($($tt:tt)*) => {{
+        use $crate::__private::ufmt;
+        let mut writer = $crate::console::Console::writer();
+        let _ = ufmt::uwrite!(&mut writer, $($tt)*);
+    }};
+}
+
+/// Writes formatted text and a newline to the console.
+#[macro_export]
+macro_rules! uprintln {
+    () => {
+        $crate::uprint!("\n")
+    };
+    ($($tt:tt)*) => {{
+        use $crate::__private::ufmt;
+        let mut writer = $crate::console::Console::writer();
+        let _ = ufmt::uwriteln!(&mut writer, $($tt)*);
+    }};
+}
+
+/// Writes formatted text to the console. Tock has no separate error stream,
+/// so this is the same as `uprint!`.
+#[macro_export]
+macro_rules! ueprint {
+    ($($tt:tt)*) => {
+        $crate::uprint!($($tt)*)
+    };
+}
+
+/// Writes formatted text and a newline to the console. Tock has no separate
+/// error stream, so this is the same as `uprintln!`.
+#[macro_export]
+macro_rules! ueprintln {
+    ($($tt:tt)*) => {
+        $crate::uprintln!($($tt)*)
+    };
+}
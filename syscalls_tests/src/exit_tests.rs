@@ -0,0 +1,41 @@
+//! Tests for the Exit system call implementation in
+//! `libtock_platform::Syscalls`.
+
+use libtock_platform::{exit_id, Syscalls};
+use libtock_unittest::{catch_exit, fake, ExitCall, ExpectedSyscall, SyscallLogEntry};
+
+#[test]
+fn exit_terminate() {
+    let kernel = fake::Kernel::new();
+    kernel.add_expected_syscall(ExpectedSyscall::Exit {
+        exit_num: exit_id::TERMINATE,
+        completion_code: 7,
+    });
+    assert_eq!(
+        catch_exit(|| fake::Syscalls::exit_terminate(7)),
+        Err(ExitCall::Terminate(7))
+    );
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [SyscallLogEntry::Exit {
+            exit_num: exit_id::TERMINATE,
+            completion_code: 7,
+        }]
+    );
+}
+
+#[test]
+fn exit_restart() {
+    let kernel = fake::Kernel::new();
+    assert_eq!(
+        catch_exit(|| fake::Syscalls::exit_restart(12)),
+        Err(ExitCall::Restart(12))
+    );
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [SyscallLogEntry::Exit {
+            exit_num: exit_id::RESTART,
+            completion_code: 12,
+        }]
+    );
+}
@@ -23,7 +23,8 @@ mod command_tests;
 #[cfg(test)]
 mod exit_on_drop;
 
-// TODO: Add Exit.
+#[cfg(test)]
+mod exit_tests;
 
 #[cfg(test)]
 mod memop_tests;
@@ -31,5 +32,8 @@ mod memop_tests;
 #[cfg(test)]
 mod subscribe_tests;
 
+#[cfg(test)]
+mod trace_tests;
+
 #[cfg(test)]
 mod yield_tests;
@@ -0,0 +1,63 @@
+//! Tests that exercise `TockSyscalls` and the API crates against a real Tock
+//! kernel. Run them in QEMU with `make qemu-test`.
+
+#![no_main]
+#![no_std]
+use libtock::alarm::{Alarm, Milliseconds};
+use libtock::console::Console;
+use libtock::platform::{ErrorCode, Syscalls, YieldNoWaitReturn};
+use libtock::runtime::{stack_size, TockSyscalls};
+use libtock_test::{check, check_eq, test_main, TestResult};
+
+stack_size! {0x800}
+test_main! {
+    console_exists,
+    missing_driver,
+    yield_no_wait_without_upcall,
+    alarm_frequency,
+    alarm_sleep,
+}
+
+fn console_exists() -> TestResult {
+    check!(Console::exists());
+    Ok(())
+}
+
+fn missing_driver() -> TestResult {
+    check_eq!(
+        TockSyscalls::command(MISSING_DRIVER, 0, 0, 0).get_failure(),
+        Some(ErrorCode::NoDevice)
+    );
+    Ok(())
+}
+
+fn yield_no_wait_without_upcall() -> TestResult {
+    check_eq!(TockSyscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+    Ok(())
+}
+
+fn alarm_frequency() -> TestResult {
+    let frequency = Alarm::get_frequency();
+    check!(frequency.is_ok(), "{:?}", frequency);
+    Ok(())
+}
+
+fn alarm_sleep() -> TestResult {
+    let start = Alarm::get_milliseconds();
+    check_eq!(Alarm::sleep_for(Milliseconds(10)), Ok(()));
+    let end = Alarm::get_milliseconds();
+    check!(
+        matches!((start, end), (Ok(start), Ok(end)) if end >= start + 10),
+        "start {:?}, end {:?}",
+        start,
+        end
+    );
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Implementation details below
+// -----------------------------------------------------------------------------
+
+// No Tock kernel provides a driver with this number.
+const MISSING_DRIVER: u32 = 0xdead0;
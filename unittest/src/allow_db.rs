@@ -206,6 +206,15 @@ impl Default for RwAllowBuffer {
     }
 }
 
+impl RwAllowBuffer {
+    // Returns the buffer's address. Unlike a pointer derived from the Deref
+    // implementations, it keeps the provenance of the pointer the process
+    // passed to Allow, so it remains usable while the buffer is shared.
+    pub(crate) fn address(&self) -> *mut u8 {
+        self.address
+    }
+}
+
 // Allows access to the pointed-to-buffer. The returned reference has the same
 // lifetime as the &self reference, so the caller can't keep the reference for
 // longer than it has access to the RwAllowBuffer.
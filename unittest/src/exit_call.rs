@@ -0,0 +1,106 @@
+//! Tools for testing code that calls the Exit system call without leaving the
+//! test process.
+
+use std::cell::Cell;
+use std::panic::{catch_unwind, resume_unwind, UnwindSafe};
+
+/// Indicates what type of Exit call was performed, and what completion code was
+/// provided.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExitCall {
+    Terminate(u32),
+    Restart(u32),
+}
+
+/// Runs `fcn`, capturing any Exit system call it makes. Returns `Ok` with
+/// `fcn`'s return value if it returns normally, or `Err` with the Exit call if
+/// it calls Exit. Panics raised by `fcn` propagate normally.
+///
+/// ```
+/// use libtock_platform::Syscalls;
+/// let _kernel = libtock_unittest::fake::Kernel::new();
+/// let exit = libtock_unittest::catch_exit(|| {
+///     libtock_unittest::fake::Syscalls::exit_terminate(0);
+/// });
+/// assert_eq!(exit, Err(libtock_unittest::ExitCall::Terminate(0)));
+/// ```
+///
+/// While `fcn` runs, `fake::Syscalls`' Exit implementation unwinds out of the
+/// `exit_*` call rather than terminating the test process. As a result, unlike
+/// `exit_test`, `catch_exit` works under Miri. However, it cannot capture Exit
+/// calls that are made while the stack is already unwinding (e.g. from a
+/// `Drop` implementation during a panic), as a second unwind would abort the
+/// process. Use `exit_test` to test those cases.
+pub fn catch_exit<R, F: FnOnce() -> R + UnwindSafe>(fcn: F) -> Result<R, ExitCall> {
+    // Restores CATCH_DEPTH even if fcn unwinds.
+    struct DepthGuard;
+    impl Drop for DepthGuard {
+        fn drop(&mut self) {
+            CATCH_DEPTH.with(|depth| depth.set(depth.get() - 1));
+        }
+    }
+
+    CATCH_DEPTH.with(|depth| depth.set(depth.get() + 1));
+    let guard = DepthGuard;
+    let result = catch_unwind(fcn);
+    drop(guard);
+    match result {
+        Ok(value) => Ok(value),
+        Err(payload) => match payload.downcast::<CaughtExit>() {
+            Ok(caught) => Err(caught.0),
+            Err(payload) => resume_unwind(payload),
+        },
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Public API above, implementation details below.
+// -----------------------------------------------------------------------------
+
+// Called by the fake Exit implementation. If a catch_exit call is active on
+// this thread, unwinds to it. Otherwise, returns.
+pub(crate) fn unwind_if_caught(exit_call: ExitCall) {
+    if CATCH_DEPTH.with(Cell::get) > 0 {
+        // resume_unwind does not invoke the panic hook, so this does not print
+        // a spurious panic message.
+        resume_unwind(Box::new(CaughtExit(exit_call)));
+    }
+}
+
+// The number of active catch_exit calls on this thread.
+thread_local!(static CATCH_DEPTH: Cell<u32> = const { Cell::new(0) });
+
+// The unwind payload used to carry an Exit call to catch_exit.
+struct CaughtExit(ExitCall);
+
+#[doc(hidden)]
+impl std::fmt::Display for ExitCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            ExitCall::Terminate(code) => write!(f, "exit-terminate({code})"),
+            ExitCall::Restart(code) => write!(f, "exit-restart({code})"),
+        }
+    }
+}
+
+#[doc(hidden)]
+impl std::str::FromStr for ExitCall {
+    type Err = ParseExitError;
+
+    fn from_str(s: &str) -> Result<ExitCall, ParseExitError> {
+        // Strip off the trailing ), leaving the name and (
+        let s = s.strip_suffix(')').ok_or(ParseExitError)?;
+
+        if let Some(s) = s.strip_prefix("exit-terminate(") {
+            Ok(ExitCall::Terminate(s.parse().or(Err(ParseExitError))?))
+        } else if let Some(s) = s.strip_prefix("exit-restart(") {
+            Ok(ExitCall::Restart(s.parse().or(Err(ParseExitError))?))
+        } else {
+            Err(ParseExitError)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[doc(hidden)]
+pub struct ParseExitError;
@@ -2,11 +2,13 @@
 //!
 //! This module is not compatible with Miri because it requires the ability to
 //! spawn external processes, which Miri does not support by default. Therefore
-//! it is only available for non-Miri tests.
+//! it is only available for non-Miri tests. `catch_exit` is an in-process
+//! alternative that works under Miri.
 
 #[cfg(test)]
 mod tests;
 
+use crate::exit_call::{ExitCall, ParseExitError};
 use std::panic::{catch_unwind, Location, UnwindSafe};
 
 /// Utility for testing code that is expected to call the Exit system call. It
@@ -67,14 +69,6 @@ pub fn exit_test<F: FnOnce() + UnwindSafe>(test_name: &str, fcn: F) -> ExitCall
     }
 }
 
-/// Indicates what type of Exit call was performed, and what completion code was
-/// provided.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub enum ExitCall {
-    Terminate(u32),
-    Restart(u32),
-}
-
 // -----------------------------------------------------------------------------
 // Public API above, implementation details below.
 // -----------------------------------------------------------------------------
@@ -84,38 +78,6 @@ pub(crate) fn signal_exit(exit_call: ExitCall) {
     signal_message(ExitMessage::ExitCall(exit_call));
 }
 
-#[doc(hidden)]
-impl std::fmt::Display for ExitCall {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        match self {
-            ExitCall::Terminate(code) => write!(f, "exit-terminate({code})"),
-            ExitCall::Restart(code) => write!(f, "exit-restart({code})"),
-        }
-    }
-}
-
-#[doc(hidden)]
-impl std::str::FromStr for ExitCall {
-    type Err = ParseExitError;
-
-    fn from_str(s: &str) -> Result<ExitCall, ParseExitError> {
-        // Strip off the trailing ), leaving the name and (
-        let s = s.strip_suffix(')').ok_or(ParseExitError)?;
-
-        if let Some(s) = s.strip_prefix("exit-terminate(") {
-            Ok(ExitCall::Terminate(s.parse().or(Err(ParseExitError))?))
-        } else if let Some(s) = s.strip_prefix("exit-restart(") {
-            Ok(ExitCall::Restart(s.parse().or(Err(ParseExitError))?))
-        } else {
-            Err(ParseExitError)
-        }
-    }
-}
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-#[doc(hidden)]
-pub struct ParseExitError;
-
 // The name of the environment variable used by process A to tell process B that
 // it is process B. The value of the environment variable is the location where
 // exit_test was called (this location is used to help verify that test_name is
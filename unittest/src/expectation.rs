@@ -0,0 +1,904 @@
+//! Flexible expectations for `fake::Kernel`'s expected syscall queue, and
+//! assertions over the system call log.
+
+use crate::kernel_data::KernelData;
+use crate::{ExpectedSyscall, SyscallLogEntry};
+use core::fmt;
+use libtock_platform::{CommandReturn, ErrorCode};
+use std::collections::{HashMap, VecDeque};
+
+/// A pattern for one or more system calls, queued with `fake::Kernel::expect`.
+/// Unlike an `ExpectedSyscall`, which must match exactly one system call
+/// exactly, an `Expectation` may use wildcards for arguments (`Arg::Any`),
+/// match a run of yields (`any_yields`), match several calls in any order
+/// (`unordered`), and be repeated (`times`).
+///
+/// ```
+/// use libtock_platform::Syscalls;
+/// use libtock_unittest::{fake, Arg, Expectation};
+///
+/// let kernel = fake::Kernel::new();
+/// kernel.expect(Expectation::unordered([
+///     Expectation::command(0x1, 0, Arg::Any, Arg::Any),
+///     Expectation::command(0x2, 0, Arg::Any, Arg::Any),
+/// ]));
+/// kernel.expect(Expectation::any_yields());
+/// kernel.expect(Expectation::command(0x2, 1, 7, Arg::Any).times(2));
+/// fake::Syscalls::command(0x2, 0, 0, 0);
+/// fake::Syscalls::command(0x1, 0, 0, 0);
+/// fake::Syscalls::yield_no_wait();
+/// fake::Syscalls::command(0x2, 1, 7, 0);
+/// fake::Syscalls::command(0x2, 1, 7, 1);
+/// kernel.assert_expectations_met();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Expectation {
+    kind: Kind,
+    // The number of times this expectation must still be matched.
+    times: u32,
+}
+
+/// A system call argument in an `Expectation`: either a specific value or
+/// `Any` value. Values convert into `Arg`s with `From`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Arg {
+    Any,
+    Is(u32),
+}
+
+impl From<u32> for Arg {
+    fn from(value: u32) -> Arg {
+        Arg::Is(value)
+    }
+}
+
+impl Expectation {
+    pub fn yield_no_wait() -> Expectation {
+        Expectation::call(Pattern::YieldNoWait, Action::None)
+    }
+
+    pub fn yield_wait() -> Expectation {
+        Expectation::call(Pattern::YieldWait, Action::None)
+    }
+
+    /// Matches any number (including zero) of Yield calls. The run of yields
+    /// ends at the first system call that is not a Yield, which is matched
+    /// against the next expectation.
+    pub fn any_yields() -> Expectation {
+        Expectation {
+            kind: Kind::AnyYields,
+            times: 1,
+        }
+    }
+
+    pub fn subscribe(driver_num: impl Into<Arg>, subscribe_num: impl Into<Arg>) -> Expectation {
+        Expectation::call(
+            Pattern::Subscribe {
+                driver_num: driver_num.into(),
+                subscribe_num: subscribe_num.into(),
+            },
+            Action::None,
+        )
+    }
+
+    pub fn command(
+        driver_id: impl Into<Arg>,
+        command_id: impl Into<Arg>,
+        argument0: impl Into<Arg>,
+        argument1: impl Into<Arg>,
+    ) -> Expectation {
+        Expectation::call(
+            Pattern::Command {
+                driver_id: driver_id.into(),
+                command_id: command_id.into(),
+                argument0: argument0.into(),
+                argument1: argument1.into(),
+            },
+            Action::None,
+        )
+    }
+
+    pub fn allow_ro(driver_num: impl Into<Arg>, buffer_num: impl Into<Arg>) -> Expectation {
+        Expectation::call(
+            Pattern::AllowRo {
+                driver_num: driver_num.into(),
+                buffer_num: buffer_num.into(),
+            },
+            Action::None,
+        )
+    }
+
+    pub fn allow_rw(driver_num: impl Into<Arg>, buffer_num: impl Into<Arg>) -> Expectation {
+        Expectation::call(
+            Pattern::AllowRw {
+                driver_num: driver_num.into(),
+                buffer_num: buffer_num.into(),
+            },
+            Action::None,
+        )
+    }
+
+    /// Matches a Memop call with any argument.
+    pub fn memop(memop_num: impl Into<Arg>) -> Expectation {
+        Expectation::call(
+            Pattern::Memop {
+                memop_num: memop_num.into(),
+                argument0: None,
+            },
+            Action::None,
+        )
+    }
+
+    pub fn exit(exit_num: impl Into<Arg>, completion_code: impl Into<Arg>) -> Expectation {
+        Expectation::call(
+            Pattern::Exit {
+                exit_num: exit_num.into(),
+                completion_code: completion_code.into(),
+            },
+            Action::None,
+        )
+    }
+
+    /// Matches each of `expectations` once, in any order. The expectations
+    /// may be repeated with `times`, but may not be `any_yields` or groups.
+    pub fn unordered<I: IntoIterator<Item = Expectation>>(expectations: I) -> Expectation {
+        let items: Vec<_> = expectations.into_iter().collect();
+        assert!(
+            items
+                .iter()
+                .all(|item| matches!(item.kind, Kind::Call { .. })),
+            "unordered groups may only contain single system call expectations"
+        );
+        Expectation {
+            kind: Kind::Unordered {
+                pending: items.clone(),
+                items,
+            },
+            times: 1,
+        }
+    }
+
+    /// Requires this expectation to be matched `times` times in a row.
+    pub fn times(mut self, times: u32) -> Expectation {
+        assert!(times > 0, "an expectation must be matched at least once");
+        assert!(
+            !matches!(self.kind, Kind::AnyYields),
+            "any_yields cannot be repeated"
+        );
+        self.times = times;
+        self
+    }
+
+    /// Makes a matched Command call return `command_return` instead of calling
+    /// the driver.
+    pub fn returning(mut self, command_return: CommandReturn) -> Expectation {
+        match &mut self.kind {
+            Kind::Call {
+                pattern: Pattern::Command { .. },
+                action,
+            } => *action = Action::Return(command_return),
+            _ => panic!("only Command expectations can override the return value"),
+        }
+        self
+    }
+
+    /// Makes a matched Subscribe, Allow, or Memop call fail with `error`
+    /// without calling the driver.
+    pub fn failing(mut self, error: ErrorCode) -> Expectation {
+        match &mut self.kind {
+            Kind::Call {
+                pattern:
+                    Pattern::Subscribe { .. }
+                    | Pattern::AllowRo { .. }
+                    | Pattern::AllowRw { .. }
+                    | Pattern::Memop { .. },
+                action,
+            } => *action = Action::Fail(error),
+            _ => panic!("only Subscribe, Allow, and Memop expectations can fail"),
+        }
+        self
+    }
+
+    /// Makes a matched yield-wait call return without running an upcall.
+    pub fn skipping_upcall(mut self) -> Expectation {
+        match &mut self.kind {
+            Kind::Call {
+                pattern: Pattern::YieldWait,
+                action,
+            } => *action = Action::SkipUpcall,
+            _ => panic!("only yield-wait expectations can skip the upcall"),
+        }
+        self
+    }
+}
+
+/// Converts an `ExpectedSyscall` into an `Expectation` that matches the same
+/// call and takes the same action.
+impl From<ExpectedSyscall> for Expectation {
+    fn from(expected: ExpectedSyscall) -> Expectation {
+        use ExpectedSyscall::*;
+        let (pattern, action) = match expected {
+            YieldNoWait { override_return } => (
+                Pattern::YieldNoWait,
+                override_return.map_or(Action::None, Action::YieldReturn),
+            ),
+            YieldWait { skip_upcall } => (
+                Pattern::YieldWait,
+                match skip_upcall {
+                    true => Action::SkipUpcall,
+                    false => Action::None,
+                },
+            ),
+            Subscribe {
+                driver_num,
+                subscribe_num,
+                skip_with_error,
+            } => (
+                Pattern::Subscribe {
+                    driver_num: driver_num.into(),
+                    subscribe_num: subscribe_num.into(),
+                },
+                skip_with_error.map_or(Action::None, Action::Fail),
+            ),
+            Command {
+                driver_id,
+                command_id,
+                argument0,
+                argument1,
+                override_return,
+            } => (
+                Pattern::Command {
+                    driver_id: driver_id.into(),
+                    command_id: command_id.into(),
+                    argument0: argument0.into(),
+                    argument1: argument1.into(),
+                },
+                override_return.map_or(Action::None, Action::Return),
+            ),
+            AllowRo {
+                driver_num,
+                buffer_num,
+                return_error,
+            } => (
+                Pattern::AllowRo {
+                    driver_num: driver_num.into(),
+                    buffer_num: buffer_num.into(),
+                },
+                return_error.map_or(Action::None, Action::Fail),
+            ),
+            AllowRw {
+                driver_num,
+                buffer_num,
+                return_error,
+            } => (
+                Pattern::AllowRw {
+                    driver_num: driver_num.into(),
+                    buffer_num: buffer_num.into(),
+                },
+                return_error.map_or(Action::None, Action::Fail),
+            ),
+            Memop {
+                memop_num,
+                argument0,
+                return_error,
+            } => (
+                Pattern::Memop {
+                    memop_num: memop_num.into(),
+                    argument0: Some(argument0.into()),
+                },
+                return_error.map_or(Action::None, Action::Fail),
+            ),
+            Exit {
+                exit_num,
+                completion_code,
+            } => (
+                Pattern::Exit {
+                    exit_num: exit_num.into(),
+                    completion_code: completion_code.into(),
+                },
+                Action::None,
+            ),
+        };
+        Expectation::call(pattern, action)
+    }
+}
+
+/// Assertions over a system call log, such as the log returned by
+/// `fake::Kernel::take_syscall_log`.
+pub trait SyscallLogAssertions {
+    /// Asserts that every buffer shared with a nonzero length was un-allowed
+    /// (by sharing a zero-length buffer with the same number) later in the
+    /// log, as the APIs must do before returning.
+    fn assert_buffers_unallowed(&self);
+
+    /// Asserts that the log matches `expectations`, in order, and that every
+    /// expectation was met.
+    fn assert_matches<I: IntoIterator<Item = Expectation>>(&self, expectations: I);
+}
+
+impl SyscallLogAssertions for [SyscallLogEntry] {
+    fn assert_buffers_unallowed(&self) {
+        // Maps (read-write, driver number, buffer number) to the index of the
+        // log entry that shared a nonempty buffer, if it is still shared.
+        let mut shared = HashMap::new();
+        for (index, entry) in self.iter().enumerate() {
+            let (key, len) = match *entry {
+                SyscallLogEntry::AllowRo {
+                    driver_num,
+                    buffer_num,
+                    len,
+                } => ((false, driver_num, buffer_num), len),
+                SyscallLogEntry::AllowRw {
+                    driver_num,
+                    buffer_num,
+                    len,
+                } => ((true, driver_num, buffer_num), len),
+                _ => continue,
+            };
+            match len {
+                0 => shared.remove(&key),
+                _ => shared.insert(key, index),
+            };
+        }
+        if let Some(index) = shared.into_values().min() {
+            panic!(
+                "Buffer shared by log entry {index} ({}) was never un-allowed",
+                self[index]
+            );
+        }
+    }
+
+    fn assert_matches<I: IntoIterator<Item = Expectation>>(&self, expectations: I) {
+        let mut queue: VecDeque<_> = expectations.into_iter().collect();
+        for (index, entry) in self.iter().enumerate() {
+            if let Err(mismatch) = take(&mut queue, entry) {
+                panic!("Log entry {index} does not match: {mismatch}");
+            }
+        }
+        assert_met(&queue);
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Implementation details below
+// -----------------------------------------------------------------------------
+
+#[derive(Clone, Debug)]
+enum Kind {
+    Call {
+        pattern: Pattern,
+        action: Action,
+    },
+    AnyYields,
+    // `pending` holds the items that have not been matched in the current
+    // repetition of the group.
+    Unordered {
+        items: Vec<Expectation>,
+        pending: Vec<Expectation>,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Pattern {
+    YieldNoWait,
+    YieldWait,
+    Subscribe {
+        driver_num: Arg,
+        subscribe_num: Arg,
+    },
+    Command {
+        driver_id: Arg,
+        command_id: Arg,
+        argument0: Arg,
+        argument1: Arg,
+    },
+    AllowRo {
+        driver_num: Arg,
+        buffer_num: Arg,
+    },
+    AllowRw {
+        driver_num: Arg,
+        buffer_num: Arg,
+    },
+    Memop {
+        memop_num: Arg,
+        // Memop's argument may be a pointer, so it is not an Arg.
+        argument0: Option<usize>,
+    },
+    Exit {
+        exit_num: Arg,
+        completion_code: Arg,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Action {
+    None,
+    YieldReturn(libtock_platform::YieldNoWaitReturn),
+    SkipUpcall,
+    Return(CommandReturn),
+    Fail(ErrorCode),
+}
+
+impl Expectation {
+    fn call(pattern: Pattern, action: Action) -> Expectation {
+        Expectation {
+            kind: Kind::Call { pattern, action },
+            times: 1,
+        }
+    }
+}
+
+impl Arg {
+    fn matches(self, value: u32) -> bool {
+        match self {
+            Arg::Any => true,
+            Arg::Is(expected) => expected == value,
+        }
+    }
+}
+
+impl Pattern {
+    fn matches(&self, call: &SyscallLogEntry) -> bool {
+        use SyscallLogEntry as Call;
+        match (*self, call) {
+            (Pattern::YieldNoWait, Call::YieldNoWait) => true,
+            (Pattern::YieldWait, Call::YieldWait) => true,
+            (
+                Pattern::Subscribe {
+                    driver_num,
+                    subscribe_num,
+                },
+                &Call::Subscribe {
+                    driver_num: actual_driver_num,
+                    subscribe_num: actual_subscribe_num,
+                },
+            ) => {
+                driver_num.matches(actual_driver_num) && subscribe_num.matches(actual_subscribe_num)
+            }
+            (
+                Pattern::Command {
+                    driver_id,
+                    command_id,
+                    argument0,
+                    argument1,
+                },
+                &Call::Command {
+                    driver_id: actual_driver_id,
+                    command_id: actual_command_id,
+                    argument0: actual_argument0,
+                    argument1: actual_argument1,
+                },
+            ) => {
+                driver_id.matches(actual_driver_id)
+                    && command_id.matches(actual_command_id)
+                    && argument0.matches(actual_argument0)
+                    && argument1.matches(actual_argument1)
+            }
+            (
+                Pattern::AllowRo {
+                    driver_num,
+                    buffer_num,
+                },
+                &Call::AllowRo {
+                    driver_num: actual_driver_num,
+                    buffer_num: actual_buffer_num,
+                    ..
+                },
+            )
+            | (
+                Pattern::AllowRw {
+                    driver_num,
+                    buffer_num,
+                },
+                &Call::AllowRw {
+                    driver_num: actual_driver_num,
+                    buffer_num: actual_buffer_num,
+                    ..
+                },
+            ) => driver_num.matches(actual_driver_num) && buffer_num.matches(actual_buffer_num),
+            (
+                Pattern::Memop {
+                    memop_num,
+                    argument0,
+                },
+                &Call::Memop {
+                    memop_num: actual_memop_num,
+                    argument0: actual_argument0,
+                },
+            ) => {
+                memop_num.matches(actual_memop_num)
+                    && argument0.is_none_or(|argument0| argument0 == actual_argument0.into())
+            }
+            (
+                Pattern::Exit {
+                    exit_num,
+                    completion_code,
+                },
+                &Call::Exit {
+                    exit_num: actual_exit_num,
+                    completion_code: actual_completion_code,
+                },
+            ) => {
+                exit_num.matches(actual_exit_num) && completion_code.matches(actual_completion_code)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Expectation {
+    fn matches(&self, call: &SyscallLogEntry) -> bool {
+        match &self.kind {
+            Kind::Call { pattern, .. } => pattern.matches(call),
+            _ => false,
+        }
+    }
+
+    // Returns the `ExpectedSyscall` that the system call implementation
+    // should act on for `call`, which this (single call) expectation matches.
+    fn resolve(&self, call: &SyscallLogEntry) -> ExpectedSyscall {
+        let Kind::Call { action, .. } = self.kind else {
+            unreachable!("resolve called on a group");
+        };
+        let error = match action {
+            Action::Fail(error) => Some(error),
+            _ => None,
+        };
+        match *call {
+            SyscallLogEntry::YieldNoWait => ExpectedSyscall::YieldNoWait {
+                override_return: match action {
+                    Action::YieldReturn(value) => Some(value),
+                    _ => None,
+                },
+            },
+            SyscallLogEntry::YieldWait => ExpectedSyscall::YieldWait {
+                skip_upcall: matches!(action, Action::SkipUpcall),
+            },
+            SyscallLogEntry::Subscribe {
+                driver_num,
+                subscribe_num,
+            } => ExpectedSyscall::Subscribe {
+                driver_num,
+                subscribe_num,
+                skip_with_error: error,
+            },
+            SyscallLogEntry::Command {
+                driver_id,
+                command_id,
+                argument0,
+                argument1,
+            } => ExpectedSyscall::Command {
+                driver_id,
+                command_id,
+                argument0,
+                argument1,
+                override_return: match action {
+                    Action::Return(value) => Some(value),
+                    _ => None,
+                },
+            },
+            SyscallLogEntry::AllowRo {
+                driver_num,
+                buffer_num,
+                ..
+            } => ExpectedSyscall::AllowRo {
+                driver_num,
+                buffer_num,
+                return_error: error,
+            },
+            SyscallLogEntry::AllowRw {
+                driver_num,
+                buffer_num,
+                ..
+            } => ExpectedSyscall::AllowRw {
+                driver_num,
+                buffer_num,
+                return_error: error,
+            },
+            SyscallLogEntry::Memop {
+                memop_num,
+                argument0,
+            } => ExpectedSyscall::Memop {
+                memop_num,
+                argument0,
+                return_error: error,
+            },
+            SyscallLogEntry::Exit {
+                exit_num,
+                completion_code,
+            } => ExpectedSyscall::Exit {
+                exit_num,
+                completion_code,
+            },
+        }
+    }
+}
+
+// Describes a system call that did not match the next expectation.
+struct Mismatch<'e> {
+    call: &'e SyscallLogEntry,
+    expected: &'e Expectation,
+}
+
+impl fmt::Display for Mismatch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "expected {}, but {} was called",
+            self.expected, self.call
+        )
+    }
+}
+
+// Matches `call` against the front of `queue`, consuming the expectations it
+// meets. Returns the action to take (None if `queue` is empty or the call is
+// part of a run of yields), or the expectation the call did not match.
+fn take<'e>(
+    queue: &'e mut VecDeque<Expectation>,
+    call: &'e SyscallLogEntry,
+) -> Result<Option<ExpectedSyscall>, Mismatch<'e>> {
+    while matches!(
+        queue.front(),
+        Some(Expectation {
+            kind: Kind::AnyYields,
+            ..
+        })
+    ) {
+        if matches!(
+            call,
+            SyscallLogEntry::YieldNoWait | SyscallLogEntry::YieldWait
+        ) {
+            return Ok(None);
+        }
+        queue.pop_front();
+    }
+    let Some(front) = queue.front_mut() else {
+        return Ok(None);
+    };
+    let resolved = match &mut front.kind {
+        Kind::Call { pattern, .. } => {
+            if !pattern.matches(call) {
+                return Err(Mismatch {
+                    call,
+                    expected: queue.front().unwrap(),
+                });
+            }
+            let resolved = front.resolve(call);
+            front.times -= 1;
+            resolved
+        }
+        Kind::Unordered { items, pending } => {
+            let Some(position) = pending.iter().position(|item| item.matches(call)) else {
+                return Err(Mismatch {
+                    call,
+                    expected: queue.front().unwrap(),
+                });
+            };
+            let resolved = pending[position].resolve(call);
+            pending[position].times -= 1;
+            if pending[position].times == 0 {
+                pending.remove(position);
+            }
+            if pending.is_empty() {
+                *pending = items.clone();
+                front.times -= 1;
+            }
+            resolved
+        }
+        Kind::AnyYields => unreachable!(),
+    };
+    if front.times == 0 {
+        queue.pop_front();
+    }
+    Ok(Some(resolved))
+}
+
+// Panics if `queue` contains expectations that have not been met.
+#[track_caller]
+fn assert_met(queue: &VecDeque<Expectation>) {
+    let unmet: Vec<_> = queue
+        .iter()
+        .filter(|expectation| !matches!(expectation.kind, Kind::AnyYields))
+        .map(ToString::to_string)
+        .collect();
+    assert!(
+        unmet.is_empty(),
+        "Expected system calls were not made: {}",
+        unmet.join(", ")
+    );
+}
+
+// Returns the expected syscall the system call just logged must match (and
+// whose action the system call implementation takes), from the exact queue
+// filled by `add_expected_syscall` or, once that is empty, the expectations
+// queued by `fake::Kernel::expect`. Panics if the call does not match.
+pub(crate) fn next_expected(kernel_data: &mut KernelData) -> Option<ExpectedSyscall> {
+    if let Some(expected) = kernel_data.expected_syscalls.pop_front() {
+        return Some(expected);
+    }
+    let call = kernel_data
+        .syscall_log
+        .last()
+        .expect("system call was not logged");
+    match take(&mut kernel_data.expectations, call) {
+        Ok(expected) => expected,
+        Err(mismatch) => panic!("Unexpected system call: {mismatch}"),
+    }
+}
+
+// Implements `fake::Kernel::assert_expectations_met`.
+#[track_caller]
+pub(crate) fn assert_expectations_met(kernel_data: &KernelData) {
+    let mut queue: VecDeque<Expectation> = kernel_data
+        .expected_syscalls
+        .iter()
+        .map(|&expected| expected.into())
+        .collect();
+    queue.extend(kernel_data.expectations.iter().cloned());
+    assert_met(&queue);
+}
+
+fn write_arg(f: &mut fmt::Formatter, name: &str, arg: Arg, hex: bool) -> fmt::Result {
+    match (arg, hex) {
+        (Arg::Any, _) => write!(f, "{name} *"),
+        (Arg::Is(value), true) => write!(f, "{name} {value:#x}"),
+        (Arg::Is(value), false) => write!(f, "{name} {value}"),
+    }
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            Kind::AnyYields => return write!(f, "any number of yields"),
+            Kind::Unordered { pending, .. } => {
+                write!(f, "one of {{")?;
+                for (index, item) in pending.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "}} (in any order)")?;
+            }
+            Kind::Call { pattern, action } => {
+                match *pattern {
+                    Pattern::YieldNoWait => write!(f, "yield-no-wait")?,
+                    Pattern::YieldWait => write!(f, "yield-wait")?,
+                    Pattern::Subscribe {
+                        driver_num,
+                        subscribe_num,
+                    } => {
+                        write!(f, "Subscribe(")?;
+                        write_arg(f, "driver", driver_num, true)?;
+                        write_arg(f, ", subscribe", subscribe_num, false)?;
+                        write!(f, ")")?;
+                    }
+                    Pattern::Command {
+                        driver_id,
+                        command_id,
+                        argument0,
+                        argument1,
+                    } => {
+                        write!(f, "Command(")?;
+                        write_arg(f, "driver", driver_id, true)?;
+                        write_arg(f, ", command", command_id, false)?;
+                        write_arg(f, ", argument0", argument0, false)?;
+                        write_arg(f, ", argument1", argument1, false)?;
+                        write!(f, ")")?;
+                    }
+                    Pattern::AllowRo {
+                        driver_num,
+                        buffer_num,
+                    }
+                    | Pattern::AllowRw {
+                        driver_num,
+                        buffer_num,
+                    } => {
+                        match pattern {
+                            Pattern::AllowRo { .. } => write!(f, "Read-Only Allow(")?,
+                            _ => write!(f, "Read-Write Allow(")?,
+                        }
+                        write_arg(f, "driver", driver_num, true)?;
+                        write_arg(f, ", buffer", buffer_num, false)?;
+                        write!(f, ")")?;
+                    }
+                    Pattern::Memop {
+                        memop_num,
+                        argument0,
+                    } => {
+                        write!(f, "Memop(")?;
+                        write_arg(f, "memop", memop_num, false)?;
+                        match argument0 {
+                            None => write!(f, ", argument0 *)")?,
+                            Some(argument0) => write!(f, ", argument0 {argument0:#x})")?,
+                        }
+                    }
+                    Pattern::Exit {
+                        exit_num,
+                        completion_code,
+                    } => {
+                        write!(f, "Exit(")?;
+                        write_arg(f, "exit_num", exit_num, false)?;
+                        write_arg(f, ", completion_code", completion_code, false)?;
+                        write!(f, ")")?;
+                    }
+                }
+                match action {
+                    Action::None => {}
+                    Action::YieldReturn(value) => write!(f, " returning {value:?}")?,
+                    Action::SkipUpcall => write!(f, " skipping the upcall")?,
+                    Action::Return(value) => write!(f, " returning {value:?}")?,
+                    Action::Fail(error) => write!(f, " failing with {error:?}")?,
+                }
+            }
+        }
+        if self.times > 1 {
+            write!(f, " ({} times)", self.times)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ExpectedSyscall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Expectation::from(*self).fmt(f)
+    }
+}
+
+impl fmt::Display for SyscallLogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SyscallLogEntry::YieldNoWait => write!(f, "yield-no-wait"),
+            SyscallLogEntry::YieldWait => write!(f, "yield-wait"),
+            SyscallLogEntry::Subscribe {
+                driver_num,
+                subscribe_num,
+            } => write!(
+                f,
+                "Subscribe(driver {driver_num:#x}, subscribe {subscribe_num})"
+            ),
+            SyscallLogEntry::Command {
+                driver_id,
+                command_id,
+                argument0,
+                argument1,
+            } => write!(
+                f,
+                "Command(driver {driver_id:#x}, command {command_id}, argument0 {argument0}, \
+                 argument1 {argument1})"
+            ),
+            SyscallLogEntry::AllowRo {
+                driver_num,
+                buffer_num,
+                len,
+            } => write!(
+                f,
+                "Read-Only Allow(driver {driver_num:#x}, buffer {buffer_num}, len {len})"
+            ),
+            SyscallLogEntry::AllowRw {
+                driver_num,
+                buffer_num,
+                len,
+            } => write!(
+                f,
+                "Read-Write Allow(driver {driver_num:#x}, buffer {buffer_num}, len {len})"
+            ),
+            SyscallLogEntry::Memop {
+                memop_num,
+                argument0,
+            } => write!(
+                f,
+                "Memop(memop {memop_num}, argument0 {:#x})",
+                usize::from(argument0)
+            ),
+            SyscallLogEntry::Exit {
+                exit_num,
+                completion_code,
+            } => write!(
+                f,
+                "Exit(exit_num {exit_num}, completion_code {completion_code})"
+            ),
+        }
+    }
+}
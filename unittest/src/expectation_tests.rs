@@ -0,0 +1,180 @@
+//! Unit test cases for `Expectation` and `SyscallLogAssertions`.
+
+use crate::{command_return, fake, Arg, Expectation, ExpectedSyscall, SyscallLogAssertions};
+use libtock_platform::{share, AllowRo, DefaultConfig, ErrorCode, Syscalls};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+// Runs `f` and returns the message it panicked with.
+fn panic_message<F: FnOnce()>(f: F) -> String {
+    catch_unwind(AssertUnwindSafe(f))
+        .expect_err("no panic")
+        .downcast_ref::<String>()
+        .expect("wrong panic payload type")
+        .clone()
+}
+
+#[test]
+fn wildcards() {
+    let kernel = fake::Kernel::new();
+    kernel.expect(Expectation::command(DRIVER_NUM, Arg::Any, 3, Arg::Any));
+    kernel.expect(Expectation::command(Arg::Any, 2, Arg::Any, 4));
+    let _ = fake::Syscalls::command(DRIVER_NUM, 1, 3, 9);
+    let _ = fake::Syscalls::command(0x5, 2, 0, 4);
+    kernel.assert_expectations_met();
+}
+
+#[test]
+fn any_yields() {
+    let kernel = fake::Kernel::new();
+    kernel.expect(Expectation::command(DRIVER_NUM, 1, 0, 0));
+    kernel.expect(Expectation::any_yields());
+    kernel.expect(Expectation::command(DRIVER_NUM, 2, 0, 0));
+    kernel.expect(Expectation::any_yields());
+    let _ = fake::Syscalls::command(DRIVER_NUM, 1, 0, 0);
+    for _ in 0..3 {
+        fake::Syscalls::yield_no_wait();
+    }
+    // The second run of yields matches zero yields.
+    let _ = fake::Syscalls::command(DRIVER_NUM, 2, 0, 0);
+    kernel.assert_expectations_met();
+}
+
+#[test]
+fn unordered_and_times() {
+    let kernel = fake::Kernel::new();
+    kernel.expect(
+        Expectation::unordered([
+            Expectation::command(DRIVER_NUM, 1, Arg::Any, 0),
+            Expectation::command(DRIVER_NUM, 2, 0, 0).times(2),
+        ])
+        .times(2),
+    );
+    kernel.expect(Expectation::yield_no_wait().times(2));
+    for command_id in [2, 1, 2, 2, 2, 1] {
+        let _ = fake::Syscalls::command(DRIVER_NUM, command_id, 0, 0);
+    }
+    fake::Syscalls::yield_no_wait();
+    assert_eq!(
+        panic_message(|| kernel.assert_expectations_met()),
+        "Expected system calls were not made: yield-no-wait"
+    );
+    fake::Syscalls::yield_no_wait();
+    kernel.assert_expectations_met();
+}
+
+#[test]
+fn mismatch_messages() {
+    let kernel = fake::Kernel::new();
+    kernel.expect(Expectation::command(DRIVER_NUM, 1, Arg::Any, 0));
+    assert_eq!(
+        panic_message(|| {
+            let _ = fake::Syscalls::command(DRIVER_NUM, 2, 3, 4);
+        }),
+        "Unexpected system call: expected Command(driver 0x99999, command 1, argument0 *, \
+         argument1 0), but Command(driver 0x99999, command 2, argument0 3, argument1 4) was called"
+    );
+    drop(kernel);
+
+    let kernel = fake::Kernel::new();
+    kernel.expect(Expectation::unordered([
+        Expectation::yield_wait(),
+        Expectation::memop(1),
+    ]));
+    assert_eq!(
+        panic_message(|| {
+            fake::Syscalls::yield_no_wait();
+        }),
+        "Unexpected system call: expected one of {yield-wait, Memop(memop 1, argument0 *)} \
+         (in any order), but yield-no-wait was called"
+    );
+    drop(kernel);
+
+    // ExpectedSyscall's mismatch message uses Display as well.
+    let kernel = fake::Kernel::new();
+    kernel.add_expected_syscall(ExpectedSyscall::Exit {
+        exit_num: 0,
+        completion_code: 1,
+    });
+    assert_eq!(
+        panic_message(|| {
+            fake::Syscalls::yield_no_wait();
+        }),
+        "Expected system call Exit(exit_num 0, completion_code 1), but yield-no-wait was called \
+         instead."
+    );
+}
+
+// Expectations can override the result of the calls they match, and are
+// matched after the ExpectedSyscalls queued before them.
+#[test]
+fn overrides_and_ordering() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::DriverBuilder::new(DRIVER_NUM, ())
+        .allow_ro(0, "input")
+        .build();
+    kernel.add_driver(&driver);
+    kernel.add_expected_syscall(ExpectedSyscall::Command {
+        driver_id: DRIVER_NUM,
+        command_id: 0,
+        argument0: 0,
+        argument1: 0,
+        override_return: None,
+    });
+    kernel.expect(
+        Expectation::command(DRIVER_NUM, 1, Arg::Any, Arg::Any)
+            .returning(command_return::success_u32(5)),
+    );
+    kernel.expect(Expectation::allow_ro(DRIVER_NUM, Arg::Any).failing(ErrorCode::Busy));
+    // The share::scope un-allows the buffer when it returns.
+    kernel.expect(Expectation::allow_ro(DRIVER_NUM, 0));
+    // Queued after an Expectation, so it is matched after it.
+    kernel.add_expected_syscall(ExpectedSyscall::YieldNoWait {
+        override_return: None,
+    });
+
+    assert!(fake::Syscalls::command(DRIVER_NUM, 0, 0, 0).is_success());
+    assert_eq!(
+        fake::Syscalls::command(DRIVER_NUM, 1, 0, 0).get_success_u32(),
+        Some(5)
+    );
+    share::scope::<AllowRo<_, DRIVER_NUM, 0>, _, _>(|allow_ro| {
+        assert_eq!(
+            fake::Syscalls::allow_ro::<DefaultConfig, DRIVER_NUM, 0>(allow_ro, b"data"),
+            Err(ErrorCode::Busy)
+        );
+    });
+    fake::Syscalls::yield_no_wait();
+    kernel.assert_expectations_met();
+}
+
+#[test]
+fn log_assertions() {
+    let kernel = fake::Kernel::new();
+    let driver = fake::DriverBuilder::new(DRIVER_NUM, ())
+        .allow_ro(0, "input")
+        .build();
+    kernel.add_driver(&driver);
+    share::scope::<AllowRo<_, DRIVER_NUM, 0>, _, _>(|allow_ro| {
+        fake::Syscalls::allow_ro::<DefaultConfig, DRIVER_NUM, 0>(allow_ro, b"data").unwrap();
+        let _ = fake::Syscalls::command(DRIVER_NUM, 1, 0, 0);
+    });
+    let log = kernel.take_syscall_log();
+    log.assert_buffers_unallowed();
+    log.assert_matches([
+        Expectation::allow_ro(DRIVER_NUM, 0),
+        Expectation::command(DRIVER_NUM, 1, Arg::Any, Arg::Any),
+        Expectation::allow_ro(DRIVER_NUM, 0),
+    ]);
+    assert_eq!(
+        panic_message(|| log.assert_matches([Expectation::allow_ro(DRIVER_NUM, 0).times(3)])),
+        "Log entry 1 does not match: expected Read-Only Allow(driver 0x99999, buffer 0) (2 \
+         times), but Command(driver 0x99999, command 1, argument0 0, argument1 0) was called"
+    );
+    assert_eq!(
+        panic_message(|| log[..1].assert_buffers_unallowed()),
+        "Buffer shared by log entry 0 (Read-Only Allow(driver 0x99999, buffer 0, len 4)) was \
+         never un-allowed"
+    );
+}
+
+const DRIVER_NUM: u32 = 0x99999;
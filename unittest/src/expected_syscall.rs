@@ -84,7 +84,17 @@ pub enum ExpectedSyscall {
         // invoked and the provided error will be returned instead.
         return_error: Option<libtock_platform::ErrorCode>,
     },
-    // TODO: Add Exit.
+
+    // -------------------------------------------------------------------------
+    // Exit
+    // -------------------------------------------------------------------------
+    Exit {
+        // Matched values: the Exit call must give the specified exit_num and
+        // completion_code. Exit does not return, so there is nothing to
+        // override.
+        exit_num: u32,
+        completion_code: u32,
+    },
 }
 
 impl ExpectedSyscall {
@@ -92,7 +102,6 @@ impl ExpectedSyscall {
     // instead of the expected system call. Used by fake::Kernel to report
     // incorrect system calls.
     pub(crate) fn panic_wrong_call(&self, called: &str) -> ! {
-        // TODO: Implement Display for ExpectedSyscall and replace {:?} with {}
-        panic!("Expected system call {self:?}, but {called} was called instead.");
+        panic!("Expected system call {self}, but {called} was called instead.");
     }
 }
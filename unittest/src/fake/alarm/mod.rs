@@ -1,17 +1,49 @@
-//! Fake implementation of the Alarm API.
+//! Fake implementation of the Alarm API, documented here:
+//! https://github.com/tock/tock/blob/master/doc/syscalls/00000_alarm.md
 //!
-//! Supports frequency and set_relative.
-//! Will schedule the upcall immediately.
+//! `Alarm` keeps a virtual clock: a 32-bit tick counter that wraps around,
+//! like the hardware counters backing the real capsule. The clock only moves
+//! when the test calls `advance`, or when the process calls yield-wait with no
+//! upcall queued, in which case the clock skips ahead to the armed alarm's
+//! expiration or the next `fake::Timeline` event, whichever comes first (as if
+//! the process had slept until the next interrupt).
+//!
+//! Like the real capsule, `Alarm` supports one armed alarm at a time. The time
+//! the process spent sleeping can be inspected with `take_sleeps` and
+//! `elapsed`.
+//!
+//! The clock also drives `fake::Timeline`: events scheduled on a timeline run
+//! in virtual-time order, interleaved with the alarm's expirations, as the
+//! clock advances.
 
-use core::cell::Cell;
+use core::cell::{Cell, RefCell};
 use core::num::Wrapping;
+use core::time::Duration;
 use libtock_platform::{CommandReturn, ErrorCode};
+use std::collections::BTreeMap;
 
 use crate::{DriverInfo, DriverShareRef};
 
+// An event scheduled on the clock by `fake::Timeline` or
+// `fake::ieee802154::Medium`.
+type Event = Box<dyn FnOnce()>;
+
 pub struct Alarm {
     frequency_hz: u32,
     now: Cell<Wrapping<u32>>,
+    // Total number of ticks the clock has advanced since the Alarm was
+    // created. Unlike `now`, this does not wrap around.
+    elapsed_ticks: Cell<u64>,
+    // The (reference, dt) pair of the armed alarm, which expires once `dt`
+    // ticks have passed since `reference`.
+    armed: Cell<Option<(u32, u32)>>,
+    // The length of each sleep (in ticks) skipped over by `idle`.
+    sleeps: RefCell<Vec<u64>>,
+    // Events scheduled by `fake::Timeline`, keyed by the value of
+    // `elapsed_ticks` at which they run and then by the order they were
+    // scheduled in.
+    events: RefCell<BTreeMap<(u64, u64), Event>>,
+    next_event_id: Cell<u64>,
     share_ref: DriverShareRef,
 }
 
@@ -20,9 +52,161 @@ impl Alarm {
         std::rc::Rc::new(Alarm {
             frequency_hz,
             now: Cell::new(Wrapping(0)),
+            elapsed_ticks: Cell::new(0),
+            armed: Cell::new(None),
+            sleeps: Default::default(),
+            events: Default::default(),
+            next_event_id: Cell::new(0),
             share_ref: Default::default(),
         })
     }
+
+    /// Returns the current value of the tick counter.
+    pub fn now(&self) -> u32 {
+        self.now.get().0
+    }
+
+    /// Sets the tick counter, without firing the armed alarm (if any). Useful
+    /// for starting a test close to the point where the counter wraps around.
+    pub fn set_time(&self, ticks: u32) {
+        self.now.set(Wrapping(ticks));
+    }
+
+    /// Returns the expiration time (in ticks) of the armed alarm, or `None` if
+    /// no alarm is armed.
+    pub fn expiration(&self) -> Option<u32> {
+        self.armed
+            .get()
+            .map(|(reference, dt)| reference.wrapping_add(dt))
+    }
+
+    /// Advances the clock by `ticks`, firing the armed alarm and running
+    /// timeline events that come due in that time, in order.
+    pub fn advance(&self, ticks: u32) {
+        self.advance_to(self.elapsed_ticks.get() + ticks as u64);
+    }
+
+    /// Returns the number of ticks the clock has advanced since this `Alarm`
+    /// was created. Unlike `now`, this does not wrap around.
+    pub fn elapsed_ticks(&self) -> u64 {
+        self.elapsed_ticks.get()
+    }
+
+    /// Returns the total time the clock has advanced since this `Alarm` was
+    /// created, whether through `advance` or by the process sleeping.
+    pub fn elapsed(&self) -> Duration {
+        self.ticks_to_duration(self.elapsed_ticks.get())
+    }
+
+    /// Returns the length (in ticks) of each sleep the process performed since
+    /// the last call to `take_sleeps`, and clears them. A sleep is a
+    /// yield-wait call that had to wait for the clock to reach the armed
+    /// alarm's expiration or a scheduled `fake::Timeline` event.
+    pub fn take_sleeps(&self) -> Vec<u64> {
+        self.sleeps.take()
+    }
+
+    /// Converts a number of ticks of this alarm's clock into a `Duration`.
+    pub fn ticks_to_duration(&self, ticks: u64) -> Duration {
+        let nanos = ticks as u128 * 1_000_000_000 / self.frequency_hz as u128;
+        Duration::from_nanos(nanos as u64)
+    }
+
+    /// Converts a `Duration` into a number of ticks of this alarm's clock,
+    /// rounding up.
+    pub fn duration_to_ticks(&self, duration: Duration) -> u64 {
+        let ticks = (duration.as_nanos() * self.frequency_hz as u128).div_ceil(1_000_000_000);
+        ticks as u64
+    }
+
+    // Schedules `event` to run when `elapsed_ticks` reaches `at`. Used by
+    // `fake::Timeline` and `fake::ieee802154::Medium`.
+    pub(crate) fn schedule_event(&self, at: u64, event: Event) {
+        let id = self.next_event_id.get();
+        self.next_event_id.set(id + 1);
+        self.events.borrow_mut().insert((at, id), event);
+    }
+
+    // Returns the number of scheduled events that have not run yet.
+    pub(crate) fn pending_events(&self) -> usize {
+        self.events.borrow().len()
+    }
+
+    // Returns the elapsed tick count at which the clock will next do
+    // something: either fire the armed alarm or run a scheduled event.
+    fn next_deadline(&self) -> Option<u64> {
+        let elapsed = self.elapsed_ticks.get();
+        let alarm = self.remaining().map(|remaining| elapsed + remaining as u64);
+        let event = self.events.borrow().keys().next().map(|&(at, _)| at);
+        match (alarm, event) {
+            (Some(alarm), Some(event)) => Some(alarm.min(event)),
+            (alarm, event) => alarm.or(event),
+        }
+    }
+
+    // Advances the clock until `elapsed_ticks` reaches `target`, handling
+    // deadlines on the way. When the alarm and an event are due at the same
+    // time, the alarm fires first.
+    pub(crate) fn advance_to(&self, target: u64) {
+        while let Some(deadline) = self.next_deadline().filter(|&deadline| deadline <= target) {
+            self.tick_to(deadline);
+            if self.remaining() == Some(0) {
+                self.fire();
+            }
+            // Events may schedule further events, so the map must not be
+            // borrowed while an event runs.
+            let due = {
+                let mut events = self.events.borrow_mut();
+                match events.first_entry() {
+                    Some(entry) if entry.key().0 <= deadline => Some(entry.remove()),
+                    _ => None,
+                }
+            };
+            if let Some(event) = due {
+                event();
+            }
+        }
+        self.tick_to(target);
+    }
+
+    // Returns the number of ticks until the armed alarm expires, or None if no
+    // alarm is armed.
+    fn remaining(&self) -> Option<u32> {
+        self.armed.get().map(|(reference, dt)| {
+            let passed = self.now().wrapping_sub(reference);
+            dt.saturating_sub(passed)
+        })
+    }
+
+    // Moves the clock forward to the given elapsed tick count. Does nothing if
+    // the clock is already there or past it.
+    fn tick_to(&self, elapsed_ticks: u64) {
+        let elapsed = self.elapsed_ticks.get();
+        if elapsed_ticks > elapsed {
+            self.now
+                .set(self.now.get() + Wrapping((elapsed_ticks - elapsed) as u32));
+            self.elapsed_ticks.set(elapsed_ticks);
+        }
+    }
+
+    // Disarms the alarm and queues its upcall.
+    fn fire(&self) {
+        if let Some(expiration) = self.expiration() {
+            self.armed.set(None);
+            self.share_ref
+                .schedule_upcall(subscribe::CALLBACK, (self.now(), expiration, 0))
+                .expect("schedule_upcall failed");
+        }
+    }
+
+    fn arm(&self, reference: u32, dt: u32) -> CommandReturn {
+        self.armed.set(Some((reference, dt)));
+        // An alarm that has already expired fires right away.
+        if self.remaining() == Some(0) {
+            self.fire();
+        }
+        crate::command_return::success_u32(reference.wrapping_add(dt))
+    }
 }
 
 impl crate::fake::SyscallDriver for Alarm {
@@ -34,25 +218,33 @@ impl crate::fake::SyscallDriver for Alarm {
         self.share_ref.replace(share_ref);
     }
 
-    fn command(&self, command_number: u32, argument0: u32, _argument1: u32) -> CommandReturn {
+    fn command(&self, command_number: u32, argument0: u32, argument1: u32) -> CommandReturn {
         match command_number {
+            command::EXISTS => crate::command_return::success(),
             command::FREQUENCY => crate::command_return::success_u32(self.frequency_hz),
-            command::SET_RELATIVE => {
-                // We're not actually sleeping, just ticking the timer.
-                // The semantics of sleeping aren't clear,
-                // so we're assuming that all future times are equal,
-                // and waking immediately.
-                let relative = argument0;
-                let wake = self.now.get() + Wrapping(relative);
-                self.share_ref
-                    .schedule_upcall(subscribe::CALLBACK, (wake.0, 0, 0))
-                    .expect("schedule_upcall failed");
-                self.now.set(wake);
-                crate::command_return::success_u32(wake.0)
-            }
+            command::TIME => crate::command_return::success_u32(self.now()),
+            command::STOP => match self.armed.take() {
+                Some(_) => crate::command_return::success(),
+                None => crate::command_return::failure(ErrorCode::Already),
+            },
+            command::SET_RELATIVE => self.arm(self.now(), argument0),
+            command::SET_ABSOLUTE => self.arm(argument0, argument1),
             _ => crate::command_return::failure(ErrorCode::NoSupport),
         }
     }
+
+    fn idle(&self) -> bool {
+        let deadline = match self.next_deadline() {
+            Some(deadline) => deadline,
+            None => return false,
+        };
+        let elapsed = self.elapsed_ticks.get();
+        self.sleeps
+            .borrow_mut()
+            .push(deadline.saturating_sub(elapsed));
+        self.advance_to(deadline);
+        true
+    }
 }
 
 #[cfg(test)]
@@ -1,5 +1,7 @@
 use crate::fake;
+use core::time::Duration;
 use fake::alarm::*;
+use libtock_platform::{share, DefaultConfig, ErrorCode, YieldNoWaitReturn};
 
 // Tests the command implementation.
 #[test]
@@ -7,8 +9,117 @@ fn command() {
     use fake::SyscallDriver;
     let alarm = Alarm::new(10);
 
+    assert!(alarm.command(command::EXISTS, 0, 0).is_success());
     assert_eq!(
         alarm.command(command::FREQUENCY, 1, 2).get_success_u32(),
         Some(10)
     );
+    assert_eq!(
+        alarm.command(command::TIME, 0, 0).get_success_u32(),
+        Some(0)
+    );
+    alarm.advance(7);
+    assert_eq!(
+        alarm.command(command::TIME, 0, 0).get_success_u32(),
+        Some(7)
+    );
+
+    assert_eq!(
+        alarm.command(command::STOP, 0, 0).get_failure(),
+        Some(ErrorCode::Already)
+    );
+    assert_eq!(
+        alarm.command(command::SET_RELATIVE, 5, 0).get_success_u32(),
+        Some(12)
+    );
+    assert_eq!(alarm.expiration(), Some(12));
+    assert!(alarm.command(command::STOP, 0, 0).is_success());
+    assert_eq!(alarm.expiration(), None);
+
+    assert_eq!(
+        alarm
+            .command(command::SET_ABSOLUTE, 5, 10)
+            .get_success_u32(),
+        Some(15)
+    );
+    assert_eq!(alarm.expiration(), Some(15));
+}
+
+// Integration test that verifies Alarm works with fake::Kernel and
+// libtock_platform::Syscalls.
+#[test]
+fn kernel_integration() {
+    use libtock_platform::Syscalls;
+    let kernel = fake::Kernel::new();
+    let alarm = Alarm::new(1000);
+    kernel.add_driver(&alarm);
+
+    let fired = core::cell::Cell::<Option<(u32, u32)>>::new(None);
+    share::scope(|subscribe| {
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, { subscribe::CALLBACK }>(
+            subscribe, &fired,
+        )
+        .unwrap();
+
+        // The alarm does not fire until its expiration time is reached.
+        assert!(
+            fake::Syscalls::command(DRIVER_NUM, command::SET_RELATIVE, 100, 0).is_success_u32()
+        );
+        alarm.advance(99);
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+        alarm.advance(5);
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(fired.take(), Some((100, 100)));
+
+        // Yield-wait sleeps until the alarm expires.
+        assert!(fake::Syscalls::command(DRIVER_NUM, command::SET_RELATIVE, 50, 0).is_success_u32());
+        fake::Syscalls::yield_wait();
+        assert_eq!(fired.take(), Some((154, 154)));
+        assert_eq!(alarm.take_sleeps(), [50]);
+
+        // Alarms that have already expired fire immediately.
+        assert!(
+            fake::Syscalls::command(DRIVER_NUM, command::SET_ABSOLUTE, 100, 10).is_success_u32()
+        );
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(fired.take(), Some((154, 110)));
+    });
+    assert_eq!(alarm.elapsed(), Duration::from_millis(154));
+}
+
+#[test]
+fn wraparound() {
+    use libtock_platform::Syscalls;
+    let kernel = fake::Kernel::new();
+    let alarm = Alarm::new(1000);
+    kernel.add_driver(&alarm);
+    alarm.set_time(u32::MAX - 10);
+
+    let fired = core::cell::Cell::<Option<(u32, u32)>>::new(None);
+    share::scope(|subscribe| {
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, { subscribe::CALLBACK }>(
+            subscribe, &fired,
+        )
+        .unwrap();
+        assert_eq!(
+            fake::Syscalls::command(DRIVER_NUM, command::SET_RELATIVE, 20, 0).get_success_u32(),
+            Some(9)
+        );
+        alarm.advance(15);
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+        fake::Syscalls::yield_wait();
+        assert_eq!(fired.take(), Some((9, 9)));
+    });
+    assert_eq!(alarm.take_sleeps(), [5]);
+    assert_eq!(alarm.now(), 9);
+    assert_eq!(alarm.elapsed(), Duration::from_millis(20));
+}
+
+#[test]
+fn idle_without_alarm() {
+    use fake::SyscallDriver;
+    let alarm = Alarm::new(1000);
+    assert!(!alarm.idle());
+    alarm.advance(10);
+    assert!(alarm.take_sleeps().is_empty());
 }
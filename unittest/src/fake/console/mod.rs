@@ -4,10 +4,20 @@
 //! Like the real API, `Console` stores each message written to it.
 //! The resulting byte stream can be retrieved via `take_bytes`
 //! for use in unit tests.
+//!
+//! Input can be scripted for interactive apps. `push_input` makes bytes
+//! available immediately (and is suitable for use in `fake::Timeline` events),
+//! `queue_input` queues chunks that arrive one at a time as the app reads, and
+//! `respond_to` sends input when the app writes a given byte sequence (such as
+//! a prompt). A read with no input available stays pending until input
+//! arrives or the app aborts it, as it does on a real board. A read completes
+//! with the bytes that are available when it is serviced, which may be fewer
+//! than were requested.
 
 use core::cell::{Cell, RefCell};
 use core::cmp;
 use libtock_platform::{CommandReturn, ErrorCode};
+use std::collections::VecDeque;
 
 use crate::{DriverInfo, DriverShareRef, RoAllowBuffer, RwAllowBuffer};
 
@@ -16,8 +26,19 @@ pub struct Console {
     buffer: Cell<RoAllowBuffer>,
 
     read_buffer: RefCell<RwAllowBuffer>,
-    /// To be returned on read
-    input: Cell<Vec<u8>>,
+    /// Input that has arrived and will be returned by the next read.
+    input: RefCell<VecDeque<u8>>,
+    /// Input chunks that arrive one at a time, when a read finds no input.
+    queued_input: RefCell<VecDeque<Vec<u8>>>,
+    /// Output-triggered input: each entry's response is sent once the app
+    /// writes the entry's trigger. Entries are matched in order.
+    responses: RefCell<VecDeque<(Vec<u8>, Vec<u8>)>>,
+    /// Output written since the last response was triggered.
+    unmatched_output: RefCell<Vec<u8>>,
+    /// The length of the pending read, if one is in progress.
+    pending_read: Cell<Option<usize>>,
+    /// The kernel's receive buffer size. Reads longer than this fail.
+    read_limit: Cell<Option<usize>>,
 
     share_ref: DriverShareRef,
 }
@@ -32,7 +53,12 @@ impl Console {
             messages: Default::default(),
             buffer: Default::default(),
             read_buffer: Default::default(),
-            input: Cell::new(Vec::from(inputs)),
+            input: RefCell::new(inputs.iter().copied().collect()),
+            queued_input: Default::default(),
+            responses: Default::default(),
+            unmatched_output: Default::default(),
+            pending_read: Cell::new(None),
+            read_limit: Cell::new(None),
             share_ref: Default::default(),
         })
     }
@@ -42,6 +68,100 @@ impl Console {
     pub fn take_bytes(&self) -> Vec<u8> {
         self.messages.take()
     }
+
+    /// Makes `bytes` available to the app immediately, completing the pending
+    /// read if there is one.
+    pub fn push_input(&self, bytes: &[u8]) {
+        self.input.borrow_mut().extend(bytes);
+        self.service_read();
+    }
+
+    /// Queues a chunk of input. Queued chunks arrive one at a time: the next
+    /// chunk arrives when the app reads and all earlier input has been
+    /// consumed.
+    pub fn queue_input(&self, bytes: &[u8]) {
+        self.queued_input.borrow_mut().push_back(Vec::from(bytes));
+    }
+
+    /// Sends `response` as input once the app writes `trigger` (for example a
+    /// shell prompt). Each registered response is sent once; responses are
+    /// matched in the order they were registered, each against output written
+    /// after the previous one was sent.
+    pub fn respond_to(&self, trigger: &[u8], response: &[u8]) {
+        self.responses
+            .borrow_mut()
+            .push_back((Vec::from(trigger), Vec::from(response)));
+    }
+
+    /// Limits reads to `limit` bytes, like the kernel's receive buffer. Reads
+    /// of more than `limit` bytes fail with `SIZE`. There is no limit by
+    /// default.
+    pub fn set_read_limit(&self, limit: usize) {
+        self.read_limit.set(Some(limit));
+    }
+
+    /// Returns `true` if the app has a read in progress that is waiting for
+    /// input.
+    pub fn is_reading(&self) -> bool {
+        self.pending_read.get().is_some()
+    }
+
+    /// Returns the number of bytes of input that have arrived but not yet been
+    /// read, not counting chunks queued with `queue_input` that have not
+    /// arrived yet.
+    pub fn pending_input(&self) -> usize {
+        self.input.borrow().len()
+    }
+
+    /// Returns the number of `queue_input` chunks and `respond_to` responses
+    /// that have not been delivered yet.
+    pub fn remaining_script(&self) -> usize {
+        self.queued_input.borrow().len() + self.responses.borrow().len()
+    }
+
+    // Completes the pending read if there is input for it, delivering the next
+    // queued chunk if no input has arrived.
+    fn service_read(&self) {
+        let Some(len) = self.pending_read.get() else {
+            return;
+        };
+        if self.input.borrow().is_empty() {
+            match self.queued_input.borrow_mut().pop_front() {
+                Some(chunk) => self.input.borrow_mut().extend(chunk),
+                None => return,
+            }
+        }
+        let mut input = self.input.borrow_mut();
+        let mut read_buffer = self.read_buffer.borrow_mut();
+        let count = cmp::min(cmp::min(len, read_buffer.len()), input.len());
+        for (dest, byte) in read_buffer.iter_mut().zip(input.drain(..count)) {
+            *dest = byte;
+        }
+        self.pending_read.set(None);
+        self.share_ref
+            .schedule_upcall(SUBSCRIBE_READ, (0, count as u32, 0))
+            .expect("Unable to schedule upcall {}");
+    }
+
+    // Records written output and sends the responses it triggers.
+    fn check_responses(&self, written: &[u8]) {
+        let mut output = self.unmatched_output.borrow_mut();
+        output.extend_from_slice(written);
+        loop {
+            let mut responses = self.responses.borrow_mut();
+            let Some((trigger, _)) = responses.front() else {
+                output.clear();
+                return;
+            };
+            let Some(position) = find(&output, trigger) else {
+                return;
+            };
+            output.drain(..position + trigger.len());
+            let (_, response) = responses.pop_front().unwrap();
+            drop(responses);
+            self.input.borrow_mut().extend(response);
+        }
+    }
 }
 
 impl crate::fake::SyscallDriver for Console {
@@ -85,25 +205,33 @@ impl crate::fake::SyscallDriver for Console {
                 let buffer = self.buffer.take();
                 let size = cmp::min(buffer.len(), argument0 as usize);
                 bytes.extend_from_slice(&(*buffer)[..size]);
+                self.check_responses(&(*buffer)[..size]);
                 self.buffer.set(buffer);
                 self.messages.set(bytes);
                 self.share_ref
                     .schedule_upcall(SUBSCRIBE_WRITE, (size as u32, 0, 0))
                     .expect("Unable to schedule upcall {}");
+                // The response to a prompt may complete a read that was
+                // started before the prompt was written.
+                self.service_read();
             }
             READ => {
-                let count_wanted = argument0 as usize;
-                let bytes = self.input.take();
-                let count_wanted = cmp::min(count_wanted, bytes.len());
-                let to_send = &bytes[..count_wanted];
-                let to_keep = &bytes[count_wanted..];
-                self.input.set(Vec::from(to_keep));
-
-                let count_available = to_send.len();
-                self.read_buffer.borrow_mut()[..count_wanted].copy_from_slice(to_send);
-                self.share_ref
-                    .schedule_upcall(SUBSCRIBE_READ, (0, count_available as u32, 0))
-                    .expect("Unable to schedule upcall {}");
+                if self.pending_read.get().is_some() {
+                    return crate::command_return::failure(ErrorCode::Busy);
+                }
+                let len = argument0 as usize;
+                if self.read_limit.get().is_some_and(|limit| len > limit) {
+                    return crate::command_return::failure(ErrorCode::Size);
+                }
+                self.pending_read.set(Some(len));
+                self.service_read();
+            }
+            ABORT => {
+                if self.pending_read.take().is_some() {
+                    self.share_ref
+                        .schedule_upcall(SUBSCRIBE_READ, (ErrorCode::Cancel as u32, 0, 0))
+                        .expect("Unable to schedule upcall {}");
+                }
             }
             _ => return crate::command_return::failure(ErrorCode::NoSupport),
         }
@@ -115,6 +243,16 @@ impl crate::fake::SyscallDriver for Console {
 // Implementation details below
 // -----------------------------------------------------------------------------
 
+// Returns the position of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(0);
+    }
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
 #[cfg(test)]
 mod tests;
 
@@ -124,7 +262,7 @@ const DRIVER_NUM: u32 = 0x1;
 const EXISTS: u32 = 0;
 const WRITE: u32 = 1;
 const READ: u32 = 2;
-//const ABORT: u32 = 3;
+const ABORT: u32 = 3;
 const SUBSCRIBE_WRITE: u32 = 1;
 const SUBSCRIBE_READ: u32 = 2;
 const ALLOW_WRITE: u32 = 1;
@@ -1,7 +1,9 @@
 use crate::fake;
 use crate::{RoAllowBuffer, RwAllowBuffer};
+use fake::console::{ABORT, ALLOW_READ, ALLOW_WRITE, DRIVER_NUM, READ, SUBSCRIBE_READ, WRITE};
 use libtock_platform::share;
 use libtock_platform::DefaultConfig;
+use libtock_platform::{AllowRw, ErrorCode, Subscribe, Syscalls};
 
 // Tests the command implementation.
 #[test]
@@ -60,3 +62,146 @@ fn kernel_integration() {
         );
     });
 }
+
+// Writes `bytes` through the kernel.
+fn write(bytes: &[u8]) {
+    share::scope(|allow_ro| {
+        fake::Syscalls::allow_ro::<DefaultConfig, DRIVER_NUM, ALLOW_WRITE>(allow_ro, bytes)
+            .unwrap();
+        fake::Syscalls::command(DRIVER_NUM, WRITE, bytes.len() as u32, 0)
+            .to_result::<(), ErrorCode>()
+            .unwrap();
+    });
+}
+
+// Starts a read of `len` bytes into `buf`, calls `before_yield`, then yields
+// once. Returns the read upcall's arguments, if the upcall was delivered.
+fn read(buf: &mut [u8], len: u32, before_yield: impl FnOnce()) -> Option<(u32, u32, u32)> {
+    let called = core::cell::Cell::new(None);
+    share::scope::<
+        (
+            AllowRw<_, DRIVER_NUM, ALLOW_READ>,
+            Subscribe<_, DRIVER_NUM, SUBSCRIBE_READ>,
+        ),
+        _,
+        _,
+    >(|handle| {
+        let (allow_rw, subscribe) = handle.split();
+        fake::Syscalls::allow_rw::<DefaultConfig, DRIVER_NUM, ALLOW_READ>(allow_rw, buf).unwrap();
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, SUBSCRIBE_READ>(
+            subscribe, &called,
+        )
+        .unwrap();
+        fake::Syscalls::command(DRIVER_NUM, READ, len, 0)
+            .to_result::<(), ErrorCode>()
+            .unwrap();
+        before_yield();
+        fake::Syscalls::yield_no_wait();
+    });
+    called.get()
+}
+
+#[test]
+fn pending_read() {
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    kernel.add_driver(&console);
+
+    let mut buf = [0; 4];
+    assert_eq!(
+        read(&mut buf, 4, || {
+            assert!(console.is_reading());
+            console.push_input(b"hi");
+        }),
+        Some((0, 2, 0))
+    );
+    assert_eq!(&buf[..2], b"hi");
+    assert!(!console.is_reading());
+}
+
+#[test]
+fn abort() {
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    kernel.add_driver(&console);
+
+    let mut buf = [0; 4];
+    let abort = || {
+        fake::Syscalls::command(DRIVER_NUM, ABORT, 0, 0)
+            .to_result::<(), ErrorCode>()
+            .unwrap();
+    };
+    assert_eq!(
+        read(&mut buf, 4, abort),
+        Some((ErrorCode::Cancel as u32, 0, 0))
+    );
+    assert!(!console.is_reading());
+    // Aborting with no read in progress does nothing.
+    abort();
+}
+
+#[test]
+fn queued_chunks() {
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new_with_input(b"ab");
+    kernel.add_driver(&console);
+    console.queue_input(b"cde");
+    console.queue_input(b"f");
+    assert_eq!(console.remaining_script(), 2);
+
+    let mut buf = [0; 8];
+    // The initial input is returned before the first chunk arrives.
+    assert_eq!(read(&mut buf, 8, || {}), Some((0, 2, 0)));
+    assert_eq!(&buf[..2], b"ab");
+    assert_eq!(read(&mut buf, 2, || {}), Some((0, 2, 0)));
+    assert_eq!(&buf[..2], b"cd");
+    assert_eq!(console.pending_input(), 1);
+    assert_eq!(read(&mut buf, 8, || {}), Some((0, 1, 0)));
+    assert_eq!(&buf[..1], b"e");
+    assert_eq!(read(&mut buf, 8, || {}), Some((0, 1, 0)));
+    assert_eq!(&buf[..1], b"f");
+    assert_eq!(console.remaining_script(), 0);
+    assert_eq!(read(&mut buf, 8, || {}), None);
+}
+
+#[test]
+fn respond_to_output() {
+    let kernel = fake::Kernel::new();
+    let console = fake::Console::new();
+    kernel.add_driver(&console);
+    console.respond_to(b"> ", b"help\n");
+    console.respond_to(b"> ", b"exit\n");
+
+    write(b"welcome\n>");
+    assert_eq!(console.pending_input(), 0);
+    // The trigger may be split across writes.
+    write(b" ");
+    assert_eq!(console.pending_input(), 5);
+    write(b"commands: help exit\n> ");
+    assert_eq!(console.pending_input(), 10);
+    assert_eq!(console.remaining_script(), 0);
+    assert_eq!(console.take_bytes(), b"welcome\n> commands: help exit\n> ");
+
+    let mut buf = [0; 16];
+    assert_eq!(read(&mut buf, 16, || {}), Some((0, 10, 0)));
+    assert_eq!(&buf[..10], b"help\nexit\n");
+}
+
+#[test]
+fn read_errors() {
+    use fake::SyscallDriver;
+    let console = fake::Console::new();
+    console.set_read_limit(4);
+    assert_eq!(
+        console.command(READ, 5, 0).get_failure(),
+        Some(ErrorCode::Size)
+    );
+    assert!(console.command(READ, 4, 0).is_success());
+    assert_eq!(
+        console.command(READ, 4, 0).get_failure(),
+        Some(ErrorCode::Busy)
+    );
+    assert!(console.command(ABORT, 0, 0).is_success());
+    assert!(!console.is_reading());
+    assert!(console.command(READ, 4, 0).is_success());
+}
@@ -0,0 +1,264 @@
+use crate::{DriverInfo, DriverShareRef, RoAllowBuffer, RwAllowBuffer};
+use core::cell::RefCell;
+use libtock_platform::{CommandReturn, ErrorCode};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Declares a fake driver as a table of command handlers, named Allow buffers,
+/// and named upcalls, so that tests of a custom capsule's userspace library do
+/// not need to implement `fake::SyscallDriver` by hand. The resulting
+/// `BuiltDriver` handles the bookkeeping:
+///
+/// - Command 0 (the existence check) returns Success unless a handler is
+///   declared for it, and undeclared commands fail with `NOSUPPORT`.
+/// - Allow calls with a declared buffer number swap buffers, and other Allow
+///   calls fail with `NOSUPPORT`.
+/// - The driver's upcall count is one more than the largest declared subscribe
+///   number.
+///
+/// Command handlers receive the `BuiltDriver`, through which they can access
+/// the driver's state (of type `S`), its Allow buffers, and its upcalls.
+///
+/// # Example
+/// ```
+/// use libtock_unittest::{command_return, fake};
+/// use std::cell::Cell;
+///
+/// // A capsule that adds up the bytes it is given.
+/// let driver = fake::DriverBuilder::new(0x99999, Cell::new(0u32))
+///     .allow_ro(0, "input")
+///     .subscribe(0, "done")
+///     .command(1, |driver, _, _| {
+///         let sum = driver.with_ro_buffer("input", |input| {
+///             input.iter().map(|&byte| u32::from(byte)).sum::<u32>()
+///         });
+///         driver.set(driver.get() + sum);
+///         driver.schedule_upcall("done", (sum, 0, 0));
+///         command_return::success()
+///     })
+///     .build();
+/// let kernel = fake::Kernel::new();
+/// kernel.add_driver(&driver);
+/// ```
+pub struct DriverBuilder<S> {
+    driver_num: u32,
+    state: S,
+    commands: HashMap<u32, CommandHandler<S>>,
+    ro_buffers: Vec<Slot<RoAllowBuffer>>,
+    rw_buffers: Vec<Slot<RwAllowBuffer>>,
+    upcalls: Vec<(u32, &'static str)>,
+    idle: Option<IdleHandler<S>>,
+}
+
+/// A fake driver declared with a `DriverBuilder`. Dereferences to its state.
+pub struct BuiltDriver<S> {
+    driver_num: u32,
+    state: S,
+    commands: HashMap<u32, CommandHandler<S>>,
+    ro_buffers: Vec<Slot<RoAllowBuffer>>,
+    rw_buffers: Vec<Slot<RwAllowBuffer>>,
+    upcalls: Vec<(u32, &'static str)>,
+    idle: Option<IdleHandler<S>>,
+    share_ref: DriverShareRef,
+}
+
+impl<S> DriverBuilder<S> {
+    pub fn new(driver_num: u32, state: S) -> DriverBuilder<S> {
+        DriverBuilder {
+            driver_num,
+            state,
+            commands: HashMap::new(),
+            ro_buffers: Vec::new(),
+            rw_buffers: Vec::new(),
+            upcalls: Vec::new(),
+            idle: None,
+        }
+    }
+
+    /// Declares the handler for command `command_num`. The handler receives
+    /// the driver and the command's two arguments.
+    pub fn command<F>(mut self, command_num: u32, handler: F) -> Self
+    where
+        F: Fn(&BuiltDriver<S>, u32, u32) -> CommandReturn + 'static,
+    {
+        let previous = self.commands.insert(command_num, Box::new(handler));
+        assert!(previous.is_none(), "command {command_num} declared twice");
+        self
+    }
+
+    /// Declares Read-Only Allow buffer `buffer_num`, which handlers access by
+    /// `name`.
+    pub fn allow_ro(mut self, buffer_num: u32, name: &'static str) -> Self {
+        declare(&mut self.ro_buffers, buffer_num, name);
+        self
+    }
+
+    /// Declares Read-Write Allow buffer `buffer_num`, which handlers access by
+    /// `name`.
+    pub fn allow_rw(mut self, buffer_num: u32, name: &'static str) -> Self {
+        declare(&mut self.rw_buffers, buffer_num, name);
+        self
+    }
+
+    /// Declares upcall `subscribe_num`, which handlers schedule by `name`.
+    pub fn subscribe(mut self, subscribe_num: u32, name: &'static str) -> Self {
+        assert!(
+            self.upcalls
+                .iter()
+                .all(|&(num, existing)| num != subscribe_num && existing != name),
+            "upcall {subscribe_num} ({name}) declared twice"
+        );
+        self.upcalls.push((subscribe_num, name));
+        self
+    }
+
+    /// Sets the driver's `fake::SyscallDriver::idle` implementation.
+    pub fn idle<F: Fn(&BuiltDriver<S>) -> bool + 'static>(mut self, handler: F) -> Self {
+        self.idle = Some(Box::new(handler));
+        self
+    }
+
+    pub fn build(self) -> Rc<BuiltDriver<S>> {
+        Rc::new(BuiltDriver {
+            driver_num: self.driver_num,
+            state: self.state,
+            commands: self.commands,
+            ro_buffers: self.ro_buffers,
+            rw_buffers: self.rw_buffers,
+            upcalls: self.upcalls,
+            idle: self.idle,
+            share_ref: Default::default(),
+        })
+    }
+}
+
+impl<S> BuiltDriver<S> {
+    /// Returns the driver's state.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Calls `f` with the contents of the Read-Only Allow buffer called `name`
+    /// (which is empty if the process has not shared one).
+    pub fn with_ro_buffer<R, F: FnOnce(&[u8]) -> R>(&self, name: &str, f: F) -> R {
+        f(&find(&self.ro_buffers, name).buffer.borrow())
+    }
+
+    /// Calls `f` with the contents of the Read-Write Allow buffer called
+    /// `name` (which is empty if the process has not shared one).
+    pub fn with_rw_buffer<R, F: FnOnce(&mut [u8]) -> R>(&self, name: &str, f: F) -> R {
+        f(&mut find(&self.rw_buffers, name).buffer.borrow_mut())
+    }
+
+    /// Copies `data` into the start of the Read-Write Allow buffer called
+    /// `name`, truncating it if it does not fit. Returns the number of bytes
+    /// copied.
+    pub fn write_rw_buffer(&self, name: &str, data: &[u8]) -> usize {
+        self.with_rw_buffer(name, |buffer| {
+            let len = data.len().min(buffer.len());
+            buffer[..len].copy_from_slice(&data[..len]);
+            len
+        })
+    }
+
+    /// Schedules the upcall called `name`, if the process has subscribed to
+    /// it.
+    #[track_caller]
+    pub fn schedule_upcall(&self, name: &str, args: (u32, u32, u32)) {
+        let Some(&(subscribe_num, _)) = self.upcalls.iter().find(|&&(_, n)| n == name) else {
+            panic!("driver {:#x} has no upcall named {name}", self.driver_num);
+        };
+        self.share_ref
+            .schedule_upcall(subscribe_num, args)
+            .expect("Unable to schedule upcall");
+    }
+}
+
+impl<S> core::ops::Deref for BuiltDriver<S> {
+    type Target = S;
+    fn deref(&self) -> &S {
+        &self.state
+    }
+}
+
+impl<S: 'static> crate::fake::SyscallDriver for BuiltDriver<S> {
+    fn info(&self) -> DriverInfo {
+        let upcall_count = self.upcalls.iter().map(|&(num, _)| num + 1).max();
+        DriverInfo::new(self.driver_num).upcall_count(upcall_count.unwrap_or(0))
+    }
+
+    fn register(&self, share_ref: DriverShareRef) {
+        self.share_ref.replace(share_ref);
+    }
+
+    fn command(&self, command_id: u32, argument0: u32, argument1: u32) -> CommandReturn {
+        match self.commands.get(&command_id) {
+            Some(handler) => handler(self, argument0, argument1),
+            None if command_id == EXISTS => crate::command_return::success(),
+            None => crate::command_return::failure(ErrorCode::NoSupport),
+        }
+    }
+
+    fn allow_readonly(
+        &self,
+        buffer_num: u32,
+        buffer: RoAllowBuffer,
+    ) -> Result<RoAllowBuffer, (RoAllowBuffer, ErrorCode)> {
+        match self.ro_buffers.iter().find(|slot| slot.num == buffer_num) {
+            Some(slot) => Ok(slot.buffer.replace(buffer)),
+            None => Err((buffer, ErrorCode::NoSupport)),
+        }
+    }
+
+    fn allow_readwrite(
+        &self,
+        buffer_num: u32,
+        buffer: RwAllowBuffer,
+    ) -> Result<RwAllowBuffer, (RwAllowBuffer, ErrorCode)> {
+        match self.rw_buffers.iter().find(|slot| slot.num == buffer_num) {
+            Some(slot) => Ok(slot.buffer.replace(buffer)),
+            None => Err((buffer, ErrorCode::NoSupport)),
+        }
+    }
+
+    fn idle(&self) -> bool {
+        self.idle.as_ref().is_some_and(|idle| idle(self))
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Implementation details below
+// -----------------------------------------------------------------------------
+
+const EXISTS: u32 = 0;
+
+type CommandHandler<S> = Box<dyn Fn(&BuiltDriver<S>, u32, u32) -> CommandReturn>;
+type IdleHandler<S> = Box<dyn Fn(&BuiltDriver<S>) -> bool>;
+
+struct Slot<B> {
+    num: u32,
+    name: &'static str,
+    buffer: RefCell<B>,
+}
+
+fn declare<B: Default>(slots: &mut Vec<Slot<B>>, num: u32, name: &'static str) {
+    assert!(
+        slots
+            .iter()
+            .all(|slot| slot.num != num && slot.name != name),
+        "buffer {num} ({name}) declared twice"
+    );
+    slots.push(Slot {
+        num,
+        name,
+        buffer: Default::default(),
+    });
+}
+
+#[track_caller]
+fn find<'s, B>(slots: &'s [Slot<B>], name: &str) -> &'s Slot<B> {
+    slots
+        .iter()
+        .find(|slot| slot.name == name)
+        .unwrap_or_else(|| panic!("no buffer named {name}"))
+}
@@ -0,0 +1,101 @@
+use crate::fake::{self, DriverBuilder, SyscallDriver};
+use crate::{command_return, RoAllowBuffer, RwAllowBuffer};
+use libtock_platform::{share, AllowRo, AllowRw, DefaultConfig, ErrorCode, Subscribe, Syscalls};
+use std::cell::Cell;
+
+const DRIVER_NUM: u32 = 0x99999;
+
+#[test]
+fn commands() {
+    let driver = DriverBuilder::new(DRIVER_NUM, Cell::new(0))
+        .command(1, |driver, argument0, argument1| {
+            driver.set(argument0 + argument1);
+            command_return::success_u32(driver.get())
+        })
+        .build();
+    assert_eq!(driver.info().driver_num, DRIVER_NUM);
+    assert_eq!(driver.info().upcall_count, 0);
+    // EXISTS is handled automatically.
+    assert!(driver.command(0, 0, 0).is_success());
+    assert_eq!(driver.command(1, 2, 3).get_success_u32(), Some(5));
+    assert_eq!(driver.get(), 5);
+    assert_eq!(
+        driver.command(2, 0, 0).get_failure(),
+        Some(ErrorCode::NoSupport)
+    );
+
+    // A handler for command 0 overrides the automatic EXISTS handling.
+    let driver = DriverBuilder::new(DRIVER_NUM, ())
+        .command(0, |_, _, _| command_return::success_u32(7))
+        .build();
+    assert_eq!(driver.command(0, 0, 0).get_success_u32(), Some(7));
+}
+
+#[test]
+fn allow_slots() {
+    let driver = DriverBuilder::new(DRIVER_NUM, ())
+        .allow_ro(0, "input")
+        .allow_rw(1, "output")
+        .build();
+    assert!(driver.allow_readonly(0, RoAllowBuffer::default()).is_ok());
+    assert_eq!(
+        driver
+            .allow_readonly(1, RoAllowBuffer::default())
+            .unwrap_err()
+            .1,
+        ErrorCode::NoSupport
+    );
+    assert!(driver.allow_readwrite(1, RwAllowBuffer::default()).is_ok());
+    assert!(driver.allow_readwrite(0, RwAllowBuffer::default()).is_err());
+    // Without shared buffers, the buffers are empty.
+    assert_eq!(driver.with_ro_buffer("input", |input| input.len()), 0);
+    assert_eq!(driver.write_rw_buffer("output", b"data"), 0);
+}
+
+// Integration test that verifies a built driver works with fake::Kernel and
+// libtock_platform::Syscalls.
+#[test]
+fn kernel_integration() {
+    let kernel = fake::Kernel::new();
+    let driver = DriverBuilder::new(DRIVER_NUM, ())
+        .allow_ro(0, "input")
+        .allow_rw(0, "output")
+        .subscribe(2, "done")
+        .command(1, |driver, _, _| {
+            let input = driver.with_ro_buffer("input", <[u8]>::to_ascii_uppercase);
+            let written = driver.write_rw_buffer("output", &input);
+            driver.schedule_upcall("done", (written as u32, 0, 0));
+            command_return::success()
+        })
+        .build();
+    kernel.add_driver(&driver);
+    assert_eq!(driver.info().upcall_count, 3);
+
+    let mut output = [0; 3];
+    let done = Cell::new(None::<(u32,)>);
+    share::scope::<
+        (
+            AllowRo<_, DRIVER_NUM, 0>,
+            AllowRw<_, DRIVER_NUM, 0>,
+            Subscribe<_, DRIVER_NUM, 2>,
+        ),
+        _,
+        _,
+    >(|handle| {
+        let (allow_ro, allow_rw, subscribe) = handle.split();
+        fake::Syscalls::allow_ro::<DefaultConfig, DRIVER_NUM, 0>(allow_ro, b"tock").unwrap();
+        fake::Syscalls::allow_rw::<DefaultConfig, DRIVER_NUM, 0>(allow_rw, &mut output).unwrap();
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 2>(subscribe, &done).unwrap();
+        assert!(fake::Syscalls::command(DRIVER_NUM, 1, 0, 0).is_success());
+        fake::Syscalls::yield_wait();
+    });
+    assert_eq!(done.get(), Some((3,)));
+    assert_eq!(&output, b"TOC");
+}
+
+#[test]
+#[should_panic(expected = "has no upcall named missing")]
+fn unknown_upcall() {
+    let driver = DriverBuilder::new(DRIVER_NUM, ()).build();
+    driver.schedule_upcall("missing", (0, 0, 0));
+}
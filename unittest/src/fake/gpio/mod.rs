@@ -2,17 +2,30 @@
 //! https://github.com/tock/tock/blob/master/doc/syscalls/00004_gpio.md
 //!
 //! Like the real API, `Gpio` controls a set of fake gpios. It provides
-//! a function `get_button_state` used to retrieve the state and interrupt
-//! status of a button.
+//! a function `get_gpio_state` used to retrieve the state and interrupt
+//! status of a pin.
 //!
-//! It also provides the function `set_pressed` that set the button's state.
+//! `Gpio` models the pins' electrical behavior. Each pin's level is decided by
+//! what drives it: the process (through an output), the test (through
+//! `drive`), and fake peripherals such as `PushButton`. `set_value` changes a
+//! pin's level once, without driving it. Pins can be
+//! connected together with `connect`, and can have pull resistors (in the pin
+//! or on the board) and open-drain outputs, so bit-banged buses can be
+//! simulated. Level changes raise interrupts according to the edge configured
+//! by the process.
 
-use core::cell::Cell;
+use core::cell::{Cell, RefCell};
 use libtock_platform::{CommandReturn, ErrorCode};
 use std::convert::TryFrom;
+use std::rc::{Rc, Weak};
 
+use crate::fake::Alarm;
 use crate::{DriverInfo, DriverShareRef};
 
+mod peripherals;
+
+pub use peripherals::{LevelRecorder, PushButton};
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum GpioMode {
     Output,
@@ -69,9 +82,26 @@ pub struct GpioState {
 
 pub struct Gpio<const NUM_GPIOS: usize> {
     gpios: [Cell<Option<GpioState>>; NUM_GPIOS],
+    wiring: [Cell<Wiring>; NUM_GPIOS],
+    buttons: RefCell<Vec<Rc<PushButton>>>,
+    recorders: RefCell<Vec<Rc<LevelRecorder>>>,
     share_ref: DriverShareRef,
 }
 
+// How a pin is connected to the outside world.
+#[derive(Copy, Clone)]
+struct Wiring {
+    // The pins with the same net are connected together.
+    net: usize,
+    // The level the process last wrote to the pin's output register.
+    output: bool,
+    // The level the test drives the pin to, if any.
+    external: Option<bool>,
+    open_drain: bool,
+    // A pull resistor on the board (rather than in the pin).
+    board_pull: Option<PullMode>,
+}
+
 impl<const NUM_GPIOS: usize> Gpio<NUM_GPIOS> {
     pub fn new() -> std::rc::Rc<Gpio<NUM_GPIOS>> {
         #[allow(clippy::declare_interior_mutable_const)]
@@ -82,6 +112,17 @@ impl<const NUM_GPIOS: usize> Gpio<NUM_GPIOS> {
         }));
         std::rc::Rc::new(Gpio {
             gpios: [OFF; NUM_GPIOS],
+            wiring: core::array::from_fn(|pin| {
+                Cell::new(Wiring {
+                    net: pin,
+                    output: false,
+                    external: None,
+                    open_drain: false,
+                    board_pull: None,
+                })
+            }),
+            buttons: Default::default(),
+            recorders: Default::default(),
             share_ref: Default::default(),
         })
     }
@@ -92,40 +133,86 @@ impl<const NUM_GPIOS: usize> Gpio<NUM_GPIOS> {
         }
     }
 
+    /// Sets the level of `pin` (and the pins connected to it), raising
+    /// interrupts as configured. This is a one-shot change: the level lasts
+    /// until something connected to the pin changes, such as the process
+    /// writing to it. To keep driving the pin, use `drive`.
     pub fn set_value(&self, pin: u32, value: bool) -> Result<(), ErrorCode> {
-        self.gpios
-            .get(pin as usize)
-            .map(|gpio| {
-                if let Some(gpio_state) = gpio.get() {
-                    let original_value = gpio_state.value;
-                    gpio.set(Some(GpioState {
-                        value,
-                        ..gpio_state
-                    }));
-                    if original_value != value {
-                        if value {
-                            if gpio_state.interrupt_enabled == Some(InterruptEdge::Either)
-                                || gpio_state.interrupt_enabled == Some(InterruptEdge::Rising)
-                            {
-                                self.share_ref
-                                    .schedule_upcall(0, (pin, value as u32, 0))
-                                    .expect("Unable to schedule upcall");
-                            }
-                        } else if gpio_state.interrupt_enabled == Some(InterruptEdge::Falling)
-                            || gpio_state.interrupt_enabled == Some(InterruptEdge::Either)
-                        {
-                            self.share_ref
-                                .schedule_upcall(0, (pin, value as u32, 0))
-                                .expect("Unable to schedule upcall");
-                        }
-                    }
-                    Ok(())
-                } else {
-                    Err(ErrorCode::NoDevice)
-                }
-            })
-            .ok_or(ErrorCode::Invalid)
-            .and_then(|value| value)
+        self.check_pin(pin)?;
+        for pin in self.net_pins(pin) {
+            self.set_level(pin as u32, value);
+        }
+        Ok(())
+    }
+
+    /// Drives `pin` to `value` from outside the chip, as if it were connected
+    /// to a push-pull output. The pin keeps being driven until `release` is
+    /// called.
+    pub fn drive(&self, pin: u32, value: bool) -> Result<(), ErrorCode> {
+        self.check_pin(pin)?;
+        self.update_wiring(pin, |wiring| wiring.external = Some(value));
+        Ok(())
+    }
+
+    /// Stops driving `pin` from outside the chip. If nothing else drives it,
+    /// pull resistors determine its level; if there are none, it keeps its
+    /// previous level.
+    pub fn release(&self, pin: u32) {
+        self.update_wiring(pin, |wiring| wiring.external = None);
+    }
+
+    /// Connects `pin_a` and `pin_b`, so that they (and the pins already
+    /// connected to either) always have the same level. Driving connected pins
+    /// to different levels is a short circuit, which panics.
+    pub fn connect(&self, pin_a: u32, pin_b: u32) {
+        let net_a = self.wiring(pin_a).net;
+        let net_b = self.wiring(pin_b).net;
+        for wiring in &self.wiring {
+            let mut new = wiring.get();
+            if new.net == net_b {
+                new.net = net_a;
+                wiring.set(new);
+            }
+        }
+        self.resolve(pin_a);
+    }
+
+    /// Adds a pull resistor on the board to `pin`, such as the pull-up on an
+    /// I2C or 1-Wire bus. Passing `PullMode::PullNone` removes it.
+    pub fn set_board_pull(&self, pin: u32, pull: PullMode) {
+        let pull = match pull {
+            PullMode::PullNone => None,
+            pull => Some(pull),
+        };
+        self.update_wiring(pin, |wiring| wiring.board_pull = pull);
+    }
+
+    /// Configures `pin`'s output as open-drain: when the process sets it high,
+    /// the pin is released rather than driven high.
+    pub fn set_open_drain(&self, pin: u32, open_drain: bool) {
+        self.update_wiring(pin, |wiring| wiring.open_drain = open_drain);
+    }
+
+    /// Connects a push button to `pin`. While pressed, the button drives the
+    /// pin to `pressed_level`; while released, it leaves the pin alone (so
+    /// active-low buttons need a pull-up).
+    pub fn add_button(self: &Rc<Self>, pin: u32, pressed_level: bool) -> Rc<PushButton> {
+        self.wiring(pin);
+        let weak_self: Weak<Self> = Rc::downgrade(self);
+        let button = Rc::new(PushButton::new(pin, pressed_level, weak_self));
+        self.buttons.borrow_mut().push(button.clone());
+        button
+    }
+
+    /// Records the level of `pin` each time it changes, e.g. to check the
+    /// pattern an LED blinks in.
+    pub fn record(&self, pin: u32) -> Rc<LevelRecorder> {
+        self.add_recorder(pin, None)
+    }
+
+    /// Like `record`, but also records the time of each change on `clock`.
+    pub fn record_with_clock(&self, pin: u32, clock: &Rc<Alarm>) -> Rc<LevelRecorder> {
+        self.add_recorder(pin, Some(clock.clone()))
     }
 
     pub fn get_gpio_state(&self, button: u32) -> Option<GpioState> {
@@ -134,6 +221,143 @@ impl<const NUM_GPIOS: usize> Gpio<NUM_GPIOS> {
             .map(|button| button.get())
             .and_then(|value| value)
     }
+
+    fn add_recorder(&self, pin: u32, clock: Option<Rc<Alarm>>) -> Rc<LevelRecorder> {
+        self.wiring(pin);
+        let level = self.get_gpio_state(pin).is_some_and(|state| state.value);
+        let recorder = Rc::new(LevelRecorder::new(pin, level, clock));
+        self.recorders.borrow_mut().push(recorder.clone());
+        recorder
+    }
+
+    // Returns Invalid if `pin` does not exist, and NoDevice if it is missing.
+    fn check_pin(&self, pin: u32) -> Result<(), ErrorCode> {
+        match self.gpios.get(pin as usize) {
+            None => Err(ErrorCode::Invalid),
+            Some(gpio) if gpio.get().is_none() => Err(ErrorCode::NoDevice),
+            Some(_) => Ok(()),
+        }
+    }
+
+    fn wiring(&self, pin: u32) -> Wiring {
+        match self.wiring.get(pin as usize) {
+            Some(wiring) => wiring.get(),
+            None => panic!("GPIO pin {} does not exist", pin),
+        }
+    }
+
+    fn update_wiring(&self, pin: u32, f: impl FnOnce(&mut Wiring)) {
+        let mut wiring = self.wiring(pin);
+        f(&mut wiring);
+        self.wiring[pin as usize].set(wiring);
+        self.resolve(pin);
+    }
+
+    // Sets the process' output register for `pin`.
+    fn set_output(&self, pin: u32, value: bool) {
+        self.update_wiring(pin, |wiring| wiring.output = value);
+    }
+
+    // Returns the pins connected to `pin` (including `pin`) that exist.
+    fn net_pins(&self, pin: u32) -> Vec<usize> {
+        let net = self.wiring(pin).net;
+        (0..NUM_GPIOS)
+            .filter(|&pin| self.wiring[pin].get().net == net)
+            .filter(|&pin| self.gpios[pin].get().is_some())
+            .collect()
+    }
+
+    // Recomputes the level of the net `pin` belongs to and updates its pins,
+    // raising interrupts and notifying recorders for the pins that changed.
+    // Other nets are left alone, so levels set there with `set_value` last.
+    fn resolve(&self, pin: u32) {
+        let pins = self.net_pins(pin);
+        if let Some(level) = self.net_level(&pins) {
+            for pin in pins {
+                self.set_level(pin as u32, level);
+            }
+        }
+    }
+
+    // Returns the level of the net made of `pins`, or None if the net is
+    // floating (in which case it keeps its level).
+    fn net_level(&self, pins: &[usize]) -> Option<bool> {
+        let (mut high, mut low) = (false, false);
+        let (mut pull_up, mut pull_down) = (false, false);
+        let buttons = self.buttons.borrow();
+        for &pin in pins {
+            let state = self.gpios[pin].get().unwrap();
+            let wiring = self.wiring[pin].get();
+            let mut drives = Vec::new();
+            if state.mode == GpioMode::Output && !(wiring.open_drain && wiring.output) {
+                drives.push(wiring.output);
+            }
+            drives.extend(wiring.external);
+            drives.extend(
+                buttons
+                    .iter()
+                    .filter(|button| button.pin() == pin as u32 && button.is_pressed())
+                    .map(|button| button.pressed_level()),
+            );
+            for level in drives {
+                high |= level;
+                low |= !level;
+            }
+            for pull in [Some(state.mode), wiring.board_pull.map(GpioMode::Input)] {
+                match pull {
+                    Some(GpioMode::Input(PullMode::PullUp)) => pull_up = true,
+                    Some(GpioMode::Input(PullMode::PullDown)) => pull_down = true,
+                    _ => {}
+                }
+            }
+        }
+        assert!(
+            !(high && low),
+            "GPIO short circuit: pins {:?} are driven both high and low",
+            pins
+        );
+        match (high, low, pull_up, pull_down) {
+            (true, _, _, _) => Some(true),
+            (_, true, _, _) => Some(false),
+            (_, _, true, false) => Some(true),
+            (_, _, false, true) => Some(false),
+            _ => None,
+        }
+    }
+
+    fn set_level(&self, pin: u32, value: bool) {
+        let gpio = &self.gpios[pin as usize];
+        let gpio_state = gpio.get().unwrap();
+        if gpio_state.value == value {
+            return;
+        }
+        gpio.set(Some(GpioState {
+            value,
+            ..gpio_state
+        }));
+        let edge_enabled = match gpio_state.interrupt_enabled {
+            Some(InterruptEdge::Either) => true,
+            Some(InterruptEdge::Rising) => value,
+            Some(InterruptEdge::Falling) => !value,
+            None => false,
+        };
+        if edge_enabled {
+            self.share_ref
+                .schedule_upcall(0, (pin, value as u32, 0))
+                .expect("Unable to schedule upcall");
+        }
+        for recorder in self.recorders.borrow().iter() {
+            if recorder.pin() == pin {
+                recorder.record(value);
+            }
+        }
+    }
+}
+
+impl<const NUM_GPIOS: usize> peripherals::Wired for Gpio<NUM_GPIOS> {
+    fn resolve(&self, pin: u32) {
+        Gpio::resolve(self, pin);
+    }
 }
 
 impl<const NUM_GPIOS: usize> crate::fake::SyscallDriver for Gpio<NUM_GPIOS> {
@@ -159,32 +383,24 @@ impl<const NUM_GPIOS: usize> crate::fake::SyscallDriver for Gpio<NUM_GPIOS> {
                             mode: GpioMode::Output,
                             ..gpio
                         }));
+                        self.resolve(argument0);
                         crate::command_return::success()
                     }
                     GPIO_SET => {
                         if let GpioMode::Output = gpio.mode {
-                            self.gpios[argument0 as usize].set(Some(GpioState {
-                                value: true,
-                                ..gpio
-                            }));
+                            self.set_output(argument0, true);
                         }
                         crate::command_return::success()
                     }
                     GPIO_CLEAR => {
                         if let GpioMode::Output = gpio.mode {
-                            self.gpios[argument0 as usize].set(Some(GpioState {
-                                value: false,
-                                ..gpio
-                            }));
+                            self.set_output(argument0, false);
                         }
                         crate::command_return::success()
                     }
                     GPIO_TOGGLE => {
                         if let GpioMode::Output = gpio.mode {
-                            self.gpios[argument0 as usize].set(Some(GpioState {
-                                value: !gpio.value,
-                                ..gpio
-                            }));
+                            self.set_output(argument0, !self.wiring(argument0).output);
                         }
                         crate::command_return::success()
                     }
@@ -196,6 +412,7 @@ impl<const NUM_GPIOS: usize> crate::fake::SyscallDriver for Gpio<NUM_GPIOS> {
                                     mode: GpioMode::Input(mode),
                                     ..gpio
                                 }));
+                                self.resolve(argument0);
                                 crate::command_return::success()
                             }
                             Err(error) => crate::command_return::failure(error),
@@ -233,6 +450,7 @@ impl<const NUM_GPIOS: usize> crate::fake::SyscallDriver for Gpio<NUM_GPIOS> {
                             mode: GpioMode::Disable,
                             ..gpio
                         }));
+                        self.resolve(argument0);
                         crate::command_return::success()
                     }
                     _ => crate::command_return::failure(ErrorCode::NoSupport),
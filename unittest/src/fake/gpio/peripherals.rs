@@ -0,0 +1,158 @@
+//! Fake peripherals that can be connected to `fake::Gpio` pins.
+
+use core::cell::{Cell, RefCell};
+use core::time::Duration;
+use std::rc::{Rc, Weak};
+
+use crate::fake::{Alarm, Timeline};
+
+// Implemented by `Gpio` so that peripherals can tell it to recompute a pin's
+// level without knowing its number of pins.
+pub(super) trait Wired {
+    fn resolve(&self, pin: u32);
+}
+
+/// A push button connected to a `fake::Gpio` pin, created by
+/// `Gpio::add_button`.
+pub struct PushButton {
+    pin: u32,
+    pressed_level: bool,
+    pressed: Cell<bool>,
+    gpio: Weak<dyn Wired>,
+}
+
+impl PushButton {
+    pub(super) fn new(pin: u32, pressed_level: bool, gpio: Weak<dyn Wired>) -> PushButton {
+        PushButton {
+            pin,
+            pressed_level,
+            pressed: Cell::new(false),
+            gpio,
+        }
+    }
+
+    pub fn pin(&self) -> u32 {
+        self.pin
+    }
+
+    /// Returns the level the button drives its pin to while pressed.
+    pub fn pressed_level(&self) -> bool {
+        self.pressed_level
+    }
+
+    pub fn is_pressed(&self) -> bool {
+        self.pressed.get()
+    }
+
+    pub fn press(&self) {
+        self.set_pressed(true);
+    }
+
+    pub fn release(&self) {
+        self.set_pressed(false);
+    }
+
+    /// Presses the button the way a real switch closes: the contacts bounce
+    /// `bounces` times, `interval` apart, before settling. The first contact
+    /// happens immediately; the rest are scheduled on `timeline`.
+    pub fn press_with_bounce(
+        self: &Rc<Self>,
+        timeline: &Timeline,
+        bounces: u32,
+        interval: Duration,
+    ) {
+        self.bounce_to(true, timeline, bounces, interval);
+    }
+
+    /// Releases the button with contact bounce, like `press_with_bounce`.
+    pub fn release_with_bounce(
+        self: &Rc<Self>,
+        timeline: &Timeline,
+        bounces: u32,
+        interval: Duration,
+    ) {
+        self.bounce_to(false, timeline, bounces, interval);
+    }
+
+    fn bounce_to(
+        self: &Rc<Self>,
+        pressed: bool,
+        timeline: &Timeline,
+        bounces: u32,
+        interval: Duration,
+    ) {
+        self.set_pressed(pressed);
+        // Each bounce opens the contacts, then closes them again.
+        for transition in 1..=2 * bounces {
+            let button = self.clone();
+            let state = if transition % 2 == 1 {
+                !pressed
+            } else {
+                pressed
+            };
+            timeline.after(interval * transition, move || button.set_pressed(state));
+        }
+    }
+
+    fn set_pressed(&self, pressed: bool) {
+        self.pressed.set(pressed);
+        if let Some(gpio) = self.gpio.upgrade() {
+            gpio.resolve(self.pin);
+        }
+    }
+}
+
+/// Records the level of a `fake::Gpio` pin each time it changes. Created by
+/// `Gpio::record` and `Gpio::record_with_clock`.
+pub struct LevelRecorder {
+    pin: u32,
+    level: Cell<bool>,
+    clock: Option<Rc<Alarm>>,
+    changes: RefCell<Vec<(Duration, bool)>>,
+}
+
+impl LevelRecorder {
+    pub(super) fn new(pin: u32, level: bool, clock: Option<Rc<Alarm>>) -> LevelRecorder {
+        LevelRecorder {
+            pin,
+            level: Cell::new(level),
+            clock,
+            changes: Default::default(),
+        }
+    }
+
+    pub fn pin(&self) -> u32 {
+        self.pin
+    }
+
+    /// Returns the pin's current level.
+    pub fn level(&self) -> bool {
+        self.level.get()
+    }
+
+    /// Returns the levels the pin changed to since the last call, and clears
+    /// them.
+    pub fn take_levels(&self) -> Vec<bool> {
+        self.changes
+            .take()
+            .into_iter()
+            .map(|(_, level)| level)
+            .collect()
+    }
+
+    /// Returns the levels the pin changed to since the last call, with the
+    /// time of each change on the recorder's clock, and clears them. Without a
+    /// clock, all times are zero.
+    pub fn take_changes(&self) -> Vec<(Duration, bool)> {
+        self.changes.take()
+    }
+
+    pub(super) fn record(&self, level: bool) {
+        self.level.set(level);
+        let time = self
+            .clock
+            .as_ref()
+            .map_or(Duration::ZERO, |clock| clock.elapsed());
+        self.changes.borrow_mut().push((time, level));
+    }
+}
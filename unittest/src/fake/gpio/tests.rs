@@ -401,3 +401,153 @@ fn kernel_integration() {
     assert!(fake::Syscalls::command(DRIVER_NUM, GPIO_DISABLE, 0, 0).is_success());
     assert_eq!(gpio.get_gpio_state(0).unwrap().mode, GpioMode::Disable);
 }
+
+#[test]
+fn loopback() {
+    use fake::SyscallDriver;
+    let gpio = Gpio::<4>::new();
+    gpio.connect(0, 1);
+    assert!(gpio.command(GPIO_ENABLE_OUTPUT, 0, 0).is_success());
+    assert!(gpio.command(GPIO_ENABLE_INPUT, 1, 0).is_success());
+
+    assert!(gpio.command(GPIO_SET, 0, 0).is_success());
+    assert_eq!(
+        gpio.command(GPIO_READ_INPUT, 1, 0).get_success_u32(),
+        Some(1)
+    );
+    assert!(gpio.command(GPIO_TOGGLE, 0, 0).is_success());
+    assert_eq!(
+        gpio.command(GPIO_READ_INPUT, 1, 0).get_success_u32(),
+        Some(0)
+    );
+    // Unconnected pins are unaffected.
+    assert!(!gpio.get_gpio_state(2).unwrap().value);
+}
+
+#[test]
+fn pulls_and_floating() {
+    use fake::SyscallDriver;
+    let gpio = Gpio::<2>::new();
+    assert!(gpio.command(GPIO_ENABLE_INPUT, 0, 1).is_success());
+    assert!(gpio.get_gpio_state(0).unwrap().value);
+    assert_eq!(gpio.drive(0, false), Ok(()));
+    assert!(!gpio.get_gpio_state(0).unwrap().value);
+    gpio.release(0);
+    assert!(gpio.get_gpio_state(0).unwrap().value);
+
+    // A floating pin keeps its level.
+    assert!(gpio.command(GPIO_ENABLE_INPUT, 0, 0).is_success());
+    assert!(gpio.get_gpio_state(0).unwrap().value);
+    gpio.set_board_pull(0, PullMode::PullDown);
+    assert!(!gpio.get_gpio_state(0).unwrap().value);
+    gpio.set_board_pull(0, PullMode::PullNone);
+    assert!(!gpio.get_gpio_state(0).unwrap().value);
+}
+
+// Two open-drain outputs on a bus with a pull-up, like I2C's SDA line.
+#[test]
+fn open_drain_bus() {
+    use fake::SyscallDriver;
+    let gpio = Gpio::<3>::new();
+    gpio.connect(0, 1);
+    gpio.connect(1, 2);
+    gpio.set_board_pull(2, PullMode::PullUp);
+    for pin in [0, 1] {
+        gpio.set_open_drain(pin, true);
+        assert!(gpio.command(GPIO_ENABLE_OUTPUT, pin, 0).is_success());
+        assert!(gpio.command(GPIO_SET, pin, 0).is_success());
+    }
+    let bus = gpio.record(2);
+    assert!(bus.level());
+
+    assert!(gpio.command(GPIO_CLEAR, 0, 0).is_success());
+    assert!(!bus.level());
+    // Either device can hold the line low without a short circuit.
+    assert!(gpio.command(GPIO_CLEAR, 1, 0).is_success());
+    assert!(gpio.command(GPIO_SET, 0, 0).is_success());
+    assert!(!bus.level());
+    assert!(gpio.command(GPIO_SET, 1, 0).is_success());
+    assert!(bus.level());
+    assert_eq!(bus.take_levels(), [false, true]);
+}
+
+#[test]
+#[should_panic = "GPIO short circuit: pins [0, 1] are driven both high and low"]
+fn short_circuit() {
+    use fake::SyscallDriver;
+    let gpio = Gpio::<2>::new();
+    gpio.connect(0, 1);
+    assert!(gpio.command(GPIO_ENABLE_OUTPUT, 0, 0).is_success());
+    let _ = gpio.drive(1, true);
+}
+
+// set_value is a one-shot level change, so the process can still drive the pin
+// afterwards.
+#[test]
+fn set_value_then_output() {
+    use fake::SyscallDriver;
+    let gpio = Gpio::<2>::new();
+    assert!(gpio.command(GPIO_ENABLE_OUTPUT, 0, 0).is_success());
+    assert_eq!(gpio.set_value(0, true), Ok(()));
+    assert!(gpio.get_gpio_state(0).unwrap().value);
+    assert!(gpio.command(GPIO_CLEAR, 0, 0).is_success());
+    assert!(!gpio.get_gpio_state(0).unwrap().value);
+
+    // A level set on an input lasts until something on its net changes.
+    assert!(gpio.command(GPIO_ENABLE_INPUT, 1, 2).is_success());
+    assert_eq!(gpio.set_value(1, true), Ok(()));
+    assert!(gpio.command(GPIO_SET, 0, 0).is_success());
+    assert!(gpio.get_gpio_state(1).unwrap().value);
+    gpio.set_board_pull(1, PullMode::PullDown);
+    assert!(!gpio.get_gpio_state(1).unwrap().value);
+}
+
+#[test]
+fn button_bounce() {
+    use core::time::Duration;
+    use libtock_platform::Syscalls;
+    let kernel = fake::Kernel::new();
+    let alarm = fake::Alarm::new(1000);
+    let gpio = Gpio::<1>::new();
+    kernel.add_driver(&alarm);
+    kernel.add_driver(&gpio);
+    let timeline = fake::Timeline::new(&alarm);
+
+    let button = gpio.add_button(0, false);
+    let recorder = gpio.record_with_clock(0, &alarm);
+    assert!(fake::Syscalls::command(DRIVER_NUM, GPIO_ENABLE_INPUT, 0, 1).is_success());
+    assert!(fake::Syscalls::command(DRIVER_NUM, GPIO_ENABLE_INTERRUPTS, 0, 2).is_success());
+    // The pull-up raised the line.
+    assert_eq!(recorder.take_levels(), [true]);
+
+    let listener = Cell::<Option<(u32, u32)>>::new(None);
+    let mut interrupts = 0;
+    share::scope(|subscribe| {
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, &listener)
+            .unwrap();
+        button.press_with_bounce(&timeline, 2, Duration::from_millis(1));
+        assert!(button.is_pressed());
+        timeline.advance(Duration::from_millis(10));
+        while fake::Syscalls::yield_no_wait() == YieldNoWaitReturn::Upcall {
+            interrupts += 1;
+        }
+    });
+    assert!(button.is_pressed());
+    // Three falling edges: the first contact and one per bounce.
+    assert_eq!(interrupts, 3);
+    assert_eq!(listener.get(), Some((0, 0)));
+    let ms = Duration::from_millis;
+    assert_eq!(
+        recorder.take_changes(),
+        [
+            (ms(0), false),
+            (ms(1), true),
+            (ms(2), false),
+            (ms(3), true),
+            (ms(4), false)
+        ]
+    );
+
+    button.release();
+    assert!(recorder.level());
+}
@@ -0,0 +1,152 @@
+//! A fake I2C bus, used by the fake I2C master drivers (`fake::I2cMaster` and
+//! `fake::I2cMasterSlave`) to route transfers to fake I2C devices.
+//!
+//! Devices implement the `I2cDevice` trait and are attached to the bus at an
+//! address. Transfers to an address with no device attached fail with
+//! `ErrorCode::NoAck`, as they would on a real bus. `I2cRegisterMap` is a
+//! ready-made device modelling the common "write a register pointer, then read
+//! or write registers" protocol used by most I2C sensors and EEPROMs.
+
+use core::cell::{Cell, RefCell};
+use libtock_platform::ErrorCode;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A fake device attached to a `fake::I2cBus`.
+pub trait I2cDevice: 'static {
+    /// Called when the bus master writes `data` to this device.
+    fn write(&self, data: &[u8]) -> Result<(), ErrorCode>;
+
+    /// Called when the bus master reads `buffer.len()` bytes from this device.
+    fn read(&self, buffer: &mut [u8]) -> Result<(), ErrorCode>;
+
+    /// Called for a combined write-then-read transfer (a write followed by a
+    /// repeated start and a read). The default implementation performs the
+    /// write followed by the read.
+    fn write_read(&self, data: &[u8], buffer: &mut [u8]) -> Result<(), ErrorCode> {
+        self.write(data)?;
+        self.read(buffer)
+    }
+}
+
+#[derive(Default)]
+pub struct I2cBus {
+    devices: RefCell<HashMap<u16, Rc<dyn I2cDevice>>>,
+}
+
+impl I2cBus {
+    pub fn new() -> Rc<I2cBus> {
+        Rc::new(Default::default())
+    }
+
+    /// Attaches `device` to the bus at `address`, replacing any device that was
+    /// previously attached there.
+    pub fn add_device<D: I2cDevice>(&self, address: u16, device: &Rc<D>) {
+        self.devices
+            .borrow_mut()
+            .insert(address, device.clone() as Rc<dyn I2cDevice>);
+    }
+
+    /// Detaches the device at `address`, if there is one.
+    pub fn remove_device(&self, address: u16) {
+        self.devices.borrow_mut().remove(&address);
+    }
+
+    pub fn write(&self, address: u16, data: &[u8]) -> Result<(), ErrorCode> {
+        self.device(address)?.write(data)
+    }
+
+    pub fn read(&self, address: u16, buffer: &mut [u8]) -> Result<(), ErrorCode> {
+        self.device(address)?.read(buffer)
+    }
+
+    pub fn write_read(
+        &self,
+        address: u16,
+        data: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), ErrorCode> {
+        self.device(address)?.write_read(data, buffer)
+    }
+
+    // Clones the device out of the map so that the device is free to access the
+    // bus while it handles the transfer.
+    fn device(&self, address: u16) -> Result<Rc<dyn I2cDevice>, ErrorCode> {
+        self.devices
+            .borrow()
+            .get(&address)
+            .cloned()
+            .ok_or(ErrorCode::NoAck)
+    }
+}
+
+/// A fake I2C device with a bank of byte-wide registers. The first byte of
+/// each write selects the register pointer, and the remaining bytes are
+/// written to consecutive registers. Reads return consecutive registers
+/// starting at the register pointer. The pointer wraps around at the end of
+/// the register bank.
+pub struct I2cRegisterMap {
+    registers: RefCell<Vec<u8>>,
+    pointer: Cell<usize>,
+}
+
+impl I2cRegisterMap {
+    pub fn new(register_count: usize) -> Rc<I2cRegisterMap> {
+        assert!(register_count > 0, "I2cRegisterMap needs a register");
+        Rc::new(I2cRegisterMap {
+            registers: RefCell::new(vec![0; register_count]),
+            pointer: Cell::new(0),
+        })
+    }
+
+    /// Returns a copy of the register bank.
+    pub fn registers(&self) -> Vec<u8> {
+        self.registers.borrow().clone()
+    }
+
+    /// Sets consecutive registers, starting at `start`, without moving the
+    /// register pointer.
+    pub fn set_registers(&self, start: usize, values: &[u8]) {
+        let mut registers = self.registers.borrow_mut();
+        let len = registers.len();
+        for (i, &value) in values.iter().enumerate() {
+            registers[(start + i) % len] = value;
+        }
+    }
+
+    fn next_register(&self) -> usize {
+        let register = self.pointer.get();
+        self.pointer
+            .set((register + 1) % self.registers.borrow().len());
+        register
+    }
+}
+
+impl I2cDevice for I2cRegisterMap {
+    fn write(&self, data: &[u8]) -> Result<(), ErrorCode> {
+        let (&pointer, values) = match data.split_first() {
+            Some(split) => split,
+            None => return Ok(()),
+        };
+        if pointer as usize >= self.registers.borrow().len() {
+            return Err(ErrorCode::Invalid);
+        }
+        self.pointer.set(pointer as usize);
+        for &value in values {
+            let register = self.next_register();
+            self.registers.borrow_mut()[register] = value;
+        }
+        Ok(())
+    }
+
+    fn read(&self, buffer: &mut [u8]) -> Result<(), ErrorCode> {
+        for byte in buffer {
+            let register = self.next_register();
+            *byte = self.registers.borrow()[register];
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
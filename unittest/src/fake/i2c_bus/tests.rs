@@ -0,0 +1,38 @@
+use crate::fake::{I2cBus, I2cRegisterMap};
+use libtock_platform::ErrorCode;
+
+#[test]
+fn missing_device() {
+    let bus = I2cBus::new();
+    assert_eq!(bus.write(0x10, &[1, 2]), Err(ErrorCode::NoAck));
+    assert_eq!(bus.read(0x10, &mut [0; 2]), Err(ErrorCode::NoAck));
+
+    let device = I2cRegisterMap::new(4);
+    bus.add_device(0x10, &device);
+    assert_eq!(bus.write(0x10, &[1, 2]), Ok(()));
+    bus.remove_device(0x10);
+    assert_eq!(bus.write(0x10, &[1, 2]), Err(ErrorCode::NoAck));
+}
+
+#[test]
+fn register_map() {
+    let bus = I2cBus::new();
+    let device = I2cRegisterMap::new(4);
+    bus.add_device(0x10, &device);
+
+    // Write registers 2 and 3, then wrap around to register 0.
+    assert_eq!(bus.write(0x10, &[2, 0xaa, 0xbb, 0xcc]), Ok(()));
+    assert_eq!(device.registers(), [0xcc, 0, 0xaa, 0xbb]);
+
+    let mut buf = [0; 3];
+    assert_eq!(bus.write_read(0x10, &[3], &mut buf), Ok(()));
+    assert_eq!(buf, [0xbb, 0xcc, 0]);
+
+    // Reads continue from where the previous transfer left the pointer.
+    device.set_registers(2, &[0x12]);
+    let mut buf = [0; 1];
+    assert_eq!(bus.read(0x10, &mut buf), Ok(()));
+    assert_eq!(buf, [0x12]);
+
+    assert_eq!(bus.write(0x10, &[4]), Err(ErrorCode::Invalid));
+}
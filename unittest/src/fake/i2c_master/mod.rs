@@ -0,0 +1,126 @@
+//! Fake implementation of the I2C master API, documented here:
+//! https://github.com/tock/tock/blob/master/doc/syscalls/20003_i2c_master.md
+//!
+//! Like the real API, `I2cMaster` performs transfers on an I2C bus using a
+//! single shared buffer. The bus is a `fake::I2cBus`, to which tests attach
+//! fake devices. Transfers complete immediately: the upcall is queued by the
+//! command that started the transfer.
+
+use core::cell::RefCell;
+use libtock_platform::{CommandReturn, ErrorCode};
+use std::rc::Rc;
+
+use crate::fake::I2cBus;
+use crate::{DriverInfo, DriverShareRef, RwAllowBuffer};
+
+pub struct I2cMaster {
+    bus: Rc<I2cBus>,
+    buffer: RefCell<RwAllowBuffer>,
+    share_ref: DriverShareRef,
+}
+
+impl I2cMaster {
+    /// Creates an `I2cMaster` connected to a new, empty bus.
+    pub fn new() -> Rc<I2cMaster> {
+        Self::new_with_bus(I2cBus::new())
+    }
+
+    pub fn new_with_bus(bus: Rc<I2cBus>) -> Rc<I2cMaster> {
+        Rc::new(I2cMaster {
+            bus,
+            buffer: Default::default(),
+            share_ref: Default::default(),
+        })
+    }
+
+    /// Returns the bus this driver is connected to, for attaching devices.
+    pub fn bus(&self) -> &I2cBus {
+        &self.bus
+    }
+
+    // Schedules the completion upcall for a transfer with the given result.
+    fn complete(&self, result: Result<(), ErrorCode>) -> CommandReturn {
+        let status = match result {
+            Ok(()) => 0,
+            Err(error) => error as u32,
+        };
+        self.share_ref
+            .schedule_upcall(SUBSCRIBE_CALLBACK, (0, status, 0))
+            .expect("Unable to schedule upcall");
+        crate::command_return::success()
+    }
+}
+
+impl crate::fake::SyscallDriver for I2cMaster {
+    fn info(&self) -> DriverInfo {
+        DriverInfo::new(DRIVER_NUM).upcall_count(1)
+    }
+
+    fn register(&self, share_ref: DriverShareRef) {
+        self.share_ref.replace(share_ref);
+    }
+
+    fn allow_readwrite(
+        &self,
+        buffer_num: u32,
+        buffer: RwAllowBuffer,
+    ) -> Result<RwAllowBuffer, (RwAllowBuffer, ErrorCode)> {
+        if buffer_num == ALLOW_BUFFER {
+            Ok(self.buffer.replace(buffer))
+        } else {
+            Err((buffer, ErrorCode::Invalid))
+        }
+    }
+
+    fn command(&self, command_num: u32, argument0: u32, argument1: u32) -> CommandReturn {
+        // Like the real capsule, the address is the low byte of argument 0.
+        let address = (argument0 & 0xff) as u16;
+        let mut buffer = self.buffer.borrow_mut();
+        match command_num {
+            EXISTS => crate::command_return::success(),
+            WRITE => {
+                let len = argument1 as usize;
+                if len > buffer.len() {
+                    return crate::command_return::failure(ErrorCode::Size);
+                }
+                self.complete(self.bus.write(address, &buffer[..len]))
+            }
+            READ => {
+                let len = argument1 as usize;
+                if len > buffer.len() {
+                    return crate::command_return::failure(ErrorCode::Size);
+                }
+                self.complete(self.bus.read(address, &mut buffer[..len]))
+            }
+            WRITE_READ => {
+                let write_len = (argument0 >> 8) as usize;
+                let read_len = argument1 as usize;
+                if write_len > buffer.len() || read_len > buffer.len() {
+                    return crate::command_return::failure(ErrorCode::Size);
+                }
+                // The real capsule writes from and reads into the same buffer.
+                let data = buffer[..write_len].to_vec();
+                self.complete(self.bus.write_read(address, &data, &mut buffer[..read_len]))
+            }
+            _ => crate::command_return::failure(ErrorCode::NoSupport),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
+
+// -----------------------------------------------------------------------------
+// Driver number and command IDs
+// -----------------------------------------------------------------------------
+
+const DRIVER_NUM: u32 = 0x20003;
+
+// Command IDs
+const EXISTS: u32 = 0;
+const WRITE: u32 = 1;
+const READ: u32 = 2;
+const WRITE_READ: u32 = 3;
+
+const SUBSCRIBE_CALLBACK: u32 = 0;
+const ALLOW_BUFFER: u32 = 1;
@@ -0,0 +1,65 @@
+use crate::fake::{self, SyscallDriver};
+use crate::RwAllowBuffer;
+use fake::i2c_master::*;
+use libtock_platform::{share, AllowRw, DefaultConfig, Subscribe, YieldNoWaitReturn};
+
+// Tests the command implementation.
+#[test]
+fn command() {
+    let i2c = I2cMaster::new();
+    assert!(i2c.command(EXISTS, 0, 0).is_success());
+    assert!(i2c
+        .allow_readwrite(ALLOW_BUFFER, RwAllowBuffer::default())
+        .is_ok());
+    assert!(i2c.allow_readwrite(0, RwAllowBuffer::default()).is_err());
+    assert_eq!(
+        i2c.command(WRITE, 0x10, 1).get_failure(),
+        Some(ErrorCode::Size)
+    );
+    assert_eq!(
+        i2c.command(4, 0, 0).get_failure(),
+        Some(ErrorCode::NoSupport)
+    );
+}
+
+// Integration test that verifies I2cMaster works with fake::Kernel and
+// libtock_platform::Syscalls.
+#[test]
+fn kernel_integration() {
+    use libtock_platform::Syscalls;
+    let kernel = fake::Kernel::new();
+    let i2c = I2cMaster::new();
+    kernel.add_driver(&i2c);
+    let device = fake::I2cRegisterMap::new(8);
+    i2c.bus().add_device(0x20, &device);
+    device.set_registers(4, &[0x44, 0x55]);
+
+    let mut buf = [4, 0, 0];
+    let listener = core::cell::Cell::<Option<(u32, u32)>>::new(None);
+    share::scope::<
+        (
+            AllowRw<fake::Syscalls, DRIVER_NUM, ALLOW_BUFFER>,
+            Subscribe<fake::Syscalls, DRIVER_NUM, SUBSCRIBE_CALLBACK>,
+        ),
+        _,
+        _,
+    >(|handle| {
+        let (allow_rw, subscribe) = handle.split();
+        fake::Syscalls::allow_rw::<DefaultConfig, DRIVER_NUM, ALLOW_BUFFER>(allow_rw, &mut buf)
+            .unwrap();
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, SUBSCRIBE_CALLBACK>(
+            subscribe, &listener,
+        )
+        .unwrap();
+
+        assert!(fake::Syscalls::command(DRIVER_NUM, WRITE_READ, 1 << 8 | 0x20, 2).is_success());
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(listener.take(), Some((0, 0)));
+
+        // No device at 0x21.
+        assert!(fake::Syscalls::command(DRIVER_NUM, READ, 0x21, 1).is_success());
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(listener.take(), Some((0, ErrorCode::NoAck as u32)));
+    });
+    assert_eq!(buf, [0x44, 0x55, 0]);
+}
@@ -0,0 +1,276 @@
+//! Fake implementation of the I2C master/slave API, documented here:
+//! https://github.com/tock/tock/blob/master/doc/syscalls/20006_i2c_master_slave.md
+//!
+//! In master mode, `I2cMasterSlave` performs transfers on a `fake::I2cBus`,
+//! like `fake::I2cMaster`. In slave mode, the process is the device being
+//! addressed: tests simulate an external bus master with `add_master_write`
+//! and `add_master_read`. Those transfers are queued until the process is
+//! ready for them (by listening or by providing data to send), at which point
+//! they complete and the corresponding upcall is queued.
+
+use core::cell::{Cell, RefCell};
+use libtock_platform::{CommandReturn, ErrorCode};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use crate::fake::I2cBus;
+use crate::{DriverInfo, DriverShareRef, RoAllowBuffer, RwAllowBuffer};
+
+pub struct I2cMasterSlave {
+    bus: Rc<I2cBus>,
+
+    master_tx: Cell<RoAllowBuffer>,
+    master_rx: RefCell<RwAllowBuffer>,
+    slave_tx: Cell<RoAllowBuffer>,
+    slave_rx: RefCell<RwAllowBuffer>,
+
+    slave_address: Cell<Option<u8>>,
+    listening: Cell<bool>,
+    // The number of bytes the process has offered to send, set by the
+    // SLAVE_READ_SEND command.
+    send_len: Cell<Option<usize>>,
+
+    // Transfers initiated by the simulated external master.
+    pending_writes: RefCell<VecDeque<Vec<u8>>>,
+    pending_reads: RefCell<VecDeque<usize>>,
+    // The data sent by the process to the simulated external master.
+    sent: RefCell<Vec<Vec<u8>>>,
+
+    share_ref: DriverShareRef,
+}
+
+impl I2cMasterSlave {
+    /// Creates an `I2cMasterSlave` connected to a new, empty bus.
+    pub fn new() -> Rc<I2cMasterSlave> {
+        Self::new_with_bus(I2cBus::new())
+    }
+
+    pub fn new_with_bus(bus: Rc<I2cBus>) -> Rc<I2cMasterSlave> {
+        Rc::new(I2cMasterSlave {
+            bus,
+            master_tx: Default::default(),
+            master_rx: Default::default(),
+            slave_tx: Default::default(),
+            slave_rx: Default::default(),
+            slave_address: Cell::new(None),
+            listening: Cell::new(false),
+            send_len: Cell::new(None),
+            pending_writes: Default::default(),
+            pending_reads: Default::default(),
+            sent: Default::default(),
+            share_ref: Default::default(),
+        })
+    }
+
+    /// Returns the bus this driver is connected to, for attaching devices.
+    pub fn bus(&self) -> &I2cBus {
+        &self.bus
+    }
+
+    /// Returns the slave address set by the process, if any.
+    pub fn slave_address(&self) -> Option<u8> {
+        self.slave_address.get()
+    }
+
+    /// Simulates an external bus master writing `data` to `address`. The write
+    /// is ignored (NACKed) unless `address` is the process' slave address. It
+    /// is delivered once the process is listening.
+    pub fn add_master_write(&self, address: u8, data: &[u8]) {
+        if self.slave_address.get() != Some(address) {
+            return;
+        }
+        self.pending_writes.borrow_mut().push_back(data.into());
+        self.deliver_writes();
+    }
+
+    /// Simulates an external bus master reading `len` bytes from `address`.
+    /// The read is ignored (NACKed) unless `address` is the process' slave
+    /// address. It completes once the process provides data to send; the data
+    /// sent can be retrieved with `take_sent`.
+    pub fn add_master_read(&self, address: u8, len: usize) {
+        if self.slave_address.get() != Some(address) {
+            return;
+        }
+        self.pending_reads.borrow_mut().push_back(len);
+        self.deliver_reads();
+    }
+
+    /// Returns the data the process sent to the simulated external master, one
+    /// entry per completed read, and clears it.
+    pub fn take_sent(&self) -> Vec<Vec<u8>> {
+        self.sent.take()
+    }
+
+    fn deliver_writes(&self) {
+        if !self.listening.get() {
+            return;
+        }
+        while let Some(data) = self.pending_writes.borrow_mut().pop_front() {
+            let mut slave_rx = self.slave_rx.borrow_mut();
+            let len = core::cmp::min(data.len(), slave_rx.len());
+            slave_rx[..len].copy_from_slice(&data[..len]);
+            self.share_ref
+                .schedule_upcall(SUBSCRIBE_CALLBACK, (SLAVE_START_LISTEN, len as u32, 0))
+                .expect("Unable to schedule upcall");
+        }
+    }
+
+    fn deliver_reads(&self) {
+        let send_len = match self.send_len.get() {
+            Some(send_len) => send_len,
+            None => return,
+        };
+        let requested = match self.pending_reads.borrow_mut().pop_front() {
+            Some(requested) => requested,
+            None => return,
+        };
+        self.send_len.set(None);
+        let slave_tx = self.slave_tx.take();
+        let len = core::cmp::min(requested, core::cmp::min(send_len, slave_tx.len()));
+        self.sent.borrow_mut().push(slave_tx[..len].into());
+        self.slave_tx.set(slave_tx);
+        self.share_ref
+            .schedule_upcall(SUBSCRIBE_CALLBACK, (SLAVE_READ_SEND, len as u32, 0))
+            .expect("Unable to schedule upcall");
+    }
+}
+
+// Converts a transfer result into the status reported in upcalls.
+fn status(result: Result<(), ErrorCode>) -> u32 {
+    match result {
+        Ok(()) => 0,
+        Err(error) => error as u32,
+    }
+}
+
+impl crate::fake::SyscallDriver for I2cMasterSlave {
+    fn info(&self) -> DriverInfo {
+        DriverInfo::new(DRIVER_NUM).upcall_count(1)
+    }
+
+    fn register(&self, share_ref: DriverShareRef) {
+        self.share_ref.replace(share_ref);
+    }
+
+    fn allow_readonly(
+        &self,
+        buffer_num: u32,
+        buffer: RoAllowBuffer,
+    ) -> Result<RoAllowBuffer, (RoAllowBuffer, ErrorCode)> {
+        match buffer_num {
+            ALLOW_MASTER_TX => Ok(self.master_tx.replace(buffer)),
+            ALLOW_SLAVE_TX => Ok(self.slave_tx.replace(buffer)),
+            _ => Err((buffer, ErrorCode::Invalid)),
+        }
+    }
+
+    fn allow_readwrite(
+        &self,
+        buffer_num: u32,
+        buffer: RwAllowBuffer,
+    ) -> Result<RwAllowBuffer, (RwAllowBuffer, ErrorCode)> {
+        match buffer_num {
+            ALLOW_MASTER_RX => Ok(self.master_rx.replace(buffer)),
+            ALLOW_SLAVE_RX => Ok(self.slave_rx.replace(buffer)),
+            _ => Err((buffer, ErrorCode::Invalid)),
+        }
+    }
+
+    fn command(&self, command_num: u32, argument0: u32, _argument1: u32) -> CommandReturn {
+        match command_num {
+            EXISTS => {}
+            MASTER_WRITE => {
+                let address = (argument0 & 0xffff) as u16;
+                let len = (argument0 >> 16) as usize;
+                let master_tx = self.master_tx.take();
+                let result = match master_tx.get(..len) {
+                    Some(data) => self.bus.write(address, data),
+                    None => Err(ErrorCode::Size),
+                };
+                self.master_tx.set(master_tx);
+                self.share_ref
+                    .schedule_upcall(SUBSCRIBE_CALLBACK, (UPCALL_MASTER_WRITE, status(result), 0))
+                    .expect("Unable to schedule upcall");
+            }
+            MASTER_READ => {
+                let address = (argument0 & 0xffff) as u16;
+                let len = (argument0 >> 16) as usize;
+                let mut master_rx = self.master_rx.borrow_mut();
+                let result = match master_rx.get_mut(..len) {
+                    Some(buffer) => self.bus.read(address, buffer),
+                    None => Err(ErrorCode::Size),
+                };
+                self.share_ref
+                    .schedule_upcall(
+                        SUBSCRIBE_CALLBACK,
+                        (UPCALL_MASTER_READ, len as u32, status(result)),
+                    )
+                    .expect("Unable to schedule upcall");
+            }
+            MASTER_WRITE_READ => {
+                let address = (argument0 & 0xff) as u16;
+                let read_len = ((argument0 >> 8) & 0xff) as usize;
+                let write_len = (argument0 >> 16) as usize;
+                let master_tx = self.master_tx.take();
+                let mut master_rx = self.master_rx.borrow_mut();
+                let result = match (master_tx.get(..write_len), master_rx.get_mut(..read_len)) {
+                    (Some(data), Some(buffer)) => self.bus.write_read(address, data, buffer),
+                    _ => Err(ErrorCode::Size),
+                };
+                self.master_tx.set(master_tx);
+                self.share_ref
+                    .schedule_upcall(
+                        SUBSCRIBE_CALLBACK,
+                        (MASTER_WRITE_READ, read_len as u32, status(result)),
+                    )
+                    .expect("Unable to schedule upcall");
+            }
+            SLAVE_SET_ADDR => {
+                if argument0 > 0x7f {
+                    return crate::command_return::failure(ErrorCode::Invalid);
+                }
+                self.slave_address.set(Some(argument0 as u8));
+            }
+            SLAVE_START_LISTEN => {
+                self.listening.set(true);
+                self.deliver_writes();
+            }
+            SLAVE_READ_SEND => {
+                self.send_len.set(Some(argument0 as usize));
+                self.deliver_reads();
+            }
+            _ => return crate::command_return::failure(ErrorCode::NoSupport),
+        }
+        crate::command_return::success()
+    }
+}
+
+#[cfg(test)]
+mod tests;
+
+// -----------------------------------------------------------------------------
+// Driver number and command IDs
+// -----------------------------------------------------------------------------
+
+const DRIVER_NUM: u32 = 0x20006;
+
+// Command IDs
+const EXISTS: u32 = 0;
+const MASTER_WRITE: u32 = 1;
+const MASTER_READ: u32 = 2;
+const SLAVE_START_LISTEN: u32 = 3;
+const SLAVE_READ_SEND: u32 = 4;
+const SLAVE_SET_ADDR: u32 = 6;
+const MASTER_WRITE_READ: u32 = 7;
+
+const SUBSCRIBE_CALLBACK: u32 = 0;
+
+// The first upcall argument identifies the operation that completed. For the
+// master write and read operations, the capsule does not use the command ID.
+const UPCALL_MASTER_WRITE: u32 = 0;
+const UPCALL_MASTER_READ: u32 = 1;
+
+const ALLOW_MASTER_TX: u32 = 0;
+const ALLOW_MASTER_RX: u32 = 1;
+const ALLOW_SLAVE_TX: u32 = 2;
+const ALLOW_SLAVE_RX: u32 = 3;
@@ -0,0 +1,135 @@
+use crate::fake::{self, SyscallDriver};
+use crate::{RoAllowBuffer, RwAllowBuffer};
+use fake::i2c_master_slave::*;
+use libtock_platform::{share, AllowRo, AllowRw, DefaultConfig, Subscribe, YieldNoWaitReturn};
+
+// Tests the command implementation.
+#[test]
+fn command() {
+    let i2c = I2cMasterSlave::new();
+    assert!(i2c.command(EXISTS, 0, 0).is_success());
+    for buffer_num in [ALLOW_MASTER_TX, ALLOW_SLAVE_TX] {
+        assert!(i2c
+            .allow_readonly(buffer_num, RoAllowBuffer::default())
+            .is_ok());
+    }
+    assert!(i2c.allow_readonly(1, RoAllowBuffer::default()).is_err());
+    for buffer_num in [ALLOW_MASTER_RX, ALLOW_SLAVE_RX] {
+        assert!(i2c
+            .allow_readwrite(buffer_num, RwAllowBuffer::default())
+            .is_ok());
+    }
+    assert!(i2c.allow_readwrite(0, RwAllowBuffer::default()).is_err());
+
+    assert_eq!(
+        i2c.command(SLAVE_SET_ADDR, 0x80, 0).get_failure(),
+        Some(ErrorCode::Invalid)
+    );
+    assert_eq!(i2c.slave_address(), None);
+    assert!(i2c.command(SLAVE_SET_ADDR, 0x42, 0).is_success());
+    assert_eq!(i2c.slave_address(), Some(0x42));
+}
+
+// Integration test for master mode.
+#[test]
+fn master() {
+    use libtock_platform::Syscalls;
+    let kernel = fake::Kernel::new();
+    let i2c = I2cMasterSlave::new();
+    kernel.add_driver(&i2c);
+    let device = fake::I2cRegisterMap::new(4);
+    i2c.bus().add_device(0x20, &device);
+
+    let listener = core::cell::Cell::<Option<(u32, u32, u32)>>::new(None);
+    let mut rx = [0; 2];
+    share::scope::<
+        (
+            AllowRo<fake::Syscalls, DRIVER_NUM, ALLOW_MASTER_TX>,
+            AllowRw<fake::Syscalls, DRIVER_NUM, ALLOW_MASTER_RX>,
+            Subscribe<fake::Syscalls, DRIVER_NUM, SUBSCRIBE_CALLBACK>,
+        ),
+        _,
+        _,
+    >(|handle| {
+        let (allow_ro, allow_rw, subscribe) = handle.split();
+        fake::Syscalls::allow_ro::<DefaultConfig, DRIVER_NUM, ALLOW_MASTER_TX>(
+            allow_ro,
+            &[1, 0xaa, 0xbb],
+        )
+        .unwrap();
+        fake::Syscalls::allow_rw::<DefaultConfig, DRIVER_NUM, ALLOW_MASTER_RX>(allow_rw, &mut rx)
+            .unwrap();
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, SUBSCRIBE_CALLBACK>(
+            subscribe, &listener,
+        )
+        .unwrap();
+
+        assert!(fake::Syscalls::command(DRIVER_NUM, MASTER_WRITE, 3 << 16 | 0x20, 0).is_success());
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(listener.take(), Some((UPCALL_MASTER_WRITE, 0, 0)));
+        assert_eq!(device.registers(), [0, 0xaa, 0xbb, 0]);
+
+        let arg0 = 1 << 16 | 2 << 8 | 0x20;
+        assert!(fake::Syscalls::command(DRIVER_NUM, MASTER_WRITE_READ, arg0, 0).is_success());
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(listener.take(), Some((MASTER_WRITE_READ, 2, 0)));
+
+        assert!(fake::Syscalls::command(DRIVER_NUM, MASTER_READ, 1 << 16 | 0x30, 0).is_success());
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(
+            listener.take(),
+            Some((UPCALL_MASTER_READ, 1, ErrorCode::NoAck as u32))
+        );
+    });
+    assert_eq!(rx, [0xaa, 0xbb]);
+}
+
+// Integration test for slave mode.
+#[test]
+fn slave() {
+    use libtock_platform::Syscalls;
+    let kernel = fake::Kernel::new();
+    let i2c = I2cMasterSlave::new();
+    kernel.add_driver(&i2c);
+
+    // Transfers to other addresses, or before an address is set, are ignored.
+    i2c.add_master_write(0x42, b"ignored");
+    assert!(fake::Syscalls::command(DRIVER_NUM, SLAVE_SET_ADDR, 0x42, 0).is_success());
+    i2c.add_master_write(0x43, b"ignored");
+    i2c.add_master_write(0x42, b"hello");
+    i2c.add_master_read(0x42, 2);
+
+    let listener = core::cell::Cell::<Option<(u32, u32, u32)>>::new(None);
+    let mut rx = [0; 4];
+    share::scope::<
+        (
+            AllowRo<fake::Syscalls, DRIVER_NUM, ALLOW_SLAVE_TX>,
+            AllowRw<fake::Syscalls, DRIVER_NUM, ALLOW_SLAVE_RX>,
+            Subscribe<fake::Syscalls, DRIVER_NUM, SUBSCRIBE_CALLBACK>,
+        ),
+        _,
+        _,
+    >(|handle| {
+        let (allow_ro, allow_rw, subscribe) = handle.split();
+        fake::Syscalls::allow_ro::<DefaultConfig, DRIVER_NUM, ALLOW_SLAVE_TX>(allow_ro, b"xyz")
+            .unwrap();
+        fake::Syscalls::allow_rw::<DefaultConfig, DRIVER_NUM, ALLOW_SLAVE_RX>(allow_rw, &mut rx)
+            .unwrap();
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, SUBSCRIBE_CALLBACK>(
+            subscribe, &listener,
+        )
+        .unwrap();
+
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+        assert!(fake::Syscalls::command(DRIVER_NUM, SLAVE_START_LISTEN, 0, 0).is_success());
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(listener.take(), Some((SLAVE_START_LISTEN, 4, 0)));
+
+        assert!(fake::Syscalls::command(DRIVER_NUM, SLAVE_READ_SEND, 3, 0).is_success());
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(listener.take(), Some((SLAVE_READ_SEND, 2, 0)));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+    });
+    assert_eq!(&rx, b"hell");
+    assert_eq!(i2c.take_sent(), [b"xy".to_vec()]);
+}
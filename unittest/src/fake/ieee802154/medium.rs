@@ -0,0 +1,502 @@
+//! A shared virtual radio medium that links fake IEEE 802.15.4 radios.
+
+use core::time::Duration;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::{Rc, Weak};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, ThreadId};
+
+use super::Ieee802154Phy;
+use crate::fake::Alarm;
+
+/// A `Medium` carries frames between the fake radios attached to it. Each
+/// radio is either an `Ieee802154Phy` driving a process under test, or a
+/// `Peer` that the test drives directly.
+///
+/// A transmitted frame is delivered to every other node whose radio is on and
+/// tuned to the sender's channel and, unless disabled with
+/// `set_address_filtering`, whose PAN ID and address match the frame's
+/// destination (broadcast PAN IDs and short addresses always match). Frames
+/// may be dropped at random with `set_loss`, and delayed with `set_latency`.
+///
+/// Frames sent to a radio on the same thread are written into the process'
+/// receive buffer right away if it has one allowed. Otherwise, they are held
+/// until the process next yield-waits with no upcall queued, as if they had
+/// arrived while it slept.
+///
+/// `Medium` is `Send` and `Sync`: cloning it and moving the clone to another
+/// thread with its own `fake::Kernel` links processes running on different
+/// threads. A process that yield-waits with no upcall queued waits for a radio
+/// on another thread to send it a frame. It stops waiting once every radio on
+/// other threads is idle, i.e. dropped or itself waiting with no frames to
+/// receive, as no frame can arrive after that. `set_receive_timeout` also
+/// limits the wait to a fixed amount of real time.
+///
+/// # Example
+/// ```
+/// use libtock_unittest::fake::{self, ieee802154::Medium};
+///
+/// let kernel = fake::Kernel::new();
+/// let radio = fake::Ieee802154Phy::new();
+/// kernel.add_driver(&radio);
+///
+/// let medium = Medium::new();
+/// medium.attach(&radio);
+/// let peer = medium.add_peer();
+/// // Frames the process transmits (once it turns the radio on) are received
+/// // by `peer`, and frames `peer` transmits are written into the process'
+/// // receive buffer. This is a broadcast data frame.
+/// peer.transmit(&[0x41, 0x88, 0, 0xff, 0xff, 0xff, 0xff, b'h', b'i']);
+/// ```
+#[derive(Clone)]
+pub struct Medium {
+    shared: Arc<Shared>,
+}
+
+/// A node on a `Medium` that is driven by the test rather than by a process.
+/// Peers are created with their radio on, on channel 0, with PAN ID and
+/// addresses 0, matching the defaults of `fake::Ieee802154Phy`.
+#[derive(Clone)]
+pub struct Peer {
+    medium: Medium,
+    id: usize,
+}
+
+impl Medium {
+    pub fn new() -> Medium {
+        Medium {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    nodes: Vec::new(),
+                    loss: 0.0,
+                    rng: DEFAULT_SEED,
+                    latency: Duration::ZERO,
+                    address_filtering: true,
+                    receive_timeout: None,
+                    lost_frames: 0,
+                }),
+                radio_changed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Attaches `radio` to this medium. Frames sent to `radio` are delivered
+    /// immediately.
+    pub fn attach(&self, radio: &Rc<Ieee802154Phy>) {
+        self.attach_radio(radio, None);
+    }
+
+    /// Attaches `radio` to this medium. Frames sent to `radio` are delayed by
+    /// the medium's latency, measured on `clock`.
+    pub fn attach_with_clock(&self, radio: &Rc<Ieee802154Phy>, clock: &Rc<Alarm>) {
+        self.attach_radio(radio, Some(clock.clone()));
+    }
+
+    /// Adds a test-driven node to this medium.
+    pub fn add_peer(&self) -> Peer {
+        let id = self.add_node(
+            NodeConfig {
+                radio_on: true,
+                ..Default::default()
+            },
+            Endpoint::Peer {
+                received: Vec::new(),
+            },
+        );
+        Peer {
+            medium: self.clone(),
+            id,
+        }
+    }
+
+    /// Sets the probability (between 0 and 1) that each delivery of a frame
+    /// to a node is lost. Losses are pseudorandom, but deterministic for a
+    /// given seed (see `set_seed`).
+    pub fn set_loss(&self, probability: f64) {
+        assert!(
+            (0.0..=1.0).contains(&probability),
+            "loss probability must be between 0 and 1"
+        );
+        self.lock().loss = probability;
+    }
+
+    /// Seeds the pseudorandom number generator used to decide which frames
+    /// are lost.
+    pub fn set_seed(&self, seed: u64) {
+        // Xorshift requires a nonzero state.
+        self.lock().rng = seed.max(1);
+    }
+
+    /// Sets the delay between a frame being sent and it arriving at radios
+    /// attached with `attach_with_clock`. Peers and radios without a clock
+    /// receive frames immediately.
+    pub fn set_latency(&self, latency: Duration) {
+        self.lock().latency = latency;
+    }
+
+    /// Enables or disables PAN ID and destination address filtering. It is
+    /// enabled by default.
+    pub fn set_address_filtering(&self, enabled: bool) {
+        self.lock().address_filtering = enabled;
+    }
+
+    /// Limits how long (in real time) a process that yield-waits waits for a
+    /// radio on another thread to send it a frame. By default, there is no
+    /// limit: it waits until a frame arrives or the other radios are idle.
+    pub fn set_receive_timeout(&self, timeout: Duration) {
+        self.lock().receive_timeout = Some(timeout);
+    }
+
+    /// Returns the number of frame deliveries dropped by `set_loss`.
+    pub fn lost_frames(&self) -> usize {
+        self.lock().lost_frames
+    }
+
+    // -------------------------------------------------------------------------
+    // Functions used by Ieee802154Phy.
+    // -------------------------------------------------------------------------
+
+    pub(super) fn set_config(&self, id: usize, config: NodeConfig) {
+        self.lock().nodes[id].config = config;
+    }
+
+    // Sends `frame` from node `id`, then delivers it to the receiving radios
+    // on the current thread.
+    pub(super) fn transmit(&self, id: usize, frame: &[u8]) {
+        let mut local_receivers = Vec::new();
+        {
+            let mut state = self.lock();
+            let state = &mut *state;
+            let sender = state.nodes[id].config;
+            if !sender.radio_on {
+                return;
+            }
+            let current_thread = thread::current().id();
+            for (receiver_id, node) in state.nodes.iter_mut().enumerate() {
+                let detached = matches!(
+                    node.endpoint,
+                    Endpoint::Radio {
+                        status: RadioStatus::Detached,
+                        ..
+                    }
+                );
+                if receiver_id == id
+                    || detached
+                    || !node.config.radio_on
+                    || node.config.channel != sender.channel
+                    || (state.address_filtering && !node.config.accepts(frame))
+                {
+                    continue;
+                }
+                if state.loss > 0.0 && next_f64(&mut state.rng) < state.loss {
+                    state.lost_frames += 1;
+                    continue;
+                }
+                match &mut node.endpoint {
+                    Endpoint::Radio { thread, inbox, .. } => {
+                        inbox.push_back(Vec::from(frame));
+                        if *thread == current_thread {
+                            local_receivers.push(receiver_id);
+                        }
+                    }
+                    Endpoint::Peer { received } => received.push(Vec::from(frame)),
+                }
+            }
+        }
+        self.shared.radio_changed.notify_all();
+        for radio in LOCAL_RADIOS
+            .with_borrow(|radios| radios.iter().filter_map(Weak::upgrade).collect::<Vec<_>>())
+        {
+            if radio
+                .attachment_id(self)
+                .is_some_and(|id| local_receivers.contains(&id))
+            {
+                radio.poll_medium();
+            }
+        }
+    }
+
+    // Removes and returns the frames waiting for node `id`. If there are none
+    // and `wait` is true, waits for a radio on another thread to send one,
+    // until the radios on other threads are idle.
+    pub(super) fn take_frames(&self, id: usize, wait: bool) -> Vec<Vec<u8>> {
+        let mut state = self.lock();
+        if wait && state.inbox(id).is_empty() && state.has_remote_radios(id) {
+            state.set_status(id, RadioStatus::Waiting);
+            // Radios waiting on other threads may be waiting for this one to
+            // become idle.
+            self.shared.radio_changed.notify_all();
+            let keep_waiting =
+                |state: &mut State| state.inbox(id).is_empty() && !state.remote_radios_idle(id);
+            state = match state.receive_timeout {
+                None => self
+                    .shared
+                    .radio_changed
+                    .wait_while(state, keep_waiting)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()),
+                Some(timeout) => {
+                    self.shared
+                        .radio_changed
+                        .wait_timeout_while(state, timeout, keep_waiting)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .0
+                }
+            };
+            state.set_status(id, RadioStatus::Running);
+        }
+        state.inbox(id).drain(..).collect()
+    }
+
+    // Marks node `id` as detached, so it stops receiving frames and radios on
+    // other threads stop waiting for it. Called when its radio is dropped, on
+    // the radio's thread.
+    pub(super) fn detach(&self, id: usize) {
+        {
+            let mut state = self.lock();
+            state.set_status(id, RadioStatus::Detached);
+            state.inbox(id).clear();
+        }
+        self.shared.radio_changed.notify_all();
+        // The thread's radios may already be gone if the thread is exiting.
+        let _ = LOCAL_RADIOS
+            .try_with(|radios| radios.borrow_mut().retain(|radio| radio.strong_count() > 0));
+    }
+
+    pub(super) fn latency(&self) -> Duration {
+        self.lock().latency
+    }
+
+    pub(super) fn ptr_eq(&self, other: &Medium) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+
+    fn attach_radio(&self, radio: &Rc<Ieee802154Phy>, clock: Option<Rc<Alarm>>) {
+        let id = self.add_node(
+            radio.node_config(),
+            Endpoint::Radio {
+                thread: thread::current().id(),
+                inbox: VecDeque::new(),
+                status: RadioStatus::Running,
+            },
+        );
+        radio.set_attachment(self.clone(), id, Rc::downgrade(radio), clock);
+        LOCAL_RADIOS.with_borrow_mut(|radios| radios.push(Rc::downgrade(radio)));
+    }
+
+    fn add_node(&self, config: NodeConfig, endpoint: Endpoint) -> usize {
+        let mut state = self.lock();
+        state.nodes.push(Node { config, endpoint });
+        state.nodes.len() - 1
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // A panic in another test thread should not hide this thread's result.
+        self.shared
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for Medium {
+    fn default() -> Medium {
+        Medium::new()
+    }
+}
+
+impl Peer {
+    pub fn set_channel(&self, channel: u8) {
+        self.update(|config| config.channel = channel);
+    }
+
+    pub fn set_pan(&self, pan: u16) {
+        self.update(|config| config.pan = pan);
+    }
+
+    pub fn set_address_short(&self, address: u16) {
+        self.update(|config| config.address_short = address);
+    }
+
+    pub fn set_address_long(&self, address: u64) {
+        self.update(|config| config.address_long = address);
+    }
+
+    pub fn set_radio_on(&self, on: bool) {
+        self.update(|config| config.radio_on = on);
+    }
+
+    /// Sends `frame` (a MAC frame without the FCS) on the medium.
+    pub fn transmit(&self, frame: &[u8]) {
+        self.medium.transmit(self.id, frame);
+    }
+
+    /// Returns the frames this peer has received so far, and clears them.
+    pub fn take_received(&self) -> Vec<Vec<u8>> {
+        match &mut self.medium.lock().nodes[self.id].endpoint {
+            Endpoint::Peer { received } => core::mem::take(received),
+            Endpoint::Radio { .. } => unreachable!("Peer refers to a radio"),
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut NodeConfig)) {
+        f(&mut self.medium.lock().nodes[self.id].config);
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Implementation details below
+// -----------------------------------------------------------------------------
+
+struct Shared {
+    state: Mutex<State>,
+    // Notified whenever a frame is sent or a radio starts waiting or is
+    // detached, to wake radios waiting for a frame.
+    radio_changed: Condvar,
+}
+
+struct State {
+    nodes: Vec<Node>,
+    loss: f64,
+    rng: u64,
+    latency: Duration,
+    address_filtering: bool,
+    receive_timeout: Option<Duration>,
+    lost_frames: usize,
+}
+
+impl State {
+    fn inbox(&mut self, id: usize) -> &mut VecDeque<Vec<u8>> {
+        match &mut self.nodes[id].endpoint {
+            Endpoint::Radio { inbox, .. } => inbox,
+            Endpoint::Peer { .. } => unreachable!("Ieee802154Phy refers to a peer"),
+        }
+    }
+
+    fn set_status(&mut self, id: usize, new_status: RadioStatus) {
+        if let Endpoint::Radio { status, .. } = &mut self.nodes[id].endpoint {
+            *status = new_status;
+        }
+    }
+
+    // Returns the radios other than `id` that are attached on another thread.
+    fn remote_radios(&self, id: usize) -> impl Iterator<Item = &Endpoint> {
+        let current_thread = thread::current().id();
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(move |&(node_id, node)| {
+                node_id != id
+                    && matches!(node.endpoint, Endpoint::Radio { thread, .. } if thread != current_thread)
+            })
+            .map(|(_, node)| &node.endpoint)
+    }
+
+    // Returns whether a radio other than `id` is attached on another thread,
+    // and so may send a frame while this thread waits.
+    fn has_remote_radios(&self, id: usize) -> bool {
+        self.remote_radios(id).next().is_some()
+    }
+
+    // Returns whether no radio on another thread can send a frame: each one
+    // is detached, or is waiting with no frames to wake it up.
+    fn remote_radios_idle(&self, id: usize) -> bool {
+        self.remote_radios(id).all(|endpoint| match endpoint {
+            Endpoint::Radio { status, inbox, .. } => match status {
+                RadioStatus::Running => false,
+                RadioStatus::Waiting => inbox.is_empty(),
+                RadioStatus::Detached => true,
+            },
+            Endpoint::Peer { .. } => true,
+        })
+    }
+}
+
+struct Node {
+    config: NodeConfig,
+    endpoint: Endpoint,
+}
+
+enum Endpoint {
+    Radio {
+        thread: ThreadId,
+        inbox: VecDeque<Vec<u8>>,
+        status: RadioStatus,
+    },
+    Peer {
+        received: Vec<Vec<u8>>,
+    },
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum RadioStatus {
+    // The radio's process is running, and may send a frame.
+    Running,
+    // The radio's process is yield-waiting for a frame.
+    Waiting,
+    // The radio has been dropped.
+    Detached,
+}
+
+#[derive(Clone, Copy, Default)]
+pub(super) struct NodeConfig {
+    pub channel: u8,
+    pub pan: u16,
+    pub address_short: u16,
+    pub address_long: u64,
+    pub radio_on: bool,
+}
+
+impl NodeConfig {
+    // Returns whether a radio with this configuration accepts `frame`, based
+    // on the destination PAN ID and address in its MAC header. Frames without
+    // a destination address (e.g. beacons) are always accepted; frames too
+    // short to contain the destination they claim are not.
+    fn accepts(&self, frame: &[u8]) -> bool {
+        let Some(&[fcf_low, fcf_high]) = frame.get(..2) else {
+            return false;
+        };
+        let frame_control = u16::from_le_bytes([fcf_low, fcf_high]);
+        let address_len = match (frame_control >> 10) & 0b11 {
+            0b10 => 2,
+            0b11 => 8,
+            _ => return true,
+        };
+        // The destination PAN ID follows the frame control field and the
+        // sequence number.
+        let Some(destination) = frame.get(3..5 + address_len) else {
+            return false;
+        };
+        let pan = u16::from_le_bytes([destination[0], destination[1]]);
+        if pan != BROADCAST && pan != self.pan {
+            return false;
+        }
+        let address = &destination[2..];
+        match address_len {
+            2 => {
+                let address = u16::from_le_bytes([address[0], address[1]]);
+                address == BROADCAST || address == self.address_short
+            }
+            _ => u64::from_le_bytes(address.try_into().unwrap()) == self.address_long,
+        }
+    }
+}
+
+// The broadcast PAN ID and short address.
+const BROADCAST: u16 = 0xffff;
+
+const DEFAULT_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+// Returns a pseudorandom number in [0, 1) using xorshift64.
+fn next_f64(state: &mut u64) -> f64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    (*state >> 11) as f64 / (1u64 << 53) as f64
+}
+
+thread_local! {
+    // The radios attached to a medium on this thread, which receive frames as
+    // soon as they are sent. Dropped radios are removed when they detach.
+    pub(super) static LOCAL_RADIOS: RefCell<Vec<Weak<Ieee802154Phy>>> = const { RefCell::new(Vec::new()) };
+}
@@ -1,4 +1,9 @@
 //! Fake implementation of the raw IEEE 802.15.4 API.
+//!
+//! On its own, `Ieee802154Phy` models one radio in isolation: the test queues
+//! frames for it to receive and inspects the frames it transmitted. Attaching
+//! it to a `Medium` links it to other radios, so that frames it transmits are
+//! received by them and vice versa.
 
 use core::cell::Cell;
 use libtock_platform::{CommandReturn, ErrorCode};
@@ -9,8 +14,14 @@ use std::{
     rc::{self, Rc},
 };
 
+use crate::fake::Alarm;
 use crate::{command_return, DriverInfo, DriverShareRef, RoAllowBuffer, RwAllowBuffer};
 
+mod medium;
+
+use medium::NodeConfig;
+pub use medium::{Medium, Peer};
+
 /// Maximum length of a MAC frame.
 const MAX_MTU: usize = 127;
 
@@ -56,9 +67,24 @@ pub struct Ieee802154Phy {
 
     frames_to_be_received: RefCell<VecDeque<Frame>>,
 
+    medium: RefCell<Option<Attachment>>,
+    // Frames dropped because no valid receive buffer was allowed.
+    rx_dropped: Cell<usize>,
+    // Frames overwritten in the receive ring buffer before the process read
+    // them.
+    rx_overwritten: Cell<usize>,
+
     share_ref: DriverShareRef,
 }
 
+// This radio's connection to a `Medium`.
+struct Attachment {
+    medium: Medium,
+    id: usize,
+    radio: rc::Weak<Ieee802154Phy>,
+    clock: Option<Rc<Alarm>>,
+}
+
 // Needed for scheduling an receive upcall immediately after subscribing to it.
 // Without that,
 
@@ -89,6 +115,9 @@ impl Ieee802154Phy {
             rx_buf: Default::default(),
             transmitted_frames: Default::default(),
             frames_to_be_received: RefCell::new(frames_to_be_received.into_iter().collect()),
+            medium: Default::default(),
+            rx_dropped: Default::default(),
+            rx_overwritten: Default::default(),
             share_ref: Default::default(),
         })
     }
@@ -117,13 +146,29 @@ impl Ieee802154Phy {
         }
     }
 
+    /// Returns the number of frames received from a `Medium` that were dropped
+    /// because the process had no valid receive buffer allowed.
+    pub fn rx_dropped(&self) -> usize {
+        self.rx_dropped.get()
+    }
+
+    /// Returns the number of frames that were overwritten in the process'
+    /// receive ring buffer by newer frames before the process read them.
+    pub fn rx_overwritten(&self) -> usize {
+        self.rx_overwritten.get()
+    }
+
     fn driver_receive_frame(&self, frame: &[u8]) {
         let mut rx_buf = self.rx_buf.borrow_mut();
-        Self::phy_driver_receive_frame(&mut rx_buf, frame);
+        match Self::phy_driver_receive_frame(&mut rx_buf, frame) {
+            RxOutcome::Dropped => self.rx_dropped.set(self.rx_dropped.get() + 1),
+            RxOutcome::Overwrote => self.rx_overwritten.set(self.rx_overwritten.get() + 1),
+            RxOutcome::Stored => {}
+        }
     }
 
     // Code taken and adapted from capsules/extra/src/ieee802154/phy_driver.rs.
-    fn phy_driver_receive_frame(rbuf: &mut [u8], frame: &[u8]) {
+    fn phy_driver_receive_frame(rbuf: &mut [u8], frame: &[u8]) -> RxOutcome {
         let frame_len = frame.len() - PSDU_OFFSET;
 
         ////////////////////////////////////////////////////////
@@ -165,8 +210,11 @@ impl Ieee802154Phy {
         // of length 1) may otherwise errantly pass the second
         // conditional check (due to unsigned integer
         // arithmetic).
-        assert!(rbuf.len() > RING_BUF_METADATA_SIZE);
-        assert!((rbuf.len() - RING_BUF_METADATA_SIZE) % USER_FRAME_MAX_SIZE == 0);
+        if rbuf.len() <= RING_BUF_METADATA_SIZE
+            || (rbuf.len() - RING_BUF_METADATA_SIZE) % USER_FRAME_MAX_SIZE != 0
+        {
+            return RxOutcome::Dropped;
+        }
 
         let mut read_index = rbuf[0] as usize;
         let mut write_index = rbuf[1] as usize;
@@ -208,14 +256,17 @@ impl Ieee802154Phy {
         // now at the read index + 1. We must update the read
         // index to reflect this.
         write_index = (write_index + 1) % max_pending_rx;
+        let mut outcome = RxOutcome::Stored;
         if write_index == read_index {
             read_index = (read_index + 1) % max_pending_rx;
             rbuf[0] = read_index as u8;
+            outcome = RxOutcome::Overwrote;
         }
 
         // Update write index metadata since we have added a
         // frame.
         rbuf[1] = write_index as u8;
+        outcome
     }
 
     pub fn trigger_rx_upcall(&self) {
@@ -223,6 +274,128 @@ impl Ieee802154Phy {
             .schedule_upcall(subscribe::FRAME_RECEIVED, (0, 0, 0))
             .expect("Unable to schedule upcall {}");
     }
+
+    // -------------------------------------------------------------------------
+    // Medium support, used by `Medium`.
+    // -------------------------------------------------------------------------
+
+    fn node_config(&self) -> NodeConfig {
+        // The driver's PAN ID and short address arguments have 1 added.
+        NodeConfig {
+            channel: self.chan.get(),
+            pan: self.pan.get().wrapping_sub(1),
+            address_short: self.addr_short.get().wrapping_sub(1),
+            address_long: self.addr_long.get(),
+            radio_on: self.radio_on.get(),
+        }
+    }
+
+    fn set_attachment(
+        &self,
+        medium: Medium,
+        id: usize,
+        radio: rc::Weak<Ieee802154Phy>,
+        clock: Option<Rc<Alarm>>,
+    ) {
+        let previous = self.medium.replace(Some(Attachment {
+            medium,
+            id,
+            radio,
+            clock,
+        }));
+        assert!(
+            previous.is_none(),
+            "Ieee802154Phy is already attached to a medium"
+        );
+    }
+
+    // Returns this radio's node ID on `medium`, if it is attached to it.
+    fn attachment_id(&self, medium: &Medium) -> Option<usize> {
+        self.medium
+            .borrow()
+            .as_ref()
+            .filter(|attachment| attachment.medium.ptr_eq(medium))
+            .map(|attachment| attachment.id)
+    }
+
+    // Tells the medium about configuration changes. Like the real driver,
+    // address, PAN ID and channel changes take effect when committed.
+    fn sync_config(&self) {
+        if let Some(attachment) = &*self.medium.borrow() {
+            attachment
+                .medium
+                .set_config(attachment.id, self.node_config());
+        }
+    }
+
+    // Receives the frames the medium has for this radio, waiting for one if
+    // `wait` is true. Returns whether any frames were received.
+    fn receive_from_medium(&self, wait: bool) -> bool {
+        let (frames, latency, clock, radio) = match &*self.medium.borrow() {
+            None => return false,
+            Some(attachment) => (
+                attachment.medium.take_frames(attachment.id, wait),
+                attachment.medium.latency(),
+                attachment.clock.clone(),
+                attachment.radio.clone(),
+            ),
+        };
+        if frames.is_empty() {
+            return false;
+        }
+        for frame in frames {
+            match &clock {
+                Some(clock) if !latency.is_zero() => {
+                    let at = clock.elapsed_ticks() + clock.duration_to_ticks(latency);
+                    let radio = radio.clone();
+                    clock.schedule_event(
+                        at,
+                        Box::new(move || {
+                            if let Some(radio) = radio.upgrade() {
+                                radio.deliver(&frame);
+                            }
+                        }),
+                    );
+                }
+                _ => self.deliver(&frame),
+            }
+        }
+        true
+    }
+
+    // Receives frames the medium has for this radio if the process is
+    // listening (i.e. has a receive buffer allowed). Otherwise, the frames
+    // are held until the process next waits for an upcall.
+    fn poll_medium(&self) {
+        if !self.rx_buf.borrow().is_empty() {
+            self.receive_from_medium(false);
+        }
+    }
+
+    // Writes `frame` into the process' receive buffer and notifies it.
+    fn deliver(&self, frame: &[u8]) {
+        let mut psdu = vec![0; PSDU_OFFSET];
+        psdu.extend_from_slice(frame);
+        self.driver_receive_frame(&psdu);
+        self.trigger_rx_upcall();
+    }
+}
+
+enum RxOutcome {
+    Stored,
+    // The ring buffer was full, so the oldest frame was overwritten.
+    Overwrote,
+    // The process had not allowed a valid buffer.
+    Dropped,
+}
+
+impl Drop for Ieee802154Phy {
+    fn drop(&mut self) {
+        // Radios on other threads no longer wait for this one.
+        if let Some(attachment) = self.medium.get_mut() {
+            attachment.medium.detach(attachment.id);
+        }
+    }
 }
 
 impl crate::fake::SyscallDriver for Ieee802154Phy {
@@ -260,7 +433,10 @@ impl crate::fake::SyscallDriver for Ieee802154Phy {
                 self.tx_power.set(i8::try_from(argument0 as i32).unwrap());
                 command_return::success()
             }
-            command::COMMIT_CFG => command_return::success(),
+            command::COMMIT_CFG => {
+                self.sync_config();
+                command_return::success()
+            }
             command::GET_SHORT_ADDR => command_return::success_u32(self.addr_short.get() as u32),
             command::GET_PAN => command_return::success_u32(self.pan.get() as u32),
             command::GET_CHAN => command_return::success_u32(self.chan.get() as u32),
@@ -273,16 +449,21 @@ impl crate::fake::SyscallDriver for Ieee802154Phy {
             command::GET_LONG_ADDR => command_return::success_u64(self.addr_long.get()),
             command::TURN_ON => {
                 self.radio_on.set(true);
+                self.sync_config();
                 command_return::success()
             }
             command::TURN_OFF => {
                 self.radio_on.set(false);
+                self.sync_config();
                 command_return::success()
             }
             command::TRANSMIT => {
                 let mut transmitted_frames = self.transmitted_frames.take();
                 let tx_buf = self.tx_buf.take();
                 transmitted_frames.push(Vec::from(tx_buf.as_ref()));
+                if let Some(attachment) = &*self.medium.borrow() {
+                    attachment.medium.transmit(attachment.id, &tx_buf);
+                }
 
                 self.tx_buf.set(tx_buf);
                 self.transmitted_frames.set(transmitted_frames);
@@ -296,6 +477,10 @@ impl crate::fake::SyscallDriver for Ieee802154Phy {
         }
     }
 
+    fn idle(&self) -> bool {
+        self.receive_from_medium(true)
+    }
+
     fn allow_readonly(
         &self,
         buffer_num: u32,
@@ -321,9 +506,8 @@ impl crate::fake::SyscallDriver for Ieee802154Phy {
     }
 }
 
-// -----------------------------------------------------------------------------
-// Driver number and command IDs
-// -----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests;
 
 // -----------------------------------------------------------------------------
 // Driver number and command IDs
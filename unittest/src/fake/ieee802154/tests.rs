@@ -0,0 +1,136 @@
+use super::Medium;
+use crate::fake;
+
+// Builds a data frame addressed to short address `address` on PAN `pan`.
+fn frame_to(pan: u16, address: u16, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x01, 0x08, 0];
+    frame.extend_from_slice(&pan.to_le_bytes());
+    frame.extend_from_slice(&address.to_le_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+#[test]
+fn filtering() {
+    let medium = Medium::new();
+    let sender = medium.add_peer();
+    let receiver = medium.add_peer();
+    receiver.set_pan(0xabcd);
+    receiver.set_address_short(0x0102);
+
+    sender.transmit(&frame_to(0xabcd, 0x0102, b"unicast"));
+    sender.transmit(&frame_to(0xabcd, 0xffff, b"broadcast"));
+    sender.transmit(&frame_to(0xffff, 0x0102, b"any pan"));
+    sender.transmit(&frame_to(0xabcd, 0x0103, b"other address"));
+    sender.transmit(&frame_to(0x1234, 0x0102, b"other pan"));
+    // Frames without a destination address are always accepted.
+    sender.transmit(&[0x00, 0x00, 0]);
+    // A truncated header is not.
+    sender.transmit(&[0x01, 0x08, 0, 0xcd]);
+    let received = receiver.take_received();
+    assert_eq!(received.len(), 4);
+    assert!(received[0].ends_with(b"unicast"));
+    assert!(received[1].ends_with(b"broadcast"));
+    assert!(received[2].ends_with(b"any pan"));
+    assert_eq!(received[3], [0x00, 0x00, 0]);
+    // The sender does not hear itself.
+    assert_eq!(sender.take_received(), Vec::<Vec<u8>>::new());
+
+    medium.set_address_filtering(false);
+    sender.transmit(&frame_to(0x1234, 0x0102, b"other pan"));
+    assert_eq!(receiver.take_received().len(), 1);
+}
+
+#[test]
+fn channels_and_power() {
+    let medium = Medium::new();
+    let sender = medium.add_peer();
+    let receiver = medium.add_peer();
+    sender.set_channel(11);
+    sender.transmit(b"\0\0\0");
+    assert!(receiver.take_received().is_empty());
+
+    receiver.set_channel(11);
+    receiver.set_radio_on(false);
+    sender.transmit(b"\0\0\0");
+    assert!(receiver.take_received().is_empty());
+
+    receiver.set_radio_on(true);
+    sender.transmit(b"\0\0\0");
+    assert_eq!(receiver.take_received().len(), 1);
+}
+
+#[test]
+fn loss() {
+    let received = |seed| {
+        let medium = Medium::new();
+        medium.set_seed(seed);
+        medium.set_loss(0.5);
+        let sender = medium.add_peer();
+        let receiver = medium.add_peer();
+        for i in 0..100u8 {
+            sender.transmit(&[0, 0, i]);
+        }
+        assert_eq!(medium.lost_frames() + receiver.take_received().len(), 100);
+        medium.lost_frames()
+    };
+    let lost = received(7);
+    assert!((25..75).contains(&lost), "lost {} of 100 frames", lost);
+    // Losses are deterministic for a given seed.
+    assert_eq!(received(7), lost);
+}
+
+// Verifies that a radio transmits to peers only when it is on, using its
+// committed configuration.
+#[test]
+fn radio_transmit() {
+    use fake::SyscallDriver;
+    let kernel = fake::Kernel::new();
+    let radio = fake::Ieee802154Phy::new();
+    kernel.add_driver(&radio);
+    let medium = Medium::new();
+    medium.attach(&radio);
+    let peer = medium.add_peer();
+
+    let transmit = |frame: &'static [u8]| {
+        use libtock_platform::{share, DefaultConfig, Syscalls};
+        share::scope(|allow_ro| {
+            fake::Syscalls::allow_ro::<DefaultConfig, 0x30001, 0>(allow_ro, frame).unwrap();
+            assert!(fake::Syscalls::command(0x30001, super::command::TRANSMIT, 0, 0).is_success());
+        });
+    };
+    transmit(b"\0\0off");
+    assert!(peer.take_received().is_empty());
+
+    assert!(radio.command(super::command::TURN_ON, 0, 0).is_success());
+    assert!(radio.command(super::command::SET_CHAN, 15, 0).is_success());
+    transmit(b"\0\0uncommitted");
+    assert_eq!(peer.take_received(), [b"\0\0uncommitted"]);
+
+    assert!(radio.command(super::command::COMMIT_CFG, 0, 0).is_success());
+    transmit(b"\0\0channel 15");
+    assert!(peer.take_received().is_empty());
+    peer.set_channel(15);
+    transmit(b"\0\0channel 15");
+    assert_eq!(peer.take_received(), [b"\0\0channel 15"]);
+}
+
+// Verifies that a dropped radio stops receiving frames and is forgotten by its
+// thread, so it does not leak into later tests.
+#[test]
+fn dropped_radio() {
+    use fake::SyscallDriver;
+    let medium = Medium::new();
+    let radio = fake::Ieee802154Phy::new();
+    medium.attach(&radio);
+    assert!(radio.command(super::command::TURN_ON, 0, 0).is_success());
+    let radio_count = || super::medium::LOCAL_RADIOS.with_borrow(Vec::len);
+    assert_eq!(radio_count(), 1);
+    drop(radio);
+    assert_eq!(radio_count(), 0);
+
+    // Every delivery is lost, so a delivery to the dropped radio would count.
+    medium.set_loss(1.0);
+    medium.add_peer().transmit(b"\0\0dropped");
+    assert_eq!(medium.lost_frames(), 0);
+}
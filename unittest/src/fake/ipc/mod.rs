@@ -0,0 +1,190 @@
+//! Fake implementation of Tock's inter-process communication (IPC) driver,
+//! for use by the processes of a `fake::Simulation`.
+//!
+//! IPC identifies a process by its `ProcessId` plus one, as subscribe number 0
+//! is reserved for the service upcall:
+//!
+//! - A service subscribes to upcall 0, which is invoked when a client notifies
+//!   it.
+//! - A client discovers a service by allowing the service's package name (its
+//!   process name in the simulation) as Read-Only buffer 0 and calling
+//!   command 1, which returns the service's ID.
+//! - A client shares a buffer with a service by allowing it as the Read-Write
+//!   buffer whose number is the service's ID, and subscribes to the upcall
+//!   whose number is the service's ID to be notified by that service.
+//! - Command 2 notifies the service whose ID is argument 0, and command 3
+//!   notifies the client whose ID is argument 0.
+//!
+//! Both notifications invoke the upcall with the notifier's ID and the length
+//! and address of the buffer the client shares with the service (or 0 and 0).
+//! On a 64-bit host the address does not fit in an upcall argument, so
+//! services access the client's buffer through `Ipc::read_client_buffer` and
+//! `Ipc::write_client_buffer` instead.
+
+use crate::fake::simulation::{self, IpcShare, Process, ProcessId};
+use crate::{DriverInfo, DriverShareRef, RoAllowBuffer, RwAllowBuffer};
+use core::cell::{Cell, RefCell};
+use libtock_platform::{CommandReturn, ErrorCode};
+use std::collections::HashMap;
+
+pub struct Ipc {
+    package_name: Cell<RoAllowBuffer>,
+    // The buffers shared with other processes. The key is the other process'
+    // IPC ID.
+    shared_buffers: RefCell<HashMap<u32, RwAllowBuffer>>,
+}
+
+impl Ipc {
+    pub fn new() -> std::rc::Rc<Ipc> {
+        std::rc::Rc::new(Ipc {
+            package_name: Default::default(),
+            shared_buffers: Default::default(),
+        })
+    }
+
+    /// Returns a copy of the buffer the client with IPC ID `client_id` shares
+    /// with the current process, or `None` if it does not share one.
+    pub fn read_client_buffer(&self, client_id: u32) -> Option<Vec<u8>> {
+        let share = self.client_buffer(client_id)?;
+        // Safety: The client shared this buffer with the kernel, so it does not
+        // access the buffer itself until it un-allows it, which it cannot do
+        // while this process runs.
+        Some(unsafe { core::slice::from_raw_parts(share.address, share.len) }.to_vec())
+    }
+
+    /// Copies `data` into the start of the buffer the client with IPC ID
+    /// `client_id` shares with the current process, truncating it if it does
+    /// not fit. Returns the number of bytes copied, or `None` if the client
+    /// does not share a buffer.
+    pub fn write_client_buffer(&self, client_id: u32, data: &[u8]) -> Option<usize> {
+        let share = self.client_buffer(client_id)?;
+        let count = data.len().min(share.len);
+        // Safety: As in read_client_buffer, nothing else accesses the buffer
+        // while this process runs.
+        unsafe { core::ptr::copy_nonoverlapping(data.as_ptr(), share.address, count) };
+        Some(count)
+    }
+
+    fn client_buffer(&self, client_id: u32) -> Option<IpcShare> {
+        let process = Process::current()?;
+        let client = peer(&process, client_id)?;
+        simulation::ipc_share(&client, &process)
+    }
+}
+
+impl crate::fake::SyscallDriver for Ipc {
+    fn info(&self) -> DriverInfo {
+        // One upcall for the service, and one per process it can be a client
+        // of.
+        let process_count = Process::current().map_or(0, |process| process.process_count());
+        DriverInfo::new(DRIVER_NUM).upcall_count(process_count as u32 + 1)
+    }
+
+    fn register(&self, _share_ref: DriverShareRef) {}
+
+    fn command(&self, command_id: u32, argument0: u32, _argument1: u32) -> CommandReturn {
+        let Some(process) = Process::current() else {
+            return crate::command_return::failure(ErrorCode::NoDevice);
+        };
+        let ipc_id = process.id().0 as u32 + 1;
+        match command_id {
+            DRIVER_CHECK => crate::command_return::success(),
+            DISCOVER => {
+                let name = self.package_name.take();
+                let service = core::str::from_utf8(&name)
+                    .ok()
+                    .and_then(|name| process.find(name));
+                self.package_name.set(name);
+                match service {
+                    Some(service) => crate::command_return::success_u32(service.id().0 as u32 + 1),
+                    None => crate::command_return::failure(ErrorCode::NoDevice),
+                }
+            }
+            NOTIFY_SERVICE => {
+                let Some(service) = peer(&process, argument0) else {
+                    return crate::command_return::failure(ErrorCode::Invalid);
+                };
+                let (address, len) = upcall_buffer(simulation::ipc_share(&process, &service));
+                service.schedule_upcall(DRIVER_NUM, 0, (ipc_id, len, address));
+                crate::command_return::success()
+            }
+            NOTIFY_CLIENT => {
+                let Some(client) = peer(&process, argument0) else {
+                    return crate::command_return::failure(ErrorCode::Invalid);
+                };
+                let (address, len) = upcall_buffer(simulation::ipc_share(&client, &process));
+                client.schedule_upcall(DRIVER_NUM, ipc_id, (ipc_id, len, address));
+                crate::command_return::success()
+            }
+            _ => crate::command_return::failure(ErrorCode::NoSupport),
+        }
+    }
+
+    fn allow_readonly(
+        &self,
+        buffer_num: u32,
+        buffer: RoAllowBuffer,
+    ) -> Result<RoAllowBuffer, (RoAllowBuffer, ErrorCode)> {
+        match buffer_num {
+            PACKAGE_NAME => Ok(self.package_name.replace(buffer)),
+            _ => Err((buffer, ErrorCode::Invalid)),
+        }
+    }
+
+    fn allow_readwrite(
+        &self,
+        buffer_num: u32,
+        buffer: RwAllowBuffer,
+    ) -> Result<RwAllowBuffer, (RwAllowBuffer, ErrorCode)> {
+        let Some(process) = Process::current() else {
+            return Err((buffer, ErrorCode::NoDevice));
+        };
+        let Some(service) = buffer_num
+            .checked_sub(1)
+            .and_then(|index| process.get(ProcessId(index as usize)))
+        else {
+            return Err((buffer, ErrorCode::Invalid));
+        };
+        let share = match buffer.len() {
+            0 => None,
+            len => Some(IpcShare {
+                address: buffer.address(),
+                len,
+            }),
+        };
+        simulation::set_ipc_share(&process, service.id(), share);
+        let previous = self.shared_buffers.borrow_mut().insert(buffer_num, buffer);
+        Ok(previous.unwrap_or_default())
+    }
+}
+
+// Returns the (address, length) upcall arguments describing `share`. The
+// address is truncated on a 64-bit host.
+fn upcall_buffer(share: Option<IpcShare>) -> (u32, u32) {
+    share.map_or((0, 0), |share| {
+        (share.address.addr() as u32, share.len as u32)
+    })
+}
+
+// Returns the running process with the given IPC ID.
+fn peer(process: &Process, ipc_id: u32) -> Option<Process> {
+    let peer = process.get(ProcessId(ipc_id.checked_sub(1)? as usize))?;
+    (!peer.has_exited()).then_some(peer)
+}
+
+#[cfg(test)]
+mod tests;
+
+// -----------------------------------------------------------------------------
+// Driver number and command IDs
+// -----------------------------------------------------------------------------
+
+const DRIVER_NUM: u32 = 0x10000;
+
+// Command IDs
+const DRIVER_CHECK: u32 = 0;
+const DISCOVER: u32 = 1;
+const NOTIFY_SERVICE: u32 = 2;
+const NOTIFY_CLIENT: u32 = 3;
+
+const PACKAGE_NAME: u32 = 0;
@@ -0,0 +1,76 @@
+use crate::fake::{self, SyscallDriver};
+use fake::ipc::*;
+use libtock_platform::{share, AllowRo, AllowRw, DefaultConfig, ErrorCode, Subscribe, Syscalls};
+use std::cell::Cell;
+
+// Outside of a simulation, there are no other processes to communicate with.
+#[test]
+fn command_without_simulation() {
+    let ipc = Ipc::new();
+    assert_eq!(
+        ipc.command(DRIVER_CHECK, 0, 0).get_failure(),
+        Some(ErrorCode::NoDevice)
+    );
+    assert_eq!(ipc.info().upcall_count, 1);
+}
+
+// A client discovers a service, shares a buffer with it, and exchanges
+// notifications with it.
+#[test]
+fn service_and_client() {
+    let mut simulation = fake::Simulation::new();
+    simulation.add_process("org.tock.echo", |kernel| {
+        let ipc = Ipc::new();
+        kernel.add_driver(&ipc);
+        let notified = Cell::new(None::<(u32, u32, u32)>);
+        share::scope::<Subscribe<_, DRIVER_NUM, 0>, _, _>(|subscribe| {
+            fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, &notified)
+                .unwrap();
+            // Sleeps until the client notifies it.
+            fake::Syscalls::yield_wait();
+        });
+        let (client, len, _) = notified.get().unwrap();
+        assert_eq!((client, len), (2, 4));
+        assert_eq!(ipc.read_client_buffer(client).unwrap(), b"ping");
+        assert_eq!(ipc.write_client_buffer(client, b"pong!"), Some(4));
+        assert!(fake::Syscalls::command(DRIVER_NUM, NOTIFY_CLIENT, client, 0).is_success());
+    });
+    simulation.add_process("client", |kernel| {
+        kernel.add_driver(&Ipc::new());
+        assert_eq!(
+            discover(b"org.tock.missing").get_failure(),
+            Some(ErrorCode::NoDevice)
+        );
+        let service = discover(b"org.tock.echo").get_success_u32().unwrap();
+        assert_eq!(service, 1);
+        let mut buffer = *b"ping";
+        let notified = Cell::new(None::<(u32, u32, u32)>);
+        share::scope::<(AllowRw<_, DRIVER_NUM, 1>, Subscribe<_, DRIVER_NUM, 1>), _, _>(|handle| {
+            let (allow_rw, subscribe) = handle.split();
+            fake::Syscalls::allow_rw::<DefaultConfig, DRIVER_NUM, 1>(allow_rw, &mut buffer)
+                .unwrap();
+            fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 1>(subscribe, &notified)
+                .unwrap();
+            assert!(fake::Syscalls::command(DRIVER_NUM, NOTIFY_SERVICE, service, 0).is_success());
+            fake::Syscalls::yield_wait();
+        });
+        assert_eq!(notified.get().map(|(id, len, _)| (id, len)), Some((1, 4)));
+        assert_eq!(&buffer, b"pong");
+        // The service has ended, so it can no longer be notified.
+        fake::Syscalls::yield_no_wait();
+        assert_eq!(
+            fake::Syscalls::command(DRIVER_NUM, NOTIFY_SERVICE, service, 0).get_failure(),
+            Some(ErrorCode::Invalid)
+        );
+    });
+    let outcomes = simulation.run();
+    assert_eq!(outcomes[0].exit, None);
+    assert_eq!(outcomes[1].exit, None);
+}
+
+fn discover(name: &[u8]) -> libtock_platform::CommandReturn {
+    share::scope::<AllowRo<_, DRIVER_NUM, 0>, _, _>(|allow_ro| {
+        fake::Syscalls::allow_ro::<DefaultConfig, DRIVER_NUM, 0>(allow_ro, name).unwrap();
+        fake::Syscalls::command(DRIVER_NUM, DISCOVER, 0, 0)
+    })
+}
@@ -1,5 +1,5 @@
 use crate::kernel_data::{with_kernel_data, DriverData, KernelData, KERNEL_DATA};
-use crate::{DriverShareRef, ExpectedSyscall, SyscallLogEntry};
+use crate::{DriverShareRef, Expectation, ExpectedSyscall, SyscallLogEntry};
 use std::cell::Cell;
 
 /// A fake implementation of the Tock kernel. Used with `fake::Syscalls`, which
@@ -35,9 +35,12 @@ impl Kernel {
                 create_location: std::panic::Location::caller(),
                 drivers: Default::default(),
                 expected_syscalls: Default::default(),
+                expectations: Default::default(),
                 syscall_log: Vec::new(),
                 upcall_queue: Default::default(),
                 memory_break: core::ptr::null(),
+                replayed_upcalls: Default::default(),
+                strict: None,
             }))
         });
         if let Some(old_kernel_data) = old_option {
@@ -96,15 +99,78 @@ impl Kernel {
     /// queue entry. If the system call matches, then the action defined by the
     /// expected syscall is taken. If the call does not match, the call panics
     /// (to make the unit test fail).
+    ///
+    /// For more flexible matching, see `expect`.
     pub fn add_expected_syscall(&self, expected_syscall: ExpectedSyscall) {
         with_kernel_data(|kernel_data| {
-            kernel_data
-                .unwrap()
-                .expected_syscalls
-                .push_back(expected_syscall)
+            let kernel_data = kernel_data.unwrap();
+            // Keeps the expected syscall in order with expectations queued
+            // before it.
+            match kernel_data.expectations.is_empty() {
+                true => kernel_data.expected_syscalls.push_back(expected_syscall),
+                false => kernel_data.expectations.push_back(expected_syscall.into()),
+            }
+        });
+    }
+
+    /// Adds an `Expectation` to the expected syscall queue. Expectations are
+    /// matched in the order they were added (including `ExpectedSyscall`s added
+    /// by `add_expected_syscall`), but may match calls with any value for some
+    /// arguments, a run of yields, several calls in any order, or repeated
+    /// calls. A system call that does not match the next expectation panics
+    /// with a description of both.
+    pub fn expect(&self, expectation: Expectation) {
+        with_kernel_data(|kernel_data| kernel_data.unwrap().expectations.push_back(expectation));
+    }
+
+    /// Panics if the expected syscall queue contains expectations that have
+    /// not been met.
+    #[track_caller]
+    pub fn assert_expectations_met(&self) {
+        with_kernel_data(|kernel_data| {
+            crate::expectation::assert_expectations_met(kernel_data.unwrap())
         });
     }
 
+    /// Enables or disables strict mode. In strict mode, the kernel checks
+    /// system calls and upcalls against the rules of TRD 104 (Tock's system
+    /// call ABI):
+    ///
+    /// - Memop models the process' memory: `brk` and `sbrk` fail with `NOMEM`
+    ///   if they would move the break outside of it, each operation returns
+    ///   the variant TRD 104 specifies, and unknown operations fail with
+    ///   `NOSUPPORT` rather than panicking.
+    /// - Each driver command must return a valid return variant, command 0
+    ///   must return Success or Success with u32, and a command must return
+    ///   the same success variant (and the same failure variant) every time.
+    /// - A successful Allow must return the buffer previously shared with that
+    ///   buffer number (or the null buffer), and a failed Allow must return the
+    ///   buffer that was passed in. Zero-length buffers are tracked like any
+    ///   other buffer, so their address is returned unchanged.
+    /// - Drivers may only schedule upcalls with subscribe numbers less than
+    ///   their upcall count.
+    ///
+    /// Violations of the rules a fake driver is responsible for cause a panic
+    /// that describes the rule and the location of the call that triggered it.
+    /// Strict mode is disabled by default.
+    pub fn set_strict(&self, strict: bool) {
+        with_kernel_data(|kernel_data| {
+            let kernel_data = kernel_data.unwrap();
+            if strict != kernel_data.strict.is_some() {
+                kernel_data.strict = strict.then(Default::default);
+                kernel_data.memory_break = match strict {
+                    true => crate::strict::APP_RAM_START as *const u8,
+                    false => core::ptr::null(),
+                };
+            }
+        });
+    }
+
+    /// Returns true if strict mode is enabled.
+    pub fn is_strict(&self) -> bool {
+        with_kernel_data(|kernel_data| kernel_data.unwrap().strict.is_some())
+    }
+
     /// Returns the system call log and empties it.
     pub fn take_syscall_log(&self) -> Vec<SyscallLogEntry> {
         with_kernel_data(|kernel_data| std::mem::take(&mut kernel_data.unwrap().syscall_log))
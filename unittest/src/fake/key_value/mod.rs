@@ -3,6 +3,7 @@ use libtock_platform::{CommandReturn, ErrorCode};
 use core::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::str;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 use crate::{DriverInfo, DriverShareRef, RoAllowBuffer, RwAllowBuffer};
 
@@ -14,11 +15,48 @@ pub struct KeyValue {
 
     share_ref: DriverShareRef,
 
-    database: RefCell<HashMap<String, String>>,
+    database: KeyValueStore,
+}
+
+/// The database behind one or more `KeyValue` drivers. `KeyValueStore` is
+/// `Send` and `Sync`, so drivers on different threads (such as the processes
+/// of a `fake::Simulation`) can share a namespace, as the processes on a Tock
+/// board share the key-value capsule's storage.
+#[derive(Clone, Default)]
+pub struct KeyValueStore {
+    database: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl KeyValueStore {
+    pub fn new() -> KeyValueStore {
+        Default::default()
+    }
+
+    /// Returns the value stored under `key`, if any.
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.lock().get(key).cloned()
+    }
+
+    /// Stores `value` under `key`, replacing any previous value.
+    pub fn set(&self, key: &str, value: &str) {
+        self.lock().insert(key.to_string(), value.to_string());
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, String>> {
+        // A panic in another test thread should not hide this thread's result.
+        self.database
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
 }
 
 impl KeyValue {
     pub fn new() -> std::rc::Rc<KeyValue> {
+        KeyValue::with_store(&KeyValueStore::new())
+    }
+
+    /// Creates a `KeyValue` driver that reads and writes `store`.
+    pub fn with_store(store: &KeyValueStore) -> std::rc::Rc<KeyValue> {
         std::rc::Rc::new(KeyValue {
             buffer_in_key: Default::default(),
             buffer_in_val: Default::default(),
@@ -26,9 +64,14 @@ impl KeyValue {
 
             share_ref: Default::default(),
 
-            database: Default::default(),
+            database: store.clone(),
         })
     }
+
+    /// Returns the store this driver reads and writes.
+    pub fn store(&self) -> KeyValueStore {
+        self.database.clone()
+    }
 }
 
 impl crate::fake::SyscallDriver for KeyValue {
@@ -70,7 +113,7 @@ impl crate::fake::SyscallDriver for KeyValue {
                 let k = self.buffer_in_key.take();
                 let k_str = str::from_utf8(&k).unwrap();
 
-                let db = self.database.take();
+                let db = self.database.lock();
                 match db.get(k_str) {
                     Some(val) => {
                         let cp_len = core::cmp::min(self.buffer_out_val.borrow().len(), val.len());
@@ -88,7 +131,6 @@ impl crate::fake::SyscallDriver for KeyValue {
                     }
                 }
                 self.buffer_in_key.set(k);
-                self.database.replace(db);
 
                 crate::command_return::success()
             }
@@ -98,12 +140,11 @@ impl crate::fake::SyscallDriver for KeyValue {
                 let v = self.buffer_in_val.take();
                 let v_str = str::from_utf8(&v).unwrap();
 
-                let mut db = self.database.take();
+                let mut db = self.database.lock();
                 db.insert(k_str.to_string(), v_str.to_string());
 
                 self.buffer_in_key.set(k);
                 self.buffer_in_val.set(v);
-                self.database.replace(db);
 
                 self.share_ref
                     .schedule_upcall(SUB_CALLBACK, (0, 0, 0))
@@ -117,7 +158,7 @@ impl crate::fake::SyscallDriver for KeyValue {
                 let v = self.buffer_in_val.take();
                 let v_str = str::from_utf8(&v).unwrap();
 
-                let mut db = self.database.take();
+                let mut db = self.database.lock();
 
                 let mut found = false;
                 if let Some(_val) = db.get(k_str) {
@@ -137,7 +178,6 @@ impl crate::fake::SyscallDriver for KeyValue {
 
                 self.buffer_in_key.set(k);
                 self.buffer_in_val.set(v);
-                self.database.replace(db);
 
                 crate::command_return::success()
             }
@@ -147,7 +187,7 @@ impl crate::fake::SyscallDriver for KeyValue {
                 let v = self.buffer_in_val.take();
                 let v_str = str::from_utf8(&v).unwrap();
 
-                let mut db = self.database.take();
+                let mut db = self.database.lock();
 
                 let mut found = false;
                 match db.get(k_str) {
@@ -171,7 +211,6 @@ impl crate::fake::SyscallDriver for KeyValue {
 
                 self.buffer_in_key.set(k);
                 self.buffer_in_val.set(v);
-                self.database.replace(db);
 
                 crate::command_return::success()
             }
@@ -179,7 +218,7 @@ impl crate::fake::SyscallDriver for KeyValue {
                 let k = self.buffer_in_key.take();
                 let k_str = str::from_utf8(&k).unwrap();
 
-                let mut db = self.database.take();
+                let mut db = self.database.lock();
 
                 match db.remove(k_str) {
                     Some(_val) => {
@@ -195,7 +234,6 @@ impl crate::fake::SyscallDriver for KeyValue {
                 }
 
                 self.buffer_in_key.set(k);
-                self.database.replace(db);
 
                 crate::command_return::success()
             }
@@ -4,11 +4,14 @@
 //! Like the real API, `Leds` controls a set of fake LEDs. It provides
 //! a function `get_led` used to retrieve the state of an LED.
 
-use crate::DriverInfo;
+use crate::fake::{BuiltDriver, DriverBuilder};
 use core::cell::Cell;
 use libtock_platform::{CommandReturn, ErrorCode};
 
-pub struct Leds<const LEDS_COUNT: usize> {
+pub type Leds<const LEDS_COUNT: usize> = BuiltDriver<LedsState<LEDS_COUNT>>;
+
+/// The state of a fake `Leds` driver.
+pub struct LedsState<const LEDS_COUNT: usize> {
     leds: [Cell<bool>; LEDS_COUNT],
 }
 
@@ -16,49 +19,30 @@ impl<const LEDS_COUNT: usize> Leds<LEDS_COUNT> {
     pub fn new() -> std::rc::Rc<Leds<LEDS_COUNT>> {
         #[allow(clippy::declare_interior_mutable_const)]
         const OFF: Cell<bool> = Cell::new(false);
-        std::rc::Rc::new(Leds {
+        let state = LedsState {
             leds: [OFF; LEDS_COUNT],
-        })
+        };
+        DriverBuilder::new(DRIVER_NUM, state)
+            .command(EXISTS, |_, _, _| {
+                crate::command_return::success_u32(LEDS_COUNT as u32)
+            })
+            .command(LED_ON, |leds, led, _| leds.update(led, |_| true))
+            .command(LED_OFF, |leds, led, _| leds.update(led, |_| false))
+            .command(LED_TOGGLE, |leds, led, _| leds.update(led, |on| !on))
+            .build()
     }
 
     pub fn get_led(&self, led: u32) -> Option<bool> {
         self.leds.get(led as usize).map(|led| led.get())
     }
-}
-
-impl<const LEDS_COUNT: usize> crate::fake::SyscallDriver for Leds<LEDS_COUNT> {
-    fn info(&self) -> DriverInfo {
-        DriverInfo::new(DRIVER_NUM)
-    }
 
-    fn command(&self, command_num: u32, argument0: u32, _argument1: u32) -> CommandReturn {
-        match command_num {
-            EXISTS => crate::command_return::success_u32(LEDS_COUNT as u32),
-            LED_ON => {
-                if argument0 < LEDS_COUNT as u32 {
-                    self.leds[argument0 as usize].set(true);
-                    crate::command_return::success()
-                } else {
-                    crate::command_return::failure(ErrorCode::Invalid)
-                }
-            }
-            LED_OFF => {
-                if argument0 < LEDS_COUNT as u32 {
-                    self.leds[argument0 as usize].set(false);
-                    crate::command_return::success()
-                } else {
-                    crate::command_return::failure(ErrorCode::Invalid)
-                }
-            }
-            LED_TOGGLE => {
-                if argument0 < LEDS_COUNT as u32 {
-                    self.leds[argument0 as usize].set(!self.leds[argument0 as usize].get());
-                    crate::command_return::success()
-                } else {
-                    crate::command_return::failure(ErrorCode::Invalid)
-                }
+    fn update(&self, led: u32, f: fn(bool) -> bool) -> CommandReturn {
+        match self.leds.get(led as usize) {
+            Some(led) => {
+                led.set(f(led.get()));
+                crate::command_return::success()
             }
-            _ => crate::command_return::failure(ErrorCode::NoSupport),
+            None => crate::command_return::failure(ErrorCode::Invalid),
         }
     }
 }
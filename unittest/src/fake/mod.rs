@@ -16,19 +16,29 @@ mod ambient_light;
 mod buttons;
 mod buzzer;
 mod console;
+mod driver_builder;
 mod gpio;
+mod i2c_bus;
+mod i2c_master;
+mod i2c_master_slave;
 pub mod ieee802154;
+mod ipc;
 mod kernel;
 mod key_value;
 mod leds;
 mod low_level_debug;
 mod ninedof;
 mod proximity;
+mod replay;
+mod rng;
 mod screen;
+pub mod simulation;
 mod sound_pressure;
+mod spi_controller;
 mod syscall_driver;
 mod syscalls;
 mod temperature;
+mod timeline;
 
 pub use adc::Adc;
 pub use air_quality::AirQuality;
@@ -37,19 +47,33 @@ pub use ambient_light::AmbientLight;
 pub use buttons::Buttons;
 pub use buzzer::Buzzer;
 pub use console::Console;
-pub use gpio::{Gpio, GpioMode, InterruptEdge, PullMode};
+pub use driver_builder::{BuiltDriver, DriverBuilder};
+pub use gpio::{Gpio, GpioMode, InterruptEdge, LevelRecorder, PullMode, PushButton};
+pub use i2c_bus::{I2cBus, I2cDevice, I2cRegisterMap};
+pub use i2c_master::I2cMaster;
+pub use i2c_master_slave::I2cMasterSlave;
 pub use ieee802154::Ieee802154Phy;
+pub use ipc::Ipc;
 pub use kernel::Kernel;
-pub use key_value::KeyValue;
+pub use key_value::{KeyValue, KeyValueStore};
 pub use leds::Leds;
 pub use low_level_debug::{LowLevelDebug, Message};
 pub use ninedof::{NineDof, NineDofData};
 pub use proximity::Proximity;
+pub use replay::{Replay, ReplayError};
+pub use rng::Rng;
 pub use screen::Screen;
+pub use simulation::{ProcessId, ProcessOutcome, Simulation};
 pub use sound_pressure::SoundPressure;
+pub use spi_controller::{SpiController, SpiDevice, SpiLoopback};
 pub use syscall_driver::SyscallDriver;
 pub use syscalls::Syscalls;
 pub use temperature::Temperature;
+pub use timeline::Timeline;
 
+#[cfg(test)]
+mod driver_builder_tests;
 #[cfg(test)]
 mod kernel_tests;
+#[cfg(test)]
+mod timeline_tests;
@@ -0,0 +1,333 @@
+//! `Replay` feeds a system call trace recorded on hardware (by
+//! `libtock_platform::trace::TracingSyscalls`) back into `fake::Kernel`.
+//!
+//! Each recorded system call becomes an `Expectation`, so the code under test
+//! must make the same system calls in the same order, and receives the
+//! recorded return values. Each recorded upcall is queued when the Yield call
+//! that invoked it on hardware is replayed. For every driver in the trace that
+//! does not already have a fake driver, `Replay` installs a stand-in driver
+//! that accepts Allow and Subscribe calls.
+//!
+//! Memop calls are expected with the recorded operation, but any argument, and
+//! are not given the recorded results: their arguments and results are
+//! addresses that are specific to the device's memory layout. The contents of
+//! allowed
+//! buffers are not recorded either, so data the kernel wrote into a buffer on
+//! hardware must be supplied by a fake driver if the test needs it.
+
+use crate::kernel_data::{with_kernel_data, ReplayedUpcall};
+use crate::upcall::UpcallId;
+use crate::{
+    command_return, fake, DriverInfo, Expectation, ExpectedSyscall, RoAllowBuffer, RwAllowBuffer,
+};
+use libtock_platform::trace::{DecodeError, Decoder, TraceEntry, DUMP_PREFIX};
+use libtock_platform::{return_variant, CommandReturn, ErrorCode, ReturnVariant};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+/// A decoded system call trace, ready to be installed into a `fake::Kernel`.
+///
+/// ```
+/// use libtock_platform::Syscalls;
+/// use libtock_unittest::fake;
+/// // A trace in which the app asked the LEDs driver (driver 2) how many LEDs
+/// // it has, and the kernel answered 4.
+/// let replay = fake::Replay::from_dump(
+///     "libtock-trace: 04020000008101040000\nlibtock-trace: end\n",
+/// )
+/// .unwrap();
+/// let kernel = fake::Kernel::new();
+/// replay.install(&kernel);
+/// let count = fake::Syscalls::command(2, 0, 0, 0);
+/// assert_eq!(count.get_success_u32(), Some(4));
+/// ```
+pub struct Replay {
+    entries: Vec<TraceEntry>,
+    truncated: bool,
+}
+
+/// Error returned when a trace cannot be loaded.
+#[derive(Debug, Eq, PartialEq, thiserror::Error)]
+pub enum ReplayError {
+    #[error("dump line {line} is not valid hex")]
+    InvalidHex { line: usize },
+
+    #[error("dump does not contain an end line")]
+    MissingEnd,
+
+    #[error("malformed trace: {0:?}")]
+    Decode(DecodeError),
+
+    #[error("trace entry {index} is an upcall that does not follow a Yield")]
+    MisplacedUpcall { index: usize },
+}
+
+impl Replay {
+    /// Parses the output of `libtock_platform::trace::dump`. Lines that do not
+    /// contain `DUMP_PREFIX` (such as other console output) are ignored, as is
+    /// any text before the prefix on a line.
+    pub fn from_dump(dump: &str) -> Result<Replay, ReplayError> {
+        let mut bytes = Vec::new();
+        for (line_num, line) in dump.lines().enumerate() {
+            let Some((_, data)) = line.split_once(DUMP_PREFIX) else {
+                continue;
+            };
+            match data.trim() {
+                "end" => return Self::decode(&bytes, false),
+                "end truncated" => return Self::decode(&bytes, true),
+                hex => bytes
+                    .extend(parse_hex(hex).ok_or(ReplayError::InvalidHex { line: line_num + 1 })?),
+            }
+        }
+        Err(ReplayError::MissingEnd)
+    }
+
+    /// Decodes a raw (binary) trace.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, ReplayError> {
+        Self::decode(bytes, false)
+    }
+
+    /// The recorded entries, in order.
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+
+    /// Returns true if the trace buffer filled up during recording, so the
+    /// trace ends before the app stopped making system calls.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Installs this trace into `kernel`: adds stand-in drivers for drivers
+    /// that are not already present, queues an `Expectation` (see
+    /// `fake::Kernel::expect`) for each recorded system call, and schedules the
+    /// recorded upcalls.
+    pub fn install(&self, kernel: &fake::Kernel) {
+        for (&driver_num, &upcall_count) in &self.driver_upcall_counts() {
+            if !fake::Kernel::is_driver_present(driver_num) {
+                kernel.add_driver(&Rc::new(ReplayDriver {
+                    driver_num,
+                    upcall_count,
+                    ro_buffers: Default::default(),
+                    rw_buffers: Default::default(),
+                }));
+            }
+        }
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            let expected_syscall = match *entry {
+                TraceEntry::YieldNoWait | TraceEntry::YieldWait => {
+                    let upcall = match self.entries.get(index + 1) {
+                        Some(&TraceEntry::Upcall {
+                            driver_num,
+                            subscribe_num,
+                            args,
+                        }) => Some(ReplayedUpcall {
+                            id: UpcallId {
+                                driver_num,
+                                subscribe_num,
+                            },
+                            args,
+                        }),
+                        _ => None,
+                    };
+                    with_kernel_data(|kernel_data| {
+                        kernel_data.unwrap().replayed_upcalls.push_back(upcall)
+                    });
+                    match entry {
+                        TraceEntry::YieldNoWait => ExpectedSyscall::YieldNoWait {
+                            override_return: None,
+                        },
+                        _ => ExpectedSyscall::YieldWait { skip_upcall: false },
+                    }
+                }
+                TraceEntry::Subscribe {
+                    driver_num,
+                    subscribe_num,
+                    error,
+                } => ExpectedSyscall::Subscribe {
+                    driver_num,
+                    subscribe_num,
+                    skip_with_error: error,
+                },
+                TraceEntry::Command {
+                    driver_id,
+                    command_id,
+                    argument0,
+                    argument1,
+                    return_variant,
+                    r1,
+                    r2,
+                    r3,
+                } => ExpectedSyscall::Command {
+                    driver_id,
+                    command_id,
+                    argument0,
+                    argument1,
+                    override_return: Some(to_command_return(return_variant, r1, r2, r3)),
+                },
+                TraceEntry::AllowRo {
+                    driver_num,
+                    buffer_num,
+                    error,
+                    ..
+                } => ExpectedSyscall::AllowRo {
+                    driver_num,
+                    buffer_num,
+                    return_error: error,
+                },
+                TraceEntry::AllowRw {
+                    driver_num,
+                    buffer_num,
+                    error,
+                    ..
+                } => ExpectedSyscall::AllowRw {
+                    driver_num,
+                    buffer_num,
+                    return_error: error,
+                },
+                TraceEntry::Exit {
+                    exit_num,
+                    completion_code,
+                } => ExpectedSyscall::Exit {
+                    exit_num,
+                    completion_code,
+                },
+                TraceEntry::Memop { memop_num, .. } => {
+                    kernel.expect(Expectation::memop(memop_num));
+                    continue;
+                }
+                TraceEntry::Upcall { .. } => continue,
+            };
+            kernel.expect(expected_syscall.into());
+        }
+    }
+
+    fn decode(bytes: &[u8], truncated: bool) -> Result<Replay, ReplayError> {
+        let entries = Decoder::new(bytes)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ReplayError::Decode)?;
+        for (index, entry) in entries.iter().enumerate() {
+            let follows_yield = index.checked_sub(1).is_some_and(|prev| {
+                matches!(
+                    entries[prev],
+                    TraceEntry::YieldNoWait | TraceEntry::YieldWait
+                )
+            });
+            if matches!(entry, TraceEntry::Upcall { .. }) && !follows_yield {
+                return Err(ReplayError::MisplacedUpcall { index });
+            }
+        }
+        Ok(Replay { entries, truncated })
+    }
+
+    // Returns every driver number in the trace, along with the number of
+    // upcalls its stand-in driver needs to support.
+    fn driver_upcall_counts(&self) -> BTreeMap<u32, u32> {
+        let mut counts = BTreeMap::new();
+        for entry in &self.entries {
+            let (driver_num, upcall_count) = match *entry {
+                TraceEntry::Subscribe {
+                    driver_num,
+                    subscribe_num,
+                    ..
+                }
+                | TraceEntry::Upcall {
+                    driver_num,
+                    subscribe_num,
+                    ..
+                } => (driver_num, subscribe_num + 1),
+                TraceEntry::Command { driver_id, .. } => (driver_id, 0),
+                TraceEntry::AllowRo { driver_num, .. } | TraceEntry::AllowRw { driver_num, .. } => {
+                    (driver_num, 0)
+                }
+                _ => continue,
+            };
+            let count = counts.entry(driver_num).or_insert(0);
+            *count = upcall_count.max(*count);
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests;
+
+// -----------------------------------------------------------------------------
+// Implementation details below.
+// -----------------------------------------------------------------------------
+
+// Stands in for a driver that appears in a trace but has no fake driver. The
+// expected syscall queue supplies the recorded results, so this only needs to
+// exist and accept Allow calls. Like a real driver, it holds each allowed buffer
+// until the next Allow call with the same buffer number swaps it out.
+struct ReplayDriver {
+    driver_num: u32,
+    upcall_count: u32,
+    ro_buffers: RefCell<HashMap<u32, RoAllowBuffer>>,
+    rw_buffers: RefCell<HashMap<u32, RwAllowBuffer>>,
+}
+
+impl fake::SyscallDriver for ReplayDriver {
+    fn info(&self) -> DriverInfo {
+        DriverInfo::new(self.driver_num).upcall_count(self.upcall_count)
+    }
+
+    fn command(&self, _command_id: u32, _argument0: u32, _argument1: u32) -> CommandReturn {
+        // Every replayed Command has an override_return, so this is only
+        // reached if the code under test diverges from the trace after the
+        // expected syscall queue empties.
+        command_return::failure(ErrorCode::NoSupport)
+    }
+
+    fn allow_readonly(
+        &self,
+        buffer_num: u32,
+        buffer: RoAllowBuffer,
+    ) -> Result<RoAllowBuffer, (RoAllowBuffer, ErrorCode)> {
+        let old = self.ro_buffers.borrow_mut().insert(buffer_num, buffer);
+        Ok(old.unwrap_or_default())
+    }
+
+    fn allow_readwrite(
+        &self,
+        buffer_num: u32,
+        buffer: RwAllowBuffer,
+    ) -> Result<RwAllowBuffer, (RwAllowBuffer, ErrorCode)> {
+        let old = self.rw_buffers.borrow_mut().insert(buffer_num, buffer);
+        Ok(old.unwrap_or_default())
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// Converts recorded CommandReturn registers into a CommandReturn. The trace
+// decoder guarantees failure variants carry a valid error code.
+fn to_command_return(return_variant: ReturnVariant, r1: u32, r2: u32, r3: u32) -> CommandReturn {
+    let error = || ErrorCode::try_from(r1).expect("invalid error code in trace");
+    let u64_value = u64::from(r2) | (u64::from(r3) << 32);
+    match return_variant {
+        return_variant::FAILURE => command_return::failure(error()),
+        return_variant::FAILURE_U32 => command_return::failure_u32(error(), r2),
+        return_variant::FAILURE_2_U32 => command_return::failure_2_u32(error(), r2, r3),
+        return_variant::FAILURE_U64 => command_return::failure_u64(error(), u64_value),
+        return_variant::SUCCESS => command_return::success(),
+        return_variant::SUCCESS_U32 => command_return::success_u32(r1),
+        return_variant::SUCCESS_2_U32 => command_return::success_2_u32(r1, r2),
+        return_variant::SUCCESS_U64 => {
+            command_return::success_u64(u64::from(r1) | (u64::from(r2) << 32))
+        }
+        return_variant::SUCCESS_3_U32 => command_return::success_3_u32(r1, r2, r3),
+        return_variant::SUCCESS_U32_U64 => command_return::success_u32_u64(r1, u64_value),
+        _ => panic!("unknown return variant {return_variant:?} in trace"),
+    }
+}
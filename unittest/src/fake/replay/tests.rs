@@ -0,0 +1,236 @@
+use crate::fake::{self, Replay, ReplayError};
+use crate::SyscallLogEntry;
+use libtock_platform::trace::{DecodeError, TraceEntry};
+use libtock_platform::{share, DefaultConfig, ErrorCode, Subscribe, Syscalls, YieldNoWaitReturn};
+use std::cell::Cell;
+
+// Subscribe 0 to driver 0x90000, yield-wait (upcall (1, 2, 3)), yield-no-wait
+// (no upcall), then a Command returning Failure(BUSY).
+const TRACE: &[u8] = &[
+    0x03, 0x80, 0x80, 0x24, 0x00, 0x00, //
+    0x01, //
+    0x02, 0x80, 0x80, 0x24, 0x00, 0x01, 0x02, 0x03, //
+    0x00, //
+    0x04, 0x80, 0x80, 0x24, 0x07, 0x05, 0x00, 0x00, 0x02, 0x00, 0x00,
+];
+
+#[test]
+fn from_bytes() {
+    let replay = Replay::from_bytes(TRACE).unwrap();
+    assert_eq!(
+        replay.entries(),
+        [
+            TraceEntry::Subscribe {
+                driver_num: 0x90000,
+                subscribe_num: 0,
+                error: None,
+            },
+            TraceEntry::YieldWait,
+            TraceEntry::Upcall {
+                driver_num: 0x90000,
+                subscribe_num: 0,
+                args: (1, 2, 3),
+            },
+            TraceEntry::YieldNoWait,
+            TraceEntry::Command {
+                driver_id: 0x90000,
+                command_id: 7,
+                argument0: 5,
+                argument1: 0,
+                return_variant: libtock_platform::return_variant::FAILURE,
+                r1: ErrorCode::Busy as u32,
+                r2: 0,
+                r3: 0,
+            },
+        ]
+    );
+    assert!(!replay.is_truncated());
+    assert_eq!(
+        Replay::from_bytes(&[0x02, 0, 0, 0, 0, 0]).err(),
+        Some(ReplayError::MisplacedUpcall { index: 0 })
+    );
+    assert_eq!(
+        Replay::from_bytes(&[0x04, 0x80]).err(),
+        Some(ReplayError::Decode(DecodeError::UnexpectedEnd))
+    );
+}
+
+#[test]
+fn from_dump() {
+    let dump = "booting\n\
+                [app] libtock-trace: 0380802400000102808024000102030004\n\
+                libtock-trace: 80802407050000\n\
+                unrelated output\n\
+                libtock-trace: 020000\n\
+                libtock-trace: end truncated\n";
+    let replay = Replay::from_dump(dump).unwrap();
+    assert_eq!(
+        replay.entries(),
+        Replay::from_bytes(TRACE).unwrap().entries()
+    );
+    assert!(replay.is_truncated());
+    assert_eq!(
+        Replay::from_dump("libtock-trace: 01\n").err(),
+        Some(ReplayError::MissingEnd)
+    );
+    assert_eq!(
+        Replay::from_dump("x\nlibtock-trace: 0g\nlibtock-trace: end\n").err(),
+        Some(ReplayError::InvalidHex { line: 2 })
+    );
+}
+
+#[test]
+fn install() {
+    let kernel = fake::Kernel::new();
+    Replay::from_bytes(TRACE).unwrap().install(&kernel);
+    assert!(fake::Kernel::is_driver_present(0x90000));
+
+    let called = Cell::new(None);
+    share::scope::<Subscribe<fake::Syscalls, 0x90000, 0>, _, _>(|subscribe| {
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, 0x90000, 0>(subscribe, &called).unwrap();
+        fake::Syscalls::yield_wait();
+        assert_eq!(called.get(), Some((1, 2, 3)));
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+        assert_eq!(
+            fake::Syscalls::command(0x90000, 7, 5, 0).get_failure(),
+            Some(ErrorCode::Busy)
+        );
+    });
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [
+            SyscallLogEntry::Subscribe {
+                driver_num: 0x90000,
+                subscribe_num: 0,
+            },
+            SyscallLogEntry::YieldWait,
+            SyscallLogEntry::YieldNoWait,
+            SyscallLogEntry::Command {
+                driver_id: 0x90000,
+                command_id: 7,
+                argument0: 5,
+                argument1: 0,
+            },
+            SyscallLogEntry::Subscribe {
+                driver_num: 0x90000,
+                subscribe_num: 0,
+            },
+        ]
+    );
+}
+
+#[test]
+fn existing_driver() {
+    // Drivers that already have a fake are left in place.
+    let kernel = fake::Kernel::new();
+    let rng = fake::Rng::new();
+    kernel.add_driver(&rng);
+    Replay::from_bytes(&[
+        0x04, 0x81, 0x80, 0x10, 0x00, 0x00, 0x00, 0x80, 0x01, 0x00, 0x00, 0x00,
+    ])
+    .unwrap()
+    .install(&kernel);
+    assert!(fake::Syscalls::command(0x40001, 0, 0, 0).is_success());
+}
+
+#[test]
+fn diverged() {
+    let kernel = fake::Kernel::new();
+    Replay::from_bytes(TRACE).unwrap().install(&kernel);
+    let result = std::panic::catch_unwind(|| fake::Syscalls::command(0x90000, 7, 5, 0));
+    assert!(result.is_err(), "replay did not catch divergent syscall");
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [SyscallLogEntry::Command {
+            driver_id: 0x90000,
+            command_id: 7,
+            argument0: 5,
+            argument1: 0,
+        }]
+    );
+}
+
+// Allow calls to a driver without a fake swap buffers like a real driver: each
+// call returns the buffer passed to the previous call.
+#[test]
+fn allow_swaps_buffers() {
+    use libtock_platform::{syscall_class, RawSyscalls};
+    let kernel = fake::Kernel::new();
+    Replay::from_bytes(&[
+        0x05, 0x80, 0x80, 0x24, 0x00, 0x03, 0x00, //
+        0x05, 0x80, 0x80, 0x24, 0x00, 0x02, 0x00, //
+        0x06, 0x80, 0x80, 0x24, 0x01, 0x03, 0x00, //
+        0x06, 0x80, 0x80, 0x24, 0x01, 0x02, 0x00,
+    ])
+    .unwrap()
+    .install(&kernel);
+
+    let ro_first = [1u8; 3];
+    let ro_second = [2u8; 2];
+    let mut rw_first = [3u8; 3];
+    let mut rw_second = [4u8; 2];
+    // Safety: The buffers outlive the kernel's use of them, as the driver only
+    // stores them until they are swapped out or the kernel is dropped.
+    unsafe {
+        let [_, r1, r2, _] = fake::Syscalls::syscall4::<{ syscall_class::ALLOW_RO }>([
+            0x90000u32.into(),
+            0u32.into(),
+            ro_first.as_ptr().into(),
+            ro_first.len().into(),
+        ]);
+        assert_eq!((usize::from(r1), usize::from(r2)), (0, 0));
+        let [_, r1, r2, _] = fake::Syscalls::syscall4::<{ syscall_class::ALLOW_RO }>([
+            0x90000u32.into(),
+            0u32.into(),
+            ro_second.as_ptr().into(),
+            ro_second.len().into(),
+        ]);
+        assert_eq!(
+            (usize::from(r1), usize::from(r2)),
+            (ro_first.as_ptr() as usize, 3)
+        );
+
+        let [_, r1, r2, _] = fake::Syscalls::syscall4::<{ syscall_class::ALLOW_RW }>([
+            0x90000u32.into(),
+            1u32.into(),
+            rw_first.as_mut_ptr().into(),
+            rw_first.len().into(),
+        ]);
+        assert_eq!((usize::from(r1), usize::from(r2)), (0, 0));
+        let [_, r1, r2, _] = fake::Syscalls::syscall4::<{ syscall_class::ALLOW_RW }>([
+            0x90000u32.into(),
+            1u32.into(),
+            rw_second.as_mut_ptr().into(),
+            rw_second.len().into(),
+        ]);
+        assert_eq!(
+            (usize::from(r1), usize::from(r2)),
+            (rw_first.as_ptr() as usize, 3)
+        );
+    }
+}
+
+// Memop calls are expected, but their device-specific arguments and results
+// are not replayed.
+#[test]
+fn memop() {
+    use libtock_platform::{syscall_class, RawSyscalls};
+    let kernel = fake::Kernel::new();
+    // sbrk(0x100) returning Success with u32 0x20001000, then the Command from
+    // TRACE.
+    let mut trace = vec![
+        0x07, 0x01, 0x80, 0x02, 0x81, 0x01, 0x80, 0xa0, 0x80, 0x80, 0x02,
+    ];
+    trace.extend_from_slice(&TRACE[TRACE.len() - 11..]);
+    Replay::from_bytes(&trace).unwrap().install(&kernel);
+
+    // Safety: sbrk does not access memory.
+    unsafe {
+        fake::Syscalls::syscall2::<{ syscall_class::MEMOP }>([1u32.into(), 0x40u32.into()]);
+    }
+    assert_eq!(
+        fake::Syscalls::command(0x90000, 7, 5, 0).get_failure(),
+        Some(ErrorCode::Busy)
+    );
+    kernel.assert_expectations_met();
+}
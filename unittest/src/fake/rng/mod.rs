@@ -0,0 +1,129 @@
+//! Fake implementation of the RNG API, documented here:
+//! https://github.com/tock/tock/blob/master/doc/syscalls/40001_rng.md
+//!
+//! Like the real API, `Rng` fills the shared buffer with random bytes when
+//! asked to. The bytes come from a pluggable source: by default a
+//! deterministic counter, so that tests are reproducible, but tests can supply
+//! their own source via `new_with_source` or queue exact bytes with
+//! `add_bytes`.
+
+use core::cell::{Cell, RefCell};
+use libtock_platform::{CommandReturn, ErrorCode};
+use std::collections::VecDeque;
+
+use crate::{DriverInfo, DriverShareRef, RwAllowBuffer};
+
+pub struct Rng {
+    buffer: RefCell<RwAllowBuffer>,
+    // Bytes queued by `add_bytes`. These are handed out before any bytes from
+    // `source`.
+    queued: RefCell<VecDeque<u8>>,
+    source: RefCell<Box<dyn FnMut() -> u8>>,
+    // The number of bytes the next GET_BYTES command should deliver, if it
+    // should deliver fewer than requested (to simulate a slow entropy source).
+    limit: Cell<Option<u32>>,
+    share_ref: DriverShareRef,
+}
+
+impl Rng {
+    /// Creates a fake RNG whose output is the sequence 0, 1, 2, ... (wrapping
+    /// at 255).
+    pub fn new() -> std::rc::Rc<Rng> {
+        let mut next = 0u8;
+        Self::new_with_source(move || {
+            let byte = next;
+            next = next.wrapping_add(1);
+            byte
+        })
+    }
+
+    /// Creates a fake RNG that calls `source` for every random byte it
+    /// produces.
+    pub fn new_with_source<F: FnMut() -> u8 + 'static>(source: F) -> std::rc::Rc<Rng> {
+        std::rc::Rc::new(Rng {
+            buffer: Default::default(),
+            queued: Default::default(),
+            source: RefCell::new(Box::new(source)),
+            limit: Cell::new(None),
+            share_ref: Default::default(),
+        })
+    }
+
+    /// Queues bytes to be returned by the next GET_BYTES commands, ahead of the
+    /// bytes produced by the source.
+    pub fn add_bytes(&self, bytes: &[u8]) {
+        self.queued.borrow_mut().extend(bytes);
+    }
+
+    /// Makes the next GET_BYTES command deliver at most `limit` bytes, even if
+    /// more were requested.
+    pub fn limit_next_request(&self, limit: u32) {
+        self.limit.set(Some(limit));
+    }
+
+    fn next_byte(&self) -> u8 {
+        match self.queued.borrow_mut().pop_front() {
+            Some(byte) => byte,
+            None => (self.source.borrow_mut())(),
+        }
+    }
+}
+
+impl crate::fake::SyscallDriver for Rng {
+    fn info(&self) -> DriverInfo {
+        DriverInfo::new(DRIVER_NUM).upcall_count(1)
+    }
+
+    fn register(&self, share_ref: DriverShareRef) {
+        self.share_ref.replace(share_ref);
+    }
+
+    fn allow_readwrite(
+        &self,
+        buffer_num: u32,
+        buffer: RwAllowBuffer,
+    ) -> Result<RwAllowBuffer, (RwAllowBuffer, ErrorCode)> {
+        if buffer_num == ALLOW_BUFFER {
+            Ok(self.buffer.replace(buffer))
+        } else {
+            Err((buffer, ErrorCode::Invalid))
+        }
+    }
+
+    fn command(&self, command_num: u32, argument0: u32, _argument1: u32) -> CommandReturn {
+        match command_num {
+            EXISTS => crate::command_return::success(),
+            GET_BYTES => {
+                let mut buffer = self.buffer.borrow_mut();
+                let mut count = core::cmp::min(argument0 as usize, buffer.len());
+                if let Some(limit) = self.limit.take() {
+                    count = core::cmp::min(count, limit as usize);
+                }
+                for byte in &mut buffer[..count] {
+                    *byte = self.next_byte();
+                }
+                self.share_ref
+                    .schedule_upcall(SUBSCRIBE_GET_BYTES, (0, count as u32, 0))
+                    .expect("Unable to schedule upcall");
+                crate::command_return::success()
+            }
+            _ => crate::command_return::failure(ErrorCode::NoSupport),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
+
+// -----------------------------------------------------------------------------
+// Driver number and command IDs
+// -----------------------------------------------------------------------------
+
+const DRIVER_NUM: u32 = 0x40001;
+
+// Command IDs
+const EXISTS: u32 = 0;
+const GET_BYTES: u32 = 1;
+
+const SUBSCRIBE_GET_BYTES: u32 = 0;
+const ALLOW_BUFFER: u32 = 0;
@@ -0,0 +1,77 @@
+use crate::fake::{self, SyscallDriver};
+use crate::RwAllowBuffer;
+use fake::rng::*;
+use libtock_platform::{share, AllowRw, DefaultConfig, Subscribe, YieldNoWaitReturn};
+
+// Tests the command implementation.
+#[test]
+fn command() {
+    let rng = Rng::new();
+    assert!(rng.command(EXISTS, 1, 2).is_success());
+    assert!(rng
+        .allow_readwrite(ALLOW_BUFFER, RwAllowBuffer::default())
+        .is_ok());
+    assert!(rng.allow_readwrite(1, RwAllowBuffer::default()).is_err());
+    // Without a shared buffer, no bytes can be delivered.
+    assert!(rng.command(GET_BYTES, 4, 0).is_success());
+    assert_eq!(
+        rng.command(2, 0, 0).get_failure(),
+        Some(ErrorCode::NoSupport)
+    );
+}
+
+// Integration test that verifies Rng works with fake::Kernel and
+// libtock_platform::Syscalls.
+#[test]
+fn kernel_integration() {
+    use libtock_platform::Syscalls;
+    let kernel = fake::Kernel::new();
+    let rng = Rng::new();
+    kernel.add_driver(&rng);
+    assert!(fake::Syscalls::command(DRIVER_NUM, EXISTS, 0, 0).is_success());
+
+    let mut buf = [0xff; 6];
+    let listener = core::cell::Cell::<Option<(u32, u32)>>::new(None);
+    share::scope::<
+        (
+            AllowRw<fake::Syscalls, DRIVER_NUM, ALLOW_BUFFER>,
+            Subscribe<fake::Syscalls, DRIVER_NUM, SUBSCRIBE_GET_BYTES>,
+        ),
+        _,
+        _,
+    >(|handle| {
+        let (allow_rw, subscribe) = handle.split();
+        fake::Syscalls::allow_rw::<DefaultConfig, DRIVER_NUM, ALLOW_BUFFER>(allow_rw, &mut buf)
+            .unwrap();
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, SUBSCRIBE_GET_BYTES>(
+            subscribe, &listener,
+        )
+        .unwrap();
+
+        rng.add_bytes(&[0xaa, 0xbb]);
+        assert!(fake::Syscalls::command(DRIVER_NUM, GET_BYTES, 4, 0).is_success());
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(listener.take(), Some((0, 4)));
+
+        // Requests larger than the buffer are truncated to the buffer size.
+        rng.limit_next_request(100);
+        assert!(fake::Syscalls::command(DRIVER_NUM, GET_BYTES, 10, 0).is_success());
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(listener.take(), Some((0, 6)));
+
+        rng.limit_next_request(1);
+        assert!(fake::Syscalls::command(DRIVER_NUM, GET_BYTES, 6, 0).is_success());
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(listener.take(), Some((0, 1)));
+    });
+    assert_eq!(buf, [8, 3, 4, 5, 6, 7]);
+}
+
+#[test]
+fn custom_source() {
+    let rng = Rng::new_with_source(|| 0x42);
+    assert_eq!(rng.next_byte(), 0x42);
+    rng.add_bytes(&[1]);
+    assert_eq!(rng.next_byte(), 1);
+    assert_eq!(rng.next_byte(), 0x42);
+}
@@ -0,0 +1,170 @@
+//! Image export and snapshot comparison for `fake::Screen`'s framebuffer.
+
+use std::path::Path;
+
+/// The environment variable that, when set to `1`, makes
+/// `Screen::assert_snapshot` write snapshots instead of comparing against them.
+const UPDATE_SNAPSHOTS_VAR: &str = "LIBTOCK_UPDATE_SNAPSHOTS";
+
+// An RGB image, stored row-major.
+pub(super) struct Image<'p> {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: &'p [[u8; 3]],
+}
+
+impl Image<'_> {
+    // Encodes the image as a binary (P6) PPM.
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        ppm.extend(self.pixels.iter().flatten());
+        ppm
+    }
+
+    // Encodes the image as an uncompressed PNG. The output is not small, but
+    // any image viewer can open it.
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity((1 + 3 * self.width) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            raw.push(0); // Filter type: none
+            raw.extend(row.iter().flatten());
+        }
+
+        // zlib stream containing deflate "stored" (uncompressed) blocks.
+        let mut zlib = vec![0x78, 0x01];
+        let mut blocks = raw.chunks(u16::MAX as usize).peekable();
+        if blocks.peek().is_none() {
+            zlib.extend([1, 0, 0, 0xff, 0xff]);
+        }
+        while let Some(block) = blocks.next() {
+            zlib.push(blocks.peek().is_none() as u8);
+            zlib.extend((block.len() as u16).to_le_bytes());
+            zlib.extend((!(block.len() as u16)).to_le_bytes());
+            zlib.extend(block);
+        }
+        zlib.extend(adler32(&raw).to_be_bytes());
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend((self.width as u32).to_be_bytes());
+        ihdr.extend((self.height as u32).to_be_bytes());
+        // Bit depth 8, color type 2 (RGB), default compression, filter, and
+        // interlace methods.
+        ihdr.extend([8, 2, 0, 0, 0]);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png_chunk(&mut png, b"IHDR", &ihdr);
+        png_chunk(&mut png, b"IDAT", &zlib);
+        png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    // Compares the image against the PPM snapshot at `path`, as described in
+    // `Screen::assert_snapshot`.
+    #[track_caller]
+    pub fn assert_snapshot(&self, path: &Path) {
+        let actual = self.to_ppm();
+        if std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some_and(|value| value == "1") {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).expect("Unable to create snapshot directory");
+            }
+            std::fs::write(path, &actual).expect("Unable to write snapshot");
+            println!("Wrote screen snapshot {}", path.display());
+            return;
+        }
+        let expected = match std::fs::read(path) {
+            Ok(expected) => expected,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => panic!(
+                "Snapshot {} does not exist. Set {UPDATE_SNAPSHOTS_VAR}=1 to create it.",
+                path.display()
+            ),
+            Err(error) => panic!("Unable to read snapshot {}: {error}", path.display()),
+        };
+        if expected == actual {
+            return;
+        }
+        let actual_path = path.with_extension("actual.png");
+        std::fs::write(&actual_path, self.to_png()).expect("Unable to write actual image");
+        panic!(
+            "Screen does not match snapshot {}: {}. The actual image was written to {}. Set {}=1 \
+             to accept the new image.",
+            path.display(),
+            self.describe_difference(&expected),
+            actual_path.display(),
+            UPDATE_SNAPSHOTS_VAR,
+        );
+    }
+
+    // Describes how the image differs from the PPM `expected`.
+    fn describe_difference(&self, expected: &[u8]) -> String {
+        let Some((width, height, data)) = parse_ppm(expected) else {
+            return "the snapshot is not a valid PPM file".into();
+        };
+        if (width, height) != (self.width, self.height) {
+            return format!(
+                "the snapshot is {width}x{height} but the screen is {}x{}",
+                self.width, self.height
+            );
+        }
+        let mut differing = (0..self.pixels.len())
+            .filter(|&i| self.pixels[i][..] != data[3 * i..3 * i + 3])
+            .peekable();
+        let first = differing.peek().copied().unwrap_or(0);
+        format!(
+            "{} pixels differ, starting at ({}, {})",
+            differing.count(),
+            first % self.width,
+            first / self.width
+        )
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Implementation details below.
+// -----------------------------------------------------------------------------
+
+// Parses a binary PPM with a maxval of 255, as written by `Image::to_ppm`.
+// Returns the width, height, and pixel data.
+fn parse_ppm(ppm: &[u8]) -> Option<(usize, usize, &[u8])> {
+    let mut rest = ppm.strip_prefix(b"P6")?;
+    let mut header = [0usize; 3];
+    for field in &mut header {
+        let start = rest.iter().position(|b| !b.is_ascii_whitespace())?;
+        rest = &rest[start..];
+        let len = rest.iter().position(|b| !b.is_ascii_digit())?;
+        *field = std::str::from_utf8(&rest[..len]).ok()?.parse().ok()?;
+        rest = &rest[len..];
+    }
+    let [width, height, maxval] = header;
+    // Exactly one whitespace byte separates the header from the data.
+    let data = rest.get(1..)?;
+    (maxval == 255 && data.len() == 3 * width * height).then_some((width, height, data))
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb88320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
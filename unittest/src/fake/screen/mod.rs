@@ -1,27 +1,48 @@
+//! Fake implementation of the Screen API, documented here:
+//! https://github.com/tock/tock/blob/master/doc/syscalls/90001_screen.md
+//!
+//! Like a real screen, `Screen` maintains a framebuffer. Writes and fills land
+//! in the current write frame, are decoded according to the current pixel
+//! format, and are mapped onto the panel according to the current rotation.
+//! Tests can read individual pixels, export the framebuffer as a PPM or PNG
+//! image, or compare it against a stored snapshot with `assert_snapshot`.
+
 use crate::{command_return, DriverInfo, DriverShareRef, RoAllowBuffer};
-use core::cell::Cell;
+use core::cell::{Cell, RefCell};
 use libtock_platform::{CommandReturn, ErrorCode};
+use std::path::Path;
+
+mod image;
 
 pub struct Screen {
     screen_setup: Option<u16>,     // Optional screen setup state
     resolution_modes: Option<u16>, // Number of supported resolution modes
     invert: Cell<bool>,            // Current invert state (true = inverted)
     screen_resolution_width_height: [Option<(u16, u16)>; 3], // Predefined resolutions
-    resolution_width_height: [Cell<u16>; 2], // Current resolution (width, height)
     pixel_modes: Option<u16>,      // Number of pixel formats supported
     screen_pixel_format: [u16; 2], // Predefined pixel formats
     pixel_format: Cell<u32>,       // Currently selected pixel format
     brightness: Cell<u16>,         // Current brightness level
-    rotation: Cell<u16>,           // Current screen rotation
-    write_frame: [Cell<u16>; 2],   // Coordinates for the write frame
+    rotation: Cell<u16>,           // Current screen rotation, in degrees
+    write_frame: [Cell<u16>; 2],   // Raw arguments of the last SET_WRITE_FRAME
     power: Cell<u16>,              // Power state
     share_ref: DriverShareRef,     // Handle for kernel-user communication
     write_buffer: Cell<Option<RoAllowBuffer>>, // Optional buffer for write operations
     messages: Cell<Vec<u8>>,
+    panel: RefCell<Panel>, // The framebuffer, in unrotated panel coordinates
+    frame: Cell<Frame>,    // The write frame, in rotated (logical) coordinates
+    // How far into the write frame the next WRITE starts, in pixels (or in
+    // bytes, for Mono_8BitPage).
+    cursor: Cell<usize>,
 }
 
 impl Screen {
     pub fn new() -> std::rc::Rc<Screen> {
+        Self::new_with_resolution(0, 0)
+    }
+
+    /// Creates a `Screen` whose panel has the given resolution.
+    pub fn new_with_resolution(width: u16, height: u16) -> std::rc::Rc<Screen> {
         #[allow(clippy::declare_interior_mutable_const)]
         const VALUE_U16: Cell<u16> = Cell::new(0);
         #[allow(clippy::declare_interior_mutable_const)]
@@ -36,7 +57,6 @@ impl Screen {
             ],
             pixel_format: VALUE_U32,
             resolution_modes: Some(2),
-            resolution_width_height: [VALUE_U16, VALUE_U16],
             invert: Cell::new(false),
             brightness: VALUE_U16,
             pixel_modes: Some(5),
@@ -46,24 +66,115 @@ impl Screen {
             share_ref: Default::default(),
             write_buffer: Cell::new(None),
             messages: Default::default(),
+            panel: RefCell::new(Panel::new(width.into(), height.into())),
+            frame: Default::default(),
+            cursor: Cell::new(0),
         })
     }
 
+    /// Returns the raw bytes of every WRITE so far, and empties the record.
     pub fn take_bytes(&self) -> Vec<u8> {
         self.messages.take()
     }
 
+    /// The width of the (unrotated) panel, in pixels.
+    pub fn width(&self) -> usize {
+        self.panel.borrow().width
+    }
+
+    /// The height of the (unrotated) panel, in pixels.
+    pub fn height(&self) -> usize {
+        self.panel.borrow().height
+    }
+
+    /// Returns the color displayed at the given (unrotated) panel coordinates,
+    /// taking color inversion into account. Monochrome pixels are black or
+    /// white.
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 3] {
+        let panel = self.panel.borrow();
+        assert!(
+            x < panel.width && y < panel.height,
+            "({x}, {y}) is outside the {}x{} screen",
+            panel.width,
+            panel.height
+        );
+        self.displayed(panel.pixels[y * panel.width + x])
+    }
+
+    /// Returns every displayed pixel, row by row.
+    pub fn pixels(&self) -> Vec<[u8; 3]> {
+        let panel = self.panel.borrow();
+        panel.pixels.iter().map(|&p| self.displayed(p)).collect()
+    }
+
+    /// Encodes the displayed image as a binary PPM file.
+    pub fn to_ppm(&self) -> Vec<u8> {
+        self.with_image(|image| image.to_ppm())
+    }
+
+    /// Encodes the displayed image as a PNG file.
+    pub fn to_png(&self) -> Vec<u8> {
+        self.with_image(|image| image.to_png())
+    }
+
+    /// Compares the displayed image against the PPM snapshot stored at `path`,
+    /// panicking if they differ. On a mismatch, the actual image is written
+    /// next to the snapshot as a PNG for inspection. A missing snapshot is also
+    /// a failure. If the `LIBTOCK_UPDATE_SNAPSHOTS` environment variable is set
+    /// to `1`, the snapshot is (re)written instead.
+    #[track_caller]
+    pub fn assert_snapshot<P: AsRef<Path>>(&self, path: P) {
+        self.with_image(|image| image.assert_snapshot(path.as_ref()))
+    }
+
+    fn with_image<R, F: FnOnce(&image::Image) -> R>(&self, f: F) -> R {
+        let pixels = self.pixels();
+        let panel = self.panel.borrow();
+        f(&image::Image {
+            width: panel.width,
+            height: panel.height,
+            pixels: &pixels,
+        })
+    }
+
+    fn displayed(&self, [r, g, b]: [u8; 3]) -> [u8; 3] {
+        match self.invert.get() {
+            false => [r, g, b],
+            true => [!r, !g, !b],
+        }
+    }
+
     // Checks if the buffer size is compatible with the pixel format
     fn is_buffer_length_valid(&self, buffer_len: usize) -> bool {
-        let bytes_per_pixel = match self.pixel_format.get() {
-            1 => 1,            // Monochrome
-            2 => 2,            // RGB_565
-            3 => 3,            // RGB_888
-            4 => 4,            // ARGB_8888
-            _ => return false, // Unknown/unsupported format
-        };
+        match self.pixel_format.get() {
+            // Monochrome formats pack several pixels into each byte.
+            pixel_format::MONO | pixel_format::MONO_8BIT_PAGE => true,
+            pixel_format::RGB_332 => true,
+            pixel_format::RGB_565 => buffer_len % 2 == 0,
+            pixel_format::RGB_888 => buffer_len % 3 == 0,
+            pixel_format::ARGB_8888 => buffer_len % 4 == 0,
+            _ => false, // Unknown/unsupported format
+        }
+    }
 
-        buffer_len % bytes_per_pixel == 0
+    // The number of quarter turns clockwise the screen is rotated by.
+    fn quarter_turns(&self) -> u16 {
+        self.rotation.get() / 90 % 4
+    }
+
+    // Sets the pixel at logical (rotated) coordinates, ignoring pixels that
+    // fall outside the screen.
+    fn set_pixel(&self, x: usize, y: usize, color: [u8; 3]) {
+        let mut panel = self.panel.borrow_mut();
+        let (w, h) = (panel.width, panel.height);
+        let (px, py) = match self.quarter_turns() {
+            0 if x < w && y < h => (x, y),
+            1 if x < h && y < w => (w - 1 - y, x),
+            2 if x < w && y < h => (w - 1 - x, h - 1 - y),
+            3 if x < h && y < w => (y, h - 1 - x),
+            _ => return,
+        };
+        panel.pixels[py * w + px] = color;
     }
 
     // Simulates writing to the screen
@@ -71,6 +182,40 @@ impl Screen {
         if !self.is_buffer_length_valid(buffer.len()) {
             return Err(ErrorCode::Invalid);
         }
+        let mut messages = self.messages.take();
+        messages.extend_from_slice(buffer);
+        self.messages.set(messages);
+
+        let frame = self.frame.get();
+        let area = frame.width * frame.height;
+        if area > 0 {
+            let format = self.pixel_format.get();
+            let mut cursor = self.cursor.get();
+            for_each_pixel(format, buffer, |unit, color| {
+                if format == pixel_format::MONO_8BIT_PAGE {
+                    // Each byte is a column of 8 pixels; bytes fill the frame
+                    // one 8-pixel-tall page at a time.
+                    let pages = frame.height.div_ceil(8);
+                    let byte = (cursor + unit / 8) % (frame.width * pages);
+                    let y = byte / frame.width * 8 + unit % 8;
+                    if y < frame.height {
+                        self.set_pixel(frame.x + byte % frame.width, frame.y + y, color);
+                    }
+                } else {
+                    let index = (cursor + unit) % area;
+                    self.set_pixel(
+                        frame.x + index % frame.width,
+                        frame.y + index / frame.width,
+                        color,
+                    );
+                }
+            });
+            cursor += match format {
+                pixel_format::MONO_8BIT_PAGE => buffer.len(),
+                _ => pixel_count(format, buffer.len()),
+            };
+            self.cursor.set(cursor);
+        }
 
         self.share_ref
             .schedule_upcall(0, (0, 0, 0))
@@ -78,8 +223,27 @@ impl Screen {
         Ok(())
     }
 
-    // Simulates filling the screen with a color
-    fn fill(&self, _color: u16) -> Result<(), ErrorCode> {
+    // Simulates filling the write frame with the first pixel of `buffer`.
+    fn fill(&self, buffer: &[u8]) -> Result<(), ErrorCode> {
+        let format = self.pixel_format.get();
+        let mut first = None;
+        let mut page_colors = [[0; 3]; 8];
+        for_each_pixel(format, buffer, |unit, color| match format {
+            pixel_format::MONO_8BIT_PAGE if unit < 8 => page_colors[unit] = color,
+            _ => {
+                first.get_or_insert(color);
+            }
+        });
+        let frame = self.frame.get();
+        for y in 0..frame.height {
+            for x in 0..frame.width {
+                let color = match format {
+                    pixel_format::MONO_8BIT_PAGE => page_colors[y % 8],
+                    _ => first.ok_or(ErrorCode::Invalid)?,
+                };
+                self.set_pixel(frame.x + x, frame.y + y, color);
+            }
+        }
         self.share_ref
             .schedule_upcall(0, (0, 0, 0))
             .expect("Unable to schedule upcall");
@@ -228,24 +392,32 @@ impl crate::fake::SyscallDriver for Screen {
                 }
             }
 
-            GET_RESOLUTION => command_return::success_2_u32(
-                self.resolution_width_height[0].get() as u32,
-                self.resolution_width_height[1].get() as u32,
-            ),
+            GET_RESOLUTION => {
+                let panel = self.panel.borrow();
+                let (width, height) = match self.quarter_turns() % 2 {
+                    0 => (panel.width, panel.height),
+                    _ => (panel.height, panel.width),
+                };
+                command_return::success_2_u32(width as u32, height as u32)
+            }
 
             SET_RESOLUTION => {
                 self.share_ref
                     .schedule_upcall(0, (0, 0, 0))
                     .expect("Unable to schedule upcall {}");
-                self.resolution_width_height[0].set(argument0 as u16);
-                self.resolution_width_height[1].set(argument1 as u16);
+                // The resolution is given in rotated coordinates.
+                let (width, height) = match self.quarter_turns() % 2 {
+                    0 => (argument0 as u16, argument1 as u16),
+                    _ => (argument1 as u16, argument0 as u16),
+                };
+                *self.panel.borrow_mut() = Panel::new(width.into(), height.into());
                 command_return::success()
             }
 
             GET_PIXEL_FORMAT => command_return::success_u32(self.pixel_format.get()),
 
             SET_PIXEL_FORMAT => {
-                if argument0 < self.pixel_modes.unwrap() as u32 {
+                if pixel_format::SUPPORTED.contains(&argument0) {
                     self.pixel_format.set(argument0);
                     self.share_ref
                         .schedule_upcall(0, (0, 0, 0))
@@ -262,6 +434,13 @@ impl crate::fake::SyscallDriver for Screen {
                     .expect("Unable to schedule upcall {}");
                 self.write_frame[0].set(argument0 as u16);
                 self.write_frame[1].set(argument1 as u16);
+                self.frame.set(Frame {
+                    x: (argument0 >> 16) as usize,
+                    y: (argument0 & 0xffff) as usize,
+                    width: (argument1 >> 16) as usize,
+                    height: (argument1 & 0xffff) as usize,
+                });
+                self.cursor.set(0);
                 command_return::success()
             }
 
@@ -276,20 +455,23 @@ impl crate::fake::SyscallDriver for Screen {
                     .write_buffer
                     .take()
                     .expect("No buffer provided for WRITE command");
+                let result = match buffer.len() == buffer_len {
+                    true => self.write(&buffer),
+                    false => Err(ErrorCode::Invalid),
+                };
+                self.write_buffer.set(Some(buffer));
 
-                if buffer.len() != buffer_len || !self.is_buffer_length_valid(buffer_len) {
-                    return command_return::failure(ErrorCode::Invalid);
-                }
-
-                match self.write(buffer.as_ref()) {
+                match result {
                     Ok(()) => command_return::success(),
                     Err(e) => command_return::failure(e),
                 }
             }
 
             FILL => {
-                let color = argument0 as u16;
-                match self.fill(color) {
+                let buffer = self.write_buffer.take().unwrap_or_default();
+                let result = self.fill(&buffer);
+                self.write_buffer.set(Some(buffer));
+                match result {
                     Ok(()) => command_return::success(),
                     Err(e) => command_return::failure(e),
                 }
@@ -304,6 +486,108 @@ impl crate::fake::SyscallDriver for Screen {
 // Implementation details below
 // -----------------------------------------------------------------------------
 
+// The framebuffer, in unrotated panel coordinates.
+struct Panel {
+    width: usize,
+    height: usize,
+    pixels: Vec<[u8; 3]>,
+}
+
+impl Panel {
+    fn new(width: usize, height: usize) -> Panel {
+        Panel {
+            width,
+            height,
+            pixels: vec![[0; 3]; width * height],
+        }
+    }
+}
+
+// The region set by SET_WRITE_FRAME.
+#[derive(Clone, Copy, Default)]
+struct Frame {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+// The number of pixels `len` bytes hold in the given (row-major) format.
+fn pixel_count(format: u32, len: usize) -> usize {
+    match format {
+        pixel_format::MONO => 8 * len,
+        pixel_format::RGB_565 => len / 2,
+        pixel_format::RGB_888 => len / 3,
+        pixel_format::ARGB_8888 => len / 4,
+        _ => len,
+    }
+}
+
+// Decodes `buffer` in the given pixel format, calling `f` with the index and
+// color of each pixel. For the monochrome formats, the index is the bit index
+// (most significant bit first for MONO, least significant bit first for
+// MONO_8BIT_PAGE).
+fn for_each_pixel<F: FnMut(usize, [u8; 3])>(format: u32, buffer: &[u8], mut f: F) {
+    // Scales an n-bit channel value to 8 bits.
+    fn scale(value: u8, bits: u32) -> u8 {
+        (value as u32 * 255 / ((1 << bits) - 1)) as u8
+    }
+    let mono = |on: bool| [if on { 255 } else { 0 }; 3];
+    match format {
+        pixel_format::MONO => {
+            for (i, byte) in buffer.iter().enumerate() {
+                for bit in 0..8 {
+                    f(8 * i + bit, mono(byte & (0x80 >> bit) != 0));
+                }
+            }
+        }
+        pixel_format::MONO_8BIT_PAGE => {
+            for (i, byte) in buffer.iter().enumerate() {
+                for bit in 0..8 {
+                    f(8 * i + bit, mono(byte & (1 << bit) != 0));
+                }
+            }
+        }
+        pixel_format::RGB_332 => {
+            for (i, &byte) in buffer.iter().enumerate() {
+                f(
+                    i,
+                    [
+                        scale(byte >> 5, 3),
+                        scale(byte >> 2 & 0x7, 3),
+                        scale(byte & 0x3, 2),
+                    ],
+                );
+            }
+        }
+        pixel_format::RGB_565 => {
+            for (i, bytes) in buffer.chunks_exact(2).enumerate() {
+                let value = u16::from_be_bytes([bytes[0], bytes[1]]);
+                f(
+                    i,
+                    [
+                        scale((value >> 11) as u8, 5),
+                        scale((value >> 5 & 0x3f) as u8, 6),
+                        scale((value & 0x1f) as u8, 5),
+                    ],
+                );
+            }
+        }
+        pixel_format::RGB_888 => {
+            for (i, bytes) in buffer.chunks_exact(3).enumerate() {
+                f(i, [bytes[0], bytes[1], bytes[2]]);
+            }
+        }
+        pixel_format::ARGB_8888 => {
+            // The alpha channel is ignored, as there is nothing to blend with.
+            for (i, bytes) in buffer.chunks_exact(4).enumerate() {
+                f(i, [bytes[1], bytes[2], bytes[3]]);
+            }
+        }
+        _ => {}
+    }
+}
+
 #[cfg(test)]
 mod tests;
 
@@ -336,3 +620,16 @@ pub const GET_POWER: u32 = 400;
 pub const GET_BRIGHTNESS: u32 = 401;
 pub const GET_INVERT: u32 = 402;
 pub const GET_WRITE_FRAME: u32 = 403;
+
+// Pixel formats
+pub mod pixel_format {
+    pub const MONO: u32 = 0;
+    pub const RGB_332: u32 = 1;
+    pub const RGB_565: u32 = 2;
+    pub const RGB_888: u32 = 3;
+    pub const ARGB_8888: u32 = 4;
+    pub const MONO_8BIT_PAGE: u32 = 6;
+
+    pub(super) const SUPPORTED: [u32; 6] =
+        [MONO, RGB_332, RGB_565, RGB_888, ARGB_8888, MONO_8BIT_PAGE];
+}
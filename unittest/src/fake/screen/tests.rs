@@ -155,3 +155,208 @@ fn kernel_integration() {
         Some((360, 720))
     );
 }
+
+// Sets the pixel format and write frame, then writes `data`.
+fn write(screen: &Screen, format: u32, frame: (u32, u32, u32, u32), data: &[u8]) {
+    let (x, y, width, height) = frame;
+    assert!(screen.command(SET_PIXEL_FORMAT, format, 0).is_success());
+    assert!(screen
+        .command(SET_WRITE_FRAME, x << 16 | y, width << 16 | height)
+        .is_success());
+    write_data(data);
+}
+
+// Writes `data` to the current write frame.
+fn write_data(data: &[u8]) {
+    share::scope(|allow_ro| {
+        fake::Syscalls::allow_ro::<DefaultConfig, DRIVER_NUM, WRITE_BUFFER_ID>(allow_ro, data)
+            .unwrap();
+        assert!(fake::Syscalls::command(DRIVER_NUM, WRITE, data.len() as u32, 0).is_success());
+    });
+}
+
+#[test]
+fn framebuffer_rgb() {
+    let kernel = fake::Kernel::new();
+    let screen = Screen::new_with_resolution(4, 3);
+    kernel.add_driver(&screen);
+
+    // Two RGB_565 pixels (red, blue) into a 2x1 frame at (1, 1).
+    write(
+        &screen,
+        pixel_format::RGB_565,
+        (1, 1, 2, 1),
+        &[0xf8, 0x00, 0x00, 0x1f],
+    );
+    assert_eq!(screen.pixel(1, 1), [255, 0, 0]);
+    assert_eq!(screen.pixel(2, 1), [0, 0, 255]);
+    assert_eq!(screen.pixel(0, 0), [0, 0, 0]);
+
+    // A second write continues where the first left off, wrapping around the
+    // frame.
+    write(&screen, pixel_format::RGB_888, (0, 2, 2, 1), &[1, 2, 3]);
+    write_data(&[4, 5, 6, 7, 8, 9]);
+    assert_eq!(screen.pixel(0, 2), [7, 8, 9]);
+    assert_eq!(screen.pixel(1, 2), [4, 5, 6]);
+
+    // RGB_332 and ARGB_8888.
+    write(
+        &screen,
+        pixel_format::RGB_332,
+        (3, 0, 1, 1),
+        &[0b1110_0011], // RGB 332: red 7, green 0, blue 3,
+    );
+    assert_eq!(screen.pixel(3, 0), [255, 0, 255]);
+    write(
+        &screen,
+        pixel_format::ARGB_8888,
+        (3, 0, 1, 1),
+        &[0, 10, 20, 30],
+    );
+    assert_eq!(screen.pixel(3, 0), [10, 20, 30]);
+
+    // Pixels outside the panel are dropped.
+    write(&screen, pixel_format::RGB_888, (3, 2, 2, 1), &[1; 6]);
+    assert_eq!(screen.pixel(3, 2), [1, 1, 1]);
+
+    assert_eq!(screen.take_bytes().len(), 4 + 3 + 6 + 1 + 4 + 6);
+}
+
+#[test]
+fn framebuffer_mono() {
+    let kernel = fake::Kernel::new();
+    let screen = Screen::new_with_resolution(8, 10);
+    kernel.add_driver(&screen);
+
+    // MONO is row-major, most significant bit first.
+    write(&screen, pixel_format::MONO, (0, 0, 8, 1), &[0b1000_0001]);
+    assert_eq!(screen.pixel(0, 0), [255; 3]);
+    assert_eq!(screen.pixel(1, 0), [0; 3]);
+    assert_eq!(screen.pixel(7, 0), [255; 3]);
+
+    // MONO_8BIT_PAGE bytes are vertical 8-pixel columns, least significant
+    // bit on top, filling one page at a time.
+    write(
+        &screen,
+        pixel_format::MONO_8BIT_PAGE,
+        (0, 0, 2, 10),
+        &[0b0000_0010, 0, 0b0000_0011, 0],
+    );
+    assert_eq!(screen.pixel(0, 0), [0; 3]);
+    assert_eq!(screen.pixel(0, 1), [255; 3]);
+    assert_eq!(screen.pixel(0, 8), [255; 3]);
+    assert_eq!(screen.pixel(0, 9), [255; 3]);
+    assert_eq!(screen.pixel(1, 8), [0; 3]);
+}
+
+#[test]
+fn fill_and_invert() {
+    let kernel = fake::Kernel::new();
+    let screen = Screen::new_with_resolution(3, 3);
+    kernel.add_driver(&screen);
+    assert!(screen
+        .command(SET_PIXEL_FORMAT, pixel_format::RGB_565, 0)
+        .is_success());
+    assert!(screen
+        .command(SET_WRITE_FRAME, 1 << 16 | 1, 2 << 16 | 2)
+        .is_success());
+    let color = [0x07, 0xe0]; // Green
+    share::scope(|allow_ro| {
+        fake::Syscalls::allow_ro::<DefaultConfig, DRIVER_NUM, WRITE_BUFFER_ID>(allow_ro, &color)
+            .unwrap();
+        assert!(fake::Syscalls::command(DRIVER_NUM, FILL, 0, 0).is_success());
+    });
+    assert_eq!(screen.pixel(0, 0), [0, 0, 0]);
+    assert_eq!(screen.pixel(1, 1), [0, 255, 0]);
+    assert_eq!(screen.pixel(2, 2), [0, 255, 0]);
+
+    assert!(screen.command(SET_INVERT, 1, 0).is_success());
+    assert_eq!(screen.pixel(0, 0), [255, 255, 255]);
+    assert_eq!(screen.pixel(1, 1), [255, 0, 255]);
+}
+
+#[test]
+fn rotation() {
+    let kernel = fake::Kernel::new();
+    let screen = Screen::new_with_resolution(4, 2);
+    kernel.add_driver(&screen);
+
+    // Rotated by 90 degrees, the 4x2 panel is a 2x4 screen, and the logical
+    // top-left corner is the panel's top-right corner.
+    assert!(screen.command(SET_ROTATION, 90, 0).is_success());
+    assert_eq!(
+        screen.command(GET_RESOLUTION, 0, 0).get_success_2_u32(),
+        Some((2, 4))
+    );
+    write(
+        &screen,
+        pixel_format::RGB_888,
+        (0, 0, 1, 2),
+        &[1, 1, 1, 2, 2, 2],
+    );
+    assert_eq!(screen.pixel(3, 0), [1; 3]);
+    assert_eq!(screen.pixel(2, 0), [2; 3]);
+
+    assert!(screen.command(SET_ROTATION, 180, 0).is_success());
+    write(&screen, pixel_format::RGB_888, (0, 0, 1, 1), &[3; 3]);
+    assert_eq!(screen.pixel(3, 1), [3; 3]);
+
+    assert!(screen.command(SET_ROTATION, 270, 0).is_success());
+    write(&screen, pixel_format::RGB_888, (0, 0, 1, 1), &[4; 3]);
+    assert_eq!(screen.pixel(0, 1), [4; 3]);
+}
+
+#[test]
+fn export() {
+    let screen = Screen::new_with_resolution(2, 1);
+    let kernel = fake::Kernel::new();
+    kernel.add_driver(&screen);
+    write(
+        &screen,
+        pixel_format::RGB_888,
+        (0, 0, 2, 1),
+        &[1, 2, 3, 4, 5, 6],
+    );
+    assert_eq!(screen.to_ppm(), b"P6\n2 1\n255\n\x01\x02\x03\x04\x05\x06");
+
+    let png = screen.to_png();
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x02\0\0\0\x01"));
+    assert!(png.ends_with(b"\0\0\0\0IEND\xae\x42\x60\x82"));
+}
+
+#[cfg_attr(miri, ignore)] // Miri does not support file system access.
+#[test]
+fn snapshot() {
+    let dir = std::env::temp_dir().join(format!("libtock_screen_snapshot_{}", std::process::id()));
+    let path = dir.join("screen.ppm");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let screen = Screen::new_with_resolution(2, 2);
+    let kernel = fake::Kernel::new();
+    kernel.add_driver(&screen);
+    write(&screen, pixel_format::RGB_888, (0, 0, 1, 1), &[9, 9, 9]);
+
+    // A missing snapshot is a failure, rather than being silently created.
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        screen.assert_snapshot(&path)
+    }));
+    let message = *result.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.contains("does not exist"), "{message}");
+    assert!(!path.exists());
+
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(&path, screen.to_ppm()).unwrap();
+    screen.assert_snapshot(&path);
+
+    write(&screen, pixel_format::RGB_888, (1, 1, 1, 1), &[1, 1, 1]);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        screen.assert_snapshot(&path)
+    }));
+    let message = *result.unwrap_err().downcast::<String>().unwrap();
+    assert!(
+        message.contains("1 pixels differ, starting at (1, 1)"),
+        "{message}"
+    );
+    assert!(dir.join("screen.actual.png").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
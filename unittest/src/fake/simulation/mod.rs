@@ -0,0 +1,546 @@
+//! A simulated Tock system that runs several processes, each with its own
+//! `fake::Kernel`.
+
+use crate::kernel_data::with_kernel_data;
+use crate::upcall::{UpcallId, UpcallQueueEntry};
+use crate::{catch_exit, fake, ExitCall};
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+
+/// `Simulation` hosts several processes, each of which runs its app code on
+/// its own thread with its own `fake::Kernel` (and therefore its own Allow,
+/// Subscribe, and upcall state). Like the Tock kernel, the simulation only
+/// runs one process at a time, and only switches processes at yield points:
+///
+/// - Each Yield call lets the next ready process (in round-robin order) run
+///   before it returns.
+/// - A process that yield-waits with no queued upcall, when none of its
+///   drivers can make progress (see `fake::SyscallDriver::idle`), sleeps until
+///   another process has run. If every process is asleep, the simulation has
+///   deadlocked, which is reported as a panic.
+///
+/// Each process' `main` function creates the process' drivers, then runs its
+/// app code. Drivers that model a capsule shared between processes, such as
+/// `fake::KeyValue` (see `fake::KeyValueStore`) and `fake::Ipc`, share their
+/// state with the other processes' drivers. A process ends when `main` returns
+/// or calls `exit_terminate`. When it calls `exit_restart`, its kernel is
+/// dropped and `main` runs again with a new kernel, up to the simulation's
+/// restart limit.
+///
+/// # Example
+/// ```
+/// use libtock_platform::Syscalls;
+/// use libtock_unittest::fake;
+///
+/// let store = fake::KeyValueStore::new();
+/// let mut simulation = fake::Simulation::new();
+/// for name in ["first", "second"] {
+///     let store = store.clone();
+///     simulation.add_process(name, move |kernel| {
+///         kernel.add_driver(&fake::KeyValue::with_store(&store));
+///         fake::Syscalls::yield_no_wait();
+///     });
+/// }
+/// let outcomes = simulation.run();
+/// assert_eq!(outcomes[1].name, "second");
+/// assert_eq!(outcomes[1].exit, None);
+/// ```
+pub struct Simulation<'m> {
+    processes: Vec<(String, ProcessMain<'m>)>,
+    restart_limit: u32,
+}
+
+/// Identifies a process in a `Simulation`. Processes are numbered from 0, in
+/// the order they were added.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ProcessId(pub usize);
+
+/// How a process in a `Simulation` ended.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProcessOutcome {
+    pub name: String,
+
+    /// The process' last Exit call, or `None` if its `main` returned. This is
+    /// an `ExitCall::Restart` if the process exceeded the restart limit.
+    pub exit: Option<ExitCall>,
+
+    /// The number of times the process was restarted.
+    pub restarts: u32,
+}
+
+impl<'m> Simulation<'m> {
+    pub fn new() -> Simulation<'m> {
+        Simulation {
+            processes: Vec::new(),
+            restart_limit: DEFAULT_RESTART_LIMIT,
+        }
+    }
+
+    /// Adds a process called `name` (which `fake::Ipc` uses as its package
+    /// name). `main` is run on the process' thread with the process' kernel.
+    pub fn add_process<F: Fn(&fake::Kernel) + Send + 'm>(
+        &mut self,
+        name: &str,
+        main: F,
+    ) -> ProcessId {
+        self.processes.push((name.to_string(), Box::new(main)));
+        ProcessId(self.processes.len() - 1)
+    }
+
+    /// Sets the number of times each process may restart. A process that
+    /// calls `exit_restart` after reaching the limit ends instead. Defaults to
+    /// 10.
+    pub fn set_restart_limit(&mut self, restart_limit: u32) {
+        self.restart_limit = restart_limit;
+    }
+
+    /// Runs the processes until all of them have ended, and returns their
+    /// outcomes in the order they were added. If a process panics, the other
+    /// processes are stopped (by unwinding out of their next system call that
+    /// would wait) and the panic is propagated.
+    pub fn run(self) -> Vec<ProcessOutcome> {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                names: self
+                    .processes
+                    .iter()
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+                processes: self
+                    .processes
+                    .iter()
+                    .map(|_| ProcessState {
+                        status: Status::Ready,
+                        mailbox: Vec::new(),
+                    })
+                    .collect(),
+                running: (!self.processes.is_empty()).then_some(0),
+                generation: 0,
+                panic: None,
+                ipc_shares: HashMap::new(),
+            }),
+            turn: Condvar::new(),
+        });
+        let restart_limit = self.restart_limit;
+        let results: Vec<_> = thread::scope(|scope| {
+            let threads: Vec<_> = self
+                .processes
+                .into_iter()
+                .enumerate()
+                .map(|(index, (name, main))| {
+                    let process = Process {
+                        shared: shared.clone(),
+                        index,
+                    };
+                    thread::Builder::new()
+                        .name(name)
+                        .spawn_scoped(scope, move || process.run_main(&*main, restart_limit))
+                        .expect("failed to spawn process thread")
+                })
+                .collect();
+            threads
+                .into_iter()
+                .map(|thread| thread.join().expect("process thread panicked"))
+                .collect()
+        });
+        let mut state = shared.lock();
+        if let Some(payload) = state.panic.take() {
+            drop(state);
+            resume_unwind(payload);
+        }
+        state
+            .names
+            .iter()
+            .zip(results)
+            .map(|(name, (exit, restarts))| ProcessOutcome {
+                name: name.clone(),
+                exit,
+                restarts,
+            })
+            .collect()
+    }
+}
+
+impl Default for Simulation<'_> {
+    fn default() -> Self {
+        Simulation::new()
+    }
+}
+
+/// A handle to a running process in a `Simulation`, used by fake drivers that
+/// model capsules shared between processes. `Process` is `Send`, so drivers
+/// may deliver upcalls to processes running on other threads.
+#[derive(Clone)]
+pub struct Process {
+    shared: Arc<Shared>,
+    index: usize,
+}
+
+impl Process {
+    /// Returns the process running on this thread, or `None` if this thread
+    /// is not running a `Simulation` process.
+    pub fn current() -> Option<Process> {
+        CURRENT.with_borrow(Clone::clone)
+    }
+
+    pub fn id(&self) -> ProcessId {
+        ProcessId(self.index)
+    }
+
+    pub fn name(&self) -> String {
+        self.shared.lock().names[self.index].clone()
+    }
+
+    /// Returns the number of processes in the simulation.
+    pub fn process_count(&self) -> usize {
+        self.shared.lock().names.len()
+    }
+
+    /// Returns the process with the given ID in the same simulation.
+    pub fn get(&self, id: ProcessId) -> Option<Process> {
+        (id.0 < self.process_count()).then(|| Process {
+            shared: self.shared.clone(),
+            index: id.0,
+        })
+    }
+
+    /// Returns the process called `name` in the same simulation, if it has
+    /// not ended.
+    pub fn find(&self, name: &str) -> Option<Process> {
+        let state = self.shared.lock();
+        let index = state.names.iter().position(|n| n == name)?;
+        match state.processes[index].status {
+            Status::Exited => None,
+            _ => Some(Process {
+                shared: self.shared.clone(),
+                index,
+            }),
+        }
+    }
+
+    /// Returns true if the process has ended.
+    pub fn has_exited(&self) -> bool {
+        matches!(
+            self.shared.lock().processes[self.index].status,
+            Status::Exited
+        )
+    }
+
+    /// Schedules the upcall with the given driver and subscribe numbers in this
+    /// process. If this process is running on another thread, the upcall is
+    /// queued when it next runs. As with `DriverShareRef::schedule_upcall`,
+    /// nothing happens if the process has not subscribed to the upcall (or has
+    /// ended).
+    pub fn schedule_upcall(&self, driver_num: u32, subscribe_num: u32, args: (u32, u32, u32)) {
+        let mail = Mail {
+            driver_num,
+            subscribe_num,
+            args,
+        };
+        if Process::current().is_some_and(|current| current.is_same(self)) {
+            mail.deliver();
+            return;
+        }
+        let mut state = self.shared.lock();
+        if !matches!(state.processes[self.index].status, Status::Exited) {
+            state.processes[self.index].mailbox.push(mail);
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Implementation details below
+// -----------------------------------------------------------------------------
+
+const DEFAULT_RESTART_LIMIT: u32 = 10;
+
+type ProcessMain<'m> = Box<dyn Fn(&fake::Kernel) + Send + 'm>;
+
+thread_local!(static CURRENT: RefCell<Option<Process>> = const { RefCell::new(None) });
+
+struct Shared {
+    state: Mutex<State>,
+    // Notified whenever `running` changes or a process panics.
+    turn: Condvar,
+}
+
+struct State {
+    names: Vec<String>,
+    processes: Vec<ProcessState>,
+
+    // The process that may run, or None once every process has ended (or a
+    // process has panicked).
+    running: Option<usize>,
+
+    // Incremented whenever a process reaches a yield point or ends. A sleeping
+    // process is woken once the generation has changed, as the process that
+    // ran in the meantime may have queued an upcall for it.
+    generation: u64,
+
+    // The payload of the first process that panicked, if any.
+    panic: Option<Box<dyn Any + Send>>,
+
+    // Buffers shared through `fake::Ipc`. The key is (client, service).
+    ipc_shares: HashMap<(usize, usize), IpcShare>,
+}
+
+// A buffer a client shares with a service through `fake::Ipc`.
+#[derive(Clone, Copy)]
+pub(crate) struct IpcShare {
+    pub address: *mut u8,
+    pub len: usize,
+}
+
+// Safety: Only the running process accesses a shared buffer, and processes run
+// one at a time.
+unsafe impl Send for IpcShare {}
+
+struct ProcessState {
+    status: Status,
+    // Upcalls scheduled by other processes, to be queued when this process
+    // next runs.
+    mailbox: Vec<Mail>,
+}
+
+#[derive(Clone, Copy)]
+enum Status {
+    Ready,
+    // Waiting for an upcall since the given generation.
+    Sleeping(u64),
+    Exited,
+}
+
+struct Mail {
+    driver_num: u32,
+    subscribe_num: u32,
+    args: (u32, u32, u32),
+}
+
+// Panic payload used to unwind the processes that are still running after a
+// process panics.
+struct Aborted;
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // A panic in another test thread should not hide this thread's result.
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl State {
+    // Returns the next process after `index`, in round-robin order, that can
+    // run: either it is ready, or it is sleeping and another process has run
+    // since it fell asleep. Does not return `index` itself.
+    fn next_runnable(&self, index: usize) -> Option<usize> {
+        let count = self.processes.len();
+        (1..count)
+            .map(|offset| (index + offset) % count)
+            .find(|&other| match self.processes[other].status {
+                Status::Ready => true,
+                Status::Sleeping(since) => since != self.generation,
+                Status::Exited => false,
+            })
+    }
+}
+
+impl Mail {
+    // Queues this upcall in the current thread's kernel.
+    fn deliver(self) {
+        with_kernel_data(|kernel_data| {
+            let Some(kernel_data) = kernel_data else {
+                return;
+            };
+            let Some(&upcall) = kernel_data
+                .drivers
+                .get(&self.driver_num)
+                .and_then(|driver_data| driver_data.upcalls.get(&self.subscribe_num))
+            else {
+                return;
+            };
+            if upcall.is_null() {
+                return;
+            }
+            kernel_data.upcall_queue.push_back(UpcallQueueEntry {
+                args: self.args,
+                id: UpcallId {
+                    driver_num: self.driver_num,
+                    subscribe_num: self.subscribe_num,
+                },
+                upcall,
+            });
+        });
+    }
+}
+
+impl Process {
+    fn is_same(&self, other: &Process) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared) && self.index == other.index
+    }
+
+    // The body of a process thread. Returns the process' last exit call and
+    // its restart count.
+    fn run_main(self, main: &dyn Fn(&fake::Kernel), restart_limit: u32) -> (Option<ExitCall>, u32) {
+        CURRENT.set(Some(self.clone()));
+        let mut restarts = 0;
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            self.wait_for_turn(self.shared.lock());
+            loop {
+                let kernel = fake::Kernel::new();
+                let exit = catch_exit(AssertUnwindSafe(|| main(&kernel))).err();
+                drop(kernel);
+                self.forget_ipc_shares();
+                match exit {
+                    Some(ExitCall::Restart(_)) if restarts < restart_limit => restarts += 1,
+                    exit => return exit,
+                }
+            }
+        }));
+        CURRENT.set(None);
+        self.forget_ipc_shares();
+
+        let mut state = self.shared.lock();
+        state.processes[self.index].status = Status::Exited;
+        state.processes[self.index].mailbox.clear();
+        state.generation += 1;
+        let exit = match result {
+            Ok(exit) => exit,
+            Err(payload) => {
+                if !payload.is::<Aborted>() && state.panic.is_none() {
+                    state.panic = Some(payload);
+                }
+                None
+            }
+        };
+        if state.running == Some(self.index) {
+            state.running = match state.panic {
+                None => state.next_runnable(self.index),
+                Some(_) => None,
+            };
+        }
+        self.shared.turn.notify_all();
+        (exit, restarts)
+    }
+
+    // Removes the buffers this process shared through `fake::Ipc`, which are no
+    // longer valid once its app code has exited.
+    fn forget_ipc_shares(&self) {
+        let index = self.index;
+        self.shared
+            .lock()
+            .ipc_shares
+            .retain(|&(client, _), _| client != index);
+    }
+
+    // Waits until this process may run, then queues the upcalls other
+    // processes scheduled for it. If another process panics in the meantime,
+    // unwinds with an `Aborted` payload.
+    fn wait_for_turn(&self, mut state: MutexGuard<'_, State>) {
+        while state.running != Some(self.index) && state.panic.is_none() {
+            state = self
+                .shared
+                .turn
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        if state.panic.is_some() {
+            drop(state);
+            // A process that makes a system call while unwinding (e.g. from a
+            // destructor) continues without the other processes.
+            if thread::panicking() {
+                return;
+            }
+            resume_unwind(Box::new(Aborted));
+        }
+        state.processes[self.index].status = Status::Ready;
+        let mailbox = std::mem::take(&mut state.processes[self.index].mailbox);
+        drop(state);
+        for mail in mailbox {
+            mail.deliver();
+        }
+    }
+
+    // Passes the turn to the next runnable process, if any, and waits until
+    // this process may run again.
+    fn switch(&self, mut state: MutexGuard<'_, State>, next: usize) {
+        state.running = Some(next);
+        self.shared.turn.notify_all();
+        self.wait_for_turn(state);
+    }
+}
+
+// Called at the start of each Yield call. If this thread is running a
+// simulation process, lets the other processes run.
+pub(crate) fn yield_point() {
+    let Some(process) = Process::current() else {
+        return;
+    };
+    let mut state = process.shared.lock();
+    state.generation += 1;
+    match state.next_runnable(process.index) {
+        Some(next) => process.switch(state, next),
+        None => process.wait_for_turn(state),
+    }
+}
+
+// Called by yield-wait when no upcall is queued and no driver can make
+// progress. If this thread is running a simulation process, sleeps until
+// another process has run and returns true. Returns false if this thread is
+// not running a simulation process. Panics if every process is asleep.
+pub(crate) fn sleep() -> bool {
+    let Some(process) = Process::current() else {
+        return false;
+    };
+    let mut state = process.shared.lock();
+    if !state.processes[process.index].mailbox.is_empty() {
+        process.wait_for_turn(state);
+        return true;
+    }
+    state.processes[process.index].status = Status::Sleeping(state.generation);
+    let Some(next) = state.next_runnable(process.index) else {
+        let sleeping: Vec<_> = state
+            .names
+            .iter()
+            .zip(&state.processes)
+            .filter(|(_, process)| matches!(process.status, Status::Sleeping(_)))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        let message = format!(
+            "Simulation deadlocked: every process is waiting for an upcall (waiting: {})",
+            sleeping.join(", ")
+        );
+        state.processes[process.index].status = Status::Ready;
+        drop(state);
+        panic!("{message}");
+    };
+    process.switch(state, next);
+    true
+}
+
+// Records the buffer `client` shares with `service` through `fake::Ipc`, or
+// removes it if `buffer` is None.
+pub(crate) fn set_ipc_share(client: &Process, service: ProcessId, buffer: Option<IpcShare>) {
+    let mut state = client.shared.lock();
+    match buffer {
+        Some(buffer) => state.ipc_shares.insert((client.index, service.0), buffer),
+        None => state.ipc_shares.remove(&(client.index, service.0)),
+    };
+}
+
+// Returns the buffer `client` shares with `service`, if any.
+pub(crate) fn ipc_share(client: &Process, service: &Process) -> Option<IpcShare> {
+    client
+        .shared
+        .lock()
+        .ipc_shares
+        .get(&(client.index, service.index))
+        .copied()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,163 @@
+use crate::{fake, ExitCall};
+use libtock_platform::{share, AllowRo, AllowRw, DefaultConfig, Subscribe, Syscalls};
+use std::cell::Cell;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+// Processes take turns at each yield point.
+#[test]
+fn round_robin() {
+    let log = Mutex::new(Vec::new());
+    let mut simulation = fake::Simulation::new();
+    for name in ["a", "b", "c"] {
+        let log = &log;
+        simulation.add_process(name, move |_| {
+            for step in 0..2 {
+                log.lock().unwrap().push(format!("{name}{step}"));
+                fake::Syscalls::yield_no_wait();
+            }
+        });
+    }
+    let outcomes = simulation.run();
+    assert_eq!(*log.lock().unwrap(), ["a0", "b0", "c0", "a1", "b1", "c1"]);
+    assert_eq!(outcomes.len(), 3);
+    assert_eq!(outcomes[2].name, "c");
+    assert_eq!(outcomes[2].exit, None);
+}
+
+// Each process has its own kernel, and KeyValue drivers sharing a store see
+// each other's writes.
+#[test]
+fn shared_key_value() {
+    let store = fake::KeyValueStore::new();
+    let mut simulation = fake::Simulation::new();
+    let writer_store = store.clone();
+    simulation.add_process("writer", move |kernel| {
+        kernel.add_driver(&fake::KeyValue::with_store(&writer_store));
+        share::scope::<(AllowRo<_, KEY_VALUE, 0>, AllowRo<_, KEY_VALUE, 1>), _, _>(|handle| {
+            let (key, value) = handle.split();
+            fake::Syscalls::allow_ro::<DefaultConfig, KEY_VALUE, 0>(key, b"greeting").unwrap();
+            fake::Syscalls::allow_ro::<DefaultConfig, KEY_VALUE, 1>(value, b"hello").unwrap();
+            assert!(fake::Syscalls::command(KEY_VALUE, SET, 0, 0).is_success());
+        });
+        fake::Syscalls::exit_terminate(3);
+    });
+    let reader_store = store.clone();
+    simulation.add_process("reader", move |kernel| {
+        kernel.add_driver(&fake::KeyValue::with_store(&reader_store));
+        let mut value = [0; 5];
+        let done = Cell::new(None::<(u32, u32)>);
+        share::scope::<
+            (
+                AllowRo<_, KEY_VALUE, 0>,
+                AllowRw<_, KEY_VALUE, 0>,
+                Subscribe<_, KEY_VALUE, 0>,
+            ),
+            _,
+            _,
+        >(|handle| {
+            let (key, allow_value, subscribe) = handle.split();
+            fake::Syscalls::allow_ro::<DefaultConfig, KEY_VALUE, 0>(key, b"greeting").unwrap();
+            fake::Syscalls::allow_rw::<DefaultConfig, KEY_VALUE, 0>(allow_value, &mut value)
+                .unwrap();
+            fake::Syscalls::subscribe::<_, _, DefaultConfig, KEY_VALUE, 0>(subscribe, &done)
+                .unwrap();
+            assert!(fake::Syscalls::command(KEY_VALUE, GET, 0, 0).is_success());
+            fake::Syscalls::yield_wait();
+        });
+        assert_eq!(done.get(), Some((0, 5)));
+        assert_eq!(&value, b"hello");
+    });
+    let outcomes = simulation.run();
+    assert_eq!(outcomes[0].exit, Some(ExitCall::Terminate(3)));
+    assert_eq!(outcomes[1].exit, None);
+    assert_eq!(store.get("greeting").as_deref(), Some("hello"));
+}
+
+#[test]
+fn restart() {
+    let runs = AtomicU32::new(0);
+    let mut simulation = fake::Simulation::new();
+    simulation.add_process("flaky", |kernel| {
+        // Each run gets a new kernel.
+        assert!(kernel.take_syscall_log().is_empty());
+        if runs.fetch_add(1, Ordering::Relaxed) < 2 {
+            fake::Syscalls::exit_restart(1);
+        }
+    });
+    let outcomes = simulation.run();
+    assert_eq!(runs.load(Ordering::Relaxed), 3);
+    assert_eq!(outcomes[0].exit, None);
+    assert_eq!(outcomes[0].restarts, 2);
+
+    let mut simulation = fake::Simulation::new();
+    simulation.set_restart_limit(1);
+    simulation.add_process("crashing", |_| fake::Syscalls::exit_restart(2));
+    let outcomes = simulation.run();
+    assert_eq!(outcomes[0].exit, Some(ExitCall::Restart(2)));
+    assert_eq!(outcomes[0].restarts, 1);
+}
+
+// A process that waits sleeps until another process has run, and every
+// process waiting is reported as a deadlock.
+#[test]
+fn deadlock() {
+    let polls = AtomicU32::new(0);
+    let mut simulation = fake::Simulation::new();
+    simulation.add_process("waiter", |_| fake::Syscalls::yield_wait());
+    simulation.add_process("poller", |_| {
+        for _ in 0..3 {
+            polls.fetch_add(1, Ordering::Relaxed);
+            fake::Syscalls::yield_no_wait();
+        }
+        fake::Syscalls::yield_wait();
+    });
+    let message = *catch_unwind(AssertUnwindSafe(|| simulation.run()))
+        .expect_err("deadlock not detected")
+        .downcast::<String>()
+        .unwrap();
+    assert_eq!(polls.load(Ordering::Relaxed), 3);
+    assert_eq!(
+        message,
+        "Simulation deadlocked: every process is waiting for an upcall (waiting: waiter, poller)"
+    );
+}
+
+// A panic in one process stops the others and is propagated by run.
+#[test]
+fn panic_propagates() {
+    let mut simulation = fake::Simulation::new();
+    simulation.add_process("spinner", |_| loop {
+        fake::Syscalls::yield_no_wait();
+    });
+    simulation.add_process("buggy", |_| panic!("bug in process"));
+    let payload = catch_unwind(AssertUnwindSafe(|| simulation.run())).unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"bug in process"));
+}
+
+#[test]
+fn process_handles() {
+    assert!(fake::simulation::Process::current().is_none());
+    let mut simulation = fake::Simulation::new();
+    simulation.add_process("first", |_| {
+        let process = fake::simulation::Process::current().unwrap();
+        assert_eq!(process.id(), fake::ProcessId(0));
+        assert_eq!(process.name(), "first");
+        assert_eq!(process.process_count(), 2);
+        let second = process.find("second").unwrap();
+        assert_eq!(second.id(), fake::ProcessId(1));
+        assert!(process.find("third").is_none());
+        assert!(process.get(fake::ProcessId(2)).is_none());
+        fake::Syscalls::yield_no_wait();
+        // "second" has ended by now.
+        assert!(second.has_exited());
+        assert!(process.find("second").is_none());
+    });
+    simulation.add_process("second", |_| {});
+    simulation.run();
+}
+
+const KEY_VALUE: u32 = 0x50003;
+const GET: u32 = 1;
+const SET: u32 = 2;
@@ -0,0 +1,239 @@
+//! Fake implementation of the SPI controller API, documented here:
+//! https://github.com/tock/tock/blob/master/doc/syscalls/20001_spi_controller.md
+//!
+//! Like the real API, `SpiController` performs full-duplex transfers with the
+//! peripheral on the other end of the bus. The peripheral is a pluggable
+//! `SpiDevice`; `SpiLoopback` models a bus with MISO tied to MOSI. With no
+//! device attached, reads return zeros. Every byte written is also recorded,
+//! and can be retrieved with `take_bytes`. Transfers complete immediately: the
+//! upcall is queued by the command that started the transfer.
+
+use core::cell::{Cell, RefCell};
+use libtock_platform::{CommandReturn, ErrorCode};
+use std::rc::Rc;
+
+use crate::{DriverInfo, DriverShareRef, RoAllowBuffer, RwAllowBuffer};
+
+/// A fake peripheral on the other end of a `fake::SpiController`'s bus.
+pub trait SpiDevice: 'static {
+    /// Called for each transfer. `write` is the data the controller shifts
+    /// out, and `read` (which has the same length) should be filled with the
+    /// data the peripheral shifts back.
+    fn transfer(&self, write: &[u8], read: &mut [u8]);
+}
+
+/// A `SpiDevice` that echoes every byte back in the same transfer, as if MISO
+/// were wired to MOSI.
+pub struct SpiLoopback;
+
+impl SpiLoopback {
+    pub fn new() -> Rc<SpiLoopback> {
+        Rc::new(SpiLoopback)
+    }
+}
+
+impl SpiDevice for SpiLoopback {
+    fn transfer(&self, write: &[u8], read: &mut [u8]) {
+        read.copy_from_slice(write);
+    }
+}
+
+pub struct SpiController {
+    write_buffer: Cell<RoAllowBuffer>,
+    read_buffer: RefCell<RwAllowBuffer>,
+    device: RefCell<Option<Rc<dyn SpiDevice>>>,
+    written: Cell<Vec<u8>>,
+
+    baud_rate: Cell<u32>,
+    phase: Cell<u32>,
+    polarity: Cell<u32>,
+
+    share_ref: DriverShareRef,
+}
+
+impl SpiController {
+    pub fn new() -> Rc<SpiController> {
+        Rc::new(SpiController {
+            write_buffer: Default::default(),
+            read_buffer: Default::default(),
+            device: RefCell::new(None),
+            written: Default::default(),
+            baud_rate: Cell::new(DEFAULT_BAUD_RATE),
+            phase: Cell::new(0),
+            polarity: Cell::new(0),
+            share_ref: Default::default(),
+        })
+    }
+
+    /// Attaches `device` to the bus, replacing the previous device.
+    pub fn set_device<D: SpiDevice>(&self, device: &Rc<D>) {
+        self.device.replace(Some(device.clone()));
+    }
+
+    /// Returns the bytes written to the bus so far, and clears them.
+    pub fn take_bytes(&self) -> Vec<u8> {
+        self.written.take()
+    }
+
+    pub fn baud_rate(&self) -> u32 {
+        self.baud_rate.get()
+    }
+
+    pub fn phase(&self) -> u32 {
+        self.phase.get()
+    }
+
+    pub fn polarity(&self) -> u32 {
+        self.polarity.get()
+    }
+
+    // Runs one transfer on the bus, recording the written bytes.
+    fn transfer(&self, write: &[u8], read: &mut [u8]) {
+        let mut written = self.written.take();
+        written.extend_from_slice(write);
+        self.written.set(written);
+        let device = self.device.borrow().clone();
+        match device {
+            Some(device) => device.transfer(write, read),
+            None => read.fill(0),
+        }
+    }
+
+    // Implements READ_WRITE_BYTES, given the contents of the write buffer.
+    fn read_write(&self, write_buffer: &[u8], len: usize) -> CommandReturn {
+        let write = match write_buffer.get(..len) {
+            Some(write) => write,
+            None => return crate::command_return::failure(ErrorCode::Size),
+        };
+        // The read buffer is optional: without one, the data shifted in is
+        // discarded.
+        let mut read_buffer = self.read_buffer.borrow_mut();
+        let mut discard = vec![0; len];
+        let read = match read_buffer.len() {
+            0 => &mut discard[..],
+            read_len if read_len >= len => &mut read_buffer[..len],
+            _ => return crate::command_return::failure(ErrorCode::Size),
+        };
+        self.transfer(write, read);
+        self.complete(len as u32)
+    }
+
+    fn complete(&self, len: u32) -> CommandReturn {
+        self.share_ref
+            .schedule_upcall(SUBSCRIBE_COMPLETE, (len, 0, 0))
+            .expect("Unable to schedule upcall");
+        crate::command_return::success()
+    }
+}
+
+impl crate::fake::SyscallDriver for SpiController {
+    fn info(&self) -> DriverInfo {
+        DriverInfo::new(DRIVER_NUM).upcall_count(1)
+    }
+
+    fn register(&self, share_ref: DriverShareRef) {
+        self.share_ref.replace(share_ref);
+    }
+
+    fn allow_readonly(
+        &self,
+        buffer_num: u32,
+        buffer: RoAllowBuffer,
+    ) -> Result<RoAllowBuffer, (RoAllowBuffer, ErrorCode)> {
+        if buffer_num == ALLOW_WRITE {
+            Ok(self.write_buffer.replace(buffer))
+        } else {
+            Err((buffer, ErrorCode::Invalid))
+        }
+    }
+
+    fn allow_readwrite(
+        &self,
+        buffer_num: u32,
+        buffer: RwAllowBuffer,
+    ) -> Result<RwAllowBuffer, (RwAllowBuffer, ErrorCode)> {
+        if buffer_num == ALLOW_READ {
+            Ok(self.read_buffer.replace(buffer))
+        } else {
+            Err((buffer, ErrorCode::Invalid))
+        }
+    }
+
+    fn command(&self, command_num: u32, argument0: u32, _argument1: u32) -> CommandReturn {
+        let len = argument0 as usize;
+        match command_num {
+            EXISTS => crate::command_return::success(),
+            READ_WRITE_BYTES => {
+                let write_buffer = self.write_buffer.take();
+                let result = self.read_write(&write_buffer, len);
+                self.write_buffer.set(write_buffer);
+                result
+            }
+            READ_BYTES => {
+                let mut read_buffer = self.read_buffer.borrow_mut();
+                let read = match read_buffer.get_mut(..len) {
+                    Some(read) => read,
+                    None => return crate::command_return::failure(ErrorCode::Size),
+                };
+                self.transfer(&vec![0; len], read);
+                self.complete(argument0)
+            }
+            INPLACE_READ_WRITE_BYTES => {
+                let mut read_buffer = self.read_buffer.borrow_mut();
+                let buffer = match read_buffer.get_mut(..len) {
+                    Some(buffer) => buffer,
+                    None => return crate::command_return::failure(ErrorCode::Size),
+                };
+                let write = buffer.to_vec();
+                self.transfer(&write, buffer);
+                self.complete(argument0)
+            }
+            SET_BAUD => {
+                self.baud_rate.set(argument0);
+                crate::command_return::success()
+            }
+            GET_BAUD => crate::command_return::success_u32(self.baud_rate.get()),
+            SET_PHASE | SET_POLARITY if argument0 > 1 => {
+                crate::command_return::failure(ErrorCode::Invalid)
+            }
+            SET_PHASE => {
+                self.phase.set(argument0);
+                crate::command_return::success()
+            }
+            GET_PHASE => crate::command_return::success_u32(self.phase.get()),
+            SET_POLARITY => {
+                self.polarity.set(argument0);
+                crate::command_return::success()
+            }
+            GET_POLARITY => crate::command_return::success_u32(self.polarity.get()),
+            _ => crate::command_return::failure(ErrorCode::NoSupport),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
+
+// -----------------------------------------------------------------------------
+// Driver number and command IDs
+// -----------------------------------------------------------------------------
+
+const DRIVER_NUM: u32 = 0x20001;
+
+const DEFAULT_BAUD_RATE: u32 = 1_000_000;
+
+// Command IDs
+const EXISTS: u32 = 0;
+const READ_WRITE_BYTES: u32 = 2;
+const SET_BAUD: u32 = 5;
+const GET_BAUD: u32 = 6;
+const SET_PHASE: u32 = 7;
+const GET_PHASE: u32 = 8;
+const SET_POLARITY: u32 = 9;
+const GET_POLARITY: u32 = 10;
+const READ_BYTES: u32 = 11;
+const INPLACE_READ_WRITE_BYTES: u32 = 12;
+
+const SUBSCRIBE_COMPLETE: u32 = 0;
+const ALLOW_WRITE: u32 = 0;
+const ALLOW_READ: u32 = 0;
@@ -0,0 +1,82 @@
+use crate::fake::{self, SyscallDriver};
+use crate::{RoAllowBuffer, RwAllowBuffer};
+use fake::spi_controller::*;
+use libtock_platform::{share, AllowRo, AllowRw, DefaultConfig, Subscribe, YieldNoWaitReturn};
+
+// Tests the command implementation.
+#[test]
+fn command() {
+    let spi = SpiController::new();
+    assert!(spi.command(EXISTS, 0, 0).is_success());
+    assert!(spi
+        .allow_readonly(ALLOW_WRITE, RoAllowBuffer::default())
+        .is_ok());
+    assert!(spi.allow_readonly(1, RoAllowBuffer::default()).is_err());
+    assert!(spi
+        .allow_readwrite(ALLOW_READ, RwAllowBuffer::default())
+        .is_ok());
+    assert!(spi.allow_readwrite(1, RwAllowBuffer::default()).is_err());
+
+    assert_eq!(
+        spi.command(READ_WRITE_BYTES, 1, 0).get_failure(),
+        Some(ErrorCode::Size)
+    );
+    assert_eq!(
+        spi.command(GET_BAUD, 0, 0).get_success_u32(),
+        Some(DEFAULT_BAUD_RATE)
+    );
+    assert!(spi.command(SET_BAUD, 400_000, 0).is_success());
+    assert_eq!(spi.baud_rate(), 400_000);
+    assert_eq!(
+        spi.command(SET_PHASE, 2, 0).get_failure(),
+        Some(ErrorCode::Invalid)
+    );
+    assert!(spi.command(SET_PHASE, 1, 0).is_success());
+    assert!(spi.command(SET_POLARITY, 1, 0).is_success());
+    assert_eq!(spi.command(GET_PHASE, 0, 0).get_success_u32(), Some(1));
+    assert_eq!(spi.command(GET_POLARITY, 0, 0).get_success_u32(), Some(1));
+}
+
+// Integration test that verifies SpiController works with fake::Kernel and
+// libtock_platform::Syscalls.
+#[test]
+fn kernel_integration() {
+    use libtock_platform::Syscalls;
+    let kernel = fake::Kernel::new();
+    let spi = SpiController::new();
+    kernel.add_driver(&spi);
+
+    let listener = core::cell::Cell::<Option<(u32,)>>::new(None);
+    let mut rx = [0xff; 3];
+    share::scope::<
+        (
+            AllowRo<fake::Syscalls, DRIVER_NUM, ALLOW_WRITE>,
+            AllowRw<fake::Syscalls, DRIVER_NUM, ALLOW_READ>,
+            Subscribe<fake::Syscalls, DRIVER_NUM, SUBSCRIBE_COMPLETE>,
+        ),
+        _,
+        _,
+    >(|handle| {
+        let (allow_ro, allow_rw, subscribe) = handle.split();
+        fake::Syscalls::allow_ro::<DefaultConfig, DRIVER_NUM, ALLOW_WRITE>(allow_ro, b"abc")
+            .unwrap();
+        fake::Syscalls::allow_rw::<DefaultConfig, DRIVER_NUM, ALLOW_READ>(allow_rw, &mut rx)
+            .unwrap();
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, SUBSCRIBE_COMPLETE>(
+            subscribe, &listener,
+        )
+        .unwrap();
+
+        // No device: reads return zeros.
+        assert!(fake::Syscalls::command(DRIVER_NUM, READ_WRITE_BYTES, 1, 0).is_success());
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(listener.take(), Some((1,)));
+
+        spi.set_device(&SpiLoopback::new());
+        assert!(fake::Syscalls::command(DRIVER_NUM, READ_WRITE_BYTES, 2, 0).is_success());
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::Upcall);
+        assert_eq!(listener.take(), Some((2,)));
+    });
+    assert_eq!(rx, [b'a', b'b', 0xff]);
+    assert_eq!(spi.take_bytes(), b"aab");
+}
@@ -54,4 +54,19 @@ pub trait SyscallDriver: 'static {
         let _ = buffer_num; // Silences the unused variable warning.
         Err((buffer, ErrorCode::NoSupport))
     }
+
+    // -------------------------------------------------------------------------
+    // Virtual time
+    // -------------------------------------------------------------------------
+
+    /// Called by `fake::Kernel` when the process calls yield-wait while no
+    /// upcall is queued. A real process would sleep until an interrupt queued
+    /// an upcall; fake drivers that model the passage of time (such as
+    /// `fake::Alarm`) implement `idle` to skip ahead to their next event.
+    /// Returns `true` if the driver skipped ahead, in which case the kernel
+    /// checks its upcall queue again. The default implementation does nothing
+    /// and returns `false`.
+    fn idle(&self) -> bool {
+        false
+    }
 }
@@ -1,14 +1,18 @@
 use crate::kernel_data::with_kernel_data;
+use crate::strict::AllowKind;
 use crate::{ExpectedSyscall, SyscallLogEntry};
 use libtock_platform::{return_variant, ErrorCode, Register};
 use std::convert::TryInto;
+use std::panic::Location;
 
+#[track_caller]
 pub(super) unsafe fn allow_ro(
     driver_num: Register,
     buffer_num: Register,
     address: Register,
     len: Register,
 ) -> [Register; 4] {
+    let caller = Location::caller();
     let driver_num = driver_num.try_into().expect("Too large driver number");
     let buffer_num = buffer_num.try_into().expect("Too large buffer number");
     let result = with_kernel_data(|option_kernel_data| {
@@ -24,7 +28,7 @@ pub(super) unsafe fn allow_ro(
         // Check for an expected syscall entry. Returns an error from the lambda
         // if this syscall was expected and return_error was specified. Panics
         // if a different syscall was expected.
-        match kernel_data.expected_syscalls.pop_front() {
+        match crate::expectation::next_expected(kernel_data) {
             None => {}
             Some(ExpectedSyscall::AllowRo {
                 driver_num: expected_driver_num,
@@ -78,7 +82,18 @@ pub(super) unsafe fn allow_ro(
     let (address_out, len_out) = with_kernel_data(|option_kernel_data| {
         let kernel_data = option_kernel_data
             .expect("fake::Kernel dropped during fake::SyscallDriver::allow_readonly");
-        kernel_data.allow_db.remove_ro_buffer(buffer_out)
+        let (address_out, len_out) = kernel_data.allow_db.remove_ro_buffer(buffer_out);
+        if let Some(strict) = &mut kernel_data.strict {
+            strict.check_allow(
+                caller,
+                AllowKind::ReadOnly,
+                (driver_num, buffer_num),
+                (address.into(), len.into()),
+                (address_out.into(), len_out.into()),
+                error_code.is_none(),
+            );
+        }
+        (address_out, len_out)
     });
 
     match error_code {
@@ -4,13 +4,16 @@ use crate::kernel_data::with_kernel_data;
 use crate::{command_return, ExpectedSyscall, SyscallLogEntry};
 use libtock_platform::{ErrorCode, Register};
 use std::convert::TryInto;
+use std::panic::Location;
 
+#[track_caller]
 pub(super) fn command(
     driver_id: Register,
     command_id: Register,
     argument0: Register,
     argument1: Register,
 ) -> [Register; 4] {
+    let caller = Location::caller();
     let driver_id = driver_id.try_into().expect("Too large driver ID");
     let command_id = command_id.try_into().expect("Too large command ID");
     let argument0 = argument0.try_into().expect("Too large argument 0");
@@ -31,7 +34,7 @@ pub(super) fn command(
         // but did not specify a return override. Panics if a different syscall
         // was expected (either a non-Command syscall, or a Command call with
         // different arguments).
-        let override_return = match kernel_data.expected_syscalls.pop_front() {
+        let override_return = match crate::expectation::next_expected(kernel_data) {
             None => None,
             Some(ExpectedSyscall::Command {
                 driver_id: expected_driver_id,
@@ -72,7 +75,24 @@ pub(super) fn command(
     // Call the driver if one is present. If not, return NoDevice as required by
     // TRD 104.
     let driver_return = match driver {
-        Some(driver) => driver.command(command_id, argument0, argument1),
+        Some(driver) => {
+            let driver_return = driver.command(command_id, argument0, argument1);
+            with_kernel_data(|option_kernel_data| {
+                let kernel_data = option_kernel_data
+                    .expect("fake::Kernel dropped during fake::SyscallDriver::command");
+                // Overridden returns are supplied by the test rather than the
+                // driver, so only check the driver's return if it is used.
+                if let (Some(strict), None) = (&mut kernel_data.strict, override_return) {
+                    strict.check_command(
+                        caller,
+                        driver_id,
+                        command_id,
+                        driver_return.return_variant(),
+                    );
+                }
+            });
+            driver_return
+        }
         None => command_return::failure(ErrorCode::NoDevice),
     };
 
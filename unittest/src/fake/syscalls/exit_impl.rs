@@ -1,25 +1,57 @@
+use crate::exit_call::unwind_if_caught;
+use crate::kernel_data::with_kernel_data;
+use crate::{ExitCall, ExpectedSyscall, SyscallLogEntry};
 use core::convert::TryInto;
 
 pub(super) fn exit(r0: libtock_platform::Register, r1: libtock_platform::Register) -> ! {
     let exit_num: u32 = r0.try_into().expect("Too large exit number");
     let completion_code: u32 = r1.try_into().expect("Too large completion code");
-    match exit_num {
-        libtock_platform::exit_id::TERMINATE => {
-            println!("exit-terminate called with code {completion_code}");
 
-            #[cfg(not(miri))]
-            crate::exit_test::signal_exit(crate::ExitCall::Terminate(completion_code));
+    // Exit may be called without a fake::Kernel (e.g. by ExitOnDrop during a
+    // panic), so unlike the other system calls it does not require one. If a
+    // fake::Kernel exists, log the call and check it against the expected
+    // syscall queue.
+    with_kernel_data(|option_kernel_data| {
+        let Some(kernel_data) = option_kernel_data else {
+            return;
+        };
+        kernel_data.syscall_log.push(SyscallLogEntry::Exit {
+            exit_num,
+            completion_code,
+        });
+        match crate::expectation::next_expected(kernel_data) {
+            None => {}
+            Some(ExpectedSyscall::Exit {
+                exit_num: expected_exit_num,
+                completion_code: expected_completion_code,
+            }) => {
+                assert_eq!(exit_num, expected_exit_num, "expected different exit_num");
+                assert_eq!(
+                    completion_code, expected_completion_code,
+                    "expected different completion_code"
+                );
+            }
+            Some(expected_syscall) => expected_syscall.panic_wrong_call("Exit"),
+        }
+    });
 
-            std::process::exit(1);
+    let exit_call = match exit_num {
+        libtock_platform::exit_id::TERMINATE => {
+            println!("exit-terminate called with code {completion_code}");
+            ExitCall::Terminate(completion_code)
         }
         libtock_platform::exit_id::RESTART => {
             println!("exit-restart called with code {completion_code}");
-
-            #[cfg(not(miri))]
-            crate::exit_test::signal_exit(crate::ExitCall::Restart(completion_code));
-
-            std::process::exit(1);
+            ExitCall::Restart(completion_code)
         }
         _ => panic!("Unknown exit number {exit_num} invoked."),
-    }
+    };
+
+    // If the caller is inside catch_exit, this unwinds back to it.
+    unwind_if_caught(exit_call);
+
+    #[cfg(not(miri))]
+    crate::exit_test::signal_exit(exit_call);
+
+    std::process::exit(1);
 }
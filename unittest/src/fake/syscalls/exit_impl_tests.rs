@@ -1,18 +1,104 @@
 use super::exit_impl::*;
-use crate::{exit_test, ExitCall};
+use crate::{catch_exit, fake, ExitCall, ExpectedSyscall, SyscallLogEntry};
 
+#[cfg(not(miri))]
 #[test]
 fn exit_restart() {
+    use crate::exit_test;
     let exit_call = exit_test("fake::syscalls::exit_impl_tests::exit_restart", || {
         exit(libtock_platform::exit_id::RESTART.into(), 31415u32.into())
     });
     assert_eq!(exit_call, ExitCall::Restart(31415));
 }
 
+#[cfg(not(miri))]
 #[test]
 fn exit_terminate() {
+    use crate::exit_test;
     let exit_call = exit_test("fake::syscalls::exit_impl_tests::exit_terminate", || {
         exit(libtock_platform::exit_id::TERMINATE.into(), 9265u32.into())
     });
     assert_eq!(exit_call, ExitCall::Terminate(9265));
 }
+
+#[test]
+fn catch_exit_restart() {
+    let result = catch_exit(|| exit(libtock_platform::exit_id::RESTART.into(), 27u32.into()));
+    assert_eq!(result, Err(ExitCall::Restart(27)));
+}
+
+#[test]
+fn catch_exit_terminate() {
+    let result = catch_exit(|| exit(libtock_platform::exit_id::TERMINATE.into(), 18u32.into()));
+    assert_eq!(result, Err(ExitCall::Terminate(18)));
+}
+
+#[test]
+fn catch_exit_no_exit() {
+    assert_eq!(catch_exit(|| 5), Ok(5));
+}
+
+#[should_panic(expected = "not an exit")]
+#[test]
+fn catch_exit_other_panic() {
+    let _ = catch_exit(|| panic!("not an exit"));
+}
+
+#[test]
+fn catch_exit_nested() {
+    let outer = catch_exit(|| {
+        let inner = catch_exit(|| exit(libtock_platform::exit_id::TERMINATE.into(), 1u32.into()));
+        assert_eq!(inner, Err(ExitCall::Terminate(1)));
+        exit(libtock_platform::exit_id::RESTART.into(), 2u32.into())
+    });
+    assert_eq!(outer, Err(ExitCall::Restart(2)));
+}
+
+#[test]
+fn expected_wrong_exit() {
+    let kernel = fake::Kernel::new();
+    kernel.add_expected_syscall(ExpectedSyscall::Exit {
+        exit_num: libtock_platform::exit_id::TERMINATE,
+        completion_code: 3,
+    });
+    let result = std::panic::catch_unwind(|| {
+        let _ = catch_exit(|| exit(libtock_platform::exit_id::TERMINATE.into(), 4u32.into()));
+    })
+    .expect_err("failed to catch wrong completion code");
+    assert!(result
+        .downcast_ref::<String>()
+        .expect("wrong panic payload type")
+        .contains("expected different completion_code"));
+}
+
+#[test]
+fn expected_wrong_syscall() {
+    let kernel = fake::Kernel::new();
+    kernel.add_expected_syscall(ExpectedSyscall::YieldWait { skip_upcall: false });
+    let result = std::panic::catch_unwind(|| {
+        let _ = catch_exit(|| exit(libtock_platform::exit_id::RESTART.into(), 0u32.into()));
+    })
+    .expect_err("failed to catch wrong syscall");
+    assert!(result
+        .downcast_ref::<String>()
+        .expect("wrong panic payload type")
+        .contains("but Exit was called instead"));
+}
+
+#[test]
+fn logged() {
+    let kernel = fake::Kernel::new();
+    kernel.add_expected_syscall(ExpectedSyscall::Exit {
+        exit_num: libtock_platform::exit_id::RESTART,
+        completion_code: 9,
+    });
+    let result = catch_exit(|| exit(libtock_platform::exit_id::RESTART.into(), 9u32.into()));
+    assert_eq!(result, Err(ExitCall::Restart(9)));
+    assert_eq!(
+        kernel.take_syscall_log(),
+        [SyscallLogEntry::Exit {
+            exit_num: libtock_platform::exit_id::RESTART,
+            completion_code: 9,
+        }]
+    );
+}
@@ -1,8 +1,9 @@
 //! `fake::Kernel`'s implementation of the Memop system call.
 
-use crate::kernel_data::with_kernel_data;
+use crate::kernel_data::{with_kernel_data, KernelData};
+use crate::strict::{APP_RAM_END, APP_RAM_START};
 use crate::{ExpectedSyscall, SyscallLogEntry};
-use libtock_platform::{return_variant, ErrorCode, Register};
+use libtock_platform::{return_variant, ErrorCode, Register, ReturnVariant};
 use std::convert::TryInto;
 
 pub(super) fn memop(memop_num: Register, argument0: Register) -> [Register; 2] {
@@ -21,7 +22,7 @@ pub(super) fn memop(memop_num: Register, argument0: Register) -> [Register; 2] {
         // but did not specify a return override. Panics if a different syscall
         // was expected (either a non-Memop syscall, or a Memop call with
         // different arguments).
-        let return_error = match kernel_data.expected_syscalls.pop_front() {
+        let return_error = match crate::expectation::next_expected(kernel_data) {
             None => None,
             Some(ExpectedSyscall::Memop {
                 memop_num: expected_memop_num,
@@ -42,6 +43,11 @@ pub(super) fn memop(memop_num: Register, argument0: Register) -> [Register; 2] {
             Some(expected_syscall) => expected_syscall.panic_wrong_call("Memop"),
         };
 
+        if kernel_data.strict.is_some() {
+            let (memop_return, memop_r1) = strict_memop(kernel_data, memop_num, argument0);
+            return (return_error, memop_return, memop_r1);
+        }
+
         // Emulate the memop call
         // TODO: This emulation could be improved by adding data to kernel_data to allow us to
         // better track what input arguments might be expected to return errors.
@@ -90,3 +96,58 @@ pub(super) fn memop(memop_num: Register, argument0: Register) -> [Register; 2] {
     let r0: u32 = return_variant.into();
     [r0.into(), r1]
 }
+
+// Emulates the memop call the way the real kernel does, for strict mode. The
+// process' RAM is modelled as APP_RAM_START..APP_RAM_END, and the break may be
+// anywhere in that range.
+fn strict_memop(
+    kernel_data: &mut KernelData,
+    memop_num: u32,
+    argument0: Register,
+) -> (ReturnVariant, Register) {
+    let failure = |error_code: ErrorCode| (return_variant::FAILURE, error_code.into());
+    let success_u32 = |value: usize| (return_variant::SUCCESS_U32, value.into());
+    let in_ram = |address: usize| (APP_RAM_START..=APP_RAM_END).contains(&address);
+    match memop_num {
+        /* brk */
+        0 => {
+            let new_break: usize = argument0.into();
+            if !in_ram(new_break) {
+                return failure(ErrorCode::NoMem);
+            }
+            kernel_data.memory_break = new_break as *const u8;
+            (return_variant::SUCCESS, 0.into())
+        }
+        /* sbrk, which returns the previous break */
+        1 => {
+            let old_break = kernel_data.memory_break as usize;
+            let new_break = match old_break.checked_add_signed(argument0.as_i32() as isize) {
+                Some(new_break) if in_ram(new_break) => new_break,
+                _ => return failure(ErrorCode::NoMem),
+            };
+            kernel_data.memory_break = new_break as *const u8;
+            success_u32(old_break)
+        }
+        /* app_ram_start */
+        2 => success_u32(APP_RAM_START),
+        /* app_ram_end */
+        3 => success_u32(APP_RAM_END),
+        /* flash_start */
+        4 => success_u32(FLASH_START),
+        /* flash_end */
+        5 => success_u32(FLASH_END),
+        /* grant_start, which immediately follows the process' RAM */
+        6 => success_u32(APP_RAM_END),
+        /* number of writeable flash regions */
+        7 => success_u32(0),
+        /* writeable flash region start and end, of which there are none */
+        8 | 9 => failure(ErrorCode::Invalid),
+        /* debug_stack_start and debug_heap_start */
+        10 | 11 => (return_variant::SUCCESS, 0.into()),
+        _ => failure(ErrorCode::NoSupport),
+    }
+}
+
+// The process flash region modelled in strict mode.
+const FLASH_START: usize = 0x40000;
+const FLASH_END: usize = 0x48000;
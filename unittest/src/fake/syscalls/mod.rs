@@ -18,7 +18,7 @@ mod allow_ro_impl_tests;
 mod allow_rw_impl_tests;
 #[cfg(test)]
 mod command_impl_tests;
-#[cfg(all(not(miri), test))]
+#[cfg(test)]
 mod exit_impl_tests;
 #[cfg(test)]
 mod memop_impl_tests;
@@ -41,6 +41,9 @@ unsafe impl RawSyscalls for crate::fake::Syscalls {
         }
     }
 
+    // Passes the caller to Command and Allow, which report it in strict mode
+    // violations.
+    #[track_caller]
     unsafe fn syscall4<const CLASS: usize>([r0, r1, r2, r3]: [Register; 4]) -> [Register; 4] {
         crate::fake::syscalls::assert_valid((r0, r1, r2, r3));
         match CLASS {
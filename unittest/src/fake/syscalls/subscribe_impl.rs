@@ -27,7 +27,7 @@ pub(super) unsafe fn subscribe(
         // and it does not match this syscall. Otherwise sets skip_with_error to
         // skip_with_error from the expected syscall, or None if none was
         // provided.
-        let skip_with_error = match kernel_data.expected_syscalls.pop_front() {
+        let skip_with_error = match crate::expectation::next_expected(kernel_data) {
             None => None,
             Some(ExpectedSyscall::Subscribe {
                 driver_num: expected_driver_num,
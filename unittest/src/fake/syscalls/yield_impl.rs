@@ -1,6 +1,7 @@
 //! Implementations of Yield system calls.
 
-use crate::kernel_data::{with_kernel_data, KERNEL_DATA};
+use crate::kernel_data::{with_kernel_data, KernelData, KERNEL_DATA};
+use crate::upcall::UpcallQueueEntry;
 use crate::{ExpectedSyscall, SyscallLogEntry};
 
 /// # Safety
@@ -16,12 +17,15 @@ pub(super) unsafe fn yield_no_wait(return_ptr: *mut libtock_platform::YieldNoWai
 
         kernel_data.syscall_log.push(SyscallLogEntry::YieldNoWait);
 
-        match kernel_data.expected_syscalls.pop_front() {
+        let override_return = match crate::expectation::next_expected(kernel_data) {
             None => None,
             Some(ExpectedSyscall::YieldNoWait { override_return }) => override_return,
             Some(expected_syscall) => expected_syscall.panic_wrong_call("yield-no-wait"),
-        }
+        };
+        queue_replayed_upcall(kernel_data);
+        override_return
     });
+    crate::fake::simulation::yield_point();
 
     let upcall_ran = match invoke_next_upcall() {
         true => libtock_platform::YieldNoWaitReturn::Upcall,
@@ -42,12 +46,15 @@ pub(super) fn yield_wait() {
 
         kernel_data.syscall_log.push(SyscallLogEntry::YieldWait);
 
-        match kernel_data.expected_syscalls.pop_front() {
+        let skip_upcall = match crate::expectation::next_expected(kernel_data) {
             None => false,
             Some(ExpectedSyscall::YieldWait { skip_upcall }) => skip_upcall,
             Some(expected_syscall) => expected_syscall.panic_wrong_call("yield-wait"),
-        }
+        };
+        queue_replayed_upcall(kernel_data);
+        skip_upcall
     });
+    crate::fake::simulation::yield_point();
 
     if skip_upcall {
         return;
@@ -55,13 +62,58 @@ pub(super) fn yield_wait() {
 
     // In a real Tock system, a process that calls yield-wait with no queued
     // upcalls would be put to sleep until an upcall was queued (e.g. by an
-    // interrupt). However, in this single-threaded test environment, there is
-    // no possibility a new upcall will be enqueued while we wait. Panicing is
-    // friendlier than hanging, so we panic if there's no upcall.
-    assert!(
-        invoke_next_upcall(),
-        "yield-wait called with no queued upcall"
-    );
+    // interrupt). In this single-threaded test environment, the only way a new
+    // upcall can be queued is by a fake driver skipping ahead in virtual time
+    // (see `fake::SyscallDriver::idle`). If no driver can, there is no
+    // possibility a new upcall will be enqueued while we wait. Panicing is
+    // friendlier than hanging, so we panic in that case. In a
+    // `fake::Simulation`, another process may queue an upcall, so the process
+    // sleeps while the other processes run instead.
+    while !invoke_next_upcall() {
+        assert!(
+            idle_drivers() || crate::fake::simulation::sleep(),
+            "yield-wait called with no queued upcall"
+        );
+    }
+}
+
+// If a `fake::Replay` is installed, queues the upcall that the recorded trace
+// says this Yield call invoked (if any).
+fn queue_replayed_upcall(kernel_data: &mut KernelData) {
+    let Some(Some(replayed)) = kernel_data.replayed_upcalls.pop_front() else {
+        return;
+    };
+    let upcall = kernel_data
+        .drivers
+        .get(&replayed.id.driver_num)
+        .and_then(|driver_data| driver_data.upcalls.get(&replayed.id.subscribe_num))
+        .filter(|upcall| !upcall.is_null())
+        .unwrap_or_else(|| {
+            panic!(
+                "Replayed trace invokes upcall {} of driver {:#x}, which is not subscribed",
+                replayed.id.subscribe_num, replayed.id.driver_num
+            )
+        });
+    kernel_data.upcall_queue.push_back(UpcallQueueEntry {
+        args: replayed.args,
+        id: replayed.id,
+        upcall: *upcall,
+    });
+}
+
+// Calls `idle` on each driver, in driver number order, until one of them
+// returns true. Returns whether any driver did.
+fn idle_drivers() -> bool {
+    let mut drivers: Vec<_> = with_kernel_data(|option_kernel_data| {
+        option_kernel_data
+            .unwrap()
+            .drivers
+            .iter()
+            .map(|(&driver_num, driver_data)| (driver_num, driver_data.driver.clone()))
+            .collect()
+    });
+    drivers.sort_by_key(|&(driver_num, _)| driver_num);
+    drivers.into_iter().any(|(_, driver)| driver.idle())
 }
 
 // Pops the next upcall off the kernel data's upcall queue and invokes it, or
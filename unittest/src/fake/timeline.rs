@@ -0,0 +1,86 @@
+use crate::fake::Alarm;
+use core::time::Duration;
+use std::rc::Rc;
+
+/// A `Timeline` lets a test declare, up front, events that happen at given
+/// points in virtual time, such as "at 10 ms button 0 is pressed, at 20 ms a
+/// radio frame arrives". Events are closures, which typically call the fake
+/// drivers' test methods (e.g. `fake::Buttons::set_pressed`) to queue upcalls.
+///
+/// Virtual time is kept by a `fake::Alarm`, so a timeline's events are
+/// interleaved with the alarm's expirations in time order. Events run when the
+/// test advances the clock (with `Timeline::advance` or `fake::Alarm::advance`)
+/// or when the process calls yield-wait with no upcall queued, in which case
+/// the clock skips ahead to the next event or alarm expiration. This makes
+/// tests of code that mixes timers and other events deterministic.
+///
+/// # Example
+/// ```
+/// use core::time::Duration;
+/// use libtock_unittest::fake;
+///
+/// let kernel = fake::Kernel::new();
+/// let alarm = fake::Alarm::new(1000);
+/// let buttons = fake::Buttons::<2>::new();
+/// kernel.add_driver(&alarm);
+/// kernel.add_driver(&buttons);
+///
+/// let timeline = fake::Timeline::new(&alarm);
+/// let pressed = buttons.clone();
+/// timeline.at(Duration::from_millis(10), move || {
+///     pressed.set_pressed(0, true).unwrap();
+/// });
+/// timeline.advance(Duration::from_millis(10));
+/// assert!(buttons.get_button_state(0).unwrap().pressed);
+/// ```
+pub struct Timeline {
+    alarm: Rc<Alarm>,
+    // The alarm's elapsed tick count when this Timeline was created, which is
+    // time zero on this timeline.
+    origin: u64,
+}
+
+impl Timeline {
+    /// Creates a `Timeline` driven by `alarm`'s clock. Time zero on the
+    /// timeline is `alarm`'s current time.
+    pub fn new(alarm: &Rc<Alarm>) -> Timeline {
+        Timeline {
+            alarm: alarm.clone(),
+            origin: alarm.elapsed_ticks(),
+        }
+    }
+
+    /// Schedules `event` to run at `time` on this timeline. Times that do not
+    /// fall on a tick of the alarm's clock are rounded up to the next tick.
+    /// Events scheduled for the same tick run in the order they were added.
+    /// An event scheduled for a time that has already passed runs the next
+    /// time the clock is advanced.
+    pub fn at<F: FnOnce() + 'static>(&self, time: Duration, event: F) -> &Timeline {
+        let at = self.origin + self.alarm.duration_to_ticks(time);
+        self.alarm.schedule_event(at, Box::new(event));
+        self
+    }
+
+    /// Schedules `event` to run `delay` after the current time.
+    pub fn after<F: FnOnce() + 'static>(&self, delay: Duration, event: F) -> &Timeline {
+        self.at(self.now() + delay, event)
+    }
+
+    /// Returns the current time on this timeline.
+    pub fn now(&self) -> Duration {
+        self.alarm
+            .ticks_to_duration(self.alarm.elapsed_ticks() - self.origin)
+    }
+
+    /// Advances the clock by `duration` (rounded up to a whole number of
+    /// ticks), running the events that come due.
+    pub fn advance(&self, duration: Duration) {
+        let ticks = self.alarm.duration_to_ticks(duration);
+        self.alarm.advance_to(self.alarm.elapsed_ticks() + ticks);
+    }
+
+    /// Returns `true` if every event scheduled on the clock has run.
+    pub fn is_finished(&self) -> bool {
+        self.alarm.pending_events() == 0
+    }
+}
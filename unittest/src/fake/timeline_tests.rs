@@ -0,0 +1,106 @@
+use crate::fake;
+use core::cell::Cell;
+use core::time::Duration;
+use libtock_platform::{share, DefaultConfig, Subscribe, Syscalls, YieldNoWaitReturn};
+use std::rc::Rc;
+
+const ALARM: u32 = 0x0;
+const BUTTONS: u32 = 0x3;
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+#[test]
+fn events_run_in_time_order() {
+    let alarm = fake::Alarm::new(1000);
+    let timeline = fake::Timeline::new(&alarm);
+    let log = Rc::new(std::cell::RefCell::new(Vec::new()));
+    for (time, name) in [(30, "c"), (10, "a"), (20, "b1"), (20, "b2")] {
+        let log = log.clone();
+        timeline.at(ms(time), move || log.borrow_mut().push(name));
+    }
+
+    timeline.advance(ms(15));
+    assert_eq!(*log.borrow(), ["a"]);
+    assert_eq!(timeline.now(), ms(15));
+    assert!(!timeline.is_finished());
+
+    let after_log = log.clone();
+    timeline.after(ms(1), move || after_log.borrow_mut().push("d"));
+    alarm.advance(100);
+    assert_eq!(*log.borrow(), ["a", "d", "b1", "b2", "c"]);
+    assert!(timeline.is_finished());
+}
+
+#[test]
+fn timeline_origin() {
+    let alarm = fake::Alarm::new(32768);
+    alarm.advance(1000);
+    let timeline = fake::Timeline::new(&alarm);
+    let ran = Rc::new(Cell::new(false));
+    let event_ran = ran.clone();
+    // 1 ms is 32.768 ticks, which rounds up to 33.
+    timeline.at(ms(1), move || event_ran.set(true));
+    alarm.advance(32);
+    assert!(!ran.get());
+    alarm.advance(1);
+    assert!(ran.get());
+    assert_eq!(alarm.elapsed_ticks(), 1033);
+}
+
+// Verifies that timeline events and alarm expirations are delivered to the
+// process in virtual-time order as it yields.
+#[test]
+fn kernel_integration() {
+    let kernel = fake::Kernel::new();
+    let alarm = fake::Alarm::new(1000);
+    let buttons = fake::Buttons::<2>::new();
+    kernel.add_driver(&alarm);
+    kernel.add_driver(&buttons);
+
+    let timeline = fake::Timeline::new(&alarm);
+    let press = buttons.clone();
+    timeline.at(ms(10), move || press.set_pressed(1, true).unwrap());
+    let release = buttons.clone();
+    timeline.at(ms(30), move || release.set_pressed(1, false).unwrap());
+
+    let alarm_fired: Cell<Option<(u32, u32)>> = Cell::new(None);
+    let button_event: Cell<Option<(u32, u32)>> = Cell::new(None);
+    share::scope::<
+        (
+            Subscribe<fake::Syscalls, ALARM, 0>,
+            Subscribe<fake::Syscalls, BUTTONS, 0>,
+        ),
+        _,
+        _,
+    >(|handle| {
+        let (alarm_subscribe, buttons_subscribe) = handle.split();
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, ALARM, 0>(alarm_subscribe, &alarm_fired)
+            .unwrap();
+        fake::Syscalls::subscribe::<_, _, DefaultConfig, BUTTONS, 0>(
+            buttons_subscribe,
+            &button_event,
+        )
+        .unwrap();
+        // Enable interrupts for button 1 and set a 20 ms alarm.
+        assert!(fake::Syscalls::command(BUTTONS, 1, 1, 0).is_success());
+        assert!(fake::Syscalls::command(ALARM, 5, 20, 0).is_success_u32());
+
+        assert_eq!(fake::Syscalls::yield_no_wait(), YieldNoWaitReturn::NoUpcall);
+        fake::Syscalls::yield_wait();
+        assert_eq!(button_event.take(), Some((1, 1)));
+        assert_eq!(alarm_fired.get(), None);
+        assert_eq!(timeline.now(), ms(10));
+
+        fake::Syscalls::yield_wait();
+        assert_eq!(alarm_fired.take(), Some((20, 20)));
+        assert_eq!(button_event.get(), None);
+
+        fake::Syscalls::yield_wait();
+        assert_eq!(button_event.take(), Some((1, 0)));
+        assert_eq!(timeline.now(), ms(30));
+        assert!(timeline.is_finished());
+    });
+    assert_eq!(alarm.take_sleeps(), [10, 10, 10]);
+}
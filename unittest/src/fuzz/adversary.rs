@@ -0,0 +1,141 @@
+use super::{FuzzInput, InputExhausted};
+use crate::{command_return, fake, DriverInfo, DriverShareRef, RoAllowBuffer, RwAllowBuffer};
+use core::cell::RefCell;
+use libtock_platform::{CommandReturn, ErrorCode};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A fake driver that answers system calls with results decoded from a
+/// `FuzzInput`. Its results are always well-formed:
+///
+/// - Command 0 returns Success or Success with u32, and every other command
+///   returns an arbitrary return variant. Unlike a real driver, a command may
+///   return a different variant each time it is called.
+/// - The first Allow call with each buffer number decides whether that buffer
+///   number is supported. Allow calls with an unsupported buffer number fail
+///   with an arbitrary error code (returning the passed buffer), and calls with
+///   a supported buffer number succeed and swap buffers as the real kernel
+///   does. As on a real kernel, un-allowing a buffer therefore never fails.
+/// - Upcalls have subscribe numbers less than the driver's upcall count, but
+///   are otherwise arbitrary. Before each upcall, the contents of the allowed
+///   Read-Write buffers may be overwritten.
+///
+/// After each Command call, and whenever the code under test waits for an
+/// upcall, the input decides which upcalls to schedule. If the code under test
+/// waits after the input is exhausted, the run ends.
+pub struct Adversary {
+    driver_num: u32,
+    upcall_count: u32,
+    input: FuzzInput,
+    ro_buffers: RefCell<HashMap<u32, RoAllowBuffer>>,
+    rw_buffers: RefCell<HashMap<u32, RwAllowBuffer>>,
+    // The error returned by Allow calls with each buffer number, or None if the
+    // buffer number is supported. The key is (read-write, buffer number).
+    allow_errors: RefCell<HashMap<(bool, u32), Option<ErrorCode>>>,
+    share_ref: DriverShareRef,
+}
+
+impl Adversary {
+    pub fn new(driver_num: u32, upcall_count: u32, input: &FuzzInput) -> Rc<Adversary> {
+        Rc::new(Adversary {
+            driver_num,
+            upcall_count,
+            input: input.clone(),
+            ro_buffers: Default::default(),
+            rw_buffers: Default::default(),
+            allow_errors: Default::default(),
+            share_ref: Default::default(),
+        })
+    }
+
+    // Returns the error Allow calls with this buffer number fail with, if any.
+    fn allow_error(&self, read_write: bool, buffer_num: u32) -> Option<ErrorCode> {
+        *self
+            .allow_errors
+            .borrow_mut()
+            .entry((read_write, buffer_num))
+            .or_insert_with(|| match self.input.below(8) {
+                0 => Some(self.input.error_code()),
+                _ => None,
+            })
+    }
+
+    // Overwrites some of the Read-Write buffers, then schedules an upcall.
+    fn schedule_upcall(&self) {
+        let mut rw_buffers = self.rw_buffers.borrow_mut();
+        let mut buffer_nums: Vec<_> = rw_buffers.keys().copied().collect();
+        buffer_nums.sort_unstable();
+        for buffer_num in buffer_nums {
+            if self.input.bool() {
+                self.input.fill(rw_buffers.get_mut(&buffer_num).unwrap());
+            }
+        }
+        drop(rw_buffers);
+        let subscribe_num = self.input.below(self.upcall_count.min(256));
+        let args = (self.input.u32(), self.input.u32(), self.input.u32());
+        self.share_ref
+            .schedule_upcall(subscribe_num, args)
+            .expect("Unable to schedule upcall");
+    }
+}
+
+impl fake::SyscallDriver for Adversary {
+    fn info(&self) -> DriverInfo {
+        DriverInfo::new(self.driver_num).upcall_count(self.upcall_count)
+    }
+
+    fn register(&self, share_ref: DriverShareRef) {
+        self.share_ref.replace(share_ref);
+    }
+
+    fn command(&self, command_id: u32, _argument0: u32, _argument1: u32) -> CommandReturn {
+        let command_return = match command_id {
+            0 => match self.input.bool() {
+                false => command_return::success(),
+                true => command_return::success_u32(self.input.u32()),
+            },
+            _ => self.input.command_return(),
+        };
+        if self.upcall_count > 0 {
+            for _ in 0..self.input.below(3) {
+                self.schedule_upcall();
+            }
+        }
+        command_return
+    }
+
+    fn allow_readonly(
+        &self,
+        buffer_num: u32,
+        buffer: RoAllowBuffer,
+    ) -> Result<RoAllowBuffer, (RoAllowBuffer, ErrorCode)> {
+        if let Some(error) = self.allow_error(false, buffer_num) {
+            return Err((buffer, error));
+        }
+        let previous = self.ro_buffers.borrow_mut().insert(buffer_num, buffer);
+        Ok(previous.unwrap_or_default())
+    }
+
+    fn allow_readwrite(
+        &self,
+        buffer_num: u32,
+        buffer: RwAllowBuffer,
+    ) -> Result<RwAllowBuffer, (RwAllowBuffer, ErrorCode)> {
+        if let Some(error) = self.allow_error(true, buffer_num) {
+            return Err((buffer, error));
+        }
+        let previous = self.rw_buffers.borrow_mut().insert(buffer_num, buffer);
+        Ok(previous.unwrap_or_default())
+    }
+
+    fn idle(&self) -> bool {
+        if self.upcall_count == 0 {
+            return false;
+        }
+        if self.input.is_exhausted() {
+            std::panic::panic_any(InputExhausted);
+        }
+        self.schedule_upcall();
+        true
+    }
+}
@@ -0,0 +1,96 @@
+use crate::command_return;
+use core::cell::Cell;
+use libtock_platform::{CommandReturn, ErrorCode};
+use std::rc::Rc;
+
+/// A fuzzer-supplied byte stream that `Adversary` drivers decode their
+/// results from. Clones share the same stream, so several drivers can consume
+/// one input. Once the stream is exhausted, every read returns zeros.
+#[derive(Clone)]
+pub struct FuzzInput {
+    data: Rc<[u8]>,
+    position: Rc<Cell<usize>>,
+}
+
+impl FuzzInput {
+    pub fn new(data: &[u8]) -> FuzzInput {
+        FuzzInput {
+            data: data.into(),
+            position: Default::default(),
+        }
+    }
+
+    /// Returns the number of bytes that have not been read yet.
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.position.get()
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Fills `out` from the stream, padding with zeros if the stream runs out.
+    pub fn fill(&self, out: &mut [u8]) {
+        let start = self.position.get();
+        let count = out.len().min(self.remaining());
+        out[..count].copy_from_slice(&self.data[start..start + count]);
+        out[count..].fill(0);
+        self.position.set(start + count);
+    }
+
+    pub fn u8(&self) -> u8 {
+        let mut bytes = [0];
+        self.fill(&mut bytes);
+        bytes[0]
+    }
+
+    pub fn u32(&self) -> u32 {
+        let mut bytes = [0; 4];
+        self.fill(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    pub fn u64(&self) -> u64 {
+        let mut bytes = [0; 8];
+        self.fill(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    pub fn bool(&self) -> bool {
+        self.u8() & 1 == 1
+    }
+
+    /// Returns a value in `0..bound`, reading a single byte. `bound` must be
+    /// between 1 and 256.
+    pub fn below(&self, bound: u32) -> u32 {
+        assert!((1..=256).contains(&bound), "invalid bound {bound}");
+        u32::from(self.u8()) % bound
+    }
+
+    /// Returns an error code. The codes defined by TRD 104 are the most likely,
+    /// but `BADRVAL` and codes reserved for future use are also returned.
+    pub fn error_code(&self) -> ErrorCode {
+        let value = match self.below(16) {
+            code @ 0..=12 => code + 1,
+            13 => ErrorCode::BadRVal as u32,
+            _ => 14 + self.u32() % 1010,
+        };
+        ErrorCode::try_from(value).unwrap()
+    }
+
+    /// Returns a `CommandReturn` with an arbitrary return variant and values.
+    pub fn command_return(&self) -> CommandReturn {
+        match self.below(10) {
+            0 => command_return::failure(self.error_code()),
+            1 => command_return::failure_u32(self.error_code(), self.u32()),
+            2 => command_return::failure_2_u32(self.error_code(), self.u32(), self.u32()),
+            3 => command_return::failure_u64(self.error_code(), self.u64()),
+            4 => command_return::success(),
+            5 => command_return::success_u32(self.u32()),
+            6 => command_return::success_2_u32(self.u32(), self.u32()),
+            7 => command_return::success_u64(self.u64()),
+            8 => command_return::success_3_u32(self.u32(), self.u32(), self.u32()),
+            _ => command_return::success_u32_u64(self.u32(), self.u64()),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! `fuzz` runs code under test against an adversarial kernel. Instead of
+//! emulating real hardware, an `Adversary` driver answers every system call
+//! with results decoded from a fuzzer-supplied byte stream: arbitrary (but
+//! well-formed, as defined by TRD 104) `CommandReturn` variants, upcalls with
+//! arbitrary arguments, and arbitrary contents for Read-Write Allow buffers.
+//! This finds panics and unsoundness in API crates that trust the kernel.
+//!
+//! The byte stream is consumed sequentially, so `run` is deterministic and
+//! compatible with coverage-guided fuzzers such as libFuzzer (`cargo fuzz`):
+//!
+//! ```
+//! use libtock_platform::Syscalls;
+//! use libtock_unittest::{fake, fuzz};
+//! # let data: &[u8] = &[];
+//! fuzz::run(data, |kernel, input| {
+//!     kernel.add_driver(&fuzz::Adversary::new(0x2, 0, input));
+//!     let _ = fake::Syscalls::command(0x2, 1, 0, 0);
+//! });
+//! ```
+//!
+//! The fuzz targets for `libtock-rs`'s own API crates are in the `fuzz/`
+//! directory at the root of the repository.
+
+mod adversary;
+mod input;
+
+pub use adversary::Adversary;
+pub use input::FuzzInput;
+
+use crate::fake;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+
+/// Creates a `fake::Kernel` and runs `test` with it and a `FuzzInput` that
+/// reads from `data`. `test` should add its drivers (typically `Adversary`s
+/// sharing the input) to the kernel and then exercise the code under test.
+///
+/// An `Adversary` ends the run, by unwinding out of `test`, if the code under
+/// test waits for an upcall after the input has been exhausted. Any other
+/// panic is propagated, so the fuzzer reports it.
+pub fn run<F: FnOnce(&fake::Kernel, &FuzzInput)>(data: &[u8], test: F) {
+    install_panic_hook();
+    let input = FuzzInput::new(data);
+    let kernel = fake::Kernel::new();
+    let result = catch_unwind(AssertUnwindSafe(|| test(&kernel, &input)));
+    drop(kernel);
+    if let Err(payload) = result {
+        if !payload.is::<InputExhausted>() {
+            resume_unwind(payload);
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Implementation details below
+// -----------------------------------------------------------------------------
+
+// Panic payload used to end a run when the code under test is waiting on an
+// upcall and the input has been exhausted.
+struct InputExhausted;
+
+// Wraps the panic hook so that the InputExhausted panics that end every run do
+// not print a message.
+fn install_panic_hook() {
+    static INSTALL: std::sync::Once = std::sync::Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if !info.payload().is::<InputExhausted>() {
+                previous(info);
+            }
+        }));
+    });
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,155 @@
+use super::*;
+use libtock_platform::{
+    return_variant, share, AllowRo, AllowRw, DefaultConfig, ErrorCode, Subscribe, Syscalls,
+};
+use std::cell::Cell;
+
+const DRIVER_NUM: u32 = 0x9000;
+
+#[test]
+fn input_decoding() {
+    let input = FuzzInput::new(&[1, 2, 3, 4, 5, 6]);
+    assert_eq!(input.u32(), 0x04030201);
+    assert_eq!(input.remaining(), 2);
+    assert_eq!(input.below(4), 1);
+    assert!(!input.bool());
+    assert!(input.is_exhausted());
+    // Exhausted input reads as zeros.
+    assert_eq!(input.u64(), 0);
+    let mut bytes = [0xff; 3];
+    FuzzInput::new(&[7]).fill(&mut bytes);
+    assert_eq!(bytes, [7, 0, 0]);
+}
+
+#[test]
+fn error_codes() {
+    for byte in 0..=255 {
+        let code = FuzzInput::new(&[byte, 0xff, 0xff, 0xff, 0xff]).error_code() as u32;
+        match byte % 16 {
+            0..=12 => assert_eq!(code, u32::from(byte % 16) + 1),
+            13 => assert_eq!(code, ErrorCode::BadRVal as u32),
+            _ => assert!((14..1024).contains(&code)),
+        }
+    }
+}
+
+#[test]
+fn run_ends_when_exhausted() {
+    let called = Cell::new(false);
+    let upcalls = Cell::new(0);
+    run(&[0; 64], |kernel, input| {
+        kernel.add_driver(&Adversary::new(DRIVER_NUM, 1, input));
+        share::scope::<Subscribe<_, DRIVER_NUM, 0>, _, _>(|subscribe| {
+            fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, &called)
+                .unwrap();
+            loop {
+                fake::Syscalls::yield_wait();
+                upcalls.set(upcalls.get() + 1);
+            }
+        });
+    });
+    assert!(upcalls.get() > 0);
+}
+
+#[test]
+fn run_propagates_panics() {
+    let result = catch_unwind(|| run(&[], |_, _| panic!("bug in code under test")));
+    assert_eq!(
+        result.unwrap_err().downcast_ref::<&str>(),
+        Some(&"bug in code under test")
+    );
+}
+
+#[test]
+fn commands() {
+    // Command 0: Success with u32. Command 1: Failure with u32, with error code
+    // NOMEM. Command 1 again, once the input is exhausted: Failure.
+    run(&[1, 7, 0, 0, 0, 1, 8, 9, 0, 0, 0], |kernel, input| {
+        kernel.add_driver(&Adversary::new(DRIVER_NUM, 0, input));
+        assert_eq!(
+            fake::Syscalls::command(DRIVER_NUM, 0, 0, 0).get_success_u32(),
+            Some(7)
+        );
+        assert_eq!(
+            fake::Syscalls::command(DRIVER_NUM, 1, 0, 0).get_failure_u32(),
+            Some((ErrorCode::NoMem, 9))
+        );
+        assert_eq!(
+            fake::Syscalls::command(DRIVER_NUM, 1, 0, 0).return_variant(),
+            return_variant::FAILURE
+        );
+    });
+}
+
+#[test]
+fn buffer_contents() {
+    let mut buffer = [0; 4];
+    let upcall = Cell::new(None);
+    #[rustfmt::skip]
+    let data = [
+        1, // Allow succeeds.
+        4, 1, // Success, then 1 upcall.
+        1, 10, 20, 30, 40, // Overwrite the buffer.
+        0, 5, 0, 0, 0, 6, 0, 0, 0, 7, 0, 0, 0, // Upcall 0 with args (5, 6, 7).
+    ];
+    run(&data, |kernel, input| {
+        kernel.add_driver(&Adversary::new(DRIVER_NUM, 1, input));
+        share::scope::<(AllowRw<_, DRIVER_NUM, 0>, Subscribe<_, DRIVER_NUM, 0>), _, _>(|handle| {
+            let (allow_rw, subscribe) = handle.split();
+            fake::Syscalls::allow_rw::<DefaultConfig, DRIVER_NUM, 0>(allow_rw, &mut buffer)
+                .unwrap();
+            fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 0>(subscribe, &upcall)
+                .unwrap();
+            assert!(fake::Syscalls::command(DRIVER_NUM, 1, 0, 0).is_success());
+            fake::Syscalls::yield_wait();
+        });
+    });
+    assert_eq!(buffer, [10, 20, 30, 40]);
+    assert_eq!(upcall.get(), Some((5, 6, 7)));
+}
+
+// Checks that Adversary's results are well-formed by exercising it in strict
+// mode with pseudorandom inputs. Each command is only called once per run, as
+// an Adversary may return a different variant each time.
+#[test]
+fn well_formed() {
+    let mut state = 0x2545f491u32;
+    for _ in 0..200 {
+        let data: Vec<u8> = (0..256)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        run(&data, |kernel, input| {
+            kernel.set_strict(true);
+            kernel.add_driver(&Adversary::new(DRIVER_NUM, 2, input));
+            let mut rw_buffer = [0; 8];
+            let upcall = Cell::new(None::<(u32, u32, u32)>);
+            share::scope::<
+                (
+                    AllowRo<_, DRIVER_NUM, 0>,
+                    AllowRw<_, DRIVER_NUM, 0>,
+                    Subscribe<_, DRIVER_NUM, 1>,
+                ),
+                _,
+                _,
+            >(|handle| {
+                let (allow_ro, allow_rw, subscribe) = handle.split();
+                let _ = fake::Syscalls::allow_ro::<DefaultConfig, DRIVER_NUM, 0>(allow_ro, b"ro");
+                let _ = fake::Syscalls::allow_rw::<DefaultConfig, DRIVER_NUM, 0>(
+                    allow_rw,
+                    &mut rw_buffer,
+                );
+                fake::Syscalls::subscribe::<_, _, DefaultConfig, DRIVER_NUM, 1>(subscribe, &upcall)
+                    .unwrap();
+                for command in 0..4 {
+                    let _ = fake::Syscalls::command(DRIVER_NUM, command, 0, 0);
+                    fake::Syscalls::yield_wait();
+                }
+            });
+        });
+    }
+}
@@ -21,9 +21,26 @@ pub(crate) struct KernelData {
 
     pub drivers: std::collections::HashMap<u32, DriverData>,
     pub expected_syscalls: std::collections::VecDeque<crate::ExpectedSyscall>,
+    // Expectations queued by `fake::Kernel::expect`, which are matched once
+    // `expected_syscalls` is empty.
+    pub expectations: std::collections::VecDeque<crate::Expectation>,
     pub syscall_log: Vec<crate::SyscallLogEntry>,
     pub upcall_queue: crate::upcall::UpcallQueue,
     pub memory_break: *const u8,
+
+    // Upcalls installed by `fake::Replay`. Each Yield call pops one entry and,
+    // if it is Some, queues that upcall before running the upcall queue.
+    pub replayed_upcalls: std::collections::VecDeque<Option<ReplayedUpcall>>,
+
+    // Some if strict mode is enabled (see `fake::Kernel::set_strict`).
+    pub strict: Option<crate::strict::StrictState>,
+}
+
+// An upcall recorded in a trace, to be queued when the corresponding Yield is
+// replayed.
+pub(crate) struct ReplayedUpcall {
+    pub id: crate::upcall::UpcallId,
+    pub args: (u32, u32, u32),
 }
 
 // KERNEL_DATA is set to Some in `fake::Kernel::new` and set to None when the
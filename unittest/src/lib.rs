@@ -6,22 +6,32 @@
 mod allow_db;
 pub mod command_return;
 mod driver_info;
+mod exit_call;
 #[cfg(not(miri))]
 mod exit_test;
+mod expectation;
 mod expected_syscall;
 pub mod fake;
+pub mod fuzz;
 mod kernel_data;
 mod share_data;
+mod strict;
 mod syscall_log;
 pub mod upcall;
 
 pub use allow_db::{RoAllowBuffer, RwAllowBuffer};
 pub use driver_info::DriverInfo;
+pub use exit_call::{catch_exit, ExitCall};
 #[cfg(not(miri))]
-pub use exit_test::{exit_test, ExitCall};
+pub use exit_test::exit_test;
+pub use expectation::{Arg, Expectation, SyscallLogAssertions};
 pub use expected_syscall::ExpectedSyscall;
 pub use share_data::DriverShareRef;
 pub use syscall_log::SyscallLogEntry;
 
 #[cfg(test)]
 mod allow_db_test;
+#[cfg(test)]
+mod expectation_tests;
+#[cfg(test)]
+mod strict_tests;
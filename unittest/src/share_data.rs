@@ -25,11 +25,17 @@ impl DriverShareRef {
     /// Schedules the upcall with the specified subscribe number. Like the real
     /// kernel, this does nothing if there is no upcall with number
     /// `subscribe_num` or the upcall is the null upcall.
+    ///
+    /// In strict mode (see `fake::Kernel::set_strict`), a too-large
+    /// `subscribe_num` is reported as a violation instead of returning an
+    /// error.
+    #[track_caller]
     pub fn schedule_upcall(
         &self,
         subscribe_num: u32,
         args: (u32, u32, u32),
     ) -> Result<(), InvalidSubscribeNum> {
+        let caller = std::panic::Location::caller();
         with_kernel_data(|kernel_data| {
             let kernel_data = match kernel_data {
                 Some(kernel_data) => kernel_data,
@@ -40,6 +46,17 @@ impl DriverShareRef {
                 .get(&self.driver_num.get())
                 .expect("DriverShareRef: registered but nonexistent?");
             if subscribe_num >= driver_data.num_upcalls {
+                if kernel_data.strict.is_some() {
+                    crate::strict::violation(
+                        caller,
+                        format_args!(
+                            "driver {:#x} scheduled upcall {subscribe_num}, but only has {} \
+                             upcalls",
+                            self.driver_num.get(),
+                            driver_data.num_upcalls,
+                        ),
+                    );
+                }
                 return Err(InvalidSubscribeNum {
                     upcall_count: driver_data.num_upcalls,
                     requested: subscribe_num,
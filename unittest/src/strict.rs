@@ -0,0 +1,151 @@
+//! Strict mode checks the interactions between the code under test, the
+//! `fake::Kernel`, and the `fake::SyscallDriver`s against the rules of TRD 104
+//! (Tock 2.x's system call ABI). It is enabled by `fake::Kernel::set_strict`.
+//!
+//! Rules the code under test can break are enforced the way the real kernel
+//! enforces them (by returning errors). Rules a fake driver can break have no
+//! kernel-side equivalent, so they are reported as violations: strict mode
+//! panics with a description of the broken rule and the location of the call
+//! that triggered it.
+
+use libtock_platform::{return_variant, ReturnVariant};
+use std::collections::HashMap;
+use std::panic::Location;
+
+// The process memory modelled by strict mode's Memop implementation. The start
+// address matches the value the non-strict Memop implementation returns.
+pub(crate) const APP_RAM_START: usize = 0x123400;
+pub(crate) const APP_RAM_END: usize = APP_RAM_START + 0x10000;
+
+#[derive(Default)]
+pub(crate) struct StrictState {
+    // The return variants each (driver number, command number) has returned,
+    // in the order (success variant, failure variant).
+    command_variants: HashMap<(u32, u32), (Option<ReturnVariant>, Option<ReturnVariant>)>,
+
+    // The buffers currently shared with each (driver number, buffer number),
+    // as (address, length).
+    ro_buffers: HashMap<(u32, u32), (usize, usize)>,
+    rw_buffers: HashMap<(u32, u32), (usize, usize)>,
+}
+
+// Which kind of Allow a buffer was shared through.
+#[derive(Clone, Copy)]
+pub(crate) enum AllowKind {
+    ReadOnly,
+    ReadWrite,
+}
+
+impl StrictState {
+    // Checks the value a driver returned from a Command call made at `caller`.
+    pub fn check_command(
+        &mut self,
+        caller: &Location,
+        driver_num: u32,
+        command_num: u32,
+        return_variant: ReturnVariant,
+    ) {
+        let is_success = SUCCESS_VARIANTS.contains(&return_variant);
+        if !is_success && !FAILURE_VARIANTS.contains(&return_variant) {
+            violation(
+                caller,
+                format_args!(
+                    "driver {driver_num:#x} returned unknown return variant \
+                     {return_variant:?} from command {command_num}"
+                ),
+            );
+        }
+        if command_num == 0
+            && return_variant != return_variant::SUCCESS
+            && return_variant != return_variant::SUCCESS_U32
+        {
+            violation(
+                caller,
+                format_args!(
+                    "driver {driver_num:#x} returned {return_variant:?} from command 0, which \
+                     must return Success or Success with u32"
+                ),
+            );
+        }
+        let (success, failure) = self
+            .command_variants
+            .entry((driver_num, command_num))
+            .or_default();
+        let previous = match is_success {
+            true => success,
+            false => failure,
+        };
+        match *previous {
+            Some(previous) if previous != return_variant => violation(
+                caller,
+                format_args!(
+                    "driver {driver_num:#x} returned {return_variant:?} from command \
+                     {command_num}, which previously returned {previous:?}"
+                ),
+            ),
+            _ => *previous = Some(return_variant),
+        }
+    }
+
+    // Checks the buffer a driver returned from an Allow call. `passed` is the
+    // buffer the process passed in, and `returned` is the buffer the driver
+    // handed back. If the call succeeded, the driver must return the buffer
+    // previously shared with that buffer number (the null buffer if there was
+    // none). If it failed, the driver must return the buffer that was passed
+    // in. `caller` is the location of the Allow call.
+    pub fn check_allow(
+        &mut self,
+        caller: &Location,
+        kind: AllowKind,
+        (driver_num, buffer_num): (u32, u32),
+        passed: (usize, usize),
+        returned: (usize, usize),
+        succeeded: bool,
+    ) {
+        let (buffers, name) = match kind {
+            AllowKind::ReadOnly => (&mut self.ro_buffers, "Read-Only Allow"),
+            AllowKind::ReadWrite => (&mut self.rw_buffers, "Read-Write Allow"),
+        };
+        let expected = match succeeded {
+            true => buffers
+                .insert((driver_num, buffer_num), passed)
+                .unwrap_or((0, 0)),
+            false => passed,
+        };
+        if returned != expected {
+            violation(
+                caller,
+                format_args!(
+                    "driver {driver_num:#x} returned buffer {returned:x?} from {name} of buffer \
+                     {buffer_num}, expected {expected:x?} (the {})",
+                    match succeeded {
+                        true => "previously-shared buffer",
+                        false => "buffer passed to the failed call",
+                    }
+                ),
+            );
+        }
+    }
+}
+
+// Reports a violation of a rule the code under test cannot be blamed for.
+// `caller` is the location of the call that triggered it.
+pub(crate) fn violation(caller: &Location, message: core::fmt::Arguments) -> ! {
+    panic!("TRD 104 violation in strict mode: {message} (triggered by the call at {caller})")
+}
+
+const SUCCESS_VARIANTS: [ReturnVariant; 6] = [
+    return_variant::SUCCESS,
+    return_variant::SUCCESS_U32,
+    return_variant::SUCCESS_2_U32,
+    return_variant::SUCCESS_U64,
+    return_variant::SUCCESS_3_U32,
+    return_variant::SUCCESS_U32_U64,
+];
+
+const FAILURE_VARIANTS: [ReturnVariant; 4] = [
+    return_variant::FAILURE,
+    return_variant::FAILURE_U32,
+    return_variant::FAILURE_2_U32,
+    return_variant::FAILURE_U64,
+];
@@ -0,0 +1,208 @@
+//! Unit test cases for `fake::Kernel`'s strict mode.
+
+use crate::{command_return, fake, DriverInfo, DriverShareRef, RoAllowBuffer};
+use core::cell::Cell;
+use libtock_platform::{
+    return_variant, syscall_class, CommandReturn, ErrorCode, RawSyscalls, Syscalls,
+};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::rc::Rc;
+
+// A driver that breaks the rules strict mode checks.
+#[derive(Default)]
+struct RuleBreaker {
+    calls: Cell<u32>,
+    share_ref: DriverShareRef,
+}
+
+impl fake::SyscallDriver for RuleBreaker {
+    fn info(&self) -> DriverInfo {
+        DriverInfo::new(DRIVER_NUM).upcall_count(1)
+    }
+
+    fn register(&self, share_ref: DriverShareRef) {
+        self.share_ref.replace(share_ref);
+    }
+
+    fn command(&self, command_id: u32, _: u32, _: u32) -> CommandReturn {
+        match command_id {
+            0 => command_return::failure(ErrorCode::Fail),
+            // Alternates between two success variants.
+            1 => {
+                self.calls.set(self.calls.get() + 1);
+                match self.calls.get() % 2 {
+                    1 => command_return::success(),
+                    _ => command_return::success_u32(7),
+                }
+            }
+            2 => {
+                let _ = self.share_ref.schedule_upcall(1, (0, 0, 0));
+                command_return::success()
+            }
+            _ => command_return::failure(ErrorCode::NoSupport),
+        }
+    }
+
+    // Hands the new buffer straight back rather than swapping it.
+    fn allow_readonly(
+        &self,
+        _: u32,
+        buffer: RoAllowBuffer,
+    ) -> Result<RoAllowBuffer, (RoAllowBuffer, ErrorCode)> {
+        Ok(buffer)
+    }
+}
+
+// Runs `f` and returns the message it panicked with.
+fn panic_message<F: FnOnce()>(f: F) -> String {
+    catch_unwind(AssertUnwindSafe(f))
+        .expect_err("strict mode did not report a violation")
+        .downcast_ref::<String>()
+        .expect("wrong panic payload type")
+        .clone()
+}
+
+fn command(driver_num: u32, command_num: u32) -> [u32; 2] {
+    let [r0, r1, _, _] = unsafe {
+        fake::Syscalls::syscall4::<{ syscall_class::COMMAND }>([
+            driver_num.into(),
+            command_num.into(),
+            0u32.into(),
+            0u32.into(),
+        ])
+    };
+    [r0.as_u32(), r1.as_u32()]
+}
+
+// Shares a zero-length buffer at `address`, returning (r0, r1), which for a
+// successful call is the previously-shared buffer's address.
+fn allow_ro_empty(driver_num: u32, address: usize) -> (u32, usize) {
+    // Safety: TRD 104 permits any address for a zero-length buffer.
+    let [r0, r1, _, _] = unsafe {
+        fake::Syscalls::syscall4::<{ syscall_class::ALLOW_RO }>([
+            driver_num.into(),
+            1u32.into(),
+            address.into(),
+            0u32.into(),
+        ])
+    };
+    (r0.as_u32(), r1.into())
+}
+
+fn memop(memop_num: u32, argument0: usize) -> (u32, usize) {
+    let [r0, r1] = unsafe {
+        fake::Syscalls::syscall2::<{ syscall_class::MEMOP }>([memop_num.into(), argument0.into()])
+    };
+    (r0.as_u32(), r1.into())
+}
+
+#[test]
+fn disabled_by_default() {
+    let kernel = fake::Kernel::new();
+    kernel.add_driver(&Rc::new(RuleBreaker::default()));
+    assert!(!kernel.is_strict());
+    assert_eq!(command(DRIVER_NUM, 0)[0], return_variant::FAILURE.into());
+    assert_eq!(command(DRIVER_NUM, 1)[0], return_variant::SUCCESS.into());
+    assert_eq!(
+        command(DRIVER_NUM, 1)[0],
+        return_variant::SUCCESS_U32.into()
+    );
+}
+
+#[test]
+fn command_variants() {
+    let kernel = fake::Kernel::new();
+    kernel.add_driver(&Rc::new(RuleBreaker::default()));
+    kernel.set_strict(true);
+    // The violation names the call that triggered it, including calls made
+    // through libtock_platform's Syscalls.
+    let message = panic_message(|| {
+        let _ = fake::Syscalls::command(DRIVER_NUM, 0, 0, 0);
+    });
+    let location = format!("(triggered by the call at {}:{}:", file!(), line!() - 2);
+    assert!(message.contains("from command 0, which must return Success"));
+    assert!(message.contains(&location), "{message}");
+
+    assert_eq!(command(DRIVER_NUM, 1)[0], return_variant::SUCCESS.into());
+    let message = panic_message(|| {
+        command(DRIVER_NUM, 1);
+    });
+    assert!(message.contains("which previously returned ReturnVariant(128)"));
+    assert!(message.contains("strict_tests.rs"), "{message}");
+
+    // Failures may use a different variant than successes, and the kernel's
+    // own NODEVICE return is not checked.
+    assert_eq!(
+        command(DRIVER_NUM, 9),
+        [return_variant::FAILURE.into(), ErrorCode::NoSupport as u32]
+    );
+    assert_eq!(
+        command(DRIVER_NUM + 1, 0),
+        [return_variant::FAILURE.into(), ErrorCode::NoDevice as u32]
+    );
+}
+
+#[test]
+fn allow_swapping() {
+    let kernel = fake::Kernel::new();
+    kernel.add_driver(&fake::Console::new());
+    kernel.add_driver(&Rc::new(RuleBreaker::default()));
+    kernel.set_strict(true);
+
+    // Zero-length buffers keep their address, and the first Allow returns the
+    // null buffer.
+    let success_2_u32 = return_variant::SUCCESS_2_U32.into();
+    assert_eq!(allow_ro_empty(CONSOLE, 0x1000), (success_2_u32, 0));
+    assert_eq!(allow_ro_empty(CONSOLE, 0x2000), (success_2_u32, 0x1000));
+
+    let message = panic_message(|| {
+        allow_ro_empty(DRIVER_NUM, 0x1000);
+    });
+    assert!(message.contains("returned buffer (1000, 0) from Read-Only Allow of buffer 1"));
+    assert!(message.contains("expected (0, 0) (the previously-shared buffer)"));
+    assert!(message.contains("strict_tests.rs"), "{message}");
+}
+
+#[test]
+fn schedule_out_of_range() {
+    let kernel = fake::Kernel::new();
+    kernel.add_driver(&Rc::new(RuleBreaker::default()));
+    kernel.set_strict(true);
+    let message = panic_message(|| {
+        command(DRIVER_NUM, 2);
+    });
+    assert!(message.contains("scheduled upcall 1, but only has 1 upcalls"));
+    // The call that triggered this violation is the driver's.
+    assert!(message.contains("strict_tests.rs"), "{message}");
+}
+
+#[test]
+fn memop_models_memory() {
+    let kernel = fake::Kernel::new();
+    kernel.set_strict(true);
+    let failure = return_variant::FAILURE.into();
+    let success = return_variant::SUCCESS.into();
+    let success_u32 = return_variant::SUCCESS_U32.into();
+
+    let (_, ram_start) = memop(2, 0);
+    let (_, ram_end) = memop(3, 0);
+    assert_eq!(memop(2, 0), (success_u32, ram_start));
+    assert!(ram_start < ram_end);
+
+    // sbrk returns the previous break.
+    assert_eq!(memop(1, 0x100), (success_u32, ram_start));
+    assert_eq!(memop(1, 0), (success_u32, ram_start + 0x100));
+    assert_eq!(
+        memop(1, -0x200isize as usize),
+        (failure, ErrorCode::NoMem as usize)
+    );
+
+    assert_eq!(memop(0, ram_end), (success, 0));
+    assert_eq!(memop(0, ram_end + 1), (failure, ErrorCode::NoMem as usize));
+    assert_eq!(memop(0, 0), (failure, ErrorCode::NoMem as usize));
+
+    assert_eq!(memop(12, 0), (failure, ErrorCode::NoSupport as usize));
+}
+
+const CONSOLE: u32 = 0x1;
+const DRIVER_NUM: u32 = 0x9000;
@@ -53,5 +53,12 @@ pub enum SyscallLogEntry {
         memop_num: u32,
         argument0: Register, // Necessary for Miri ptr provenance of brk()
     },
-    // TODO: Add Exit.
+
+    // -------------------------------------------------------------------------
+    // Exit
+    // -------------------------------------------------------------------------
+    Exit {
+        exit_num: u32,
+        completion_code: u32,
+    },
 }